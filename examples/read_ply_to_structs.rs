@@ -29,8 +29,9 @@ impl ply::PropertyAccess for Vertex {
             z: 0.0,
         }
     }
-    fn set_property(&mut self, key: String, property: ply::Property) {
-        match (key.as_ref(), property) {
+    // The parser calls `set_named_property`, implementing it avoids allocating the key of each value.
+    fn set_named_property(&mut self, key: &str, property: ply::Property) {
+        match (key, property) {
             ("x", ply::Property::Float(v)) => self.x = v,
             ("y", ply::Property::Float(v)) => self.y = v,
             ("z", ply::Property::Float(v)) => self.z = v,
@@ -77,8 +78,8 @@ fn main() {
     for (_ignore_key, element) in &header.elements {
         // we could also just parse them in sequence, but the file format might change
         match element.name.as_ref() {
-            "vertex" => {vertex_list = vertex_parser.read_payload_for_element(&mut f, &element, &header).unwrap();},
-            "face" => {face_list = face_parser.read_payload_for_element(&mut f, &element, &header).unwrap();},
+            "vertex" => {vertex_list = vertex_parser.read_payload_for_element(&mut f, element, &header).unwrap();},
+            "face" => {face_list = face_parser.read_payload_for_element(&mut f, element, &header).unwrap();},
             _ => panic!("Enexpeced element!"),
         }
     }
@@ -0,0 +1,96 @@
+extern crate ply_rs;
+use ply_rs::ply::{ self, Addable, ElementDef, Encoding, Header, PropertyDef, PropertyType, ScalarType };
+use ply_rs::writer::Writer;
+
+
+/// We know, what data we want to write, hence we can write it directly from our structs.
+struct Vertex {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+
+struct Face {
+    vertex_index: Vec<i32>,
+}
+
+
+// The structs need to implement the PropertyAccess trait, otherwise the writer doesn't know how to read from them.
+// Only the getters of the declared types are needed, the values are borrowed, nothing is converted.
+
+impl ply::PropertyAccess for Vertex {
+    fn new() -> Self {
+        Vertex {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+    fn get_float(&self, key: &String) -> Option<f32> {
+        match key.as_ref() {
+            "x" => Some(self.x),
+            "y" => Some(self.y),
+            "z" => Some(self.z),
+            _ => None,
+        }
+    }
+}
+
+// same thing for Face
+impl ply::PropertyAccess for Face {
+    fn new() -> Self {
+        Face {
+            vertex_index: Vec::new(),
+        }
+    }
+    fn get_list_int(&self, key: &String) -> Option<&[i32]> {
+        match key.as_ref() {
+            "vertex_index" => Some(&self.vertex_index),
+            _ => None,
+        }
+    }
+}
+
+/// Demonstrates writing elements of different types without converting them to `DefaultElement`.
+fn main() {
+    // set up a target, could also be a file
+    let mut buf = Vec::<u8>::new();
+
+    let vertices = vec![
+        Vertex { x: 0.0, y: 0.0, z: 0.0 },
+        Vertex { x: 1.0, y: 0.0, z: 0.0 },
+        Vertex { x: 0.0, y: 1.0, z: 0.0 },
+    ];
+    let faces = vec![
+        Face { vertex_index: vec![0, 1, 2] },
+    ];
+
+    // Declare the elements, the counts have to match the data since there is no `make_consistent` for us.
+    let mut header = Header::new();
+    header.encoding = Encoding::Ascii;
+    let mut vertex = ElementDef::new("vertex".to_string());
+    vertex.count = vertices.len();
+    for name in &["x", "y", "z"] {
+        vertex.properties.add(PropertyDef::new(name.to_string(), PropertyType::Scalar(ScalarType::Float)));
+    }
+    header.elements.add(vertex);
+    let mut face = ElementDef::new("face".to_string());
+    face.count = faces.len();
+    face.properties.add(PropertyDef::new("vertex_index".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int)));
+    header.elements.add(face);
+
+    // Create a writer for each struct. Writers are cheap objects.
+    let vertex_writer = Writer::<Vertex>::new();
+    let face_writer = Writer::<Face>::new();
+
+    // The header doesn't depend on the element type, any of the writers does.
+    let mut written = vertex_writer.write_header(&mut buf, &header).unwrap();
+    written += vertex_writer.write_payload_of_element(&mut buf, &vertices, &header.elements["vertex"], &header).unwrap();
+    written += face_writer.write_payload_of_element(&mut buf, &faces, &header.elements["face"], &header).unwrap();
+    println!("{} bytes written", written);
+
+    // We can use `from_utf8` since PLY files only contain ascii characters
+    let output = String::from_utf8(buf).unwrap();
+    println!("Written data:\n{}", output);
+}
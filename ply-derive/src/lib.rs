@@ -0,0 +1,237 @@
+//! `#[derive(PlyElement)]` for ply-rs, re-exported as `ply_rs::ply::PlyElement` with feature `derive`.
+//!
+//! Implements `PropertyAccess` and `PlyElement` for structs with named fields of the types
+//! `i8`, `u8`, `i16`, `u16`, `i32`, `u32`, `f32`, `f64` or a `Vec` of them, which become lists.
+//!
+//! Attributes:
+//!
+//! - `#[ply(name = "vertex")]` on the struct sets the element name, default is the lowercase struct name.
+//! - `#[ply(name = "vertex_indices")]` on a field sets the property name, default is the field name.
+//! - `#[ply(index = "uint")]` on a list field sets the type of the list length, default is `uchar`.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{ format_ident, quote };
+use syn::{ parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitStr, PathArguments, Result, Type };
+
+/// Scalar types as `(rust type, Property and ScalarType variant, getter suffix)`.
+const SCALARS: [(&str, &str, &str); 8] = [
+    ("i8", "Char", "char"),
+    ("u8", "UChar", "uchar"),
+    ("i16", "Short", "short"),
+    ("u16", "UShort", "ushort"),
+    ("i32", "Int", "int"),
+    ("u32", "UInt", "uint"),
+    ("f32", "Float", "float"),
+    ("f64", "Double", "double"),
+];
+
+/// Index into `SCALARS` of a PLY type name, as accepted by the parser.
+fn scalar_of_ply_name(name: &str) -> Option<usize> {
+    let index = match name {
+        "char" | "int8" => 0,
+        "uchar" | "uint8" => 1,
+        "short" | "int16" => 2,
+        "ushort" | "uint16" => 3,
+        "int" | "int32" => 4,
+        "uint" | "uint32" => 5,
+        "float" | "float32" => 6,
+        "double" | "float64" => 7,
+        _ => return None,
+    };
+    Some(index)
+}
+
+/// A field mapped to a property.
+struct Property {
+    ident: Ident,
+    name: String,
+    /// Index into `SCALARS` of the values.
+    scalar: usize,
+    /// Index into `SCALARS` of the list length, `None` for scalars.
+    index: Option<usize>,
+}
+
+/// Values of a `#[ply(...)]` attribute.
+#[derive(Default)]
+struct Options {
+    name: Option<String>,
+    index: Option<LitStr>,
+}
+
+fn options(attrs: &[Attribute]) -> Result<Options> {
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("ply")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                options.name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("index") {
+                options.index = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `name` or `index`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+/// Index into `SCALARS` of `ty`, `None` if it's not a scalar type.
+fn scalar_of_type(ty: &Type) -> Option<usize> {
+    match *ty {
+        Type::Path(ref p) if p.qself.is_none() => SCALARS.iter().position(|s| p.path.is_ident(s.0)),
+        _ => None,
+    }
+}
+
+/// Type of the entries of `ty` if it is a `Vec`.
+fn vec_entry(ty: &Type) -> Option<&Type> {
+    let segment = match *ty {
+        Type::Path(ref p) if p.qself.is_none() => p.path.segments.last()?,
+        _ => return None,
+    };
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) if segment.ident == "Vec" && args.args.len() == 1 => match args.args[0] {
+            GenericArgument::Type(ref entry) => Some(entry),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn properties(input: &DeriveInput) -> Result<Vec<Property>> {
+    let fields = match input.data {
+        Data::Struct(ref s) => match s.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(Error::new_spanned(&input.ident, "PlyElement can only be derived for structs with named fields")),
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "PlyElement can only be derived for structs")),
+    };
+    let mut properties = Vec::new();
+    for field in fields {
+        let ident = field.ident.clone().unwrap();
+        let options = options(&field.attrs)?;
+        let (scalar, index) = match (scalar_of_type(&field.ty), vec_entry(&field.ty).and_then(scalar_of_type)) {
+            (Some(scalar), _) => (scalar, None),
+            (None, Some(scalar)) => {
+                let index = match options.index {
+                    Some(ref lit) => match scalar_of_ply_name(&lit.value()) {
+                        Some(index) if index < 6 => index,
+                        _ => return Err(Error::new_spanned(lit, "list index must be an integer type, e.g. `uchar` or `uint`")),
+                    },
+                    None => 1,
+                };
+                (scalar, Some(index))
+            },
+            (None, None) => return Err(Error::new_spanned(&field.ty, "field type must be i8, u8, i16, u16, i32, u32, f32, f64 or a Vec of them")),
+        };
+        if let (Some(ref lit), None) = (&options.index, index) {
+            return Err(Error::new_spanned(lit, "`index` only applies to Vec fields"));
+        }
+        let name = options.name.unwrap_or_else(|| ident.to_string());
+        properties.push(Property { ident, name, scalar, index });
+    }
+    Ok(properties)
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let properties = properties(input)?;
+    let ty = &input.ident;
+    let element_name = options(&input.attrs)?.name.unwrap_or_else(|| ty.to_string().to_lowercase());
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let idents = properties.iter().map(|p| &p.ident);
+    let setters = properties.iter().map(|p| {
+        let (ident, name) = (&p.ident, &p.name);
+        let variant = match p.index {
+            None => format_ident!("{}", SCALARS[p.scalar].1),
+            Some(_) => format_ident!("List{}", SCALARS[p.scalar].1),
+        };
+        quote! { (#name, ::ply_rs::ply::Property::#variant(v)) => self.#ident = v, }
+    });
+    let getters = (0..SCALARS.len()).flat_map(|scalar| vec![(scalar, false), (scalar, true)]).filter_map(|(scalar, list)| {
+        let arms = properties.iter().filter(|p| p.scalar == scalar && p.index.is_some() == list).map(|p| {
+            let (ident, name) = (&p.ident, &p.name);
+            match list {
+                false => quote! { #name => ::std::option::Option::Some(self.#ident), },
+                true => quote! { #name => ::std::option::Option::Some(&self.#ident), },
+            }
+        }).collect::<Vec<_>>();
+        if arms.is_empty() {
+            return None;
+        }
+        let rust_type = format_ident!("{}", SCALARS[scalar].0);
+        Some(match list {
+            false => {
+                let getter = format_ident!("get_{}", SCALARS[scalar].2);
+                quote! {
+                    fn #getter(&self, key: &::std::string::String) -> ::std::option::Option<#rust_type> {
+                        match key.as_str() {
+                            #(#arms)*
+                            _ => ::std::option::Option::None,
+                        }
+                    }
+                }
+            },
+            true => {
+                let getter = format_ident!("get_list_{}", SCALARS[scalar].2);
+                quote! {
+                    fn #getter(&self, key: &::std::string::String) -> ::std::option::Option<&[#rust_type]> {
+                        match key.as_str() {
+                            #(#arms)*
+                            _ => ::std::option::Option::None,
+                        }
+                    }
+                }
+            },
+        })
+    });
+    let definitions = properties.iter().map(|p| {
+        let name = &p.name;
+        let scalar = format_ident!("{}", SCALARS[p.scalar].1);
+        let data_type = match p.index {
+            None => quote! { ::ply_rs::ply::PropertyType::Scalar(::ply_rs::ply::ScalarType::#scalar) },
+            Some(index) => {
+                let index = format_ident!("{}", SCALARS[index].1);
+                quote! { ::ply_rs::ply::PropertyType::List(::ply_rs::ply::ScalarType::#index, ::ply_rs::ply::ScalarType::#scalar) }
+            },
+        };
+        quote! {
+            ::ply_rs::ply::Addable::add(&mut element.properties, ::ply_rs::ply::PropertyDef::new(::std::string::String::from(#name), #data_type));
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::ply_rs::ply::PropertyAccess for #ty #ty_generics #where_clause {
+            fn new() -> Self {
+                #ty {
+                    #(#idents: ::std::default::Default::default(),)*
+                }
+            }
+            fn set_named_property(&mut self, key: &str, property: ::ply_rs::ply::Property) {
+                match (key, property) {
+                    #(#setters)*
+                    _ => (),
+                }
+            }
+            #(#getters)*
+        }
+        impl #impl_generics ::ply_rs::ply::PlyElement for #ty #ty_generics #where_clause {
+            fn element_def() -> ::ply_rs::ply::ElementDef {
+                let mut element = ::ply_rs::ply::ElementDef::new(::std::string::String::from(#element_name));
+                #(#definitions)*
+                element
+            }
+        }
+    })
+}
+
+/// Implements `PropertyAccess` and `PlyElement`, see the crate documentation for the supported fields and attributes.
+#[proc_macro_derive(PlyElement, attributes(ply))]
+pub fn derive_ply_element(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
@@ -0,0 +1,257 @@
+//! Payload checksums announced in the header.
+//!
+//! A PLY file may carry a checksum of its payload (every byte after `end_header`)
+//! in a comment line of the form `comment checksum <algorithm>:<hex digest>`, e.g.
+//!
+//! ```text
+//! comment checksum sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08
+//! comment checksum crc32:1c291ca3
+//! ```
+//!
+//! With feature `checksum`, the `Parser` verifies it while reading the payload if `set_verify_checksum(true)` has been called.
+
+use std::convert::TryInto;
+use std::error;
+use std::fmt;
+use std::fmt::{ Display, Formatter };
+use std::io;
+#[cfg(feature = "checksum")]
+use std::io::{ BufRead, Read };
+
+#[cfg(feature = "checksum")]
+use sha2::{ Digest, Sha256 };
+
+use crate::ply::Header;
+
+/// Prefix of a comment carrying a payload checksum.
+pub const CHECKSUM_COMMENT_PREFIX: &str = "checksum";
+
+/// Hash algorithms usable for payload checksums.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChecksumAlgorithm {
+    /// CRC-32 (IEEE 802.3), written as 8 hex digits.
+    Crc32,
+    /// SHA-256, written as 64 hex digits.
+    Sha256,
+}
+
+impl Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.write_str(
+            match *self {
+                ChecksumAlgorithm::Crc32 => "crc32",
+                ChecksumAlgorithm::Sha256 => "sha256",
+            }
+        )
+    }
+}
+
+/// A payload checksum as found in, or computed for, a PLY file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Checksum {
+    Crc32(u32),
+    Sha256([u8; 32]),
+}
+
+impl Checksum {
+    /// Parses the content of a comment, e.g. `checksum crc32:1c291ca3`.
+    ///
+    /// Returns `None` if the comment doesn't announce a checksum,
+    /// uses an unknown algorithm or contains a malformed digest.
+    pub fn from_comment(comment: &str) -> Option<Checksum> {
+        let rest = comment.trim().strip_prefix(CHECKSUM_COMMENT_PREFIX)?;
+        if !rest.starts_with([' ', '\t']) {
+            return None;
+        }
+        let (algorithm, digest) = rest.trim_start().split_once(':')?;
+        let bytes = decode_hex(digest.trim())?;
+        if algorithm.eq_ignore_ascii_case("crc32") {
+            let bytes: [u8; 4] = bytes.try_into().ok()?;
+            Some(Checksum::Crc32(u32::from_be_bytes(bytes)))
+        } else if algorithm.eq_ignore_ascii_case("sha256") {
+            Some(Checksum::Sha256(bytes.try_into().ok()?))
+        } else {
+            None
+        }
+    }
+    /// Returns the first checksum announced in the comments of `header`.
+    pub fn from_header(header: &Header) -> Option<Checksum> {
+        header.comments.iter().filter_map(|c| Checksum::from_comment(c)).next()
+    }
+    /// Algorithm that produced this checksum.
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        match *self {
+            Checksum::Crc32(_) => ChecksumAlgorithm::Crc32,
+            Checksum::Sha256(_) => ChecksumAlgorithm::Sha256,
+        }
+    }
+    /// Formats the checksum as comment content, inverse of `from_comment()`.
+    pub fn to_comment(&self) -> String {
+        format!("{} {}", CHECKSUM_COMMENT_PREFIX, self)
+    }
+}
+
+impl Display for Checksum {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}:", self.algorithm())?;
+        match *self {
+            Checksum::Crc32(v) => write!(f, "{:08x}", v),
+            Checksum::Sha256(ref bytes) => {
+                for b in bytes {
+                    write!(f, "{:02x}", b)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Incrementally computes a `Checksum` over a byte stream.
+#[cfg(feature = "checksum")]
+#[derive(Clone)]
+pub enum ChecksumHasher {
+    Crc32(crc32fast::Hasher),
+    Sha256(Sha256),
+}
+
+#[cfg(feature = "checksum")]
+impl ChecksumHasher {
+    /// Creates a hasher for the given algorithm.
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Crc32 => ChecksumHasher::Crc32(crc32fast::Hasher::new()),
+            ChecksumAlgorithm::Sha256 => ChecksumHasher::Sha256(Sha256::new()),
+        }
+    }
+    /// Feeds `bytes` into the hasher.
+    pub fn update(&mut self, bytes: &[u8]) {
+        match *self {
+            ChecksumHasher::Crc32(ref mut h) => h.update(bytes),
+            ChecksumHasher::Sha256(ref mut h) => h.update(bytes),
+        }
+    }
+    /// Consumes the hasher and returns the checksum of all bytes seen.
+    pub fn finish(self) -> Checksum {
+        match self {
+            ChecksumHasher::Crc32(h) => Checksum::Crc32(h.finalize()),
+            ChecksumHasher::Sha256(h) => Checksum::Sha256(h.finalize().into()),
+        }
+    }
+}
+
+/// Describes a payload whose checksum differs from the one announced in its header.
+#[derive(Debug)]
+pub struct ChecksumError {
+    /// Checksum announced in the header.
+    pub expected: Checksum,
+    /// Checksum computed over the payload that has been read.
+    pub actual: Checksum,
+}
+
+impl ChecksumError {
+    /// Create a new error object for a mismatching checksum.
+    pub fn new(expected: Checksum, actual: Checksum) -> Self {
+        ChecksumError {
+            expected,
+            actual,
+        }
+    }
+}
+
+impl Display for ChecksumError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "ChecksumError: payload checksum is {}, but header announced {}", self.actual, self.expected)
+    }
+}
+
+impl error::Error for ChecksumError {}
+
+impl From<ChecksumError> for io::Error {
+    fn from(e: ChecksumError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Wraps a `BufRead` and hashes every byte consumed through it.
+#[cfg(feature = "checksum")]
+pub(crate) struct ChecksumReader<'a, R: BufRead> {
+    inner: &'a mut R,
+    hasher: ChecksumHasher,
+}
+
+#[cfg(feature = "checksum")]
+impl<'a, R: BufRead> ChecksumReader<'a, R> {
+    pub fn new(inner: &'a mut R, algorithm: ChecksumAlgorithm) -> Self {
+        ChecksumReader {
+            inner,
+            hasher: ChecksumHasher::new(algorithm),
+        }
+    }
+    pub fn finish(self) -> Checksum {
+        self.hasher.finish()
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<'a, R: BufRead> Read for ChecksumReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<'a, R: BufRead> BufRead for ChecksumReader<'a, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+    fn consume(&mut self, amt: usize) {
+        // The inner buffer still holds the bytes handed out by `fill_buf`, no read happens here.
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.hasher.update(&buf[..amt]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn from_comment_ok() {
+        assert_eq!(Checksum::from_comment("checksum crc32:1c291ca3"), Some(Checksum::Crc32(0x1c291ca3)));
+        assert_eq!(Checksum::from_comment("checksum\tCRC32:1C291CA3"), Some(Checksum::Crc32(0x1c291ca3)));
+        let c = Checksum::from_comment("checksum sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08").unwrap();
+        assert_eq!(c.algorithm(), ChecksumAlgorithm::Sha256);
+        assert_eq!(Checksum::from_comment(&c.to_comment()), Some(c));
+    }
+    #[test]
+    fn from_comment_none() {
+        assert_eq!(Checksum::from_comment("a normal comment"), None);
+        assert_eq!(Checksum::from_comment("checksums crc32:1c291ca3"), None);
+        assert_eq!(Checksum::from_comment("checksum md5:1c291ca3"), None);
+        assert_eq!(Checksum::from_comment("checksum crc32:1c29"), None);
+        assert_eq!(Checksum::from_comment("checksum crc32:1c291cz3"), None);
+    }
+    #[cfg(feature = "checksum")]
+    #[test]
+    fn hasher_known_values() {
+        let mut h = ChecksumHasher::new(ChecksumAlgorithm::Crc32);
+        h.update(b"test");
+        assert_eq!(h.finish().to_string(), "crc32:d87f7e0c");
+        let mut h = ChecksumHasher::new(ChecksumAlgorithm::Sha256);
+        h.update(b"te");
+        h.update(b"st");
+        assert_eq!(h.finish().to_string(), "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+    }
+}
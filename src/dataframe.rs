@@ -0,0 +1,151 @@
+//! Conversion between elements and `polars` data frames, enabled by the `polars` feature.
+//!
+//! Every property becomes a column of the same name. Scalars map to the equally sized polars type,
+//! lists to `List` columns. Values an element doesn't provide become nulls.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use polars::prelude::{ Column, DataFrame, DataType, IntoColumn, NamedFrom, PolarsError, Series };
+
+use crate::ply::{ ElementDef, Property, PropertyAccess, PropertyType, ScalarType };
+
+fn polars_error(e: PolarsError) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+fn data_type(scalar_type: &ScalarType) -> DataType {
+    match *scalar_type {
+        ScalarType::Char => DataType::Int8,
+        ScalarType::UChar => DataType::UInt8,
+        ScalarType::Short => DataType::Int16,
+        ScalarType::UShort => DataType::UInt16,
+        ScalarType::Int => DataType::Int32,
+        ScalarType::UInt => DataType::UInt32,
+        ScalarType::Float => DataType::Float32,
+        ScalarType::Double => DataType::Float64,
+    }
+}
+
+/// Converts `elements` of type `element_def` into a data frame with one column per property.
+pub fn to_dataframe<E: PropertyAccess>(element_def: &ElementDef, elements: &[E]) -> Result<DataFrame> {
+    let mut columns = Vec::<Column>::new();
+    for (k, p) in &element_def.properties {
+        macro_rules! column {
+            ($get:ident) => {
+                Series::new(k.as_str().into(), elements.iter().map(|e| e.$get(k)).collect::<Vec<_>>())
+            };
+            (list $get:ident) => {
+                Series::new(k.as_str().into(), elements.iter().map(|e| e.$get(k).map(|l| Series::new("".into(), l))).collect::<Vec<_>>())
+            };
+        }
+        let series = match p.data_type {
+            PropertyType::Scalar(ScalarType::Char) => column!(get_char),
+            PropertyType::Scalar(ScalarType::UChar) => column!(get_uchar),
+            PropertyType::Scalar(ScalarType::Short) => column!(get_short),
+            PropertyType::Scalar(ScalarType::UShort) => column!(get_ushort),
+            PropertyType::Scalar(ScalarType::Int) => column!(get_int),
+            PropertyType::Scalar(ScalarType::UInt) => column!(get_uint),
+            PropertyType::Scalar(ScalarType::Float) => column!(get_float),
+            PropertyType::Scalar(ScalarType::Double) => column!(get_double),
+            PropertyType::List(_, ScalarType::Char) => column!(list get_list_char),
+            PropertyType::List(_, ScalarType::UChar) => column!(list get_list_uchar),
+            PropertyType::List(_, ScalarType::Short) => column!(list get_list_short),
+            PropertyType::List(_, ScalarType::UShort) => column!(list get_list_ushort),
+            PropertyType::List(_, ScalarType::Int) => column!(list get_list_int),
+            PropertyType::List(_, ScalarType::UInt) => column!(list get_list_uint),
+            PropertyType::List(_, ScalarType::Float) => column!(list get_list_float),
+            PropertyType::List(_, ScalarType::Double) => column!(list get_list_double),
+        };
+        // Empty or all-null columns don't carry the declared type otherwise.
+        let target = match p.data_type {
+            PropertyType::Scalar(ref s) => data_type(s),
+            PropertyType::List(_, ref s) => DataType::List(Box::new(data_type(s))),
+        };
+        columns.push(series.cast(&target).map_err(polars_error)?.into_column());
+    }
+    DataFrame::new(columns).map_err(polars_error)
+}
+
+/// Converts the rows of `df` into elements of type `element_def`.
+///
+/// Columns are looked up by property name and cast to the declared type, e.g. `i64` columns resulting from a join
+/// can be stored in `int` properties. Other columns are ignored, null values aren't set.
+pub fn from_dataframe<E: PropertyAccess>(element_def: &ElementDef, df: &DataFrame) -> Result<Vec<E>> {
+    let mut elements = (0..df.height()).map(|_| E::new()).collect::<Vec<_>>();
+    for (k, p) in &element_def.properties {
+        let column = df.column(k).map_err(polars_error)?;
+        let target = match p.data_type {
+            PropertyType::Scalar(ref s) => data_type(s),
+            PropertyType::List(_, ref s) => DataType::List(Box::new(data_type(s))),
+        };
+        let series = column.as_materialized_series().cast(&target).map_err(polars_error)?;
+        macro_rules! set {
+            ($as:ident, $variant:ident) => {
+                for (e, v) in elements.iter_mut().zip(series.$as().map_err(polars_error)?.into_iter()) {
+                    if let Some(v) = v {
+                        e.set_property(k.clone(), Property::$variant(v));
+                    }
+                }
+            };
+            (list $as:ident, $variant:ident) => {
+                for (e, l) in elements.iter_mut().zip(series.list().map_err(polars_error)?.into_iter()) {
+                    if let Some(l) = l {
+                        let values = l.$as().map_err(polars_error)?.into_iter().collect::<Option<Vec<_>>>();
+                        match values {
+                            Some(v) => e.set_property(k.clone(), Property::$variant(v)),
+                            None => return Err(io::Error::new(
+                                ErrorKind::InvalidData,
+                                format!("List column `{}` contains null values.", k)
+                            )),
+                        }
+                    }
+                }
+            };
+        }
+        match p.data_type {
+            PropertyType::Scalar(ScalarType::Char) => set!(i8, Char),
+            PropertyType::Scalar(ScalarType::UChar) => set!(u8, UChar),
+            PropertyType::Scalar(ScalarType::Short) => set!(i16, Short),
+            PropertyType::Scalar(ScalarType::UShort) => set!(u16, UShort),
+            PropertyType::Scalar(ScalarType::Int) => set!(i32, Int),
+            PropertyType::Scalar(ScalarType::UInt) => set!(u32, UInt),
+            PropertyType::Scalar(ScalarType::Float) => set!(f32, Float),
+            PropertyType::Scalar(ScalarType::Double) => set!(f64, Double),
+            PropertyType::List(_, ScalarType::Char) => set!(list i8, ListChar),
+            PropertyType::List(_, ScalarType::UChar) => set!(list u8, ListUChar),
+            PropertyType::List(_, ScalarType::Short) => set!(list i16, ListShort),
+            PropertyType::List(_, ScalarType::UShort) => set!(list u16, ListUShort),
+            PropertyType::List(_, ScalarType::Int) => set!(list i32, ListInt),
+            PropertyType::List(_, ScalarType::UInt) => set!(list u32, ListUInt),
+            PropertyType::List(_, ScalarType::Float) => set!(list f32, ListFloat),
+            PropertyType::List(_, ScalarType::Double) => set!(list f64, ListDouble),
+        }
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ Addable, DefaultElement, PropertyDef };
+    #[test]
+    fn dataframe_roundtrip_ok() {
+        let mut def = ElementDef::new("face".to_string());
+        def.properties.add(PropertyDef::new("label".to_string(), PropertyType::Scalar(ScalarType::UChar)));
+        def.properties.add(PropertyDef::new("vertex_index".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int)));
+        let faces = [(1, vec![0, 1, 2]), (2, vec![2, 3, 4, 5])].iter().map(|(l, v)| {
+            let mut f = DefaultElement::new();
+            f.insert("label".to_string(), Property::UChar(*l));
+            f.insert("vertex_index".to_string(), Property::ListInt(v.clone()));
+            f
+        }).collect::<Vec<_>>();
+        let df = to_dataframe(&def, &faces).unwrap();
+        assert_eq!(df.shape(), (2, 2));
+        assert_eq!(df.column("label").unwrap().dtype(), &DataType::UInt8);
+        let back = from_dataframe::<DefaultElement>(&def, &df).unwrap();
+        assert_eq!(back, faces);
+        def.properties.add(PropertyDef::new("missing".to_string(), PropertyType::Scalar(ScalarType::Int)));
+        assert!(from_dataframe::<DefaultElement>(&def, &df).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! Editing PLY files in place, rewriting only what changed on save.
+
+use std::collections::{ BTreeMap, BTreeSet };
+use std::fs::{ File, OpenOptions };
+use std::io;
+use std::io::{ BufReader, BufWriter, ErrorKind, Result, Seek, SeekFrom, Write };
+use std::path::{ Path, PathBuf };
+
+use crate::checksum::Checksum;
+use crate::parser::Parser;
+use crate::ply::{ DefaultElement, Encoding, Header, Ply, Property, PropertyType, ScalarType };
+use crate::util::{ add_offset, element_byte_len, LocationTracker };
+use crate::writer::Writer;
+
+/// What `EditSession::save()` had to write.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SaveKind {
+    /// Nothing was modified.
+    Unchanged,
+    /// Modified rows have been overwritten in place, the rest of the file is untouched.
+    Patched {
+        rows: usize,
+    },
+    /// The header has been rewritten and the payload bytes copied, modified rows patched.
+    HeaderRewritten {
+        rows: usize,
+    },
+    /// The whole file has been serialized again, because the layout changed or rows can't be patched.
+    Rewritten,
+}
+
+fn scalar_matches(property: &Property, scalar_type: &ScalarType) -> bool {
+    matches!((property, scalar_type),
+        (Property::Char(_), ScalarType::Char) | (Property::UChar(_), ScalarType::UChar) |
+        (Property::Short(_), ScalarType::Short) | (Property::UShort(_), ScalarType::UShort) |
+        (Property::Int(_), ScalarType::Int) | (Property::UInt(_), ScalarType::UInt) |
+        (Property::Float(_), ScalarType::Float) | (Property::Double(_), ScalarType::Double))
+}
+
+fn list_matches(property: &Property, scalar_type: &ScalarType) -> bool {
+    matches!((property, scalar_type),
+        (Property::ListChar(_), ScalarType::Char) | (Property::ListUChar(_), ScalarType::UChar) |
+        (Property::ListShort(_), ScalarType::Short) | (Property::ListUShort(_), ScalarType::UShort) |
+        (Property::ListInt(_), ScalarType::Int) | (Property::ListUInt(_), ScalarType::UInt) |
+        (Property::ListFloat(_), ScalarType::Float) | (Property::ListDouble(_), ScalarType::Double))
+}
+
+/// A PLY file opened for editing, tracking which rows were modified.
+///
+/// On `save()`, binary rows of fixed size are overwritten in place. A changed header is rewritten
+/// with the payload bytes copied unchanged. Any change of the layout, i.e. elements, properties,
+/// counts or encoding, as well as modified ascii rows or rows with lists, need a full rewrite.
+/// Stale checksum comments are removed when the payload changes.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ply_rs::*;
+/// use ply_rs::edit::EditSession;
+/// let mut session = EditSession::open("scan.ply").unwrap();
+/// session.set_property("vertex", 42, "intensity", ply::Property::Float(0.5)).unwrap();
+/// session.save().unwrap();
+/// ```
+pub struct EditSession {
+    path: PathBuf,
+    ply: Ply<DefaultElement>,
+    /// Header as stored in the file.
+    saved_header: Header,
+    /// Byte offset of each element in the file.
+    offsets: Vec<u64>,
+    /// Modified rows by element name.
+    dirty_rows: BTreeMap<String, BTreeSet<usize>>,
+    /// Set when the payload was handed out mutably, changes can't be tracked.
+    dirty_all: bool,
+}
+
+impl EditSession {
+    /// Reads the file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+        let parser = Parser::<DefaultElement>::new();
+        let header = parser.read_header(&mut reader)?;
+        let mut location = LocationTracker::new();
+        let mut ply = Ply::new();
+        let mut offsets = Vec::with_capacity(header.elements.len());
+        for element_def in header.elements.values() {
+            offsets.push(reader.stream_position()?);
+            let rows = parser.__read_element_list(&mut reader, &mut location, element_def, &header.encoding)?;
+            ply.payload.insert(element_def.name.clone(), rows);
+        }
+        ply.header = header.clone();
+        Ok(EditSession { path, ply, saved_header: header, offsets, dirty_rows: BTreeMap::new(), dirty_all: false })
+    }
+    /// The current content.
+    pub fn ply(&self) -> &Ply<DefaultElement> {
+        &self.ply
+    }
+    /// The header, changes are detected on save.
+    pub fn header_mut(&mut self) -> &mut Header {
+        &mut self.ply.header
+    }
+    /// The whole content, the next save rewrites the entire file since changes can't be tracked.
+    pub fn ply_mut(&mut self) -> &mut Ply<DefaultElement> {
+        self.dirty_all = true;
+        &mut self.ply
+    }
+    /// Sets `property` of row `index` of `element`.
+    ///
+    /// The property has to be declared and `value` of its declared type.
+    pub fn set_property(&mut self, element: &str, index: usize, property: &str, value: Property) -> Result<()> {
+        let def = match self.ply.header.elements.get(element) {
+            Some(d) => d,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` declared in header.", element))),
+        };
+        let matches = match def.properties.get(property).map(|p| &p.data_type) {
+            Some(PropertyType::Scalar(ref s)) => scalar_matches(&value, s),
+            Some(PropertyType::List(_, ref s)) => list_matches(&value, s),
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, format!("Element `{}` has no property `{}`.", element, property))),
+        };
+        if !matches {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Value {:?} doesn't match the type of property `{}` of element `{}`.", value, property, element)
+            ));
+        }
+        let row = match self.ply.payload.get_mut(element).and_then(|l| l.get_mut(index)) {
+            Some(r) => r,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, format!("Element `{}` has no row {}.", element, index))),
+        };
+        match row.get_mut(property) {
+            Some(v) => *v = value,
+            None => { row.insert(property.to_string(), value); },
+        }
+        self.dirty_rows.entry(element.to_string()).or_default().insert(index);
+        Ok(())
+    }
+    /// Whether there are unsaved changes.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_all || !self.dirty_rows.is_empty() || self.ply.header != self.saved_header
+    }
+    /// Writes the changes to the file, as little as possible.
+    pub fn save(&mut self) -> Result<SaveKind> {
+        if !self.is_dirty() {
+            return Ok(SaveKind::Unchanged);
+        }
+        if self.dirty_all || !self.dirty_rows.is_empty() {
+            self.ply.header.comments.retain(|c| Checksum::from_comment(c).is_none());
+        }
+        let (old, new) = (&self.saved_header, &self.ply.header);
+        let same_layout = !self.dirty_all && old.elements == new.elements && old.encoding == new.encoding && old.version == new.version;
+        let patchable = new.encoding != Encoding::Ascii && self.dirty_rows.keys().all(|e| new.elements[e].fixed_stride().is_some());
+        let kind = if !same_layout || !patchable {
+            self.rewrite()?;
+            SaveKind::Rewritten
+        } else {
+            let rows = self.dirty_rows.values().map(|r| r.len()).sum();
+            if *old == *new {
+                self.patch_rows()?;
+                SaveKind::Patched { rows }
+            } else {
+                self.rewrite_header()?;
+                self.patch_rows()?;
+                SaveKind::HeaderRewritten { rows }
+            }
+        };
+        self.saved_header = self.ply.header.clone();
+        self.dirty_rows.clear();
+        self.dirty_all = false;
+        Ok(kind)
+    }
+    /// Path of the temporary file written next to the edited one.
+    fn temporary_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".tmp");
+        self.path.with_file_name(name)
+    }
+    fn rewrite(&mut self) -> Result<()> {
+        let writer = Writer::<DefaultElement>::new();
+        let temporary = self.temporary_path();
+        if let Err(e) = self.ply.make_consistent() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("The edited ply isn't consistent: {:?}", e)));
+        }
+        let mut out = BufWriter::new(File::create(&temporary)?);
+        let header_len = writer.write_header(&mut out, &self.ply.header)?;
+        self.offsets.clear();
+        let mut offset = header_len;
+        for element_def in self.ply.header.elements.values() {
+            self.offsets.push(offset);
+            offset += writer.write_payload_of_element(&mut out, &self.ply.payload[&element_def.name], element_def, &self.ply.header)?;
+        }
+        out.flush()?;
+        drop(out);
+        std::fs::rename(temporary, &self.path)
+    }
+    /// Writes the new header followed by the unchanged payload bytes of the file.
+    fn rewrite_header(&mut self) -> Result<()> {
+        let temporary = self.temporary_path();
+        let mut out = BufWriter::new(File::create(&temporary)?);
+        let header_len = Writer::<DefaultElement>::new().write_header(&mut out, &self.ply.header)?;
+        let mut input = File::open(&self.path)?;
+        let payload_start = self.offsets.first().copied().unwrap_or_else(|| input.metadata().map_or(0, |m| m.len()));
+        input.seek(SeekFrom::Start(payload_start))?;
+        io::copy(&mut input, &mut out)?;
+        out.flush()?;
+        drop(out);
+        std::fs::rename(temporary, &self.path)?;
+        for o in self.offsets.iter_mut() {
+            *o = *o - payload_start + header_len;
+        }
+        Ok(())
+    }
+    /// Overwrites the modified rows in place, all of them have a fixed size.
+    fn patch_rows(&mut self) -> Result<()> {
+        let writer = Writer::<DefaultElement>::new();
+        let mut file = OpenOptions::new().write(true).open(&self.path)?;
+        let mut buf = Vec::new();
+        for ((name, element_def), &offset) in self.ply.header.elements.iter().zip(&self.offsets) {
+            let rows = match self.dirty_rows.get(name) {
+                Some(r) => r,
+                None => continue,
+            };
+            for &row in rows {
+                buf.clear();
+                let element = &self.ply.payload[name][row];
+                writer.write_element(&mut buf, element, element_def, &self.ply.header.encoding)?;
+                let position = add_offset(offset, element_byte_len(element_def, row)?.unwrap_or(0))?;
+                file.seek(SeekFrom::Start(position))?;
+                file.write_all(&buf)?;
+            }
+        }
+        file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn read(path: &Path) -> Ply<DefaultElement> {
+        Parser::<DefaultElement>::new().read_ply(&mut File::open(path).unwrap()).unwrap()
+    }
+    #[test]
+    fn edit_session_save_kinds() {
+        let path = std::env::temp_dir().join(format!("ply_rs_edit_{}.ply", std::process::id()));
+        let mut ply = read(Path::new("example_plys/house_2_ok_ascii.ply"));
+        ply.header.encoding = Encoding::BinaryLittleEndian;
+        ply.header.comments = vec!["checksum crc32:00000000".to_string()];
+        Writer::new().write_ply(&mut File::create(&path).unwrap(), &mut ply).unwrap();
+
+        let mut session = EditSession::open(&path).unwrap();
+        assert_eq!(session.save().unwrap(), SaveKind::Unchanged);
+        assert!(session.set_property("vertex", 1, "x", Property::Double(3.0)).is_err());
+        assert!(session.set_property("vertex", 9, "x", Property::Float(3.0)).is_err());
+        session.set_property("vertex", 1, "x", Property::Float(3.0)).unwrap();
+        assert_eq!(session.save().unwrap(), SaveKind::HeaderRewritten { rows: 1 });
+        assert!(read(&path).header.comments.is_empty());
+
+        session.set_property("vertex", 4, "z", Property::Float(-2.0)).unwrap();
+        assert_eq!(session.save().unwrap(), SaveKind::Patched { rows: 1 });
+        session.header_mut().comments.push("edited".to_string());
+        session.set_property("vertex", 0, "y", Property::Float(7.0)).unwrap();
+        assert_eq!(session.save().unwrap(), SaveKind::HeaderRewritten { rows: 1 });
+        session.set_property("face", 0, "vertex_indices", Property::ListInt(vec![2, 1, 0])).unwrap();
+        assert_eq!(session.save().unwrap(), SaveKind::Rewritten);
+        session.set_property("vertex", 2, "y", Property::Float(8.0)).unwrap();
+        assert_eq!(session.save().unwrap(), SaveKind::Patched { rows: 1 });
+
+        let saved = read(&path);
+        assert_eq!(saved.header.comments, vec!["edited"]);
+        assert_eq!(saved.payload, session.ply().payload);
+        assert_eq!(saved.payload["vertex"][1]["x"], Property::Float(3.0));
+        assert_eq!(saved.payload["vertex"][4]["z"], Property::Float(-2.0));
+        assert_eq!(saved.payload["face"][0]["vertex_indices"], Property::ListInt(vec![2, 1, 0]));
+        std::fs::remove_file(path).unwrap();
+    }
+}
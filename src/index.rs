@@ -0,0 +1,366 @@
+//! Sidecar index files (`<file>.ply.idx`) for random access to large PLY files.
+//!
+//! An index records, per element, the byte range of its rows within the payload,
+//! the byte offset of every `chunk_rows`-th row and, for elements with `x`, `y` and `z`,
+//! bounding boxes of the whole element and of every chunk.
+//! Build it once with `PlyIndex::build()`, store it next to the file and
+//! use `Parser::read_rows_indexed()` to read any row range without scanning the payload.
+//!
+//! All offsets are relative to the start of the payload, the first byte after `end_header\n`.
+
+use std::io;
+use std::io::{ BufRead, ErrorKind, Read, Result, Seek, SeekFrom, Write };
+use std::ops::Range;
+use std::path::{ Path, PathBuf };
+
+use byteorder::{ LittleEndian, ReadBytesExt, WriteBytesExt };
+
+use crate::parser::{ Aggregate, Parser };
+use crate::ply::{ ElementDef, Header, PropertyAccess };
+use crate::util::{ get_scalar_as_f64, LocationTracker };
+
+/// Extension appended to the PLY file name, `mesh.ply` is indexed by `mesh.ply.idx`.
+pub const INDEX_EXTENSION: &str = "idx";
+/// Default number of rows per chunk.
+pub const DEFAULT_CHUNK_ROWS: usize = 65536;
+
+const MAGIC: &[u8; 8] = b"PLYIDX1\n";
+
+/// Axis aligned bounding box as `(min, max)` corners.
+pub type Bounds = ([f64; 3], [f64; 3]);
+
+/// Index of a single element type.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ElementIndex {
+    /// Name of the element.
+    pub name: String,
+    /// Number of rows.
+    pub rows: usize,
+    /// Number of rows per chunk, every chunk but the last one is complete.
+    pub chunk_rows: usize,
+    /// Byte range of all rows, relative to the payload start.
+    pub range: Range<u64>,
+    /// Byte offset of the first row of every chunk, relative to the payload start.
+    pub chunk_offsets: Vec<u64>,
+    /// Bounds of all rows, `None` if the element has no position or no rows.
+    pub bounds: Option<Bounds>,
+    /// Bounds of every chunk, empty if the element has no position.
+    pub chunk_bounds: Vec<Option<Bounds>>,
+}
+
+impl ElementIndex {
+    /// Row ranges of all chunks whose bounds intersect `bounds`.
+    pub fn chunks_intersecting(&self, bounds: &Bounds) -> Vec<Range<usize>> {
+        self.chunk_bounds.iter().enumerate().filter(|(_, b)| match **b {
+            Some((min, max)) => (0..3).all(|i| min[i] <= bounds.1[i] && bounds.0[i] <= max[i]),
+            None => false,
+        }).map(|(c, _)| c * self.chunk_rows..((c + 1) * self.chunk_rows).min(self.rows)).collect()
+    }
+}
+
+/// Index of a complete PLY file, see the module documentation.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlyIndex {
+    /// Indexes of all element types, in payload order.
+    pub elements: Vec<ElementIndex>,
+}
+
+/// Records offsets and bounds while the payload is streamed.
+struct IndexBuilder {
+    chunk_rows: usize,
+    /// Start of the current row.
+    row_start: u64,
+    /// End of the current row.
+    position: u64,
+    elements: Vec<ElementIndex>,
+}
+
+fn extend(bounds: &mut Option<Bounds>, p: [f64; 3]) {
+    let (mut min, mut max) = bounds.unwrap_or((p, p));
+    for i in 0..3 {
+        min[i] = min[i].min(p[i]);
+        max[i] = max[i].max(p[i]);
+    }
+    *bounds = Some((min, max));
+}
+
+impl<E: PropertyAccess> Aggregate<E> for IndexBuilder {
+    fn bytes(&mut self, bytes: &[u8]) {
+        // Called before `element()` with the bytes of the same row.
+        self.row_start = self.position;
+        self.position += bytes.len() as u64;
+    }
+    fn element(&mut self, element_def: &ElementDef, element: &E) {
+        if self.elements.last().map(|e| e.name != element_def.name).unwrap_or(true) {
+            self.elements.push(ElementIndex {
+                name: element_def.name.clone(),
+                rows: 0,
+                chunk_rows: self.chunk_rows,
+                range: self.row_start..self.row_start,
+                chunk_offsets: Vec::new(),
+                bounds: None,
+                chunk_bounds: Vec::new(),
+            });
+        }
+        let index = self.elements.last_mut().unwrap();
+        let row_start = index.range.end;
+        let has_position = ["x", "y", "z"].iter().all(|a| element_def.properties.contains_key(*a));
+        if index.rows % self.chunk_rows == 0 {
+            index.chunk_offsets.push(row_start);
+            if has_position {
+                index.chunk_bounds.push(None);
+            }
+        }
+        if has_position {
+            let mut p = [0.0; 3];
+            let mut valid = true;
+            for (p, a) in p.iter_mut().zip(["x", "y", "z"].iter()) {
+                match get_scalar_as_f64(element, &element_def.properties[*a]) {
+                    Some(v) if !v.is_nan() => *p = v,
+                    _ => valid = false,
+                }
+            }
+            if valid {
+                extend(&mut index.bounds, p);
+                extend(index.chunk_bounds.last_mut().unwrap(), p);
+            }
+        }
+        index.rows += 1;
+        index.range.end = self.position;
+    }
+}
+
+fn write_bounds<W: Write>(out: &mut W, bounds: &Option<Bounds>) -> Result<()> {
+    match *bounds {
+        Some((min, max)) => {
+            out.write_u8(1)?;
+            for v in min.iter().chain(max.iter()) {
+                out.write_f64::<LittleEndian>(*v)?;
+            }
+        },
+        None => out.write_u8(0)?,
+    }
+    Ok(())
+}
+
+fn read_bounds<R: Read>(input: &mut R) -> Result<Option<Bounds>> {
+    if input.read_u8()? == 0 {
+        return Ok(None);
+    }
+    let mut b = ([0.0; 3], [0.0; 3]);
+    for v in b.0.iter_mut().chain(b.1.iter_mut()) {
+        *v = input.read_f64::<LittleEndian>()?;
+    }
+    Ok(Some(b))
+}
+
+fn read_len<R: Read>(input: &mut R) -> Result<usize> {
+    Ok(input.read_u64::<LittleEndian>()? as usize)
+}
+
+impl PlyIndex {
+    /// Reads a complete PLY file and indexes its payload, with chunks of `chunk_rows` rows.
+    ///
+    /// Elements aren't stored, memory use only depends on the number of chunks.
+    /// Elements without rows are indexed with an empty range.
+    pub fn build<T: Read, E: PropertyAccess>(parser: &Parser<E>, source: &mut T, chunk_rows: usize) -> Result<(Header, PlyIndex)> {
+        if chunk_rows == 0 {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Chunks need at least one row."));
+        }
+        let mut builder = IndexBuilder { chunk_rows, row_start: 0, position: 0, elements: Vec::new() };
+        let header = parser.read_aggregates(source, &mut [&mut builder])?;
+        let mut elements = Vec::new();
+        let mut indexed = builder.elements.into_iter().peekable();
+        let mut position = 0;
+        for e in header.elements.values() {
+            match indexed.peek() {
+                Some(i) if i.name == e.name => {
+                    let i = indexed.next().unwrap();
+                    position = i.range.end;
+                    elements.push(i);
+                },
+                _ => elements.push(ElementIndex {
+                    name: e.name.clone(),
+                    rows: 0,
+                    chunk_rows,
+                    range: position..position,
+                    chunk_offsets: Vec::new(),
+                    bounds: None,
+                    chunk_bounds: Vec::new(),
+                }),
+            }
+        }
+        Ok((header, PlyIndex { elements }))
+    }
+    /// Path of the sidecar index of the PLY file at `ply_path`.
+    pub fn sidecar_path<P: AsRef<Path>>(ply_path: P) -> PathBuf {
+        let mut path = ply_path.as_ref().as_os_str().to_owned();
+        path.push(".");
+        path.push(INDEX_EXTENSION);
+        PathBuf::from(path)
+    }
+    /// True if the element names and counts of `header` match the index.
+    ///
+    /// A mismatch means the index is stale and has to be rebuilt.
+    pub fn is_valid_for(&self, header: &Header) -> bool {
+        self.elements.len() == header.elements.len()
+            && self.elements.iter().zip(header.elements.values()).all(|(i, e)| i.name == e.name && i.rows == e.count)
+    }
+    /// Index of the element named `name`.
+    pub fn element(&self, name: &str) -> Option<&ElementIndex> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+    /// Writes the index in its compact binary format.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_u64::<LittleEndian>(self.elements.len() as u64)?;
+        for e in &self.elements {
+            out.write_u64::<LittleEndian>(e.name.len() as u64)?;
+            out.write_all(e.name.as_bytes())?;
+            out.write_u64::<LittleEndian>(e.rows as u64)?;
+            out.write_u64::<LittleEndian>(e.chunk_rows as u64)?;
+            out.write_u64::<LittleEndian>(e.range.start)?;
+            out.write_u64::<LittleEndian>(e.range.end)?;
+            write_bounds(out, &e.bounds)?;
+            out.write_u64::<LittleEndian>(e.chunk_offsets.len() as u64)?;
+            for o in &e.chunk_offsets {
+                out.write_u64::<LittleEndian>(*o)?;
+            }
+            out.write_u64::<LittleEndian>(e.chunk_bounds.len() as u64)?;
+            for b in &e.chunk_bounds {
+                write_bounds(out, b)?;
+            }
+        }
+        Ok(())
+    }
+    /// Reads an index written by `write_to()`.
+    pub fn read_from<R: Read>(input: &mut R) -> Result<PlyIndex> {
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Not a PLY index file."));
+        }
+        let count = read_len(input)?;
+        let mut elements = Vec::new();
+        for _ in 0..count {
+            let len = read_len(input)?;
+            let mut name = Vec::new();
+            input.by_ref().take(len as u64).read_to_end(&mut name)?;
+            if name.len() != len {
+                return Err(io::Error::new(ErrorKind::UnexpectedEof, "Truncated PLY index file."));
+            }
+            let name = match String::from_utf8(name) {
+                Ok(n) => n,
+                Err(e) => return Err(io::Error::new(ErrorKind::InvalidData, e)),
+            };
+            let rows = read_len(input)?;
+            let chunk_rows = read_len(input)?;
+            let range = input.read_u64::<LittleEndian>()?..input.read_u64::<LittleEndian>()?;
+            let bounds = read_bounds(input)?;
+            let chunk_offsets = (0..read_len(input)?).map(|_| input.read_u64::<LittleEndian>()).collect::<Result<_>>()?;
+            let chunk_bounds = (0..read_len(input)?).map(|_| read_bounds(input)).collect::<Result<_>>()?;
+            elements.push(ElementIndex { name, rows, chunk_rows, range, chunk_offsets, bounds, chunk_bounds });
+        }
+        Ok(PlyIndex { elements })
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads the rows `rows` of `element` using `index` to seek close to the first row.
+    ///
+    /// `payload_start` is the absolute position of the payload in `reader`,
+    /// e.g. `reader.stream_position()` right after `read_header()`.
+    /// At most `chunk_rows - 1` rows are skipped before the first requested one.
+    pub fn read_rows_indexed<T: BufRead + Seek>(&self, reader: &mut T, header: &Header, index: &PlyIndex, payload_start: u64, element: &str, rows: Range<usize>) -> Result<Vec<E>> {
+        let element_def = match header.elements.get(element) {
+            Some(e) => e,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` declared in header.", element))),
+        };
+        let element_index = match index.element(element) {
+            Some(e) if e.rows == element_def.count => e,
+            _ => return Err(io::Error::new(ErrorKind::InvalidInput, format!("Index doesn't match element `{}`.", element))),
+        };
+        if rows.start > rows.end || rows.end > element_def.count {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Rows {:?} out of range for element `{}` with {} rows.", rows, element, element_def.count)
+            ));
+        }
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+        let chunk = rows.start / element_index.chunk_rows;
+        reader.seek(SeekFrom::Start(payload_start + element_index.chunk_offsets[chunk]))?;
+        let mut location = LocationTracker::new();
+        for _ in chunk * element_index.chunk_rows..rows.start {
+            self.__skip_row(reader, &mut location, element_def, &header.encoding)?;
+        }
+        let mut selection = element_def.clone();
+        selection.count = rows.len();
+        self.__read_element_list(reader, &mut location, &selection, &header.encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{ BufReader, Cursor };
+    use crate::ply::{ DefaultElement, Property };
+    fn file(encoding: &str) -> Vec<u8> {
+        let mut buf = format!("ply\nformat {} 1.0\nelement empty 0\nproperty int a\nelement vertex 10\nproperty float x\n\
+            property float y\nproperty float z\nelement face 2\nproperty list uchar int vertex_index\nend_header\n", encoding).into_bytes();
+        for i in 0..10 {
+            let v = [i as f32, -(i as f32), 1.0];
+            if encoding == "ascii" {
+                buf.extend_from_slice(format!("{} {} {}\n", v[0], v[1], v[2]).as_bytes());
+            } else {
+                for c in &v {
+                    buf.extend_from_slice(&c.to_le_bytes());
+                }
+            }
+        }
+        if encoding == "ascii" {
+            buf.extend_from_slice(b"3 0 1 2\n4 2 3 4 5\n");
+        } else {
+            buf.push(3);
+            buf.extend((0..3i32).flat_map(|i| i.to_le_bytes()));
+            buf.push(4);
+            buf.extend((2..6i32).flat_map(|i| i.to_le_bytes()));
+        }
+        buf
+    }
+    #[test]
+    fn index_roundtrip_ok() {
+        let p = Parser::<DefaultElement>::new();
+        for encoding in &["ascii", "binary_little_endian"] {
+            let buf = file(encoding);
+            let (header, index) = PlyIndex::build(&p, &mut &buf[..], 4).unwrap();
+            assert!(index.is_valid_for(&header));
+            let vertex = index.element("vertex").unwrap();
+            assert_eq!(vertex.chunk_offsets.len(), 3);
+            assert_eq!(vertex.chunk_offsets[0], 0);
+            assert_eq!(vertex.bounds, Some(([0.0, -9.0, 1.0], [9.0, 0.0, 1.0])));
+            assert_eq!(vertex.chunks_intersecting(&([4.5, -10.0, 0.0], [5.0, 10.0, 2.0])), vec![4..8]);
+            assert_eq!(index.element("face").unwrap().chunk_bounds, vec![]);
+
+            let mut stored = Vec::new();
+            index.write_to(&mut stored).unwrap();
+            let index = PlyIndex::read_from(&mut &stored[..]).unwrap();
+
+            let mut reader = BufReader::new(Cursor::new(buf));
+            let header = p.read_header(&mut reader).unwrap();
+            let payload_start = reader.stream_position().unwrap();
+            let rows = p.read_rows_indexed(&mut reader, &header, &index, payload_start, "vertex", 5..7).unwrap();
+            assert_eq!(rows[0]["x"], Property::Float(5.0));
+            assert_eq!(rows[1]["y"], Property::Float(-6.0));
+            let faces = p.read_rows_indexed(&mut reader, &header, &index, payload_start, "face", 1..2).unwrap();
+            assert_eq!(faces[0]["vertex_index"], Property::ListInt(vec![2, 3, 4, 5]));
+            assert!(p.read_rows_indexed(&mut reader, &header, &index, payload_start, "vertex", 5..11).is_err());
+        }
+    }
+    #[test]
+    fn sidecar_path_ok() {
+        assert_eq!(PlyIndex::sidecar_path("data/mesh.ply"), PathBuf::from("data/mesh.ply.idx"));
+        assert!(PlyIndex::read_from(&mut &b"PLYIDX0\n"[..]).is_err());
+    }
+}
@@ -29,8 +29,25 @@
 extern crate linked_hash_map;
 extern crate byteorder;
 extern crate peg;
+pub mod checksum;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+pub mod edit;
+pub mod index;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 pub mod parser;
 pub mod ply;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "rerun")]
+pub mod rerun_log;
+#[cfg(feature = "serde")]
+pub mod serialize;
+#[cfg(feature = "spatial")]
+pub mod spatial;
+pub mod stream;
+pub mod transcode;
 pub mod writer;
 
 mod util;
@@ -0,0 +1,235 @@
+//! Zero-copy access to binary PLY files mapped into memory.
+//!
+//! Values are read straight from the mapped pages when they are accessed, nothing is decoded up front.
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+use std::io::{ Cursor, ErrorKind, Result };
+use std::marker::PhantomData;
+use std::path::Path;
+
+use byteorder::{ BigEndian, ByteOrder, LittleEndian };
+use memmap2::Mmap;
+
+use crate::parser::Parser;
+use crate::ply::{ ElementDef, Encoding, Header, PropertyAccess, PropertyType, ScalarType, ScalarValue };
+use crate::util::{ add_offset, element_byte_len, LocationTracker };
+
+/// A binary PLY file mapped into memory, created by `Parser::read_mmap()`.
+pub struct MappedPly {
+    map: Mmap,
+    header: Header,
+    /// Byte offset of each element in the file.
+    offsets: Vec<u64>,
+}
+
+/// Values of one scalar property read in place from a `MappedPly`, created by `MappedPly::column()`.
+#[derive(Debug, Clone, Copy)]
+pub struct MappedColumn<'a, T: ScalarValue> {
+    /// Rows of the element, starting at the property.
+    bytes: &'a [u8],
+    stride: usize,
+    len: usize,
+    scalar_type: &'a ScalarType,
+    encoding: Encoding,
+    value: PhantomData<T>,
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Maps the binary PLY file at `path` into memory and reads its header.
+    ///
+    /// The payload is accessed in place, see `MappedPly`. Ascii files aren't supported.
+    /// The file mustn't be modified while it is mapped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ply_rs::*;
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let ply = p.read_mmap("scan.ply").unwrap();
+    /// let x = ply.column::<f32>("vertex", "x").unwrap();
+    /// let mean = x.iter().map(f64::from).sum::<f64>() / x.len() as f64;
+    /// ```
+    pub fn read_mmap<P: AsRef<Path>>(&self, path: P) -> Result<MappedPly> {
+        let file = File::open(path)?;
+        // Safety: the mapping is read only, modifying the file while it is mapped is documented as not allowed.
+        let map = unsafe { Mmap::map(&file)? };
+        let mut reader = Cursor::new(&map[..]);
+        let header = self.read_header(&mut reader)?;
+        if header.encoding == Encoding::Ascii {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Memory mapped reading requires a binary encoding."));
+        }
+        let offsets = self.element_offsets(&mut reader, &header, None)?;
+        Ok(MappedPly { map, header, offsets })
+    }
+}
+
+impl MappedPly {
+    /// Header of the file.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+    fn element(&self, element: &str) -> Result<(&ElementDef, usize)> {
+        match self.header.elements.values().zip(&self.offsets).find(|(e, _)| e.name == element) {
+            Some((e, &offset)) => Ok((e, offset as usize)),
+            None => Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` declared in header.", element))),
+        }
+    }
+    /// Raw bytes of all rows of `element`, which mustn't have list properties.
+    pub fn element_bytes(&self, element: &str) -> Result<&[u8]> {
+        let (element_def, offset) = self.element(element)?;
+        let len = match element_byte_len(element_def, element_def.count)? {
+            Some(len) => len,
+            None => return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` has list properties, its rows don't have a fixed size.", element)
+            )),
+        };
+        let end = add_offset(offset as u64, len)?;
+        match usize::try_from(end).ok().and_then(|end| self.map.get(offset..end)) {
+            Some(bytes) => Ok(bytes),
+            None => Err(io::Error::new(ErrorKind::UnexpectedEof, format!("Missing `{}` elements.", element))),
+        }
+    }
+    /// Values of the scalar `property` of all `element` rows, decoded in place on access.
+    ///
+    /// `element` mustn't have list properties. `T` has to hold all values of the declared type exactly,
+    /// e.g. `f64` for a `float` property, but not `f32` for an `int` one.
+    pub fn column<T: ScalarValue>(&self, element: &str, property: &str) -> Result<MappedColumn<'_, T>> {
+        let bytes = self.element_bytes(element)?;
+        let element_def = &self.header.elements[element];
+        let mut start = 0;
+        for p in element_def.properties.values() {
+            match p.data_type {
+                PropertyType::Scalar(ref scalar_type) if p.name == property && !widens(scalar_type, &T::SCALAR_TYPE) => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Property `{}` of element `{}` of type {:?} doesn't fit into type {:?}.", property, element, scalar_type, T::SCALAR_TYPE)
+                )),
+                PropertyType::Scalar(ref scalar_type) if p.name == property => return Ok(MappedColumn {
+                    bytes: bytes.get(start..).unwrap_or(&[]),
+                    stride: element_def.fixed_stride().unwrap_or(0),
+                    len: element_def.count,
+                    scalar_type,
+                    encoding: self.header.encoding,
+                    value: PhantomData,
+                }),
+                PropertyType::Scalar(ref scalar_type) => start += scalar_type.byte_size(),
+                PropertyType::List(_, _) => (),
+            }
+        }
+        Err(io::Error::new(ErrorKind::InvalidInput, format!("Element `{}` has no property `{}`.", element, property)))
+    }
+    /// Decodes all rows of `element`, lists included, from the mapped bytes.
+    pub fn read_element<E: PropertyAccess>(&self, parser: &Parser<E>, element: &str) -> Result<Vec<E>> {
+        let (element_def, offset) = self.element(element)?;
+        let mut reader = Cursor::new(&self.map[..]);
+        reader.set_position(offset as u64);
+        parser.__read_element_list(&mut reader, &mut LocationTracker::new(), element_def, &self.header.encoding)
+    }
+}
+
+impl<'a, T: ScalarValue> MappedColumn<'a, T> {
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Value of row `index` decoded from the mapped bytes, panics if out of bounds.
+    ///
+    /// See `as_slice()` to access the values without decoding them.
+    pub fn get(&self, index: usize) -> T {
+        assert!(index < self.len, "Row {} out of bounds of {} rows.", index, self.len);
+        let bytes = &self.bytes[index * self.stride..];
+        match self.encoding {
+            Encoding::BinaryBigEndian => T::from_f64(decode::<BigEndian>(bytes, self.scalar_type)),
+            _ => T::from_f64(decode::<LittleEndian>(bytes, self.scalar_type)),
+        }
+    }
+    /// Iterates over the values of all rows.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.len).map(move |i| self.get(i))
+    }
+    /// The values as a slice without any copy.
+    ///
+    /// Only possible if the property is the only one of its element, `T` is its declared type,
+    /// the file has the byte order of the host and the values are aligned in memory. `None` otherwise.
+    pub fn as_slice(&self) -> Option<&'a [T]> where T: bytemuck::Pod {
+        let host = if cfg!(target_endian = "big") { Encoding::BinaryBigEndian } else { Encoding::BinaryLittleEndian };
+        if *self.scalar_type != T::SCALAR_TYPE || self.encoding != host || self.stride != std::mem::size_of::<T>() {
+            return None;
+        }
+        bytemuck::try_cast_slice(&self.bytes[..self.len * self.stride]).ok()
+    }
+}
+
+/// Whether all values of `from` are exactly representable in `to`.
+fn widens(from: &ScalarType, to: &ScalarType) -> bool {
+    let (min, max) = from.range();
+    let (to_min, to_max) = to.range();
+    match (matches!(*from, ScalarType::Float | ScalarType::Double), to) {
+        (true, ScalarType::Double) => true,
+        (true, _) => from == to,
+        (false, ScalarType::Float) => from.byte_size() <= 2,
+        (false, _) => to_min <= min && max <= to_max,
+    }
+}
+
+/// Decodes the value of `scalar_type` at the start of `bytes`, exactly since `f64` holds all PLY scalar values.
+fn decode<B: ByteOrder>(bytes: &[u8], scalar_type: &ScalarType) -> f64 {
+    match *scalar_type {
+        ScalarType::Char => f64::from(bytes[0] as i8),
+        ScalarType::UChar => f64::from(bytes[0]),
+        ScalarType::Short => f64::from(B::read_i16(bytes)),
+        ScalarType::UShort => f64::from(B::read_u16(bytes)),
+        ScalarType::Int => f64::from(B::read_i32(bytes)),
+        ScalarType::UInt => f64::from(B::read_u32(bytes)),
+        ScalarType::Float => f64::from(B::read_f32(bytes)),
+        ScalarType::Double => B::read_f64(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property };
+    #[test]
+    fn read_mmap_ok() {
+        let path = std::env::temp_dir().join(format!("ply_rs_mmap_{}.ply", std::process::id()));
+        // In host byte order and padded so that the `value` rows are aligned, for `as_slice()`.
+        let format = if cfg!(target_endian = "big") { "binary_big_endian" } else { "binary_little_endian" };
+        let mut data = format!("ply\nformat {} 1.0\nelement face 1\nproperty list uchar int i\n\
+            element point 3\nproperty short a\nproperty float x\nelement value 2\nproperty float v\ncomment ", format).into_bytes();
+        while (data.len() + "\nend_header\n".len() + 9 + 18) % 4 != 0 {
+            data.push(b'.');
+        }
+        data.extend_from_slice(b"\nend_header\n");
+        data.push(2);
+        data.extend_from_slice(&4i32.to_ne_bytes());
+        data.extend_from_slice(&5i32.to_ne_bytes());
+        for i in 0..3i16 {
+            data.extend_from_slice(&i.to_ne_bytes());
+            data.extend_from_slice(&(i as f32 * 0.5).to_ne_bytes());
+        }
+        data.extend_from_slice(&1.5f32.to_ne_bytes());
+        data.extend_from_slice(&(-2f32).to_ne_bytes());
+        std::fs::write(&path, &data).unwrap();
+
+        let p = Parser::<DefaultElement>::new();
+        let ply = p.read_mmap(&path).unwrap();
+        assert!(ply.element_bytes("face").is_err());
+        assert_eq!(ply.element_bytes("point").unwrap().len(), 18);
+        let x = ply.column::<f64>("point", "x").unwrap();
+        assert_eq!(x.iter().collect::<Vec<_>>(), vec![0.0, 0.5, 1.0]);
+        assert_eq!(ply.column::<i32>("point", "a").unwrap().get(2), 2);
+        assert!(ply.column::<u8>("point", "a").is_err());
+        assert!(ply.column::<i32>("point", "x").is_err());
+        assert!(ply.column::<f32>("point", "x").unwrap().as_slice().is_none());
+        let v = ply.column::<f32>("value", "v").unwrap();
+        assert_eq!(v.as_slice(), Some(&[1.5, -2.0][..]));
+        assert_eq!(ply.read_element(&p, "face").unwrap()[0]["i"], Property::ListInt(vec![4, 5]));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+//! Aggregates computed while streaming the payload, without storing the elements.
+
+use std::io;
+use std::io::{ BufRead, BufReader, ErrorKind, Read, Result };
+
+use super::Parser;
+#[cfg(feature = "checksum")]
+use crate::checksum::{ Checksum, ChecksumAlgorithm, ChecksumHasher };
+use crate::ply::{ ElementDef, Encoding, Header, KeyMap, PropertyAccess };
+use crate::util::{ get_scalar_as_f64, LocationTracker };
+
+/// A computation fed with every payload element while it is read.
+///
+/// Register aggregates with `Parser::read_aggregates()`, the results stay in the aggregate objects.
+pub trait Aggregate<E: PropertyAccess> {
+    /// Called once per element, in payload order.
+    fn element(&mut self, element_def: &ElementDef, element: &E);
+    /// Called with the raw payload bytes of every element, in payload order.
+    fn bytes(&mut self, _bytes: &[u8]) {}
+    /// Called once after the last element has been read.
+    fn finish(&mut self) {}
+}
+
+/// Counts the elements read per element type.
+#[derive(Debug, Default, Clone)]
+pub struct CountAggregate {
+    /// Number of elements read, by element name.
+    pub counts: KeyMap<usize>,
+}
+
+impl<E: PropertyAccess> Aggregate<E> for CountAggregate {
+    fn element(&mut self, element_def: &ElementDef, _element: &E) {
+        *self.counts.entry(element_def.name.clone()).or_insert(0) += 1;
+    }
+}
+
+/// Minimum and maximum of a scalar property, NaN values are ignored.
+#[derive(Debug, Clone)]
+pub struct RangeAggregate {
+    element: String,
+    property: String,
+    /// `(min, max)`, `None` until a value has been seen.
+    pub range: Option<(f64, f64)>,
+}
+
+impl RangeAggregate {
+    /// Tracks the range of `property` of `element`.
+    pub fn new(element: &str, property: &str) -> Self {
+        RangeAggregate {
+            element: element.to_string(),
+            property: property.to_string(),
+            range: None,
+        }
+    }
+}
+
+impl<E: PropertyAccess> Aggregate<E> for RangeAggregate {
+    fn element(&mut self, element_def: &ElementDef, element: &E) {
+        if element_def.name != self.element {
+            return;
+        }
+        let v = match element_def.properties.get(&self.property).and_then(|p| get_scalar_as_f64(element, p)) {
+            Some(v) if !v.is_nan() => v,
+            _ => return,
+        };
+        self.range = Some(match self.range {
+            Some((min, max)) => (min.min(v), max.max(v)),
+            None => (v, v),
+        });
+    }
+}
+
+/// Axis aligned bounding box of the `x`, `y` and `z` properties of an element.
+#[derive(Debug, Clone)]
+pub struct BoundsAggregate {
+    element: String,
+    /// `(min, max)` corners, `None` until a position has been seen.
+    pub bounds: Option<([f64; 3], [f64; 3])>,
+}
+
+impl BoundsAggregate {
+    /// Tracks the bounds of `element`, usually `vertex`.
+    pub fn new(element: &str) -> Self {
+        BoundsAggregate {
+            element: element.to_string(),
+            bounds: None,
+        }
+    }
+}
+
+impl<E: PropertyAccess> Aggregate<E> for BoundsAggregate {
+    fn element(&mut self, element_def: &ElementDef, element: &E) {
+        if element_def.name != self.element {
+            return;
+        }
+        let mut p = [0.0; 3];
+        for (p, axis) in p.iter_mut().zip(["x", "y", "z"].iter()) {
+            *p = match element_def.properties.get(*axis).and_then(|d| get_scalar_as_f64(element, d)) {
+                Some(v) if !v.is_nan() => v,
+                _ => return,
+            };
+        }
+        let (mut min, mut max) = self.bounds.unwrap_or((p, p));
+        for i in 0..3 {
+            min[i] = min[i].min(p[i]);
+            max[i] = max[i].max(p[i]);
+        }
+        self.bounds = Some((min, max));
+    }
+}
+
+/// Checksum of the payload bytes, comparable to the checksum announced in the header.
+#[cfg(feature = "checksum")]
+pub struct ChecksumAggregate {
+    hasher: Option<ChecksumHasher>,
+    /// Available after reading.
+    pub checksum: Option<Checksum>,
+}
+
+#[cfg(feature = "checksum")]
+impl ChecksumAggregate {
+    /// Hashes the payload with `algorithm`.
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        ChecksumAggregate {
+            hasher: Some(ChecksumHasher::new(algorithm)),
+            checksum: None,
+        }
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<E: PropertyAccess> Aggregate<E> for ChecksumAggregate {
+    fn element(&mut self, _element_def: &ElementDef, _element: &E) {}
+    fn bytes(&mut self, bytes: &[u8]) {
+        if let Some(ref mut h) = self.hasher {
+            h.update(bytes);
+        }
+    }
+    fn finish(&mut self) {
+        self.checksum = self.hasher.take().map(ChecksumHasher::finish);
+    }
+}
+
+/// Keeps a copy of all consumed bytes until they are taken.
+struct RecordingReader<'a, R: BufRead> {
+    inner: &'a mut R,
+    recorded: Vec<u8>,
+}
+
+impl<'a, R: BufRead> Read for RecordingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<'a, R: BufRead> BufRead for RecordingReader<'a, R> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.recorded.extend_from_slice(&buf[..amt]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads a complete PLY file and feeds every element to `aggregates` instead of storing it.
+    ///
+    /// Memory use doesn't depend on the payload size. Returns the header, the results are kept by the aggregates.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// use ply_rs::parser::{ Aggregate, BoundsAggregate, CountAggregate };
+    /// let mut f = std::fs::File::open("example_plys/house_ok_ascii.ply").unwrap();
+    /// let mut bounds = BoundsAggregate::new("vertex");
+    /// let mut count = CountAggregate::default();
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let header = p.read_aggregates(&mut f, &mut [&mut bounds, &mut count]).unwrap();
+    /// assert_eq!(count.counts["vertex"], header.elements["vertex"].count);
+    /// assert!(bounds.bounds.is_some());
+    /// ```
+    pub fn read_aggregates<T: Read>(&self, source: &mut T, aggregates: &mut [&mut dyn Aggregate<E>]) -> Result<Header> {
+        let mut source = BufReader::new(source);
+        let mut location = LocationTracker::new();
+        let header = self.__read_header(&mut source, &mut location)?;
+        let mut reader = RecordingReader { inner: &mut source, recorded: Vec::new() };
+        let mut line = String::new();
+        let mut non_finite = 0;
+        for (_, element_def) in &header.elements {
+            for _ in 0..element_def.count {
+                let element = match header.encoding {
+                    Encoding::Ascii => {
+                        line.clear();
+                        if reader.read_line(&mut line)? == 0 {
+                            return Err(io::Error::new(ErrorKind::UnexpectedEof, format!("Missing `{}` elements.", element_def.name)));
+                        }
+                        match self.__read_ascii_element(&line, element_def, &mut non_finite) {
+                            Ok(e) => e,
+                            Err(e) => return super::element_line_rethrow(&location, &line, e),
+                        }
+                    },
+                    Encoding::BinaryBigEndian => self.read_big_endian_element(&mut reader, element_def)?,
+                    Encoding::BinaryLittleEndian => self.read_little_endian_element(&mut reader, element_def)?,
+                };
+                for a in aggregates.iter_mut() {
+                    a.bytes(&reader.recorded);
+                    a.element(element_def, &element);
+                }
+                reader.recorded.clear();
+                location.next_line();
+            }
+            self.__warn_non_finite(element_def, std::mem::take(&mut non_finite));
+        }
+        for a in aggregates.iter_mut() {
+            a.finish();
+        }
+        Ok(header)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    #[test]
+    fn read_aggregates_ok() {
+        let txt = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\n\
+            element face 1\nproperty list uchar int vertex_index\nend_header\n1 -2 3\n0 5 0.5\n3 0 1 2\n";
+        let mut bounds = BoundsAggregate::new("vertex");
+        let mut range = RangeAggregate::new("vertex", "y");
+        let mut count = CountAggregate::default();
+        let p = Parser::<DefaultElement>::new();
+        p.read_aggregates(&mut txt.as_bytes(), &mut [&mut bounds, &mut range, &mut count]).unwrap();
+        assert_eq!(bounds.bounds, Some(([0.0, -2.0, 0.5], [1.0, 5.0, 3.0])));
+        assert_eq!(range.range, Some((-2.0, 5.0)));
+        assert_eq!(count.counts["face"], 1);
+        assert!(p.read_aggregates(&mut &txt.as_bytes()[..txt.len() - 3], &mut [&mut count]).is_err());
+    }
+    #[cfg(feature = "checksum")]
+    #[test]
+    fn read_checksum_aggregate() {
+        let payload = "1 -2 3\n0 5 0.5\n";
+        let mut hasher = ChecksumHasher::new(ChecksumAlgorithm::Crc32);
+        hasher.update(payload.as_bytes());
+        let txt = format!("ply\nformat ascii 1.0\ncomment {}\nelement vertex 2\nproperty float x\nproperty float y\n\
+            property float z\nend_header\n{}", hasher.finish().to_comment(), payload);
+        let mut checksum = ChecksumAggregate::new(ChecksumAlgorithm::Crc32);
+        let header = Parser::<DefaultElement>::new().read_aggregates(&mut txt.as_bytes(), &mut [&mut checksum]).unwrap();
+        assert_eq!(checksum.checksum, Checksum::from_header(&header));
+    }
+}
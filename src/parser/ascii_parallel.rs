@@ -0,0 +1,117 @@
+//! Multi-threaded decoding of ascii elements.
+
+use std::io;
+use std::io::{ BufRead, Result };
+use std::slice::Iter;
+#[cfg(not(feature = "rayon"))]
+use std::thread;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use super::{ Parser, element_line_rethrow };
+use crate::ply::{ ElementDef, Property, PropertyAccess, PropertyType };
+use crate::util::LocationTracker;
+
+/// Number of lines each thread decodes per batch.
+///
+/// Bounds the amount of raw text held in memory at once.
+const LINES_PER_THREAD: usize = 8192;
+
+/// Rows of a chunk and their count of non-finite values, or the index of the failing line in the batch.
+type ChunkResult = std::result::Result<(Vec<Vec<Property>>, usize), (usize, io::Error)>;
+
+/// Elements with list properties are always decoded sequentially.
+pub(super) fn is_parallelizable(element_def: &ElementDef) -> bool {
+    element_def.properties.values().all(|p| matches!(p.data_type, PropertyType::Scalar(_)))
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    pub(super) fn __ascii_threads(&self) -> usize {
+        match self.ascii_threads {
+            #[cfg(feature = "rayon")]
+            0 => rayon::current_num_threads(),
+            #[cfg(not(feature = "rayon"))]
+            0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            n => n,
+        }
+    }
+    pub(super) fn __read_ascii_payload_for_element_parallel<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef, threads: usize) -> Result<Vec<E>> {
+        let mut elems = Vec::<E>::new();
+        let mut lines = Vec::<String>::new();
+        let mut remaining = element_def.count;
+        let mut non_finite = 0;
+        while remaining > 0 {
+            let batch = remaining.min(threads * LINES_PER_THREAD);
+            lines.clear();
+            for _ in 0..batch {
+                let mut line_str = String::new();
+                reader.read_line(&mut line_str)?;
+                lines.push(line_str);
+            }
+            let chunk_size = batch.div_ceil(threads);
+            let results = self.__decode_ascii_chunks(&lines, chunk_size, element_def);
+            let batch_start = location.line_index;
+            let selected = self.projection.selected(&element_def.name);
+            for result in results {
+                match result {
+                    Ok((rows, count)) => {
+                        for properties in rows {
+                            let mut element = E::new();
+                            for (k, property) in element_def.properties.keys().zip(properties).filter(|(k, _)| selected.contains(k)) {
+                                element.set_named_property(k, property);
+                            }
+                            elems.push(element);
+                            location.next_line();
+                            self.__progress_rows(1);
+                        }
+                        non_finite += count;
+                    },
+                    Err((i, e)) => {
+                        location.line_index = batch_start + i;
+                        return element_line_rethrow(location, &lines[i], e);
+                    }
+                }
+            }
+            remaining -= batch;
+        }
+        self.__warn_non_finite(element_def, non_finite);
+        Ok(elems)
+    }
+    /// Decodes `lines` in chunks of `chunk_size` on scoped threads.
+    #[cfg(not(feature = "rayon"))]
+    fn __decode_ascii_chunks(&self, lines: &[String], chunk_size: usize, element_def: &ElementDef) -> Vec<ChunkResult> {
+        thread::scope(|s| {
+            let handles: Vec<_> = lines.chunks(chunk_size).enumerate().map(|(chunk_index, chunk)| {
+                s.spawn(move || self.__decode_ascii_chunk(chunk_index * chunk_size, chunk, element_def))
+            }).collect();
+            handles.into_iter()
+                .map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        })
+    }
+    /// Decodes `lines` in chunks of `chunk_size` on the rayon thread pool.
+    #[cfg(feature = "rayon")]
+    fn __decode_ascii_chunks(&self, lines: &[String], chunk_size: usize, element_def: &ElementDef) -> Vec<ChunkResult> {
+        lines.par_chunks(chunk_size).enumerate()
+            .map(|(chunk_index, chunk)| self.__decode_ascii_chunk(chunk_index * chunk_size, chunk, element_def))
+            .collect()
+    }
+    fn __decode_ascii_chunk(&self, first_line: usize, chunk: &[String], element_def: &ElementDef) -> ChunkResult {
+        let mut non_finite = 0;
+        let rows = chunk.iter().enumerate()
+            .map(|(i, line)| self.__read_ascii_properties(line, element_def, &mut non_finite).map_err(|e| (first_line + i, e)))
+            .collect::<std::result::Result<Vec<_>, _>>();
+        rows.map(|rows| (rows, non_finite))
+    }
+    /// Like `read_ascii_element()` but returns the properties in the order of `element_def`.
+    fn __read_ascii_properties(&self, line: &str, element_def: &ElementDef, non_finite: &mut usize) -> Result<Vec<Property>> {
+        let elems = self.__tokenize_ascii_line(line)?;
+        let mut elem_it : Iter<&str> = elems.iter();
+        let properties = element_def.properties.values()
+            .map(|p| self.__read_ascii_property(&mut elem_it, &p.data_type, non_finite))
+            .collect::<Result<Vec<_>>>()?;
+        self.__check_trailing_values(&mut elem_it)?;
+        Ok(properties)
+    }
+}
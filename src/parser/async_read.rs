@@ -0,0 +1,163 @@
+//! Reading PLY files from asynchronous sources.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+use std::result;
+
+use byteorder::{ BigEndian, LittleEndian };
+use tokio::io::{ AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader };
+
+use super::{ LimitError, Parser, element_line_rethrow, payload_error };
+use super::incremental::list_count;
+use crate::ply::{ ElementDef, Encoding, Error, Payload, Ply, PropertyAccess, PropertyType };
+use crate::util::LocationTracker;
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads a complete PLY file from an asynchronous `source`, like `read_ply()`.
+    ///
+    /// The header is collected up to `end_header` and then parsed, the payload is read and decoded one row at a time,
+    /// so the file is never held in memory as a whole. Header lines have to end with "\n" or "\r\n".
+    /// Checksums aren't verified and `set_sampling()` is ignored, other settings apply as for `read_ply()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ply_rs::*;
+    /// # async fn load() -> Result<(), ply::Error> {
+    /// let mut f = tokio::fs::File::open("scan.ply").await?;
+    /// let ply = parser::Parser::<ply::DefaultElement>::new().read_ply_async(&mut f).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_ply_async<T: AsyncRead + Unpin>(&self, source: &mut T) -> result::Result<Ply<E>, Error> {
+        let mut reader = BufReader::new(source);
+        let header_bytes = self.__read_header_bytes_async(&mut reader).await?;
+        let mut location = LocationTracker::new();
+        let header = self.__read_header(&mut &header_bytes[..], &mut location)?;
+        let mut payload = Payload::new();
+        let mut line = String::new();
+        let mut row = Vec::new();
+        for element_def in header.elements.values() {
+            let keep = self.projection.keeps_element(&element_def.name);
+            let mut elems = Vec::new();
+            let mut non_finite = 0;
+            for index in 0..element_def.count {
+                let element = match header.encoding {
+                    Encoding::Ascii => {
+                        line.clear();
+                        reader.read_line(&mut line).await?;
+                        match keep {
+                            true => Some(self.__read_ascii_element(&line, element_def, &mut non_finite)
+                                .or_else(|e| element_line_rethrow(&location, &line, e))),
+                            false => None,
+                        }
+                    },
+                    encoding => {
+                        row.clear();
+                        self.__read_binary_row_async(&mut reader, element_def, encoding, &mut row).await
+                            .map_err(|e| payload_error(e, element_def, index))?;
+                        match (keep, encoding) {
+                            (false, _) => None,
+                            (true, Encoding::BinaryBigEndian) => Some(self.read_big_endian_element(&mut &row[..], element_def)),
+                            (true, _) => Some(self.read_little_endian_element(&mut &row[..], element_def)),
+                        }
+                    },
+                };
+                if let Some(element) = element {
+                    elems.push(element.map_err(|e| payload_error(e, element_def, index))?);
+                }
+                location.next_line();
+            }
+            self.__warn_non_finite(element_def, non_finite);
+            if keep {
+                payload.insert(element_def.name.clone(), elems);
+            }
+        }
+        Ok(Ply { header, payload })
+    }
+    /// Collects the header lines up to and including `end_header`.
+    ///
+    /// Stops early at a first line other than `ply` or at the end of the input, the header parser reports those.
+    async fn __read_header_bytes_async<T: AsyncBufRead + Unpin>(&self, reader: &mut T) -> Result<Vec<u8>> {
+        let limit = self.limits.max_header_bytes;
+        let mut bytes = Vec::new();
+        loop {
+            let remaining = limit.saturating_sub(bytes.len());
+            if remaining == 0 {
+                return Err(LimitError::HeaderTooLarge { limit }.into());
+            }
+            let start = bytes.len();
+            if (&mut *reader).take(remaining as u64).read_until(b'\n', &mut bytes).await? == 0 {
+                return Ok(bytes);
+            }
+            let line = String::from_utf8_lossy(&bytes[start..]);
+            if !line.ends_with('\n') && bytes.len() == limit {
+                return Err(LimitError::HeaderTooLarge { limit }.into());
+            }
+            let first_token = line.split_whitespace().next();
+            if first_token == Some("end_header") || (start == 0 && first_token != Some("ply")) {
+                return Ok(bytes);
+            }
+        }
+    }
+    /// Appends the bytes of one binary row of `element_def` to `row`.
+    async fn __read_binary_row_async<T: AsyncBufRead + Unpin>(&self, reader: &mut T, element_def: &ElementDef, encoding: Encoding, row: &mut Vec<u8>) -> Result<()> {
+        for p in element_def.properties.values() {
+            match p.data_type {
+                PropertyType::Scalar(ref scalar_type) => read_bytes(reader, row, scalar_type.byte_size()).await?,
+                PropertyType::List(ref index_type, ref scalar_type) => {
+                    let start = row.len();
+                    read_bytes(reader, row, index_type.byte_size()).await?;
+                    let count = match encoding {
+                        Encoding::BinaryBigEndian => list_count::<BigEndian>(&row[start..], index_type)?,
+                        _ => list_count::<LittleEndian>(&row[start..], index_type)?,
+                    };
+                    self.__check_list_len(count)?;
+                    let length = count.checked_mul(scalar_type.byte_size())
+                        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "List is too large."))?;
+                    read_bytes(reader, row, length).await?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Appends exactly `n` bytes, growing `row` only as data arrives.
+async fn read_bytes<T: AsyncBufRead + Unpin>(reader: &mut T, row: &mut Vec<u8>, n: usize) -> Result<()> {
+    if (&mut *reader).take(n as u64).read_to_end(row).await? < n {
+        return Err(io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of file."));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+    }
+    #[test]
+    fn read_ply_async_ok() {
+        let p = Parser::<DefaultElement>::new();
+        for path in &["example_plys/greg_turk_example1_ok_ascii.ply", "example_plys/house_2_ok_little_endian.ply"] {
+            let data = std::fs::read(path).unwrap();
+            let expected = p.read_ply(&mut &data[..]).unwrap();
+            let ply = block_on(p.read_ply_async(&mut &data[..])).unwrap();
+            assert_eq!(ply.header, expected.header);
+            assert_eq!(ply.payload, expected.payload);
+            let truncated = block_on(p.read_ply_async(&mut &data[..data.len() - 3]));
+            assert!(matches!(truncated, Err(Error::Payload { .. })), "{:?}", truncated.err());
+        }
+        assert!(matches!(block_on(p.read_ply_async(&mut &b"plx\nformat ascii 1.0\n"[..])), Err(Error::Syntax { line: 1, .. })));
+    }
+    #[test]
+    fn read_ply_async_negative_count() {
+        let mut data = b"ply\nformat binary_big_endian 1.0\nelement face 1\nproperty list int int i\nend_header\n".to_vec();
+        data.extend_from_slice(&(-1i32).to_be_bytes());
+        let e = block_on(Parser::<DefaultElement>::new().read_ply_async(&mut &data[..])).unwrap_err();
+        assert!(matches!(e, Error::Payload { index: 0, .. }));
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+    }
+}
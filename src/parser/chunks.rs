@@ -0,0 +1,108 @@
+//! Reading the rows of an element in batches of a fixed size.
+
+use std::io;
+use std::io::{ BufRead, ErrorKind, Result };
+
+use super::Parser;
+use super::preview::find_element;
+use crate::ply::{ ElementDef, Encoding, Header, PropertyAccess };
+use crate::util::LocationTracker;
+
+/// Iterator over the rows of one element in batches, created by `Parser::read_payload_chunks()`.
+///
+/// Every batch has `chunk_size` rows except for the last one, which has the remaining rows.
+/// Iteration ends after the last row or after the first error.
+pub struct PayloadChunks<'a, T: BufRead, E: PropertyAccess> {
+    parser: &'a Parser<E>,
+    reader: T,
+    location: LocationTracker,
+    /// Declaration of the element, its count set to the rows of the next batch.
+    chunk: ElementDef,
+    encoding: Encoding,
+    chunk_size: usize,
+    /// Rows not read yet.
+    remaining: usize,
+}
+
+impl<'a, T: BufRead, E: PropertyAccess> Iterator for PayloadChunks<'a, T, E> {
+    type Item = Result<Vec<E>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.chunk.count = self.chunk_size.min(self.remaining);
+        let rows = self.parser.__read_element_list(&mut self.reader, &mut self.location, &self.chunk, &self.encoding);
+        self.remaining = match rows {
+            Ok(_) => self.remaining - self.chunk.count,
+            Err(_) => 0,
+        };
+        Some(rows)
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads the rows of `element` in batches of `chunk_size` rows.
+    ///
+    /// `reader` has to be positioned at the start of the payload, e.g. after `read_header()`.
+    /// Rows of preceding elements are skipped without decoding where possible before this returns,
+    /// a batch is read each time the iterator is advanced.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::io::BufReader::new(std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap());
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let header = p.read_header(&mut f).unwrap();
+    /// let sizes = p.read_payload_chunks(&mut f, &header, "vertex", 3).unwrap()
+    ///     .map(|chunk| chunk.unwrap().len())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(sizes, vec![3, 3, 2]);
+    /// ```
+    pub fn read_payload_chunks<T: BufRead>(&self, mut reader: T, header: &Header, element: &str, chunk_size: usize) -> Result<PayloadChunks<'_, T, E>> {
+        if chunk_size == 0 {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Chunks must have at least one row."));
+        }
+        let (index, element_def) = find_element(header, element)?;
+        let mut location = LocationTracker::new();
+        for e in header.elements.values().take(index) {
+            self.__skip_element(&mut reader, &mut location, e, &header.encoding)?;
+        }
+        Ok(PayloadChunks {
+            parser: self,
+            reader,
+            location,
+            chunk: element_def.clone(),
+            encoding: header.encoding,
+            chunk_size,
+            remaining: element_def.count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property };
+    #[test]
+    fn read_payload_chunks_ok() {
+        let mut data = b"ply\nformat binary_little_endian 1.0\nelement face 1\nproperty list uchar uchar i\n\
+            element point 5\nproperty uchar x\nend_header\n".to_vec();
+        data.extend_from_slice(&[2, 7, 8, 0, 1, 2, 3, 4]);
+        let p = Parser::<DefaultElement>::new();
+        let mut reader = &data[..];
+        let header = p.read_header(&mut reader).unwrap();
+        assert!(p.read_payload_chunks(reader, &header, "point", 0).is_err());
+        let chunks = p.read_payload_chunks(reader, &header, "point", 2).unwrap()
+            .map(|c| c.unwrap().iter().map(|e| e["x"].clone()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(chunks, vec![
+            vec![Property::UChar(0), Property::UChar(1)],
+            vec![Property::UChar(2), Property::UChar(3)],
+            vec![Property::UChar(4)],
+        ]);
+        let mut truncated = p.read_payload_chunks(&reader[..reader.len() - 2], &header, "point", 2).unwrap();
+        assert!(truncated.nth(1).unwrap().is_err());
+        assert!(truncated.next().is_none());
+    }
+}
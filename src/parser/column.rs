@@ -0,0 +1,232 @@
+//! Streaming the values of a single property without reading entire elements.
+
+use std::io;
+use std::io::{ BufRead, ErrorKind, Result };
+use std::marker::PhantomData;
+
+use byteorder::{ BigEndian, ByteOrder, LittleEndian };
+
+use super::{ Parser, Warning, parse_ascii_error, parse_ascii_rethrow };
+use super::preview::find_element;
+use crate::ply::{ ElementDef, Encoding, Header, Property, PropertyAccess, PropertyType, ScalarType, ScalarValue };
+use crate::util::LocationTracker;
+
+/// Iterator over the values of one property, created by `Parser::column_iter()`.
+///
+/// Rows are decoded one at a time and only as far as needed, other properties are skipped.
+/// Iteration ends after the last row of the element or after the first error.
+pub struct ColumnIter<'a, T: BufRead, E: PropertyAccess, V: ScalarValue> {
+    parser: &'a Parser<E>,
+    reader: T,
+    location: LocationTracker,
+    element_def: ElementDef,
+    encoding: Encoding,
+    /// Position of the property within the row.
+    index: usize,
+    remaining: usize,
+    line: String,
+    non_finite: usize,
+    failed: bool,
+    value: PhantomData<V>,
+}
+
+fn scalar_to_f64(property: Property) -> f64 {
+    match property {
+        Property::Char(v) => f64::from(v),
+        Property::UChar(v) => f64::from(v),
+        Property::Short(v) => f64::from(v),
+        Property::UShort(v) => f64::from(v),
+        Property::Int(v) => f64::from(v),
+        Property::UInt(v) => f64::from(v),
+        Property::Float(v) => f64::from(v),
+        Property::Double(v) => v,
+        _ => unreachable!("column properties are scalars"),
+    }
+}
+
+impl<'a, T: BufRead, E: PropertyAccess, V: ScalarValue> ColumnIter<'a, T, E, V> {
+    /// Returns the underlying stream, positioned after the last row read.
+    pub fn into_inner(self) -> T {
+        self.reader
+    }
+    fn __read_ascii_value(&mut self) -> Result<Property> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return parse_ascii_error(&self.location, &self.line, "Unexpected end of file.");
+        }
+        let mut tokens = self.line.split_whitespace();
+        for (i, p) in self.element_def.properties.values().enumerate() {
+            let token = match tokens.next() {
+                Some(t) => t,
+                None => break,
+            };
+            match p.data_type {
+                PropertyType::Scalar(ref scalar_type) if i == self.index => {
+                    if let ScalarType::Float | ScalarType::Double = *scalar_type {
+                        self.parser.__check_special_float(token, &mut self.non_finite)?;
+                    }
+                    return self.parser.__parse_scalar(token, scalar_type);
+                },
+                PropertyType::Scalar(_) => (),
+                PropertyType::List(_, _) => {
+                    let count: usize = self.parser.parse(token)?;
+                    if count > 0 && tokens.nth(count - 1).is_none() {
+                        break;
+                    }
+                },
+            }
+        }
+        Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Expected a value for property `{}`, but found nothing.", self.element_def.properties.values().nth(self.index).unwrap().name)
+        ))
+    }
+    fn __read_binary_value<B: ByteOrder>(&mut self) -> Result<Property> {
+        let mut value = None;
+        let mut skipped = [0u8; 8];
+        for (i, p) in self.element_def.properties.values().enumerate() {
+            match p.data_type {
+                _ if i == self.index => value = Some(self.parser.__read_binary_property::<T, B>(&mut self.reader, &p.data_type)?),
+                PropertyType::Scalar(ref s) => self.reader.read_exact(&mut skipped[..s.byte_size()])?,
+                PropertyType::List(_, _) => { self.parser.__read_binary_property::<T, B>(&mut self.reader, &p.data_type)?; },
+            }
+        }
+        Ok(value.unwrap())
+    }
+}
+
+impl<'a, T: BufRead, E: PropertyAccess, V: ScalarValue> Iterator for ColumnIter<'a, T, E, V> {
+    type Item = Result<V>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.remaining == 0 {
+            return None;
+        }
+        let value = match self.encoding {
+            Encoding::Ascii => self.__read_ascii_value().or_else(|e| {
+                parse_ascii_rethrow(&self.location, &self.line, e, "Couldn't read element line.")
+            }),
+            Encoding::BinaryBigEndian => self.__read_binary_value::<BigEndian>(),
+            Encoding::BinaryLittleEndian => self.__read_binary_value::<LittleEndian>(),
+        };
+        self.location.next_line();
+        self.remaining -= 1;
+        self.failed = value.is_err();
+        if self.remaining == 0 && self.non_finite > 0 {
+            self.parser.__warn(Warning::NonFiniteValues { element: self.element_def.name.clone(), count: self.non_finite });
+        }
+        Some(value.map(|p| V::from_f64(scalar_to_f64(p))))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(if self.failed { 0 } else { self.remaining }))
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Streams the values of the scalar property `property` of all `element` rows, converted to `V`.
+    ///
+    /// `reader` has to be positioned at the start of the payload, e.g. after `read_header()`.
+    /// Rows of preceding elements are skipped when the iterator is created, no row is kept in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::io::BufReader::new(std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap());
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let header = p.read_header(&mut f).unwrap();
+    /// let mut histogram = [0; 2];
+    /// for z in p.column_iter::<_, u8>(f, &header, "vertex", "z").unwrap() {
+    ///     histogram[z.unwrap() as usize] += 1;
+    /// }
+    /// assert_eq!(histogram, [4, 4]);
+    /// ```
+    pub fn column_iter<T: BufRead, V: ScalarValue>(&self, mut reader: T, header: &Header, element: &str, property: &str) -> Result<ColumnIter<'_, T, E, V>> {
+        let (element_index, element_def) = find_element(header, element)?;
+        let index = match element_def.properties.values().position(|p| p.name == property) {
+            Some(i) => i,
+            None => return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` has no property `{}`.", element, property)
+            )),
+        };
+        if let PropertyType::List(_, _) = element_def.properties[property].data_type {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Property `{}` of element `{}` is a list.", property, element)
+            ));
+        }
+        let mut location = LocationTracker::new();
+        for e in header.elements.values().take(element_index) {
+            self.__skip_element(&mut reader, &mut location, e, &header.encoding)?;
+        }
+        Ok(ColumnIter {
+            parser: self,
+            reader,
+            location,
+            element_def: element_def.clone(),
+            encoding: header.encoding,
+            index,
+            remaining: element_def.count,
+            line: String::new(),
+            non_finite: 0,
+            failed: false,
+            value: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    fn file(encoding: &str) -> Vec<u8> {
+        let mut buf = format!("ply\nformat {} 1.0\nelement face 1\nproperty list uchar int vertex_index\n\
+            element point 3\nproperty list uchar short n\nproperty float x\nproperty uchar y\nend_header\n", encoding).into_bytes();
+        if encoding == "ascii" {
+            buf.extend_from_slice(b"3 0 1 2\n");
+        } else {
+            buf.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2]);
+        }
+        for i in 0..3u8 {
+            if encoding == "ascii" {
+                buf.extend_from_slice(format!("{}{} {}.5 {}\n", i, " 9".repeat(i as usize), i, 7 + i).as_bytes());
+            } else {
+                buf.push(i);
+                buf.extend((0..i).flat_map(|_| vec![0, 9]));
+                buf.extend_from_slice(&(f32::from(i) + 0.5).to_be_bytes());
+                buf.push(7 + i);
+            }
+        }
+        buf
+    }
+    #[test]
+    fn column_iter_ok() {
+        let p = Parser::<DefaultElement>::new();
+        for encoding in &["ascii", "binary_big_endian"] {
+            let buf = file(encoding);
+            let mut reader = &buf[..];
+            let header = p.read_header(&mut reader).unwrap();
+            let xs = p.column_iter::<_, f64>(&mut reader, &header, "point", "x").unwrap();
+            assert_eq!(xs.collect::<Result<Vec<_>>>().unwrap(), vec![0.5, 1.5, 2.5], "{}", encoding);
+            assert!(reader.is_empty());
+
+            let mut reader = &buf[..];
+            p.read_header(&mut reader).unwrap();
+            let ys = p.column_iter::<_, u32>(reader, &header, "point", "y").unwrap();
+            assert_eq!(ys.collect::<Result<Vec<_>>>().unwrap(), vec![7, 8, 9], "{}", encoding);
+        }
+    }
+    #[test]
+    fn column_iter_err() {
+        let p = Parser::<DefaultElement>::new();
+        let buf = file("ascii");
+        let mut reader = &buf[..];
+        let header = p.read_header(&mut reader).unwrap();
+        assert!(p.column_iter::<_, f32>(reader, &header, "point", "n").is_err());
+        assert!(p.column_iter::<_, f32>(reader, &header, "point", "z").is_err());
+        let truncated = &reader[..reader.len() - 4];
+        let ys = p.column_iter::<_, f32>(truncated, &header, "point", "y").unwrap().collect::<Vec<_>>();
+        assert_eq!(ys.len(), 3);
+        assert!(ys[2].is_err());
+    }
+}
@@ -0,0 +1,56 @@
+//! Reading the payload into columns instead of elements.
+
+use std::io::{ Read, Result };
+use std::result;
+
+use super::{ Parser, PlyVisitor, Projection };
+use crate::ply::{ ColumnarPly, ElementDef, Error, Header, Property, PropertyAccess, PropertyDef };
+
+/// Collects the values passed by `Parser::read_with()` into their columns.
+struct Columns<'a> {
+    projection: &'a Projection,
+    ply: Option<ColumnarPly>,
+}
+
+impl<'a> PlyVisitor for Columns<'a> {
+    fn header(&mut self, header: &Header) -> Result<()> {
+        self.ply = Some(ColumnarPly::new(self.projection.project(header)));
+        Ok(())
+    }
+    fn property(&mut self, element: &ElementDef, property: &PropertyDef, value: Property) -> Result<()> {
+        let ply = self.ply.as_mut().unwrap();
+        ply.payload.get_mut(&element.name).unwrap().get_mut(&property.name).unwrap().push(value)
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads the PLY file from `source` into a `ColumnarPly`, without creating any elements.
+    ///
+    /// Only the elements and properties selected by `set_projection()` are read, the header is reduced accordingly.
+    /// Checksums aren't verified.
+    pub fn read_columnar_ply<T: Read>(&self, source: &mut T) -> result::Result<ColumnarPly, Error> {
+        let mut columns = Columns { projection: &self.projection, ply: None };
+        self.read_with(source, &mut columns)?;
+        Ok(columns.ply.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ Column, DefaultElement };
+    #[test]
+    fn read_columnar_ok() {
+        for path in &["example_plys/house_2_ok_ascii.ply", "example_plys/house_2_ok_little_endian.ply"] {
+            let mut p = Parser::<DefaultElement>::new();
+            let columnar = p.read_columnar_ply(&mut std::fs::File::open(path).unwrap()).unwrap();
+            let ply = p.read_ply_from_path(path).unwrap();
+            assert_eq!(columnar, ColumnarPly::from_ply(&ply).unwrap());
+            p.set_projection(Projection::all().select("vertex", &["y"]));
+            let columnar = p.read_columnar_ply(&mut std::fs::File::open(path).unwrap()).unwrap();
+            assert_eq!(columnar.header.elements.keys().collect::<Vec<_>>(), ["vertex"]);
+            assert!(matches!(columnar.payload["vertex"]["y"], Column::Float(ref y) if y.len() == 5));
+            assert_eq!(columnar.payload["vertex"].len(), 1);
+        }
+    }
+}
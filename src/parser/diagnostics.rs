@@ -0,0 +1,107 @@
+//! Suggestions for header lines that don't parse.
+
+/// Keywords starting a header line.
+const KEYWORDS: [&str; 7] = ["ply", "format", "comment", "obj_info", "element", "property", "end_header"];
+/// Encodings of the `format` line.
+const ENCODINGS: [&str; 3] = ["ascii", "binary_big_endian", "binary_little_endian"];
+/// Scalar types, including the sized aliases.
+const SCALAR_TYPES: [&str; 16] = [
+    "char", "uchar", "short", "ushort", "int", "uint", "float", "double",
+    "int8", "uint8", "int16", "uint16", "int32", "uint32", "float32", "float64",
+];
+
+/// Number of single character insertions, deletions and substitutions turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Describes `word` if it isn't one of `known`, with the closest candidate if there is a plausible one.
+fn check(kind: &str, word: &str, known: &[&str]) -> Option<String> {
+    if known.contains(&word) {
+        return None;
+    }
+    let closest = known.iter().map(|k| (edit_distance(&word.to_lowercase(), k), *k)).min();
+    match closest {
+        Some((d, k)) if d <= (word.chars().count() / 3).max(2) || word.eq_ignore_ascii_case(k) =>
+            Some(format!("Unknown {} `{}`, did you mean `{}`?", kind, word, k)),
+        _ => Some(format!("Unknown {} `{}`, expected one of: {}.", kind, word, known.join(", "))),
+    }
+}
+
+/// Hint for a header line rejected by the grammar, `None` if no unknown keyword or type is found.
+pub(crate) fn suggest(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace();
+    let keyword = words.next()?;
+    if let Some(hint) = check("keyword", keyword, &KEYWORDS) {
+        return Some(hint);
+    }
+    match keyword {
+        "format" => check("format", words.next()?, &ENCODINGS),
+        "property" => match words.next()? {
+            "list" => words.take(2).find_map(|w| check("type", w, &SCALAR_TYPES)),
+            t => {
+                let mut types = SCALAR_TYPES.to_vec();
+                types.push("list");
+                check("type", t, &types)
+            },
+        },
+        _ => None,
+    }
+}
+
+/// Whether `line` starts with `format` or a plausible misspelling of it.
+pub(crate) fn is_format_like(line: &str) -> bool {
+    match line.split_whitespace().next() {
+        Some(keyword) => edit_distance(&keyword.to_lowercase(), "format") <= 2,
+        None => false,
+    }
+}
+
+/// Appends the suggestion for `line`, if any, to `message`.
+pub(crate) fn with_suggestion(message: &str, line: &str) -> String {
+    match suggest(line) {
+        Some(hint) => format!("{} {}", message, hint),
+        None => message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn edit_distance_ok() {
+        assert_eq!(edit_distance("elemnt", "element"), 1);
+        assert_eq!(edit_distance("flaot", "float"), 2);
+        assert_eq!(edit_distance("", "ply"), 3);
+        assert_eq!(edit_distance("int", "int"), 0);
+    }
+    #[test]
+    fn suggest_ok() {
+        assert_eq!(suggest("elemnt vertex 3\n").unwrap(), "Unknown keyword `elemnt`, did you mean `element`?");
+        assert_eq!(suggest("ELEMENT vertex 3").unwrap(), "Unknown keyword `ELEMENT`, did you mean `element`?");
+        assert_eq!(suggest("property flaot x").unwrap(), "Unknown type `flaot`, did you mean `float`?");
+        assert_eq!(suggest("property list uchar in32 vertex_index").unwrap(), "Unknown type `in32`, did you mean `int32`?");
+        assert_eq!(suggest("format binary_little_endain 1.0").unwrap(), "Unknown format `binary_little_endain`, did you mean `binary_little_endian`?");
+        assert!(suggest("property vec3 position").unwrap().starts_with("Unknown type `vec3`, expected one of: char,"));
+        assert_eq!(suggest("element vertex three"), None);
+        assert_eq!(suggest(""), None);
+    }
+    #[test]
+    fn is_format_like_ok() {
+        assert!(is_format_like("format binary_litle_endian 1.0\n"));
+        assert!(is_format_like("FROMAT ascii 1.0"));
+        assert!(!is_format_like("element vertex 3"));
+        assert!(!is_format_like(""));
+    }
+}
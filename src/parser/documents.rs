@@ -0,0 +1,76 @@
+//! Reading several PLY documents concatenated in one stream.
+
+use std::io::{ BufRead, Result };
+
+use super::Parser;
+use crate::ply::{ Ply, PropertyAccess };
+use crate::util::LocationTracker;
+
+/// Iterator over the PLY documents of a stream, created by `Parser::documents()`.
+///
+/// Each document is read up to the end of its payload, the next one starts right after.
+/// Iteration ends at the end of the stream or after the first error.
+pub struct Documents<'a, T: BufRead, E: PropertyAccess> {
+    parser: &'a Parser<E>,
+    reader: T,
+    location: LocationTracker,
+    failed: bool,
+}
+
+impl<'a, T: BufRead, E: PropertyAccess> Documents<'a, T, E> {
+    /// Returns the underlying stream, positioned after the last document read.
+    pub fn into_inner(self) -> T {
+        self.reader
+    }
+}
+
+impl<'a, T: BufRead, E: PropertyAccess> Iterator for Documents<'a, T, E> {
+    type Item = Result<Ply<E>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.reader.fill_buf() {
+            Ok([]) => return None,
+            Ok(_) => (),
+            Err(e) => {
+                self.failed = true;
+                return Some(Err(e));
+            },
+        }
+        let ply = self.parser.__read_header(&mut self.reader, &mut self.location).and_then(|header| {
+            let payload = self.parser.__read_payload(&mut self.reader, &mut self.location, &header)?;
+            Ok(Ply { header, payload })
+        });
+        self.failed = ply.is_err();
+        Some(ply)
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Iterates over the PLY documents concatenated in `reader`, e.g. clouds framed back to back in a log file.
+    ///
+    /// Line numbers in error messages count from the start of the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let doc = "ply\nformat ascii 1.0\nelement point 1\nproperty int x\nend_header\n7\n";
+    /// let stream = doc.repeat(3);
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// assert_eq!(p.documents(stream.as_bytes()).count(), 3);
+    /// ```
+    pub fn documents<T: BufRead>(&self, reader: T) -> Documents<'_, T, E> {
+        Documents {
+            parser: self,
+            reader,
+            location: LocationTracker::new(),
+            failed: false,
+        }
+    }
+    /// Reads all PLY documents concatenated in `reader`, see `documents()`.
+    pub fn read_all<T: BufRead>(&self, reader: &mut T) -> Result<Vec<Ply<E>>> {
+        self.documents(reader).collect()
+    }
+}
@@ -0,0 +1,146 @@
+//! Reading the rows of a PLY file one at a time instead of the whole payload.
+
+use std::io::{ BufRead, BufReader, Read, Result };
+
+use super::{ Parser, element_line_rethrow };
+use crate::ply::{ ElementDef, Encoding, Header, PropertyAccess };
+use crate::util::LocationTracker;
+
+/// Iterator over all rows of a PLY file, created by `Parser::read_element_iter()`.
+///
+/// Yields each row with the index of its element in `header().elements`, in file order.
+/// Iteration ends after the last row or after the first error.
+pub struct ElementIter<'a, R: Read, E: PropertyAccess> {
+    parser: &'a Parser<E>,
+    reader: BufReader<R>,
+    location: LocationTracker,
+    header: Header,
+    element_defs: Vec<ElementDef>,
+    /// Element of the next row.
+    element: usize,
+    /// Rows left in the current element.
+    remaining: usize,
+    line: String,
+    non_finite: usize,
+    failed: bool,
+}
+
+impl<'a, R: Read, E: PropertyAccess> ElementIter<'a, R, E> {
+    /// Header read before the first row.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+    /// Declaration of the element with the given index, as yielded with each row.
+    pub fn element_def(&self, index: usize) -> &ElementDef {
+        &self.element_defs[index]
+    }
+    fn __read_row(&mut self) -> Result<E> {
+        let element_def = &self.element_defs[self.element];
+        match self.header.encoding {
+            Encoding::Ascii => {
+                self.line.clear();
+                self.reader.read_line(&mut self.line)?;
+                self.parser.__read_ascii_element(&self.line, element_def, &mut self.non_finite).or_else(|e| {
+                    element_line_rethrow(&self.location, &self.line, e)
+                })
+            },
+            Encoding::BinaryBigEndian => self.parser.read_big_endian_element(&mut self.reader, element_def),
+            Encoding::BinaryLittleEndian => self.parser.read_little_endian_element(&mut self.reader, element_def),
+        }
+    }
+}
+
+impl<'a, R: Read, E: PropertyAccess> Iterator for ElementIter<'a, R, E> {
+    type Item = Result<(usize, E)>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        while self.remaining == 0 {
+            self.element += 1;
+            if self.element >= self.element_defs.len() {
+                return None;
+            }
+            self.remaining = self.element_defs[self.element].count;
+            self.non_finite = 0;
+        }
+        let row = self.__read_row();
+        self.location.next_line();
+        self.remaining -= 1;
+        self.failed = row.is_err();
+        if self.remaining == 0 {
+            self.parser.__warn_non_finite(&self.element_defs[self.element], self.non_finite);
+        }
+        Some(row.map(|r| (self.element, r)))
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads the header from `source` and returns an iterator reading the rows of the payload as they are requested.
+    ///
+    /// Only one row is held in memory at a time, unlike `read_ply()`. Checksums aren't verified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let rows = p.read_element_iter(f).unwrap();
+    /// let vertex = rows.header().elements.keys().position(|k| k == "vertex").unwrap();
+    /// let mut top = 0;
+    /// for row in rows {
+    ///     let (element, row) = row.unwrap();
+    ///     if element == vertex && row["z"] == ply::Property::Float(1.0) {
+    ///         top += 1;
+    ///     }
+    /// }
+    /// assert_eq!(top, 4);
+    /// ```
+    pub fn read_element_iter<R: Read>(&self, source: R) -> Result<ElementIter<'_, R, E>> {
+        let mut reader = BufReader::new(source);
+        let mut location = LocationTracker::new();
+        let header = self.__read_header(&mut reader, &mut location)?;
+        let element_defs = header.elements.values().cloned().collect::<Vec<_>>();
+        let remaining = element_defs.first().map_or(0, |e| e.count);
+        Ok(ElementIter {
+            parser: self,
+            reader,
+            location,
+            header,
+            element_defs,
+            element: 0,
+            remaining,
+            line: String::new(),
+            non_finite: 0,
+            failed: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property };
+    #[test]
+    fn read_element_iter_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut data = b"ply\nformat binary_big_endian 1.0\nelement empty 0\nproperty int a\n\
+            element point 2\nproperty short x\nelement face 1\nproperty list uchar uchar i\nend_header\n".to_vec();
+        data.extend_from_slice(&[0, 1, 0, 2, 2, 7, 8]);
+        let rows = p.read_element_iter(&data[..]).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1].0, 1);
+        assert_eq!(rows[1].1["x"], Property::Short(2));
+        assert_eq!(rows[2].1["i"], Property::ListUChar(vec![7, 8]));
+    }
+    #[test]
+    fn read_element_iter_err() {
+        let p = Parser::<DefaultElement>::new();
+        let data = "ply\nformat ascii 1.0\nelement point 3\nproperty int x\nend_header\n1\nz\n3\n";
+        let mut rows = p.read_element_iter(data.as_bytes()).unwrap();
+        assert!(rows.next().unwrap().is_ok());
+        assert!(rows.next().unwrap().is_err());
+        assert!(rows.next().is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Heuristic checks of the declared byte order and encoding of payloads.
+
+use std::io::{ BufRead, Result };
+
+use byteorder::{ BigEndian, ByteOrder, LittleEndian };
+
+use super::{ Parser, Warning };
+use super::incremental::list_count;
+use crate::ply::{ ElementDef, Encoding, Header, KeyMap, PropertyAccess, PropertyType, ScalarType };
+
+/// Number of rows sampled at the start of the payload.
+const SAMPLE_ROWS: usize = 64;
+/// Magnitudes of measured data are expected within this range, zero aside.
+const PLAUSIBLE_MAGNITUDE: (f64, f64) = (1e-20, 1e20);
+
+fn plausible(v: f64) -> bool {
+    v == 0.0 || (v.is_finite() && PLAUSIBLE_MAGNITUDE.0 <= v.abs() && v.abs() <= PLAUSIBLE_MAGNITUDE.1)
+}
+
+/// Fractions of plausible float values in the first rows of `bytes` decoded as big and as little endian.
+///
+/// `None` if `element_def` has lists or no float properties, or no complete row is available.
+fn plausible_fractions(bytes: &[u8], element_def: &ElementDef) -> Option<(f64, f64)> {
+    let stride = element_def.fixed_stride()?;
+    let mut floats = Vec::new();
+    let mut offset = 0;
+    for p in element_def.properties.values() {
+        if let PropertyType::Scalar(ref s) = p.data_type {
+            match *s {
+                ScalarType::Float => floats.push((offset, false)),
+                ScalarType::Double => floats.push((offset, true)),
+                _ => (),
+            }
+            offset += s.byte_size();
+        }
+    }
+    let rows = bytes.len().checked_div(stride).unwrap_or(0).min(element_def.count).min(SAMPLE_ROWS);
+    if floats.is_empty() || rows == 0 {
+        return None;
+    }
+    let (mut big, mut little) = (0, 0);
+    for row in bytes.chunks(stride).take(rows) {
+        for &(offset, double) in &floats {
+            let (b, l) = if double {
+                (BigEndian::read_f64(&row[offset..]), LittleEndian::read_f64(&row[offset..]))
+            } else {
+                (f64::from(BigEndian::read_f32(&row[offset..])), f64::from(LittleEndian::read_f32(&row[offset..])))
+            };
+            big += plausible(b) as usize;
+            little += plausible(l) as usize;
+        }
+    }
+    let total = (rows * floats.len()) as f64;
+    Some((big as f64 / total, little as f64 / total))
+}
+
+/// Numbers of plausible and of all float values and list lengths in the first rows of `bytes` decoded with byte order `B`.
+///
+/// A list longer than all of `bytes` is implausible and ends the sample.
+fn plausible_values<B: ByteOrder>(bytes: &[u8], element_def: &ElementDef) -> (usize, usize) {
+    let (mut found, mut total) = (0, 0);
+    let mut offset = 0;
+    for _ in 0..element_def.count.min(SAMPLE_ROWS) {
+        for p in element_def.properties.values() {
+            let (count, scalar_type) = match p.data_type {
+                PropertyType::Scalar(ref s) => (1, s),
+                PropertyType::List(ref index_type, ref s) => {
+                    let count = match bytes.get(offset..offset + index_type.byte_size()).map(|b| list_count::<B>(b, index_type)) {
+                        Some(Ok(count)) => count,
+                        Some(Err(_)) => return (found, total + 1),
+                        None => return (found, total),
+                    };
+                    offset += index_type.byte_size();
+                    total += 1;
+                    if count.saturating_mul(s.byte_size()) > bytes.len() {
+                        return (found, total);
+                    }
+                    found += 1;
+                    (count, s)
+                },
+            };
+            for _ in 0..count {
+                let value = match bytes.get(offset..offset + scalar_type.byte_size()) {
+                    Some(v) => v,
+                    None => return (found, total),
+                };
+                offset += scalar_type.byte_size();
+                let v = match *scalar_type {
+                    ScalarType::Float => f64::from(B::read_f32(value)),
+                    ScalarType::Double => B::read_f64(value),
+                    _ => continue,
+                };
+                found += plausible(v) as usize;
+                total += 1;
+            }
+        }
+    }
+    (found, total)
+}
+
+/// Guesses the encoding of the payload starting with `bytes`.
+///
+/// Printable characters are taken for ascii. Otherwise the first element with rows is decoded in both byte orders,
+/// the one with the larger fraction of plausible values wins, little endian if undecided.
+/// `None` if `bytes` is empty although rows are declared.
+pub(super) fn infer_encoding(bytes: &[u8], elements: &KeyMap<ElementDef>) -> Option<Encoding> {
+    let element_def = match elements.values().find(|e| e.count > 0) {
+        Some(e) => e,
+        None => return Some(Encoding::Ascii),
+    };
+    if bytes.is_empty() {
+        return None;
+    }
+    if bytes.iter().all(|&b| b == b'\t' || b == b'\n' || b == b'\r' || (0x20..0x7f).contains(&b)) {
+        return Some(Encoding::Ascii);
+    }
+    let (big, big_total) = plausible_values::<BigEndian>(bytes, element_def);
+    let (little, little_total) = plausible_values::<LittleEndian>(bytes, element_def);
+    Some(match big * little_total > little * big_total {
+        true => Encoding::BinaryBigEndian,
+        false => Encoding::BinaryLittleEndian,
+    })
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Warns if the float values of the first element look implausible in the declared byte order, but sane in the other.
+    ///
+    /// Only the bytes already buffered by `reader` are inspected, nothing is consumed.
+    pub(super) fn __check_endianness<T: BufRead>(&self, reader: &mut T, header: &Header) -> Result<()> {
+        let element_def = match header.elements.values().next() {
+            Some(e) if header.encoding != Encoding::Ascii => e,
+            _ => return Ok(()),
+        };
+        let (big, little) = match plausible_fractions(reader.fill_buf()?, element_def) {
+            Some(f) => f,
+            None => return Ok(()),
+        };
+        let (declared, other) = match header.encoding {
+            Encoding::BinaryBigEndian => (big, little),
+            _ => (little, big),
+        };
+        if declared < 0.5 && other >= 0.9 {
+            self.__warn(Warning::SuspiciousEndianness { element: element_def.name.clone(), declared: header.encoding });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    fn file(declared: &str) -> Vec<u8> {
+        let mut buf = format!("ply\nformat {} 1.0\nelement vertex 3\nproperty float x\nproperty uchar c\nproperty double y\nend_header\n", declared).into_bytes();
+        for i in 0..3 {
+            buf.extend_from_slice(&(i as f32 * 0.37 + 12.5).to_le_bytes());
+            buf.push(7);
+            buf.extend_from_slice(&(-1024.25 * i as f64).to_le_bytes());
+        }
+        buf
+    }
+    #[test]
+    fn check_endianness_warns() {
+        let mut p = Parser::<DefaultElement>::new();
+        p.set_check_endianness(true);
+        p.read_ply(&mut &file("binary_little_endian")[..]).unwrap();
+        assert!(p.take_warnings().is_empty());
+        p.read_ply(&mut &file("binary_big_endian")[..]).unwrap();
+        assert_eq!(p.take_warnings(), vec![Warning::SuspiciousEndianness { element: "vertex".to_string(), declared: Encoding::BinaryBigEndian }]);
+        p.set_check_endianness(false);
+        p.read_ply(&mut &file("binary_big_endian")[..]).unwrap();
+        assert!(p.take_warnings().is_empty());
+    }
+    #[test]
+    fn infer_encoding_ok() {
+        let header = "element face 2\nproperty list ushort float v\nend_header\n";
+        let mut p = Parser::<DefaultElement>::new();
+        p.set_infer_encoding(true);
+        let mut big = format!("ply\nformat binary_big_endain 1.0\n{}", header).into_bytes();
+        let mut little = format!("ply\n{}", header).into_bytes();
+        for v in &[vec![1.5f32, -2.0], vec![0.25]] {
+            big.extend_from_slice(&(v.len() as u16).to_be_bytes());
+            little.extend_from_slice(&(v.len() as u16).to_le_bytes());
+            for x in v {
+                big.extend_from_slice(&x.to_be_bytes());
+                little.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+        assert_eq!(p.read_ply(&mut &big[..]).unwrap().header.encoding, Encoding::BinaryBigEndian);
+        assert_eq!(p.take_warnings(), vec![Warning::InferredEncoding {
+            encoding: Encoding::BinaryBigEndian,
+            discarded: Some("format binary_big_endain 1.0".to_string()),
+        }]);
+        assert_eq!(p.read_ply(&mut &little[..]).unwrap().payload["face"].len(), 2);
+        assert_eq!(p.take_warnings(), vec![Warning::InferredEncoding { encoding: Encoding::BinaryLittleEndian, discarded: None }]);
+        let ascii = format!("ply\nformt ascii 1.0\n{}2 1.5 -2\n1 0.25\n", header);
+        assert_eq!(p.read_ply(&mut ascii.as_bytes()).unwrap().header.encoding, Encoding::Ascii);
+        p.set_infer_encoding(false);
+        assert!(p.read_ply(&mut ascii.as_bytes()).is_err());
+        assert!(p.read_ply(&mut &little[..]).is_err());
+    }
+}
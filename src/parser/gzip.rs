@@ -0,0 +1,57 @@
+//! Reading gzip compressed PLY files.
+
+use std::io::Read;
+use std::result;
+
+use flate2::read::MultiGzDecoder;
+
+use super::Parser;
+use crate::ply::{ Error, Ply, PropertyAccess };
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads a complete PLY file from the gzip compressed `source`, like `read_ply()`.
+    ///
+    /// Streams of several concatenated gzip members, e.g. written by `pigz`, are read as one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// # use std::io::Write;
+    /// # let mut e = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    /// # e.write_all(&std::fs::read("example_plys/house_2_ok_little_endian.ply").unwrap()).unwrap();
+    /// # let compressed = e.finish().unwrap();
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let ply = p.read_ply_gz(&mut &compressed[..]).unwrap();
+    /// assert_eq!(ply.payload["vertex"].len(), 5);
+    /// ```
+    pub fn read_ply_gz<T: Read>(&self, source: &mut T) -> result::Result<Ply<E>, Error> {
+        self.read_ply(&mut MultiGzDecoder::new(source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    use crate::writer::Writer;
+    #[test]
+    fn gzip_round_trip() {
+        let p = Parser::<DefaultElement>::new();
+        let mut ply = p.read_ply_from_path("example_plys/house_2_ok_little_endian.ply").unwrap();
+        let mut compressed = Vec::new();
+        Writer::new().write_ply_gz(&mut compressed, &mut ply).unwrap();
+        assert_eq!(compressed[..2], super::super::path::GZIP_MAGIC);
+        let path = std::env::temp_dir().join(format!("ply_rs_gzip_{}.ply.gz", std::process::id()));
+        std::fs::write(&path, &compressed).unwrap();
+        let read = p.read_ply_from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap().payload, ply.payload);
+        assert!(p.read_ply_gz(&mut &compressed[..compressed.len() / 2]).is_err());
+        let w = Writer::with_compression(crate::writer::Compression::Gzip(9));
+        let mut buf = Vec::new();
+        let written = w.write_ply(&mut buf, &mut ply).unwrap();
+        assert_eq!(written, Writer::new().write_ply(&mut Vec::new(), &mut ply).unwrap());
+        assert_eq!(p.read_ply_gz(&mut &buf[..]).unwrap().payload, ply.payload);
+    }
+}
@@ -0,0 +1,271 @@
+//! Push-based parsing of input that arrives in pieces, e.g. from an event loop.
+
+use std::convert::TryFrom;
+use std::io;
+use std::io::{ Cursor, ErrorKind, Result };
+use std::result;
+
+use byteorder::{ BigEndian, ByteOrder, LittleEndian };
+
+use super::{ LimitError, Parser, element_line_rethrow, payload_error };
+use crate::ply::{ ElementDef, Encoding, Error, Header, PropertyAccess, PropertyType, ScalarType };
+use crate::util::LocationTracker;
+
+/// Rows completed by the input passed to `IncrementalParser::feed()`.
+#[derive(Debug)]
+pub struct ParseProgress<E> {
+    /// Completed rows with the index of their element in the header, in file order.
+    pub rows: Vec<(usize, E)>,
+    /// Whether all rows declared in the header have been read.
+    pub finished: bool,
+}
+
+/// Parses a PLY file from input fed in pieces of any size, without blocking on a reader.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// let data = std::fs::read("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+/// let mut p = parser::IncrementalParser::new(parser::Parser::<ply::DefaultElement>::new());
+/// let mut rows = 0;
+/// for piece in data.chunks(10) {
+///     rows += p.feed(piece).unwrap().rows.len();
+/// }
+/// rows += p.finish().unwrap().len();
+/// assert_eq!(rows, 8 + 6);
+/// ```
+pub struct IncrementalParser<E: PropertyAccess> {
+    parser: Parser<E>,
+    /// Input not consumed yet.
+    buffer: Vec<u8>,
+    header: Option<Header>,
+    element_defs: Vec<ElementDef>,
+    location: LocationTracker,
+    /// Element of the next row.
+    element: usize,
+    /// Index of the next row within its element.
+    index: usize,
+}
+
+impl<E: PropertyAccess> IncrementalParser<E> {
+    /// Parses with the settings of `parser`. Checksums aren't verified and `Parser::set_sampling()` is ignored.
+    pub fn new(parser: Parser<E>) -> Self {
+        IncrementalParser {
+            parser,
+            buffer: Vec::new(),
+            header: None,
+            element_defs: Vec::new(),
+            location: LocationTracker::new(),
+            element: 0,
+            index: 0,
+        }
+    }
+    /// The parser, e.g. to take its warnings.
+    pub fn parser(&self) -> &Parser<E> {
+        &self.parser
+    }
+    /// The header, once it has been fed completely.
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+    /// Appends `data` to the input and returns the rows it completed.
+    ///
+    /// Input following the last row is ignored.
+    pub fn feed(&mut self, data: &[u8]) -> result::Result<ParseProgress<E>, Error> {
+        self.buffer.extend_from_slice(data);
+        if self.header.is_none() && !self.__read_header(false)? {
+            return Ok(ParseProgress { rows: Vec::new(), finished: false });
+        }
+        let mut consumed = 0;
+        let rows = self.__read_rows(&mut consumed, false);
+        self.buffer.drain(..consumed);
+        Ok(ParseProgress { rows: rows?, finished: self.is_finished() })
+    }
+    /// Ends the input and returns the rows it completed, e.g. an ascii row without a final line break.
+    ///
+    /// Fails if the header or any rows are incomplete.
+    pub fn finish(mut self) -> result::Result<Vec<(usize, E)>, Error> {
+        if self.header.is_none() && !self.__read_header(true)? {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "Input ended before `end_header`.").into());
+        }
+        let mut consumed = 0;
+        let rows = self.__read_rows(&mut consumed, true)?;
+        if !self.is_finished() {
+            let e = io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of file.");
+            return Err(payload_error(e, &self.element_defs[self.element], self.index).into());
+        }
+        Ok(rows)
+    }
+    fn is_finished(&self) -> bool {
+        self.header.is_some() && self.element >= self.element_defs.len()
+    }
+    /// Parses the header once `end_header` has been fed, returns whether it has.
+    fn __read_header(&mut self, end: bool) -> result::Result<bool, Error> {
+        let limit = self.parser.limits.max_header_bytes;
+        if !end && !header_complete(&self.buffer) {
+            if self.buffer.len() > limit {
+                return Err(LimitError::HeaderTooLarge { limit }.into());
+            }
+            return Ok(false);
+        }
+        let mut reader = Cursor::new(&self.buffer[..]);
+        let header = self.parser.__read_header(&mut reader, &mut self.location)?;
+        let position = reader.position() as usize;
+        self.buffer.drain(..position);
+        self.element_defs = header.elements.values().cloned().collect();
+        self.header = Some(header);
+        self.__skip_empty_elements();
+        Ok(true)
+    }
+    fn __skip_empty_elements(&mut self) {
+        while self.element < self.element_defs.len() && self.index == self.element_defs[self.element].count {
+            self.element += 1;
+            self.index = 0;
+        }
+    }
+    /// Decodes the complete rows in the buffer, counting the bytes they take in `consumed`.
+    ///
+    /// At the `end` of the input, a last ascii row doesn't need a line break.
+    fn __read_rows(&mut self, consumed: &mut usize, end: bool) -> Result<Vec<(usize, E)>> {
+        let encoding = self.header.as_ref().map_or(Encoding::Ascii, |h| h.encoding);
+        let mut rows = Vec::new();
+        while self.element < self.element_defs.len() {
+            let element_def = &self.element_defs[self.element];
+            let bytes = &self.buffer[*consumed..];
+            let len = match encoding {
+                Encoding::Ascii => match bytes.iter().position(|&b| b == b'\n') {
+                    Some(i) => i + 1,
+                    None if end && !bytes.is_empty() => bytes.len(),
+                    None => break,
+                },
+                Encoding::BinaryBigEndian => match self.parser.__binary_row_len::<BigEndian>(bytes, element_def) {
+                    Ok(Some(len)) => len,
+                    Ok(None) => break,
+                    Err(e) => return Err(payload_error(e, element_def, self.index)),
+                },
+                Encoding::BinaryLittleEndian => match self.parser.__binary_row_len::<LittleEndian>(bytes, element_def) {
+                    Ok(Some(len)) => len,
+                    Ok(None) => break,
+                    Err(e) => return Err(payload_error(e, element_def, self.index)),
+                },
+            };
+            let row = &bytes[..len];
+            if self.parser.projection.keeps_element(&element_def.name) {
+                let element = match encoding {
+                    Encoding::Ascii => self.__read_ascii_row(row, element_def),
+                    Encoding::BinaryBigEndian => self.parser.read_big_endian_element(&mut &row[..], element_def),
+                    Encoding::BinaryLittleEndian => self.parser.read_little_endian_element(&mut &row[..], element_def),
+                };
+                rows.push((self.element, element.map_err(|e| payload_error(e, element_def, self.index))?));
+            }
+            *consumed += len;
+            self.location.next_line();
+            self.index += 1;
+            self.__skip_empty_elements();
+        }
+        Ok(rows)
+    }
+    fn __read_ascii_row(&self, row: &[u8], element_def: &ElementDef) -> Result<E> {
+        let line = match std::str::from_utf8(row) {
+            Ok(line) => line,
+            Err(e) => return element_line_rethrow(&self.location, &String::from_utf8_lossy(row), e),
+        };
+        self.parser.read_ascii_element(line, element_def)
+            .or_else(|e| element_line_rethrow(&self.location, line, e))
+    }
+}
+
+/// Whether `buffer` holds a line starting with `end_header` and the byte deciding on its line break.
+fn header_complete(buffer: &[u8]) -> bool {
+    let mut start = 0;
+    while let Some(i) = buffer[start..].iter().position(|&b| b == b'\n' || b == b'\r') {
+        let end = start + i;
+        let line = String::from_utf8_lossy(&buffer[start..end]);
+        if line.split_whitespace().next() == Some("end_header") {
+            // A lone `\r` might be followed by the `\n` of `\r\n`.
+            return buffer[end] == b'\n' || end + 1 < buffer.len();
+        }
+        start = end + 1;
+    }
+    false
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Number of bytes of the binary row of `element_def` at the start of `bytes`, `None` if they don't hold all of it.
+    fn __binary_row_len<B: ByteOrder>(&self, bytes: &[u8], element_def: &ElementDef) -> Result<Option<usize>> {
+        let mut len = 0usize;
+        for p in element_def.properties.values() {
+            let row_len = match p.data_type {
+                PropertyType::Scalar(ref scalar_type) => len.checked_add(scalar_type.byte_size()),
+                PropertyType::List(ref index_type, ref scalar_type) => {
+                    let count = match bytes.get(len..len + index_type.byte_size()) {
+                        Some(index) => list_count::<B>(index, index_type)?,
+                        None => return Ok(None),
+                    };
+                    self.__check_list_len(count)?;
+                    count.checked_mul(scalar_type.byte_size()).and_then(|l| l.checked_add(len + index_type.byte_size()))
+                },
+            };
+            len = row_len.ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Row is too large."))?;
+            if len > bytes.len() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(len))
+    }
+}
+
+/// Decodes the entry count of a binary list from the bytes of its index, negative counts are invalid.
+pub(super) fn list_count<B: ByteOrder>(bytes: &[u8], index_type: &ScalarType) -> Result<usize> {
+    let count = match *index_type {
+        ScalarType::Char => bytes[0] as i8 as i64,
+        ScalarType::UChar => bytes[0] as i64,
+        ScalarType::Short => B::read_i16(bytes) as i64,
+        ScalarType::UShort => B::read_u16(bytes) as i64,
+        ScalarType::Int => B::read_i32(bytes) as i64,
+        ScalarType::UInt => B::read_u32(bytes) as i64,
+        ScalarType::Float | ScalarType::Double => return Err(io::Error::new(ErrorKind::InvalidInput, "Index of list must be an integer type.")),
+    };
+    usize::try_from(count).map_err(|_| io::Error::new(ErrorKind::InvalidData, format!("List count {} is negative.", count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property };
+    fn feed_all(data: &[u8], piece: usize) -> result::Result<Vec<(usize, DefaultElement)>, Error> {
+        let mut p = IncrementalParser::new(Parser::<DefaultElement>::new());
+        let mut rows = Vec::new();
+        for piece in data.chunks(piece) {
+            rows.extend(p.feed(piece)?.rows);
+        }
+        rows.extend(p.finish()?);
+        Ok(rows)
+    }
+    #[test]
+    fn incremental_ok() {
+        let header = "element face 2\nproperty list uchar ushort i\nelement empty 0\nproperty uchar e\nelement point 2\nproperty uchar x\nend_header";
+        let ascii = format!("ply\r\nformat ascii 1.0\r\n{}\r\n2 4 5\r\n0\r\n7\r\n8", header.replace('\n', "\r\n"));
+        let mut binary = format!("ply\nformat binary_big_endian 1.0\n{}\n", header).into_bytes();
+        binary.extend_from_slice(&[2, 0, 4, 0, 5, 0, 7, 8]);
+        for data in &[ascii.into_bytes(), binary] {
+            for piece in 1..8 {
+                let rows = feed_all(data, piece).unwrap();
+                assert_eq!(rows.iter().map(|r| r.0).collect::<Vec<_>>(), vec![0, 0, 2, 2]);
+                assert_eq!(rows[0].1["i"], Property::ListUShort(vec![4, 5]));
+                assert_eq!(rows[3].1["x"], Property::UChar(8));
+            }
+            assert!(matches!(feed_all(&data[..data.len() - 1], 3), Err(Error::Payload { index: 1, .. })));
+        }
+        assert!(feed_all(b"ply\nformat ascii 1.0\nend_hea", 4).is_err());
+    }
+    #[test]
+    fn incremental_negative_count() {
+        let mut data = b"ply\nformat binary_little_endian 1.0\nelement face 1\nproperty list int int i\nend_header\n".to_vec();
+        data.extend_from_slice(&(-1i32).to_le_bytes());
+        let e = feed_all(&data, 3).unwrap_err();
+        assert!(matches!(e, Error::Payload { index: 0, .. }));
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+    }
+}
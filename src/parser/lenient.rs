@@ -0,0 +1,89 @@
+//! Deviations from the specification tolerated in lenient mode.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+use std::result;
+use std::slice::Iter;
+
+use super::{ Parser, Strictness, Warning, grammar };
+use super::ply_grammar::Line;
+use crate::ply::PropertyAccess;
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Splits an ascii row into its values.
+    ///
+    /// In lenient mode, rows with tokens that aren't numbers are split at whitespace,
+    /// the values needed are parsed and checked individually.
+    pub(super) fn __tokenize_ascii_line<'a>(&self, line: &'a str) -> Result<Vec<&'a str>> {
+        match grammar::data_line(line) {
+            Ok(tokens) => Ok(tokens),
+            Err(_) if self.strictness == Strictness::Lenient => Ok(line.split_whitespace().collect()),
+            Err(ref e) => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Couldn't parse element line.\n\tString: '{}'\n\tError: {}", line, e)
+            )),
+        }
+    }
+    /// Rejects values following the last property of a row, unless lenient.
+    pub(super) fn __check_trailing_values(&self, elem_iter: &mut Iter<&str>) -> Result<()> {
+        if self.strictness == Strictness::Strict && elem_iter.len() > 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Found {} value(s) after the last property, only accepted in lenient mode.", elem_iter.len())
+            ));
+        }
+        Ok(())
+    }
+    /// Parses a header line, in lenient mode ignoring leading whitespace and trailing tokens that don't fit.
+    ///
+    /// `line_index` is reported with the `Warning::RepairedHeaderLine` of a repaired line.
+    pub(super) fn __read_lenient_header_line(&self, line_str: &str, line_index: usize) -> result::Result<Line, peg::error::ParseError<peg::str::LineCol>> {
+        let error = match self.__read_header_line(line_str) {
+            Ok(line) => return Ok(line),
+            Err(e) if self.strictness == Strictness::Strict => return Err(e),
+            Err(e) => e,
+        };
+        let tokens = line_str.split_whitespace().collect::<Vec<_>>();
+        for n in (1..=tokens.len()).rev() {
+            if let Ok(line) = self.__read_header_line(&tokens[..n].join(" ")) {
+                self.__warn(Warning::RepairedHeaderLine { line: line_index, text: line_str.trim_end().to_string() });
+                return Ok(line);
+            }
+        }
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property };
+    #[test]
+    fn lenient_ok() {
+        let txt = "ply\nformat ascii 1.0\n  element point 2\nproperty int x\tjunk\n\nend_header\n1 7\n2 # second\n";
+        let mut p = Parser::<DefaultElement>::new();
+        assert!(p.read_ply(&mut txt.as_bytes()).is_err());
+        assert!(p.read_ply(&mut "ply\nformat ascii 1.0\nelement point 1\nproperty int x\nend_header\n1 7\n".as_bytes()).is_err());
+        p.set_strictness(Strictness::Lenient);
+        let ply = p.read_ply(&mut txt.as_bytes()).unwrap();
+        assert_eq!(ply.payload["point"][1]["x"], Property::Int(2));
+        assert_eq!(p.take_warnings(), vec![
+            Warning::RepairedHeaderLine { line: 3, text: "  element point 2".to_string() },
+            Warning::RepairedHeaderLine { line: 4, text: "property int x\tjunk".to_string() },
+        ]);
+    }
+    #[test]
+    fn lenient_magic_number() {
+        let body = "format ascii 1.0\nelement point 1\nproperty int x\nend_header\n7\n";
+        let mut p = Parser::<DefaultElement>::new();
+        for magic in &["  ply", "ply junk"] {
+            let txt = format!("{}\n{}", magic, body);
+            p.set_strictness(Strictness::Strict);
+            let err = p.read_ply(&mut txt.as_bytes()).unwrap_err();
+            assert!(matches!(err, crate::ply::Error::Syntax { line: 1, offset: 0, .. }), "{:?}", err);
+            p.set_strictness(Strictness::Lenient);
+            assert_eq!(p.read_ply(&mut txt.as_bytes()).unwrap().payload["point"][0]["x"], Property::Int(7));
+            assert_eq!(p.take_warnings(), vec![Warning::RepairedHeaderLine { line: 1, text: magic.to_string() }]);
+        }
+    }
+}
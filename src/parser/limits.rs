@@ -0,0 +1,206 @@
+//! Bounds on the resources a `Parser` spends on its input, for untrusted files.
+
+use std::error;
+use std::fmt;
+use std::fmt::{ Display, Formatter };
+use std::io;
+use std::io::{ BufRead, Result };
+use std::sync::atomic::Ordering;
+
+use super::Parser;
+use super::line_ending::{ LineEnding, read_header_line };
+use crate::ply::{ Header, PropertyAccess, PropertyType, ScalarType };
+
+/// Upper bounds enforced while reading, see `Parser::set_limits()`.
+///
+/// Exceeding one fails the read with `ply::Error::Limit`, or an `io::Error` of kind `InvalidData` wrapping a `LimitError`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// let mut p = parser::Parser::<ply::DefaultElement>::new();
+/// p.set_limits(parser::Limits { max_rows: 1 << 20, ..parser::Limits::unlimited() });
+/// let evil = "ply\nformat ascii 1.0\nelement vertex 4294967296\nproperty float x\nend_header\n";
+/// let err = p.read_ply(&mut evil.as_bytes()).unwrap_err();
+/// assert!(matches!(err, ply::Error::Limit(parser::LimitError::TooManyRows { .. })));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Limits {
+    /// Bytes of the header, including `end_header`.
+    pub max_header_bytes: usize,
+    /// Rows declared for any one element.
+    pub max_rows: usize,
+    /// Entries of any one list value.
+    pub max_list_len: usize,
+    /// Bytes of all list values decoded since the header was read together, at their binary size.
+    ///
+    /// Unlike `max_list_len`, bounds the memory taken by many lists each within that limit.
+    pub max_list_bytes: u64,
+    /// Bytes of the values declared by the header, at their binary size with lists counted as empty.
+    pub max_payload_bytes: u64,
+}
+
+impl Limits {
+    /// No limits, the default.
+    pub fn unlimited() -> Self {
+        Limits { max_header_bytes: usize::MAX, max_rows: usize::MAX, max_list_len: usize::MAX, max_list_bytes: u64::MAX, max_payload_bytes: u64::MAX }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// Describes which of the `Limits` the input exceeded.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LimitError {
+    /// No `end_header` within `Limits::max_header_bytes`.
+    HeaderTooLarge { limit: usize },
+    /// An element declares more than `Limits::max_rows`.
+    TooManyRows { element: String, count: usize, limit: usize },
+    /// A list value is longer than `Limits::max_list_len`.
+    ListTooLong { length: usize, limit: usize },
+    /// List values decoded take more than `Limits::max_list_bytes`.
+    ListsTooLarge { bytes: u64, limit: u64 },
+    /// The header declares more than `Limits::max_payload_bytes`.
+    PayloadTooLarge { bytes: u64, limit: u64 },
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut Formatter) -> std::result::Result<(), fmt::Error> {
+        match *self {
+            LimitError::HeaderTooLarge { limit } =>
+                write!(f, "LimitError: header exceeds {} bytes", limit),
+            LimitError::TooManyRows { ref element, count, limit } =>
+                write!(f, "LimitError: element `{}` declares {} rows, limit is {}", element, count, limit),
+            LimitError::ListTooLong { length, limit } =>
+                write!(f, "LimitError: list of {} entries, limit is {}", length, limit),
+            LimitError::ListsTooLarge { bytes, limit } =>
+                write!(f, "LimitError: lists take {} bytes, limit is {}", bytes, limit),
+            LimitError::PayloadTooLarge { bytes, limit } =>
+                write!(f, "LimitError: header declares {} bytes of payload, limit is {}", bytes, limit),
+        }
+    }
+}
+
+impl error::Error for LimitError {}
+
+impl From<LimitError> for io::Error {
+    fn from(e: LimitError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Sets the bounds on the input, default is `Limits::unlimited()`.
+    ///
+    /// Header limits are checked while the header is read, before anything is allocated for the payload.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+    /// Reads a header line with its line break normalized to `\n`, `read` counts the header bytes read so far.
+    pub(super) fn __read_limited_header_line<T: BufRead>(&self, reader: &mut T, line: &mut String, read: &mut usize, ending: &mut Option<LineEnding>) -> Result<usize> {
+        let remaining = self.limits.max_header_bytes.saturating_sub(*read);
+        let too_large = LimitError::HeaderTooLarge { limit: self.limits.max_header_bytes };
+        if remaining == 0 {
+            return Err(too_large.into());
+        }
+        let (n, terminated) = read_header_line(reader, line, remaining, ending)?;
+        *read += n;
+        if n >= remaining && !terminated {
+            return Err(too_large.into());
+        }
+        Ok(n)
+    }
+    pub(super) fn __check_header_limits(&self, header: &Header) -> Result<()> {
+        self.list_bytes.store(0, Ordering::Relaxed);
+        let mut bytes = 0u64;
+        for e in header.elements.values() {
+            if e.count > self.limits.max_rows {
+                return Err(LimitError::TooManyRows { element: e.name.clone(), count: e.count, limit: self.limits.max_rows }.into());
+            }
+            let row = e.properties.values().map(|p| match p.data_type {
+                PropertyType::Scalar(ref s) | PropertyType::List(ref s, _) => s.byte_size() as u64,
+            }).sum::<u64>();
+            bytes = bytes.saturating_add(row.saturating_mul(e.count as u64));
+        }
+        if bytes > self.limits.max_payload_bytes {
+            return Err(LimitError::PayloadTooLarge { bytes, limit: self.limits.max_payload_bytes }.into());
+        }
+        Ok(())
+    }
+    pub(super) fn __check_list_len(&self, length: usize) -> Result<()> {
+        if length > self.limits.max_list_len {
+            return Err(LimitError::ListTooLong { length, limit: self.limits.max_list_len }.into());
+        }
+        Ok(())
+    }
+    /// Checks a list of `length` entries of `scalar_type` about to be decoded, counting its bytes towards `max_list_bytes`.
+    pub(super) fn __check_list_alloc(&self, length: usize, scalar_type: &ScalarType) -> Result<()> {
+        self.__check_list_len(length)?;
+        let limit = self.limits.max_list_bytes;
+        if limit == u64::MAX {
+            return Ok(());
+        }
+        let size = (length as u64).saturating_mul(scalar_type.byte_size() as u64);
+        let bytes = self.list_bytes.fetch_add(size, Ordering::Relaxed).saturating_add(size);
+        if bytes > limit {
+            return Err(LimitError::ListsTooLarge { bytes, limit }.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    fn limit_error(p: &Parser<DefaultElement>, data: &[u8]) -> Option<LimitError> {
+        match p.read_ply(&mut &data[..]) {
+            Err(crate::ply::Error::Limit(e)) => Some(e),
+            _ => None,
+        }
+    }
+    #[test]
+    fn limits_err() {
+        let header = "ply\nformat binary_little_endian 1.0\nelement face 2\nproperty list uchar int i\nproperty float a\nend_header\n";
+        let mut data = header.as_bytes().to_vec();
+        data.extend_from_slice(&[1, 7, 0, 0, 0, 0, 0, 0, 0, 4, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0]);
+        let mut p = Parser::<DefaultElement>::new();
+        assert!(p.read_ply(&mut &data[..]).is_ok());
+        p.set_limits(Limits { max_header_bytes: header.len(), max_rows: 2, max_payload_bytes: 10, max_list_len: 4, max_list_bytes: 20 });
+        assert!(p.read_ply(&mut &data[..]).is_ok());
+        p.set_limits(Limits { max_header_bytes: header.len() - 1, ..Limits::unlimited() });
+        assert_eq!(limit_error(&p, &data), Some(LimitError::HeaderTooLarge { limit: header.len() - 1 }));
+        p.set_limits(Limits { max_rows: 1, ..Limits::unlimited() });
+        assert!(matches!(limit_error(&p, &data), Some(LimitError::TooManyRows { count: 2, .. })));
+        p.set_limits(Limits { max_payload_bytes: 9, ..Limits::unlimited() });
+        assert_eq!(limit_error(&p, &data), Some(LimitError::PayloadTooLarge { bytes: 10, limit: 9 }));
+        p.set_limits(Limits { max_list_len: 3, ..Limits::unlimited() });
+        assert_eq!(limit_error(&p, &data), Some(LimitError::ListTooLong { length: 4, limit: 3 }));
+        let ascii = "ply\nformat ascii 1.0\nelement face 1\nproperty list uchar int i\nend_header\n4 0 1 2 3\n";
+        assert_eq!(limit_error(&p, ascii.as_bytes()), Some(LimitError::ListTooLong { length: 4, limit: 3 }));
+        p.set_limits(Limits { max_list_bytes: 19, ..Limits::unlimited() });
+        assert_eq!(limit_error(&p, &data), Some(LimitError::ListsTooLarge { bytes: 20, limit: 19 }));
+    }
+    #[test]
+    fn limits_many_small_lists() {
+        let rows = 1000;
+        let mut data = format!("ply\nformat binary_little_endian 1.0\nelement face {}\nproperty list uchar int i\nend_header\n", rows).into_bytes();
+        for _ in 0..rows {
+            data.push(4);
+            data.extend_from_slice(&[0; 16]);
+        }
+        let mut p = Parser::<DefaultElement>::new();
+        p.set_limits(Limits { max_list_len: 4, max_list_bytes: 16 * 1000, ..Limits::unlimited() });
+        assert!(p.read_ply(&mut &data[..]).is_ok());
+        assert!(p.read_ply(&mut &data[..]).is_ok());
+        p.set_limits(Limits { max_list_len: 4, max_list_bytes: 16 * 999, ..Limits::unlimited() });
+        assert_eq!(limit_error(&p, &data), Some(LimitError::ListsTooLarge { bytes: 16 * 1000, limit: 16 * 999 }));
+        let ascii = format!("ply\nformat ascii 1.0\nelement face {}\nproperty list uchar int i\nend_header\n{}", rows, "4 0 1 2 3\n".repeat(rows));
+        assert!(matches!(limit_error(&p, ascii.as_bytes()), Some(LimitError::ListsTooLarge { .. })));
+    }
+}
@@ -0,0 +1,119 @@
+//! Line breaks of the header, `\n`, `\r\n` as written by Windows tools or a lone `\r` of old Mac tools.
+
+use std::io;
+use std::io::{ BufRead, ErrorKind, Result };
+
+/// Line break of a header, detected on its first line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(super) enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+}
+
+/// Reads a header line of at most `limit` bytes into `line`, its line break normalized to `\n`.
+///
+/// `ending` is detected on the first line and holds for all following lines,
+/// so the byte after `end_header\r` is only taken for part of the line break if the file uses `\r\n`,
+/// never when it may start a binary payload.
+///
+/// Returns the number of bytes consumed and whether a line break was found.
+pub(super) fn read_header_line<T: BufRead>(reader: &mut T, line: &mut String, limit: usize, ending: &mut Option<LineEnding>) -> Result<(usize, bool)> {
+    let buf = reader.fill_buf()?;
+    let buf = &buf[..buf.len().min(limit)];
+    let (mut consumed, terminator) = match buf.iter().position(|&b| b == b'\n' || b == b'\r') {
+        // The whole line is buffered, it's appended without copying it first.
+        Some(i) => {
+            push_utf8(line, &buf[..i])?;
+            let terminator = buf[i];
+            reader.consume(i + 1);
+            (i, Some(terminator))
+        },
+        None => read_split_line(reader, line, limit)?,
+    };
+    match terminator {
+        Some(b'\n') => {
+            consumed += 1;
+            ending.get_or_insert(LineEnding::Lf);
+        },
+        Some(_) => {
+            consumed += 1;
+            if *ending != Some(LineEnding::Cr) {
+                let lf = reader.fill_buf()?.first() == Some(&b'\n');
+                if lf {
+                    reader.consume(1);
+                    consumed += 1;
+                }
+                ending.get_or_insert(if lf { LineEnding::CrLf } else { LineEnding::Cr });
+            }
+        },
+        None => (),
+    }
+    if terminator.is_some() {
+        line.push('\n');
+    }
+    Ok((consumed, terminator.is_some()))
+}
+
+/// Reads a line spanning several buffers, returns its length without the line break and the line break found.
+fn read_split_line<T: BufRead>(reader: &mut T, line: &mut String, limit: usize) -> Result<(usize, Option<u8>)> {
+    let mut bytes = Vec::new();
+    let mut terminator = None;
+    while terminator.is_none() && bytes.len() < limit {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let buf = &buf[..buf.len().min(limit - bytes.len())];
+        let (n, found) = match buf.iter().position(|&b| b == b'\n' || b == b'\r') {
+            Some(i) => (i + 1, Some(buf[i])),
+            None => (buf.len(), None),
+        };
+        bytes.extend_from_slice(&buf[..n - found.map_or(0, |_| 1)]);
+        reader.consume(n);
+        terminator = found;
+    }
+    push_utf8(line, &bytes)?;
+    Ok((bytes.len(), terminator))
+}
+
+fn push_utf8(line: &mut String, bytes: &[u8]) -> Result<()> {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => line.push_str(s),
+        Err(_) => return Err(io::Error::new(ErrorKind::InvalidData, "Header line isn't valid UTF-8.")),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn lines(data: &[u8]) -> (Vec<String>, Option<LineEnding>, Vec<u8>) {
+        let mut reader = data;
+        let mut ending = None;
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            read_header_line(&mut reader, &mut line, usize::MAX, &mut ending).unwrap();
+            lines.push(line.clone());
+            if line.is_empty() || line == "end_header\n" {
+                return (lines, ending, reader.to_vec());
+            }
+        }
+    }
+    #[test]
+    fn read_header_line_ok() {
+        let expected = vec!["ply\n".to_string(), "format x\n".to_string(), "end_header\n".to_string()];
+        assert_eq!(lines(b"ply\nformat x\nend_header\n\r\n"), (expected.clone(), Some(LineEnding::Lf), b"\r\n".to_vec()));
+        assert_eq!(lines(b"ply\r\nformat x\r\nend_header\r\n\n"), (expected.clone(), Some(LineEnding::CrLf), b"\n".to_vec()));
+        assert_eq!(lines(b"ply\rformat x\rend_header\r\n\r"), (expected, Some(LineEnding::Cr), b"\n\r".to_vec()));
+    }
+    #[test]
+    fn read_split_header_line() {
+        let mut reader = std::io::BufReader::with_capacity(3, &b"format x\r\nend"[..]);
+        let mut line = String::new();
+        let mut ending = None;
+        assert_eq!(read_header_line(&mut reader, &mut line, usize::MAX, &mut ending).unwrap(), (10, true));
+        assert_eq!((line.as_str(), ending), ("format x\n", Some(LineEnding::CrLf)));
+    }
+}
@@ -2,23 +2,104 @@
 
 use std::io;
 use std::io::{ Read, BufReader };
+use std::any::Any;
 use std::fmt::Debug;
 use std::result;
 
 use std::io::{ BufRead, Result, ErrorKind };
 
 mod ply_grammar;
+mod aggregate;
+pub use self::aggregate::*;
+mod ascii_parallel;
+#[cfg(feature = "async")]
+mod async_read;
+mod chunks;
+pub use self::chunks::PayloadChunks;
+mod column;
+pub use self::column::ColumnIter;
+mod columnar;
+mod diagnostics;
+mod documents;
+pub use self::documents::Documents;
+mod element_iter;
+pub use self::element_iter::ElementIter;
+mod endianness;
+#[cfg(feature = "gzip")]
+mod gzip;
+mod incremental;
+pub use self::incremental::{ IncrementalParser, ParseProgress };
+mod lenient;
+mod limits;
+mod line_ending;
+mod number;
+mod path;
+pub use self::limits::{ LimitError, Limits };
+mod preview;
+mod progress;
+mod projection;
+pub use self::projection::Projection;
+mod raw;
+pub use self::raw::{ RawElement, RawPly };
+mod regions;
+mod sampling;
+pub use self::sampling::Sampling;
+mod slice;
+mod stats;
+pub use self::stats::ParseStats;
+mod visitor;
+pub use self::visitor::PlyVisitor;
+mod warning;
+pub use self::warning::Warning;
+#[cfg(feature = "zstd")]
+mod zstd;
 
 use self::ply_grammar::grammar;
 use self::ply_grammar::Line;
+use self::number::{ Number, parse_number };
 use crate::util::LocationTracker;
+#[cfg(feature = "checksum")]
+use crate::checksum::{ Checksum, ChecksumError, ChecksumReader };
+use crate::ply::Error;
 
-fn parse_ascii_rethrow<T, E: Debug>(location: &LocationTracker, line_str: &str, e: E, message: &str) -> Result<T> {
+fn parse_ascii_rethrow<T, E: Debug + Any>(location: &LocationTracker, line_str: &str, e: E, message: &str) -> Result<T> {
+    let details = format!("{:?}", e);
+    // Structured errors, e.g. exceeded limits, are passed on unchanged.
+    if let Ok(e) = (Box::new(e) as Box<dyn Any>).downcast::<io::Error>() {
+        if e.kind() == ErrorKind::InvalidData {
+            return Err(*e);
+        }
+    }
     Err(io::Error::new(
         ErrorKind::InvalidInput,
-        format!("Line {}: {}\n\tString: '{}'\n\tError: {:?}", location.line_index, message, line_str, e)
+        format!("Line {}: {}\n\tString: '{}'\n\tError: {}", location.line_index, message, line_str, details)
     ))
 }
+/// Error for an element line that couldn't be decoded, shared by all ascii readers.
+fn element_line_rethrow<T, E: Debug + Any>(location: &LocationTracker, line_str: &str, e: E) -> Result<T> {
+    parse_ascii_rethrow(location, line_str, e, "Couln't read element line.")
+}
+fn header_syntax_error<T>(location: &LocationTracker, offset: usize, line_str: &str, message: &str) -> Result<T> {
+    Err(Error::Syntax {
+        line: location.line_index,
+        offset: offset as u64,
+        message: format!("{}\n\tString: '{}'", message, line_str),
+    }.into())
+}
+fn header_schema_error<T>(location: &LocationTracker, offset: usize, line_str: &str, message: &str) -> Result<T> {
+    Err(Error::Schema {
+        line: location.line_index,
+        offset: offset as u64,
+        message: format!("{}\n\tString: '{}'", message, line_str),
+    }.into())
+}
+/// Attaches the element and row to errors reading the payload, structured errors are passed on unchanged.
+fn payload_error(e: io::Error, element_def: &ElementDef, index: usize) -> io::Error {
+    match Error::from(e) {
+        Error::Io(source) => Error::Payload { element: element_def.name.clone(), index, source }.into(),
+        e => e.into(),
+    }
+}
 fn parse_ascii_error<T>(location: &LocationTracker, line_str: &str, message: &str) -> Result<T> {
     Err(io::Error::new(
         ErrorKind::InvalidInput,
@@ -27,6 +108,24 @@ fn parse_ascii_error<T>(location: &LocationTracker, line_str: &str, message: &st
 }
 
 use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+
+/// Defines how strictly the input has to follow the PLY specification.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Strictness {
+    /// Reject anything off-spec.
+    Strict,
+    /// Tolerate common deviations found in files of real-world exporters.
+    ///
+    /// - Float values may be given as `nan`, `inf`, `-inf` or `infinity` in ascii payloads (case-insensitive),
+    ///   they are reported as `Warning::NonFiniteValues`.
+    /// - Header lines may be indented, blank or followed by garbage, repaired lines are reported as `Warning::RepairedHeaderLine`.
+    /// - Ascii rows may have trailing values or comments after the last property, they are ignored.
+    ///
+    /// A missing line break after the last ascii row is accepted in both modes.
+    Lenient,
+}
 
 /// Reads data given by a `Read` trait into `Ply` components.
 ///
@@ -91,7 +190,22 @@ use std::marker::PhantomData;
 /// ```
 ///
 pub struct Parser<E: PropertyAccess> {
-      phantom: PhantomData<E>,
+      #[cfg(feature = "checksum")]
+      verify_checksum: bool,
+      ascii_threads: usize,
+      strictness: Strictness,
+      sampling: Sampling,
+      out_of_range: OutOfRange,
+      check_endianness: bool,
+      infer_encoding: bool,
+      projection: Projection,
+      limits: Limits,
+      list_bytes: AtomicU64,
+      progress: Option<progress::Progress>,
+      measure_time: bool,
+      stats: Mutex<Option<ParseStats>>,
+      warnings: Mutex<Vec<Warning>>,
+      phantom: PhantomData<fn() -> E>,
 }
 
 
@@ -106,19 +220,122 @@ impl<E: PropertyAccess> Parser<E> {
     /// To get started quickly try `DefaultElement` from the `ply` module.
     pub fn new() -> Self {
         Parser {
+            #[cfg(feature = "checksum")]
+            verify_checksum: false,
+            ascii_threads: 1,
+            strictness: Strictness::Strict,
+            sampling: Sampling::All,
+            out_of_range: OutOfRange::Error,
+            check_endianness: false,
+            infer_encoding: false,
+            projection: Projection::all(),
+            limits: Limits::unlimited(),
+            list_bytes: AtomicU64::new(0),
+            progress: None,
+            measure_time: false,
+            stats: Mutex::new(None),
+            warnings: Mutex::new(Vec::new()),
             phantom: PhantomData
         }
     }
+    /// Enables verification of the payload checksum.
+    ///
+    /// If enabled and the header contains a comment `checksum <algorithm>:<hex digest>`,
+    /// the payload bytes are hashed while being read.
+    /// A mismatch is reported as an `io::Error` of kind `InvalidData` wrapping a `ChecksumError`.
+    /// See the `checksum` module for the supported algorithms.
+    ///
+    /// Disabled by default.
+    #[cfg(feature = "checksum")]
+    pub fn set_verify_checksum(&mut self, verify: bool) {
+        self.verify_checksum = verify;
+    }
+    /// Sets the number of threads used to decode ascii elements.
+    ///
+    /// Only elements without list properties are decoded in parallel:
+    /// Their lines are read in batches, split into ranges and parsed concurrently.
+    /// The elements are stored in the same order as in the file.
+    ///
+    /// `0` uses the available parallelism of the system. Default is `1`, no additional threads are spawned.
+    ///
+    /// With the `rayon` feature the ranges are parsed on the rayon thread pool instead of newly spawned threads,
+    /// `0` then uses the size of the current pool.
+    pub fn set_ascii_threads(&mut self, threads: usize) {
+        self.ascii_threads = threads;
+    }
+    /// Sets how strictly the input has to follow the specification, default is `Strictness::Strict`.
+    pub fn set_strictness(&mut self, strictness: Strictness) {
+        self.strictness = strictness;
+    }
+    /// Sets which rows of each element are kept, default is `Sampling::All`.
+    ///
+    /// Skipped rows aren't stored and, where possible, not decoded.
+    /// Sampling applies to every element type, face indices may hence refer to vertices that have been skipped.
+    pub fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+    }
+    /// Sets how ascii values out of range of their property type are handled, default is `OutOfRange::Error`.
+    ///
+    /// Applies to integers too large for their type, e.g. `300` in a `uchar` property,
+    /// and finite numbers overflowing a float type, e.g. `1e40` in a `float` property.
+    /// Whole numbers in exponent form are accepted for integer types, e.g. `1e3` in an `int` property.
+    ///
+    /// Before this setting existed, float overflow was read as infinity, as with `OutOfRange::Wrap` now,
+    /// and integers in exponent form were rejected.
+    pub fn set_out_of_range(&mut self, policy: OutOfRange) {
+        self.out_of_range = policy;
+    }
+    /// Enables a plausibility check of the declared byte order of binary files.
+    ///
+    /// Before reading the payload, the float properties of the first rows are decoded in both byte orders.
+    /// If the values look implausible (e.g. huge exponents or denormals) as declared, but sane in the other order,
+    /// a `Warning::SuspiciousEndianness` is recorded. Only the first element is checked.
+    ///
+    /// Disabled by default.
+    pub fn set_check_endianness(&mut self, check: bool) {
+        self.check_endianness = check;
+    }
+    /// Enables recovery of headers whose `format` line is missing or malformed.
+    ///
+    /// A malformed line starting with `format` or a misspelling of it is dropped. Without a valid `format` line,
+    /// the encoding is guessed from the bytes buffered after `end_header`: Printable characters are taken for ascii,
+    /// otherwise the byte order decoding plausible floats and list lengths is chosen.
+    /// The guess is recorded as `Warning::InferredEncoding`, the version is assumed to be 1.0.
+    ///
+    /// The payload has to follow the header in a buffered reader, e.g. with `read_ply()`. Disabled by default.
+    pub fn set_infer_encoding(&mut self, infer: bool) {
+        self.infer_encoding = infer;
+    }
+    /// Returns all warnings collected while reading and clears them.
+    ///
+    /// Warnings accumulate over all read operations of this parser until they are taken.
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.__warnings())
+    }
+    fn __warnings(&self) -> std::sync::MutexGuard<'_, Vec<Warning>> {
+        self.warnings.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    fn __warn(&self, warning: Warning) {
+        self.__warnings().push(warning);
+    }
 
     /// Expects the complete content of a PLY file.
     ///
     /// A PLY file starts with "ply\n". `read_ply` reads until all elements have been read as
     /// defined in the header of the PLY file.
-    pub fn read_ply<T: Read>(&self, source: &mut T) -> Result<Ply<E>> {
-        let mut source = BufReader::new(source);
+    pub fn read_ply<T: Read>(&self, source: &mut T) -> result::Result<Ply<E>, Error> {
+        let start = self.__stats_start();
+        let mut source = BufReader::new(self.__progress_reader(source));
         let mut location = LocationTracker::new();
         let header = self.__read_header(&mut source, &mut location)?;
-        let payload = self.__read_payload(&mut source, &mut location, &header)?;
+        let header_bytes = source.get_ref().count - source.buffer().len() as u64;
+        let progress = self.__progress_start(&header);
+        let payload = self.__read_payload(&mut source, &mut location, &header);
+        if progress {
+            self.__progress_stop();
+        }
+        let payload = payload?;
+        self.__record_stats(start, header_bytes, source.get_ref().count - source.buffer().len() as u64, &payload);
         let mut ply = Ply::new();
         ply.header = header;
         ply.payload = payload;
@@ -126,8 +343,15 @@ impl<E: PropertyAccess> Parser<E> {
     }
 }
 
+impl<E: PropertyAccess> Default for Parser<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // use ply::{ Header, Encoding };
 use crate::ply::{ PropertyAccess, Version, ObjInfo, Comment, ElementDef, KeyMap, Addable };
+use self::diagnostics::with_suggestion;
 /*
 use util::LocationTracker;
 use super::Parser;
@@ -139,6 +363,41 @@ use std::io::{ BufRead, ErrorKind, Result };
 use std::result;
 // */
 
+/// `BufRead` over a `Read` that never reads ahead more than the byte returned by `fill_buf()`.
+struct Unbuffered<'a, T: Read> {
+    inner: &'a mut T,
+    byte: [u8; 1],
+    available: bool,
+}
+
+impl<'a, T: Read> Read for Unbuffered<'a, T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.available {
+            self.available = false;
+            buf[0] = self.byte[0];
+            return Ok(1);
+        }
+        self.inner.read(&mut buf[..1])
+    }
+}
+
+impl<'a, T: Read> BufRead for Unbuffered<'a, T> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        if !self.available {
+            self.available = self.inner.read(&mut self.byte)? == 1;
+        }
+        Ok(if self.available { &self.byte } else { &[] })
+    }
+    fn consume(&mut self, amount: usize) {
+        if amount > 0 {
+            self.available = false;
+        }
+    }
+}
+
 // ////////////////////////
 /// #Header
 // ////////////////////////
@@ -147,16 +406,39 @@ impl<E: PropertyAccess> Parser<E> {
     ///
     /// A ply file starts with "ply\n". The header and the payload are separated by a line `end_header\n`.
     /// This method reads all headere elemnts up to `end_header`.
-    pub fn read_header<T: BufRead>(&self, reader: &mut T) -> Result<Header> {
+    /// Lines may also end with "\r\n" or "\r", as detected on the first line, the line break after `end_header` is never part of the payload.
+    pub fn read_header<T: BufRead>(&self, reader: &mut T) -> result::Result<Header, Error> {
         let mut line = LocationTracker::new();
-        self.__read_header(reader, &mut line)
+        Ok(self.__read_header(reader, &mut line)?)
+    }
+    /// Reads the header from a stream without buffering, one byte at a time.
+    ///
+    /// Unlike `read_header()` nothing past `end_header` is consumed, `reader` is left positioned at the start of the payload.
+    /// Use it to inspect element counts and property definitions without touching the payload.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// use std::io::Read;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let header = p.read_header_only(&mut f).unwrap();
+    /// assert_eq!(header.elements["vertex"].count, 8);
+    /// let mut first_row = [0; 6];
+    /// f.read_exact(&mut first_row).unwrap();
+    /// assert_eq!(&first_row, b"0 0 0\n");
+    /// ```
+    pub fn read_header_only<T: Read>(&self, reader: &mut T) -> result::Result<Header, Error> {
+        let mut reader = Unbuffered { inner: reader, byte: [0], available: false };
+        self.read_header(&mut reader)
     }
     pub fn read_header_line(&self, line: &str) -> Result<Line> {
         match self.__read_header_line(line) {
             Ok(l) => Ok(l),
             Err(e) => Err(io::Error::new(
                 ErrorKind::InvalidInput,
-                format!("Couldn't parse line.\n\tString: {}\n\tError: {:?}", line, e)
+                format!("{}\n\tString: {}\n\tError: {:?}", with_suggestion("Couldn't parse line.", line), line, e)
             )),
         }
     }
@@ -168,42 +450,47 @@ impl<E: PropertyAccess> Parser<E> {
     fn __read_header<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker) -> Result<Header> {
         location.next_line();
         let mut line_str = String::new();
-        reader.read_line(&mut line_str)?;
-        match self.__read_header_line(&line_str) {
+        let mut header_bytes = 0;
+        let mut line_start = header_bytes;
+        let mut line_ending = None;
+        self.__read_limited_header_line(reader, &mut line_str, &mut header_bytes, &mut line_ending)?;
+        match self.__read_lenient_header_line(&line_str, location.line_index) {
             Ok(Line::MagicNumber) => (),
-            Ok(l) => return parse_ascii_error(location, &line_str, &format!("Expected magic number 'ply', but saw '{:?}'.", l)),
-            Err(e) => return parse_ascii_rethrow(location, &line_str, e, "Expected magic number 'ply'.")
+            Ok(l) => return header_syntax_error(location, line_start, &line_str, &format!("Expected magic number 'ply', but saw '{:?}'.", l)),
+            Err(e) => return header_syntax_error(location, line_start, &line_str,
+                &format!("{}\n\tError: {:?}", with_suggestion("Expected magic number 'ply'.", &line_str), e)),
         }
-        match grammar::line(&line_str) {
-            Err(e) => return Err(io::Error::new(ErrorKind::InvalidInput, e)),
-            Ok(l @ Line::MagicNumber) => l,
-            Ok(ob) => return Err(io::Error::new(
-                ErrorKind::InvalidInput,
-                format!("Invalid line encountered. Expected type: 'Line::MagicNumber', found: '{:?}'", ob)
-            )),
-        };
 
         let mut header_form_ver : Option<(Encoding, Version)> = None;
         let mut header_obj_infos = Vec::<ObjInfo>::new();
         let mut header_elements = KeyMap::<ElementDef>::new();
         let mut header_comments = Vec::<Comment>::new();
+        let mut discarded_format = None;
         location.next_line();
         'readlines: loop {
             line_str.clear();
-            reader.read_line(&mut line_str)?;
-            let line = self.__read_header_line(&line_str);
+            line_start = header_bytes;
+            let n = self.__read_limited_header_line(reader, &mut line_str, &mut header_bytes, &mut line_ending)?;
+            if self.strictness == Strictness::Lenient && n > 0 && line_str.trim().is_empty() {
+                location.next_line();
+                continue;
+            }
+            let line = self.__read_lenient_header_line(&line_str, location.line_index);
 
             match line {
-                Err(e) => return parse_ascii_rethrow(location, &line_str, e, "Couldn't parse line."),
-                Ok(Line::MagicNumber) => return parse_ascii_error(location, &line_str, "Unexpected 'ply' found."),
+                Err(_) if self.infer_encoding && header_form_ver.is_none() && diagnostics::is_format_like(&line_str) => {
+                    discarded_format = Some(line_str.trim_end().to_string());
+                },
+                Err(e) => return header_syntax_error(location, line_start, &line_str,
+                    &format!("{}\n\tError: {:?}", with_suggestion("Couldn't parse line.", &line_str), e)),
+                Ok(Line::MagicNumber) => return header_schema_error(location, line_start, &line_str, "Unexpected 'ply' found."),
                 Ok(Line::Format(ref t)) =>
-                    if header_form_ver.is_none() {
-                        header_form_ver = Some(t.clone());
-                    } else {
-                        let f = header_form_ver.unwrap();
-                        if f != *t {
-                            return parse_ascii_error(
+                    match header_form_ver {
+                        None => header_form_ver = Some(*t),
+                        Some(f) => if f != *t {
+                            return header_schema_error(
                                 location,
+                                line_start,
                                 &line_str,
                                 &format!(
                                     "Found contradicting format definition:\n\
@@ -212,7 +499,7 @@ impl<E: PropertyAccess> Parser<E> {
                                     \tEncoding: {:?}, Version: {:?}",
                                     t.0, t.1, f.0, f.1)
                             )
-                        }
+                        },
                     }
                 ,
                 Ok(Line::ObjInfo(ref o)) =>
@@ -226,8 +513,9 @@ impl<E: PropertyAccess> Parser<E> {
                 },
                 Ok(Line::Property(p)) =>
                     if header_elements.is_empty() {
-                        return parse_ascii_error(
+                        return header_schema_error(
                             location,
+                            line_start,
                             &line_str,
                             &format!("Property '{:?}' found without preceding element.", p)
                         );
@@ -237,67 +525,111 @@ impl<E: PropertyAccess> Parser<E> {
                         header_elements.add(e);
                     }
                 ,
-                Ok(Line::EndHeader) => { location.next_line(); break 'readlines; },
+                Ok(Line::EndHeader) => break 'readlines,
             };
             location.next_line();
         }
-        if header_form_ver.is_none() {
-            return Err(io::Error::new(
-                ErrorKind::InvalidInput,
-                "No format line found."
-            ));
-        }
-        let (encoding, version) = header_form_ver.unwrap();
-        Ok(Header{
-            encoding: encoding,
-            version: version,
+        let (encoding, version) = match header_form_ver {
+            Some(f) => f,
+            None if self.infer_encoding => match endianness::infer_encoding(reader.fill_buf()?, &header_elements) {
+                Some(encoding) => {
+                    self.__warn(Warning::InferredEncoding { encoding, discarded: discarded_format });
+                    (encoding, Version { major: 1, minor: 0 })
+                },
+                None => return header_schema_error(location, line_start, &line_str,
+                    "No format line found and the encoding couldn't be inferred from the payload."),
+            },
+            None => return header_schema_error(location, line_start, &line_str, "No format line found."),
+        };
+        location.next_line();
+        let header = Header{
+            encoding,
+            version,
             obj_infos: header_obj_infos,
             comments: header_comments,
             elements: header_elements
-        })
+        };
+        self.__check_header_limits(&header)?;
+        Ok(header)
     }
 }
 
 // //////////////////////
 /// # Payload
 // //////////////////////
-
 impl<E: PropertyAccess> Parser<E> {
     /// Reads payload. Encoding is chosen according to the encoding field in `header`.
-    pub fn read_payload<T: BufRead>(&self, reader: &mut T, header: &Header) -> Result<Payload<E>> {
+    pub fn read_payload<T: BufRead>(&self, reader: &mut T, header: &Header) -> result::Result<Payload<E>, Error> {
         let mut location = LocationTracker::new();
-        self.__read_payload(reader, &mut location, header)
+        let progress = self.__progress_start(header);
+        let payload = self.__read_payload(reader, &mut location, header);
+        if progress {
+            self.__progress_stop();
+        }
+        Ok(payload?)
     }
     /// Reads entire list of elements from payload. Encoding is chosen according to `header`.
     ///
     /// Make sure to read the elements in the order as they are defined in the header.
-    pub fn read_payload_for_element<T: BufRead>(&self, reader: &mut T, element_def: &ElementDef, header: &Header) -> Result<Vec<E>> {
+    pub fn read_payload_for_element<T: BufRead>(&self, reader: &mut T, element_def: &ElementDef, header: &Header) -> result::Result<Vec<E>, Error> {
         let mut location = LocationTracker::new();
-        match header.encoding {
-            Encoding::Ascii => self.__read_ascii_payload_for_element(reader, &mut location, element_def),
-            Encoding::BinaryBigEndian => self.__read_big_endian_payload_for_element(reader, &mut location, element_def),
-            Encoding::BinaryLittleEndian => self.__read_little_endian_payload_for_element(reader, &mut location, element_def),
+        Ok(self.__read_located_element_list(reader, &mut location, element_def, &header.encoding)?)
+    }
+    /// internal wrapper, verifies the checksum if requested
+    #[cfg(feature = "checksum")]
+    fn __read_payload<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, header: &Header) -> Result<Payload<E>> {
+        let expected = match Checksum::from_header(header) {
+            Some(c) if self.verify_checksum => c,
+            _ => return self.__read_payload_unchecked(reader, location, header),
+        };
+        let mut reader = ChecksumReader::new(reader, expected.algorithm());
+        let payload = self.__read_payload_unchecked(&mut reader, location, header)?;
+        let actual = reader.finish();
+        if actual != expected {
+            return Err(ChecksumError::new(expected, actual).into());
         }
+        Ok(payload)
     }
-    /// internal dispatcher based on the encoding
+    /// internal wrapper, checksums can only be verified with feature `checksum`
+    #[cfg(not(feature = "checksum"))]
     fn __read_payload<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, header: &Header) -> Result<Payload<E>> {
+        self.__read_payload_unchecked(reader, location, header)
+    }
+    /// internal loop over all element types
+    fn __read_payload_unchecked<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, header: &Header) -> Result<Payload<E>> {
+        if self.check_endianness {
+            self.__check_endianness(reader, header)?;
+        }
         let mut payload = Payload::new();
-        match header.encoding {
-            Encoding::Ascii => for (k, ref e) in &header.elements {
-                let elems = self.__read_ascii_payload_for_element(reader, location, e)?;
-                payload.insert(k.clone(), elems);
-            },
-            Encoding::BinaryBigEndian => for (k, ref e) in &header.elements {
-                let elems = self.__read_big_endian_payload_for_element(reader, location, e)?;
-                payload.insert(k.clone(), elems);
-            },
-            Encoding::BinaryLittleEndian => for (k, ref e) in &header.elements {
-                let elems = self.__read_little_endian_payload_for_element(reader, location, e)?;
-                payload.insert(k.clone(), elems);
+        for (k, e) in &header.elements {
+            if !self.projection.keeps_element(k) {
+                let first_line = location.line_index;
+                self.__skip_element(reader, location, e, &header.encoding)
+                    .map_err(|err| payload_error(err, e, location.line_index - first_line))?;
+                continue;
             }
+            let elems = self.__read_located_element_list(reader, location, e, &header.encoding)?;
+            payload.insert(k.clone(), elems);
         }
         Ok(payload)
     }
+    /// Like `__read_element_list()`, but errors tell the failing row.
+    fn __read_located_element_list<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef, encoding: &Encoding) -> Result<Vec<E>> {
+        let first_line = location.line_index;
+        self.__read_element_list(reader, location, element_def, encoding)
+            .map_err(|e| payload_error(e, element_def, location.line_index - first_line))
+    }
+    /// internal dispatcher based on the encoding and sampling
+    pub(crate) fn __read_element_list<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef, encoding: &Encoding) -> Result<Vec<E>> {
+        if self.sampling != Sampling::All {
+            return self.__read_sampled_payload_for_element(reader, location, element_def, encoding);
+        }
+        match *encoding {
+            Encoding::Ascii => self.__read_ascii_payload_for_element(reader, location, element_def),
+            Encoding::BinaryBigEndian => self.__read_big_endian_payload_for_element(reader, location, element_def),
+            Encoding::BinaryLittleEndian => self.__read_little_endian_payload_for_element(reader, location, element_def),
+        }
+    }
 }
 
 
@@ -319,50 +651,68 @@ use ply::{ PropertyAccess, ElementDef };
 use std::slice::Iter;
 use std::str::FromStr;
 
-use crate::ply::{ Property, PropertyType, ScalarType };
+use crate::ply::{ OutOfRange, Property, PropertyType, ScalarType };
 use std::error;
 use std::marker;
 
 /// # Ascii
 impl<E: PropertyAccess> Parser<E> {
     fn __read_ascii_payload_for_element<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef) -> Result<Vec<E>> {
+        let threads = self.__ascii_threads();
+        if threads > 1 && ascii_parallel::is_parallelizable(element_def) {
+            return self.__read_ascii_payload_for_element_parallel(reader, location, element_def, threads);
+        }
         let mut elems = Vec::<E>::new();
         let mut line_str = String::new();
+        let mut non_finite = 0;
         for _ in 0..element_def.count {
             line_str.clear();
             reader.read_line(&mut line_str)?;
 
-            let element = match self.read_ascii_element(&line_str, element_def) {
+            let element = match self.__read_ascii_element(&line_str, element_def, &mut non_finite) {
                 Ok(e) => e,
-                Err(e) => return parse_ascii_rethrow(location, &line_str, e, "Couln't read element line.")
+                Err(e) => return element_line_rethrow(location, &line_str, e)
             };
             elems.push(element);
             location.next_line();
+            self.__progress_rows(1);
         }
+        self.__warn_non_finite(element_def, non_finite);
         Ok(elems)
     }
     /// Read a single element. Assume it is encoded in ascii.
     ///
     /// Make sure all elements are parsed in the order they are defined in the header.
     pub fn read_ascii_element(&self, line: &str, element_def: &ElementDef) -> Result<E> {
-        let elems = match grammar::data_line(line) {
-            Ok(e) => e,
-            Err(ref e) => return Err(io::Error::new(
-                    ErrorKind::InvalidInput,
-                    format!("Couldn't parse element line.\n\tString: '{}'\n\tError: {}", line, e)
-                )),
-        };
+        let mut non_finite = 0;
+        let element = self.__read_ascii_element(line, element_def, &mut non_finite)?;
+        self.__warn_non_finite(element_def, non_finite);
+        Ok(element)
+    }
+    fn __warn_non_finite(&self, element_def: &ElementDef, count: usize) {
+        if count > 0 {
+            self.__warn(Warning::NonFiniteValues { element: element_def.name.clone(), count });
+        }
+    }
+    fn __read_ascii_element(&self, line: &str, element_def: &ElementDef, non_finite: &mut usize) -> Result<E> {
+        let elems = self.__tokenize_ascii_line(line)?;
 
-        let mut elem_it : Iter<String> = elems.iter();
+        let mut elem_it : Iter<&str> = elems.iter();
         let mut vals = E::new();
+        let selected = self.projection.selected(&element_def.name);
         for (k, p) in &element_def.properties {
-            let new_p : Property = self.__read_ascii_property(&mut elem_it, &p.data_type)?;
-            vals.set_property(k.clone(), new_p);
+            if !selected.contains(k) {
+                self.__skip_ascii_property(&mut elem_it, &p.data_type)?;
+                continue;
+            }
+            let new_p : Property = self.__read_ascii_property(&mut elem_it, &p.data_type, non_finite)?;
+            vals.set_named_property(k, new_p);
         }
+        self.__check_trailing_values(&mut elem_it)?;
         Ok(vals)
     }
-    fn __read_ascii_property(&self, elem_iter: &mut Iter<String>, data_type: &PropertyType) -> Result<Property> {
-        let s : &String = match elem_iter.next() {
+    fn __read_ascii_property(&self, elem_iter: &mut Iter<&str>, data_type: &PropertyType, non_finite: &mut usize) -> Result<Property> {
+        let s : &str = match elem_iter.next() {
             None => return Err(io::Error::new(
                 ErrorKind::InvalidInput,
                 format!("Expected element of type '{:?}', but found nothing.", data_type)
@@ -371,54 +721,123 @@ impl<E: PropertyAccess> Parser<E> {
         };
 
         let result = match *data_type {
-            PropertyType::Scalar(ref scalar_type) => match *scalar_type {
-                ScalarType::Char => Property::Char(self.parse(s)?),
-                ScalarType::UChar => Property::UChar(self.parse(s)?),
-                ScalarType::Short => Property::Short(self.parse(s)?),
-                ScalarType::UShort => Property::UShort(self.parse(s)?),
-                ScalarType::Int => Property::Int(self.parse(s)?),
-                ScalarType::UInt => Property::UInt(self.parse(s)?),
-                ScalarType::Float => Property::Float(self.parse(s)?),
-                ScalarType::Double => Property::Double(self.parse(s)?),
+            PropertyType::Scalar(ref scalar_type) => {
+                if let ScalarType::Float | ScalarType::Double = *scalar_type {
+                    self.__check_special_float(s, non_finite)?;
+                }
+                self.__parse_scalar(s, scalar_type)?
             },
             PropertyType::List(_, ref scalar_type) => {
                 let count : usize = self.parse(s)?;
+                self.__check_list_alloc(count, scalar_type)?;
+                if let ScalarType::Float | ScalarType::Double = *scalar_type {
+                    for s in elem_iter.clone().take(count) {
+                        self.__check_special_float(s, non_finite)?;
+                    }
+                }
+                macro_rules! list {
+                    ($list:ident, $scalar:ident) => {
+                        Property::$list(self.__read_ascii_list(elem_iter, count, scalar_type, |p| match p {
+                            Property::$scalar(v) => v,
+                            _ => unreachable!(),
+                        })?)
+                    };
+                }
                 match *scalar_type {
-                    ScalarType::Char => Property::ListChar(self.__read_ascii_list(elem_iter, count)?),
-                    ScalarType::UChar => Property::ListUChar(self.__read_ascii_list(elem_iter, count)?),
-                    ScalarType::Short => Property::ListShort(self.__read_ascii_list(elem_iter, count)?),
-                    ScalarType::UShort => Property::ListUShort(self.__read_ascii_list(elem_iter, count)?),
-                    ScalarType::Int => Property::ListInt(self.__read_ascii_list(elem_iter, count)?),
-                    ScalarType::UInt => Property::ListUInt(self.__read_ascii_list(elem_iter, count)?),
-                    ScalarType::Float => Property::ListFloat(self.__read_ascii_list(elem_iter, count)?),
-                    ScalarType::Double => Property::ListDouble(self.__read_ascii_list(elem_iter, count)?),
+                    ScalarType::Char => list!(ListChar, Char),
+                    ScalarType::UChar => list!(ListUChar, UChar),
+                    ScalarType::Short => list!(ListShort, Short),
+                    ScalarType::UShort => list!(ListUShort, UShort),
+                    ScalarType::Int => list!(ListInt, Int),
+                    ScalarType::UInt => list!(ListUInt, UInt),
+                    ScalarType::Float => list!(ListFloat, Float),
+                    ScalarType::Double => list!(ListDouble, Double),
                 }
             }
         };
         Ok(result)
     }
-
-    fn parse<D: FromStr>(&self, s: &str) -> Result<D>
+    /// Parses a scalar, values out of range of `scalar_type` are handled according to `set_out_of_range()`.
+    fn __parse_scalar(&self, s: &str, scalar_type: &ScalarType) -> Result<Property> {
+        let parsed = match *scalar_type {
+            ScalarType::Char => self.parse(s).map(Property::Char),
+            ScalarType::UChar => self.parse(s).map(Property::UChar),
+            ScalarType::Short => self.parse(s).map(Property::Short),
+            ScalarType::UShort => self.parse(s).map(Property::UShort),
+            ScalarType::Int => self.parse(s).map(Property::Int),
+            ScalarType::UInt => self.parse(s).map(Property::UInt),
+            ScalarType::Float => self.parse(s).map(Property::Float),
+            ScalarType::Double => self.parse(s).map(Property::Double),
+        };
+        let overflow = match parsed {
+            // Finite text parsed to infinity.
+            Ok(Property::Float(v)) if v.is_infinite() && s.ends_with(|c: char| c.is_ascii_digit()) => true,
+            Ok(Property::Double(v)) if v.is_infinite() && s.ends_with(|c: char| c.is_ascii_digit()) => true,
+            Ok(_) => return parsed,
+            Err(_) => false,
+        };
+        // Integers too large for their type still parse as f64, fractions are not accepted.
+        let value = match s.parse::<f64>() {
+            Ok(v) if overflow || v.fract() == 0.0 => v,
+            _ => return parsed,
+        };
+        let converted = match self.out_of_range {
+            // Beyond the range of f64, hence also a `double` overflow.
+            policy if value.is_infinite() => match policy {
+                OutOfRange::Error => None,
+                OutOfRange::Clamp => Property::from_f64(value.signum() * f64::MAX, scalar_type, policy),
+                OutOfRange::Wrap => Property::from_f64(value, scalar_type, policy),
+            },
+            policy => Property::from_f64(value, scalar_type, policy),
+        };
+        match converted {
+            Some(p) => Ok(p),
+            None => {
+                let (min, max) = scalar_type.range();
+                Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Value '{}' is out of range for type {:?} ({} to {}).", s, scalar_type, min, max)
+                ))
+            },
+        }
+    }
+    fn __check_special_float(&self, s: &str, non_finite: &mut usize) -> Result<()> {
+        if !s.trim_start_matches(['+', '-']).starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return Ok(());
+        }
+        if self.strictness == Strictness::Strict {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Non-finite value '{}' is only accepted in lenient mode.", s)
+            ));
+        }
+        *non_finite += 1;
+        Ok(())
+    }
+    fn parse<D: Number>(&self, s: &str) -> Result<D>
     where <D as FromStr>::Err: error::Error + Send + Sync + 'static {
-        let v = s.parse();
+        let v = parse_number(s);
         match v {
             Ok(r) => Ok(r),
             Err(e) => Err(io::Error::new(ErrorKind::InvalidInput,
                 format!("Parse error.\n\tValue: '{}'\n\tError: {:?}, ", s, e))),
         }
     }
-    fn __read_ascii_list<D: FromStr>(&self, elem_iter: &mut Iter<String>, count: usize) -> Result<Vec<D>>
+    fn __read_ascii_list<D: Number>(&self, elem_iter: &mut Iter<&str>, count: usize, scalar_type: &ScalarType, extract: fn(Property) -> D) -> Result<Vec<D>>
         where <D as FromStr>::Err: error::Error + marker::Send + marker::Sync + 'static {
         let mut list = Vec::<D>::new();
         for i in 0..count {
-            let s : &String = match elem_iter.next() {
+            let s : &str = match elem_iter.next() {
                 None => return Err(io::Error::new(
                     ErrorKind::InvalidInput,
                     format!("Couldn't find a list element at index {}.", i)
                 )),
                 Some(x) => x
             };
-            let value : D = self.parse(s)?;
+            let value : D = match parse_number(s) {
+                Ok(v) => v,
+                Err(_) => extract(self.__parse_scalar(s, scalar_type)?),
+            };
             list.push(value);
         }
         Ok(list)
@@ -472,15 +891,20 @@ impl<E: PropertyAccess> Parser<E> {
             let element = self.__read_binary_element::<T, B>(reader, element_def)?;
             elems.push(element);
             location.next_line();
+            self.__progress_rows(1);
         }
         Ok(elems)
     }
     fn __read_binary_element<T: Read, B: ByteOrder>(&self, reader: &mut T, element_def: &ElementDef) -> Result<E> {
         let mut raw_element = E::new();
-
+        let selected = self.projection.selected(&element_def.name);
         for (k, p) in &element_def.properties {
+            if !selected.contains(k) {
+                self.__skip_binary_property::<T, B>(reader, &p.data_type)?;
+                continue;
+            }
             let property = self.__read_binary_property::<T, B>(reader, &p.data_type)?;
-            raw_element.set_property(k.clone(), property);
+            raw_element.set_named_property(k, property);
         }
         Ok(raw_element)
     }
@@ -507,6 +931,7 @@ impl<E: PropertyAccess> Parser<E> {
                     ScalarType::Float => return Err(io::Error::new(ErrorKind::InvalidInput, "Index of list must be an integer type, float declared in ScalarType.")),
                     ScalarType::Double => return Err(io::Error::new(ErrorKind::InvalidInput, "Index of list must be an integer type, double declared in ScalarType.")),
                 };
+                self.__check_list_alloc(count, property_type)?;
                 match *property_type {
                     ScalarType::Char => Property::ListChar(self.__read_binary_list(reader, &|r| r.read_i8(), count)?),
                     ScalarType::UChar => Property::ListUChar(self.__read_binary_list(reader, &|r| r.read_u8(), count)?),
@@ -626,8 +1051,16 @@ mod tests {
         let mut elem_def = ElementDef::new("dummy".to_string());
         elem_def.properties = prop;
 
-        let properties = p.read_ascii_element(&txt, &elem_def);
-        assert!(properties.is_ok(), format!("error: {:?}", properties));
+        let properties = p.read_ascii_element(txt, &elem_def);
+        assert!(properties.is_ok(), "error: {:?}", properties);
+    }
+    #[test]
+    fn read_header_only_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut data = &b"ply\nformat binary_little_endian 1.0\nelement point 1\nproperty uchar x\nend_header\n\x07"[..];
+        let header = p.read_header_only(&mut data).unwrap();
+        assert_eq!(header.elements["point"].count, 1);
+        assert_eq!(data, &[7]);
     }
     #[test]
     fn magic_number_ok() {
@@ -751,7 +1184,14 @@ mod tests {
             g::data_line("034 8e3 8e-3"),
             vec!["034", "8e3", "8e-3"]
         );
-        assert_ok!(g::data_line(""), Vec::<String>::new());
+        assert_ok!(g::data_line(""), Vec::<&str>::new());
+    }
+    #[test]
+    fn data_line_special_floats_ok() {
+        assert_ok!(
+            g::data_line("nan -inf +Inf NaN -INFINITY infinity"),
+            vec!["nan", "-inf", "+Inf", "NaN", "-INFINITY", "infinity"]
+        );
     }
     #[test]
     fn data_line_err() {
@@ -0,0 +1,44 @@
+//! Parsing of ascii values, with `lexical-core` if the `lexical` feature is enabled.
+
+use std::str::FromStr;
+
+/// Types of ascii values, see `parse_number()`.
+#[cfg(not(feature = "lexical"))]
+pub(super) trait Number: FromStr {}
+#[cfg(not(feature = "lexical"))]
+impl<T: FromStr> Number for T {}
+#[cfg(feature = "lexical")]
+pub(super) trait Number: FromStr + lexical_core::FromLexical {}
+#[cfg(feature = "lexical")]
+impl<T: FromStr + lexical_core::FromLexical> Number for T {}
+
+/// Parses `s` as `D`.
+///
+/// With `lexical-core`, values it rejects are passed on to `FromStr`,
+/// which accepts the same input as without the feature and reports the errors.
+pub(super) fn parse_number<D: Number>(s: &str) -> Result<D, D::Err> {
+    #[cfg(feature = "lexical")]
+    {
+        if let Ok(v) = lexical_core::parse(s.as_bytes()) {
+            return Ok(v);
+        }
+    }
+    s.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn parse_number_ok() {
+        assert_eq!(parse_number::<u8>("+7"), Ok(7));
+        assert_eq!(parse_number::<i16>("-034"), Ok(-34));
+        assert!(parse_number::<u8>("256").is_err());
+        assert!(parse_number::<i32>("8e3").is_err());
+        assert_eq!(parse_number::<f32>("8e-3"), Ok(8e-3));
+        assert_eq!(parse_number::<f64>("-5.21"), Ok(-5.21));
+        assert_eq!(parse_number::<f64>("-INFINITY"), Ok(f64::NEG_INFINITY));
+        assert!(parse_number::<f32>("nan").unwrap().is_nan());
+        assert_eq!(parse_number::<f32>("1e39"), Ok(f32::INFINITY));
+    }
+}
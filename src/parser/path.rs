@@ -0,0 +1,57 @@
+//! Reading PLY files by path.
+
+use std::fs::File;
+use std::io::{ BufRead, BufReader };
+use std::path::Path;
+use std::result;
+
+use super::Parser;
+use crate::ply::{ Error, Ply, PropertyAccess };
+
+/// First bytes of a gzip stream.
+pub(super) const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// First bytes of a zstd frame.
+pub(super) const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Opens the file at `path` and reads it with `read_ply()`.
+    ///
+    /// Files starting with the gzip or zstd magic bytes are decompressed while reading
+    /// with feature `gzip` or `zstd` respectively, without it they are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let ply = p.read_ply_from_path("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// assert_eq!(ply.payload["vertex"].len(), 8);
+    /// ```
+    pub fn read_ply_from_path<P: AsRef<Path>>(&self, path: P) -> result::Result<Ply<E>, Error> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let start = reader.fill_buf()?;
+        if start.starts_with(&GZIP_MAGIC) {
+            return self.__read_ply_gz_detected(&mut reader);
+        }
+        if start.starts_with(&ZSTD_MAGIC) {
+            return self.__read_ply_zstd_detected(&mut reader);
+        }
+        self.read_ply(&mut reader)
+    }
+    #[cfg(feature = "gzip")]
+    fn __read_ply_gz_detected<T: BufRead>(&self, reader: &mut T) -> result::Result<Ply<E>, Error> {
+        self.read_ply_gz(reader)
+    }
+    #[cfg(not(feature = "gzip"))]
+    fn __read_ply_gz_detected<T: BufRead>(&self, _reader: &mut T) -> result::Result<Ply<E>, Error> {
+        Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "File is gzip compressed, enable feature `gzip` to read it.").into())
+    }
+    #[cfg(feature = "zstd")]
+    fn __read_ply_zstd_detected<T: BufRead>(&self, reader: &mut T) -> result::Result<Ply<E>, Error> {
+        self.read_ply_zstd(reader)
+    }
+    #[cfg(not(feature = "zstd"))]
+    fn __read_ply_zstd_detected<T: BufRead>(&self, _reader: &mut T) -> result::Result<Ply<E>, Error> {
+        Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "File is zstd compressed, enable feature `zstd` to read it.").into())
+    }
+}
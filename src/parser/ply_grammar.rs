@@ -114,13 +114,17 @@ rule trimmed_line() -> Line
 	/ v:element() { Line::Element(v) }
 	/ v:property() { Line::Property(v) }
 
-rule any_number() -> String
-	= s:$(['-'|'+']? ['0'..='9']+("."['0'..='9']+)?("e"['-'|'+']?['0'..='9']+)?) { s.to_string() }
+rule special_float()
+	= ['n'|'N']['a'|'A']['n'|'N']
+	/ ['i'|'I']['n'|'N']['f'|'F'](['i'|'I']['n'|'N']['i'|'I']['t'|'T']['y'|'Y'])?
 
-rule trimmed_data_line() -> Vec<String>
+rule any_number() -> &'input str
+	= $(['-'|'+']? (special_float() / ['0'..='9']+("."['0'..='9']+)?("e"['-'|'+']?['0'..='9']+)?))
+
+rule trimmed_data_line() -> Vec<&'input str>
 	= any_number() ** space()
 
-pub rule data_line() -> Vec<String>
+pub rule data_line() -> Vec<&'input str>
 	= space()? l:trimmed_data_line() space()? line_break()? {l}
 
 }}
\ No newline at end of file
@@ -0,0 +1,302 @@
+//! Reading the first, last or single rows of an element without parsing the entire payload.
+
+use std::io;
+use std::io::{ BufRead, ErrorKind, Read, Result, Seek, SeekFrom };
+
+use byteorder::{ BigEndian, LittleEndian };
+
+use super::{ Parser, element_line_rethrow };
+use crate::ply::{ ElementDef, Encoding, Header, PropertyAccess };
+use crate::util::{ add_offset, element_byte_len, LocationTracker };
+
+pub(super) fn find_element<'a>(header: &'a Header, element: &str) -> Result<(usize, &'a ElementDef)> {
+    match header.elements.values().enumerate().find(|(_, e)| e.name == element) {
+        Some(found) => Ok(found),
+        None => Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` declared in header.", element))),
+    }
+}
+
+/// Offset of element `index` relative to the payload start and its row size in bytes.
+///
+/// Only possible if the element and all preceding non-empty elements have no list properties.
+fn fixed_layout(header: &Header, index: usize) -> Result<(u64, u64)> {
+    let mut offset = 0u64;
+    for (i, e) in header.elements.values().enumerate().take(index + 1) {
+        if e.count == 0 {
+            if i == index {
+                return Ok((offset, 0));
+            }
+            continue;
+        }
+        let len = match element_byte_len(e, e.count)? {
+            Some(len) => len,
+            None => return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` has list properties, its rows don't have a fixed size.", e.name)
+            )),
+        };
+        if i == index {
+            return Ok((offset, len / e.count as u64));
+        }
+        offset = add_offset(offset, len)?;
+    }
+    unreachable!("element index {} out of bounds", index)
+}
+
+/// Consumes bytes up to and including the next line break, returns their number.
+fn skip_line<T: BufRead>(reader: &mut T) -> Result<usize> {
+    let mut skipped = 0;
+    loop {
+        let (found, used) = {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(skipped);
+            }
+            match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => (true, i + 1),
+                None => (false, buf.len()),
+            }
+        };
+        reader.consume(used);
+        skipped += used;
+        if found {
+            return Ok(skipped);
+        }
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads the first `n` rows of `element`, or all rows if it has fewer.
+    ///
+    /// `reader` has to be positioned at the start of the payload, e.g. after `read_header()`.
+    /// Rows of preceding elements are skipped without decoding where possible, following rows aren't read.
+    pub fn read_first_n<T: BufRead>(&self, reader: &mut T, header: &Header, element: &str, n: usize) -> Result<Vec<E>> {
+        let (index, element_def) = find_element(header, element)?;
+        let mut location = LocationTracker::new();
+        for e in header.elements.values().take(index) {
+            self.__skip_element(reader, &mut location, e, &header.encoding)?;
+        }
+        let mut first = element_def.clone();
+        first.count = n.min(element_def.count);
+        self.__read_element_list(reader, &mut location, &first, &header.encoding)
+    }
+    /// Reads the last `n` rows of `element`, or all rows if it has fewer.
+    ///
+    /// `reader` has to be positioned at the start of the payload.
+    /// Only possible for binary files where `element` and all preceding non-empty elements have no list properties,
+    /// the position is computed and the reader seeks there directly.
+    pub fn read_last_n<T: BufRead + Seek>(&self, reader: &mut T, header: &Header, element: &str, n: usize) -> Result<Vec<E>> {
+        if header.encoding == Encoding::Ascii {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Reading the last rows requires a binary encoding."));
+        }
+        let (index, element_def) = find_element(header, element)?;
+        let (start, stride) = fixed_layout(header, index)?;
+        let n = n.min(element_def.count);
+        let skipped = add_offset(start, stride * (element_def.count - n) as u64)?;
+        let position = add_offset(reader.stream_position()?, skipped)?;
+        reader.seek(SeekFrom::Start(position))?;
+        let mut last = element_def.clone();
+        last.count = n;
+        let mut location = LocationTracker::new();
+        self.__read_element_list(reader, &mut location, &last, &header.encoding)
+    }
+    /// Reads row `index` of `element` from a binary file, seeking directly to it.
+    ///
+    /// `payload_start` is the absolute position of the payload in `reader`, e.g. `reader.stream_position()` right after `read_header()`.
+    /// Only possible where `element` and all preceding non-empty elements have no list properties.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// use std::io::Seek;
+    /// let mut f = std::io::BufReader::new(std::fs::File::open("example_plys/house_2_ok_little_endian.ply").unwrap());
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let header = p.read_header(&mut f).unwrap();
+    /// let payload_start = f.stream_position().unwrap();
+    /// let last = p.read_element_at(&mut f, &header, payload_start, "vertex", 4).unwrap();
+    /// assert_eq!(last["y"], ply::Property::Float(2.0));
+    /// ```
+    pub fn read_element_at<T: Read + Seek>(&self, reader: &mut T, header: &Header, payload_start: u64, element: &str, index: usize) -> Result<E> {
+        if header.encoding == Encoding::Ascii {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Random access to rows requires a binary encoding."));
+        }
+        let (element_index, element_def) = find_element(header, element)?;
+        if index >= element_def.count {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Row {} out of range for element `{}` with {} rows.", index, element, element_def.count)
+            ));
+        }
+        let (start, stride) = fixed_layout(header, element_index)?;
+        let position = add_offset(add_offset(payload_start, start)?, stride * index as u64)?;
+        reader.seek(SeekFrom::Start(position))?;
+        match header.encoding {
+            Encoding::BinaryBigEndian => self.read_big_endian_element(reader, element_def),
+            _ => self.read_little_endian_element(reader, element_def),
+        }
+    }
+    /// Moves past all rows of `element_def` without decoding any values.
+    ///
+    /// Elements of fixed row size are discarded at once, the others row by row.
+    pub(crate) fn __skip_element<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef, encoding: &Encoding) -> Result<()> {
+        let length = match *encoding {
+            Encoding::Ascii => None,
+            _ => element_byte_len(element_def, element_def.count)?,
+        };
+        let length = match length {
+            Some(l) => l,
+            None => {
+                for _ in 0..element_def.count {
+                    self.__skip_row(reader, location, element_def, encoding)?;
+                }
+                return Ok(());
+            },
+        };
+        let skipped = io::copy(&mut reader.by_ref().take(length), &mut io::sink())?;
+        if skipped < length {
+            location.line_index += (skipped / (length / element_def.count as u64)) as usize;
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, format!("Missing `{}` elements.", element_def.name)));
+        }
+        location.line_index += element_def.count;
+        self.__progress_rows(element_def.count);
+        Ok(())
+    }
+    /// Moves past one row of `element_def` without decoding any values.
+    pub(crate) fn __skip_row<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef, encoding: &Encoding) -> Result<()> {
+        match *encoding {
+            Encoding::Ascii => {
+                if skip_line(reader)? == 0 {
+                    return element_line_rethrow(location, "", "unexpected end of file");
+                }
+            },
+            _ => match element_def.fixed_stride() {
+                Some(stride) => {
+                    let skipped = io::copy(&mut reader.by_ref().take(stride as u64), &mut io::sink())?;
+                    if skipped < stride as u64 {
+                        return Err(io::Error::new(ErrorKind::UnexpectedEof, format!("Missing `{}` elements.", element_def.name)));
+                    }
+                },
+                None => for p in element_def.properties.values() {
+                    match *encoding {
+                        Encoding::BinaryBigEndian => self.__skip_binary_property::<T, BigEndian>(reader, &p.data_type)?,
+                        _ => self.__skip_binary_property::<T, LittleEndian>(reader, &p.data_type)?,
+                    }
+                },
+            },
+        }
+        location.next_line();
+        self.__progress_rows(1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::ply::{ DefaultElement, Property };
+    fn file(encoding: &str) -> Vec<u8> {
+        let mut buf = format!("ply\nformat {} 1.0\nelement face 1\nproperty list uchar int vertex_index\n\
+            element point 6\nproperty short x\nproperty uchar y\nend_header\n", encoding).into_bytes();
+        if encoding == "ascii" {
+            buf.extend_from_slice(b"3 0 1 2\n");
+        } else {
+            buf.extend_from_slice(&[3, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+        }
+        for i in 0..6i16 {
+            if encoding == "ascii" {
+                buf.extend_from_slice(format!("{} 7\n", i).as_bytes());
+            } else {
+                buf.extend_from_slice(&i.to_le_bytes());
+                buf.push(7);
+            }
+        }
+        buf
+    }
+    fn xs(elements: &[DefaultElement]) -> Vec<Property> {
+        elements.iter().map(|e| e["x"].clone()).collect()
+    }
+    #[test]
+    fn read_first_n_ok() {
+        let p = Parser::<DefaultElement>::new();
+        for encoding in &["ascii", "binary_little_endian"] {
+            let buf = file(encoding);
+            let mut reader = &buf[..];
+            let header = p.read_header(&mut reader).unwrap();
+            let first = p.read_first_n(&mut reader, &header, "point", 2).unwrap();
+            assert_eq!(xs(&first), vec![Property::Short(0), Property::Short(1)]);
+        }
+    }
+    #[test]
+    fn read_last_n_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut reader = Cursor::new(file("binary_little_endian"));
+        let header = p.read_header(&mut reader).unwrap();
+        let payload_start = reader.position();
+        assert!(p.read_last_n(&mut reader, &header, "point", 2).is_err());
+
+        let mut header = header;
+        header.elements.get_mut("face").unwrap().count = 0;
+        let mut buf = reader.into_inner();
+        buf.drain(payload_start as usize..payload_start as usize + 13);
+        let mut reader = Cursor::new(buf);
+        reader.set_position(payload_start);
+        let last = p.read_last_n(&mut reader, &header, "point", 2).unwrap();
+        assert_eq!(xs(&last), vec![Property::Short(4), Property::Short(5)]);
+        reader.set_position(payload_start);
+        assert_eq!(p.read_last_n(&mut reader, &header, "point", 10).unwrap().len(), 6);
+    }
+    fn huge_file() -> Vec<u8> {
+        b"ply\nformat binary_little_endian 1.0\nelement a 4611686018427387904\nproperty double x\n\
+            element point 1\nproperty short x\nend_header\n".to_vec()
+    }
+    #[test]
+    fn read_last_n_huge_count() {
+        let p = Parser::<DefaultElement>::new();
+        let mut reader = Cursor::new(huge_file());
+        let header = p.read_header(&mut reader).unwrap();
+        assert_eq!(p.read_last_n(&mut reader, &header, "point", 1).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(p.read_last_n(&mut reader, &header, "a", 1).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+    #[test]
+    fn skip_element_huge_count() {
+        let mut p = Parser::<DefaultElement>::new();
+        let mut reader = Cursor::new(huge_file());
+        let header = p.read_header(&mut reader).unwrap();
+        assert_eq!(p.read_first_n(&mut reader, &header, "point", 1).unwrap_err().kind(), ErrorKind::InvalidData);
+        p.set_projection(crate::parser::Projection::all().select("point", &[]));
+        assert_eq!(p.read_ply(&mut &huge_file()[..]).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+    #[test]
+    fn read_element_at_huge_count() {
+        let p = Parser::<DefaultElement>::new();
+        let mut reader = Cursor::new(huge_file());
+        let header = p.read_header(&mut reader).unwrap();
+        assert_eq!(p.read_element_at(&mut reader, &header, 0, "point", 0).unwrap_err().kind(), ErrorKind::InvalidData);
+        let mut header = header;
+        header.elements.remove("a");
+        header.elements.get_mut("point").unwrap().count = 2;
+        assert_eq!(p.read_element_at(&mut reader, &header, u64::MAX, "point", 1).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+    #[test]
+    fn read_element_at_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut reader = Cursor::new(file("binary_little_endian"));
+        let header = p.read_header(&mut reader).unwrap();
+        let payload_start = reader.position();
+        assert!(p.read_element_at(&mut reader, &header, payload_start, "point", 1).is_err());
+        assert!(p.read_element_at(&mut reader, &header, payload_start, "face", 0).is_err());
+
+        let mut header = header;
+        header.elements.get_mut("face").unwrap().count = 0;
+        let mut buf = reader.into_inner();
+        buf.drain(payload_start as usize..payload_start as usize + 13);
+        let mut reader = Cursor::new(buf);
+        for &i in &[4, 1, 5] {
+            let point = p.read_element_at(&mut reader, &header, payload_start, "point", i).unwrap();
+            assert_eq!(point["x"], Property::Short(i as i16));
+        }
+        assert!(p.read_element_at(&mut reader, &header, payload_start, "point", 6).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Progress reports while reading the payload, e.g. for a progress bar.
+
+use std::io::{ Read, Result };
+use std::sync::atomic::{ AtomicBool, AtomicU64, AtomicUsize, Ordering };
+
+use super::Parser;
+use crate::ply::{ Header, PropertyAccess };
+
+/// State of the callback set with `Parser::set_progress()`.
+pub(super) struct Progress {
+    callback: Box<dyn Fn(u64, usize, usize) + Send + Sync>,
+    every: usize,
+    /// Whether a read reporting progress is running.
+    active: AtomicBool,
+    bytes: AtomicU64,
+    rows: AtomicUsize,
+    total: AtomicUsize,
+}
+
+/// Counts the bytes read from `inner` for the progress reports and `ParseStats`.
+pub(super) struct ProgressReader<'a, R: Read> {
+    inner: R,
+    bytes: Option<&'a AtomicU64>,
+    /// Bytes read, also without progress reports.
+    pub(super) count: u64,
+}
+
+impl<'a, R: Read> Read for ProgressReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        if let Some(bytes) = self.bytes {
+            bytes.fetch_add(n as u64, Ordering::Relaxed);
+        }
+        Ok(n)
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Calls `callback` with `(bytes_read, rows_done, rows_total)` while `read_ply()`, `read_payload()` or `parse_slice()` read the payload.
+    ///
+    /// It is called once every `every` rows of all elements together and after the last row.
+    /// `bytes_read` counts the bytes taken from the source including the header, it's `0` for `read_payload()` and `parse_slice()`.
+    /// The callback has to be cheap, it runs on the reading thread.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let mut p = parser::Parser::<ply::DefaultElement>::new();
+    /// p.set_progress(5, |bytes, done, total| println!("{} bytes, {}/{} rows", bytes, done, total));
+    /// let ply = p.read_ply(&mut f).unwrap();
+    /// ```
+    pub fn set_progress<F: Fn(u64, usize, usize) + Send + Sync + 'static>(&mut self, every: usize, callback: F) {
+        self.progress = Some(Progress {
+            callback: Box::new(callback),
+            every: every.max(1),
+            active: AtomicBool::new(false),
+            bytes: AtomicU64::new(0),
+            rows: AtomicUsize::new(0),
+            total: AtomicUsize::new(0),
+        });
+    }
+    /// Removes the callback set with `set_progress()`.
+    pub fn clear_progress(&mut self) {
+        self.progress = None;
+    }
+    /// Wraps `source` to count the bytes read, resetting the count.
+    pub(super) fn __progress_reader<R: Read>(&self, source: R) -> ProgressReader<'_, R> {
+        let bytes = self.progress.as_ref().map(|p| &p.bytes);
+        if let Some(bytes) = bytes {
+            bytes.store(0, Ordering::Relaxed);
+        }
+        ProgressReader { inner: source, bytes, count: 0 }
+    }
+    /// Starts reporting for the payload of `header`, returns whether it did.
+    pub(super) fn __progress_start(&self, header: &Header) -> bool {
+        match self.progress {
+            Some(ref p) if !p.active.swap(true, Ordering::Relaxed) => {
+                p.rows.store(0, Ordering::Relaxed);
+                p.total.store(header.elements.values().map(|e| e.count).sum(), Ordering::Relaxed);
+                true
+            },
+            _ => false,
+        }
+    }
+    pub(super) fn __progress_stop(&self) {
+        if let Some(ref p) = self.progress {
+            p.active.store(false, Ordering::Relaxed);
+        }
+    }
+    /// Adds `n` rows done, calling the callback if a multiple of `every` or the last row has been reached.
+    pub(super) fn __progress_rows(&self, n: usize) {
+        let p = match self.progress {
+            Some(ref p) if p.active.load(Ordering::Relaxed) => p,
+            _ => return,
+        };
+        let before = p.rows.fetch_add(n, Ordering::Relaxed);
+        let done = before + n;
+        let total = p.total.load(Ordering::Relaxed);
+        if done / p.every != before / p.every || (done == total && before < total) {
+            (p.callback)(p.bytes.load(Ordering::Relaxed), done, total);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{ Arc, Mutex };
+    use crate::parser::Projection;
+    use crate::ply::DefaultElement;
+    #[test]
+    fn progress_ok() {
+        let data = std::fs::read("example_plys/house_2_ok_little_endian.ply").unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let mut p = Parser::<DefaultElement>::new();
+        let r = reports.clone();
+        p.set_progress(3, move |bytes, done, total| r.lock().unwrap().push((bytes, done, total)));
+        p.read_ply(&mut &data[..]).unwrap();
+        let len = data.len() as u64;
+        assert_eq!(*reports.lock().unwrap(), vec![(len, 3, 8), (len, 6, 8), (len, 8, 8)]);
+        reports.lock().unwrap().clear();
+        p.set_projection(Projection::all().select("face", &[]));
+        p.read_ply(&mut &data[..]).unwrap();
+        assert_eq!(reports.lock().unwrap()[0].1, 5);
+        assert_eq!(reports.lock().unwrap().last().unwrap().1, 8);
+    }
+}
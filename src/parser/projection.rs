@@ -0,0 +1,256 @@
+//! Reading only selected elements and properties.
+
+use std::collections::{ HashMap, HashSet };
+use std::io;
+use std::io::{ ErrorKind, Read, Result };
+use std::slice::Iter;
+
+use byteorder::ByteOrder;
+
+use super::Parser;
+use super::incremental::list_count;
+use crate::ply::{ Header, PropertyAccess, PropertyType };
+
+/// Selects the elements and properties decoded by the `Parser` or written by the `Writer`,
+/// see `Parser::set_projection()` and `Writer::set_projection()`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// let mut p = parser::Parser::<ply::DefaultElement>::new();
+/// p.set_projection(parser::Projection::all().select("vertex", &["x", "y"]));
+/// let ply = p.read_ply(&mut std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap()).unwrap();
+/// assert_eq!(ply.payload["vertex"][0].len(), 2);
+/// assert!(!ply.payload.contains_key("face"));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Projection {
+    /// Selected properties by element name, `None` selects all properties of the element.
+    /// `None` if nothing has been selected, every element is kept then.
+    elements: Option<HashMap<String, Option<HashSet<String>>>>,
+    /// Excluded properties by element name, `None` excludes the element.
+    excluded: HashMap<String, Option<HashSet<String>>>,
+}
+
+/// Properties of one element kept by a `Projection`.
+pub(super) struct Selected<'a> {
+    kept: Kept<'a>,
+    excluded: Option<&'a HashSet<String>>,
+}
+
+enum Kept<'a> {
+    All,
+    Some(&'a HashSet<String>),
+    None,
+}
+
+impl<'a> Selected<'a> {
+    pub(super) fn contains(&self, property: &str) -> bool {
+        let kept = match self.kept {
+            Kept::All => true,
+            Kept::Some(properties) => properties.contains(property),
+            Kept::None => false,
+        };
+        kept && self.excluded.is_none_or(|e| !e.contains(property))
+    }
+}
+
+impl Projection {
+    /// Keeps all elements and properties, the default.
+    pub fn all() -> Self {
+        Projection { elements: None, excluded: HashMap::new() }
+    }
+    /// Adds `element` with `properties` to the selection, all its properties if `properties` is empty.
+    ///
+    /// Once an element is selected, elements not selected are skipped.
+    pub fn select(mut self, element: &str, properties: &[&str]) -> Self {
+        let selected = if properties.is_empty() {
+            None
+        } else {
+            Some(properties.iter().map(|p| p.to_string()).collect())
+        };
+        self.elements.get_or_insert_with(HashMap::new).insert(element.to_string(), selected);
+        self
+    }
+    /// Removes `properties` of `element` from the selection, the whole element if `properties` is empty.
+    ///
+    /// Exclusions take precedence over `select()`.
+    pub fn exclude(mut self, element: &str, properties: &[&str]) -> Self {
+        let excluded = if properties.is_empty() {
+            None
+        } else {
+            let mut excluded = match self.excluded.remove(element) {
+                Some(None) => None,
+                Some(Some(excluded)) => Some(excluded),
+                None => Some(HashSet::new()),
+            };
+            if let Some(ref mut excluded) = excluded {
+                excluded.extend(properties.iter().map(|p| p.to_string()));
+            }
+            excluded
+        };
+        self.excluded.insert(element.to_string(), excluded);
+        self
+    }
+    /// Whether everything is kept, as by `Projection::all()`.
+    pub fn is_all(&self) -> bool {
+        self.elements.is_none() && self.excluded.is_empty()
+    }
+    /// Whether the rows of `element` are read.
+    pub fn keeps_element(&self, element: &str) -> bool {
+        self.elements.as_ref().is_none_or(|e| e.contains_key(element))
+            && !matches!(self.excluded.get(element), Some(None))
+    }
+    /// Whether `property` of `element` is decoded.
+    pub fn keeps_property(&self, element: &str, property: &str) -> bool {
+        self.selected(element).contains(property)
+    }
+    /// Returns `header` without the elements and properties that aren't kept.
+    pub fn project(&self, header: &Header) -> Header {
+        let mut projected = header.clone();
+        projected.elements.clear();
+        for (k, e) in &header.elements {
+            if !self.keeps_element(k) {
+                continue;
+            }
+            let selected = self.selected(k);
+            let mut e = e.clone();
+            e.properties = e.properties.into_iter().filter(|(p, _)| selected.contains(p)).collect();
+            projected.elements.insert(k.clone(), e);
+        }
+        projected
+    }
+    pub(super) fn selected(&self, element: &str) -> Selected<'_> {
+        let kept = match self.elements {
+            None => Kept::All,
+            Some(ref elements) => match elements.get(element) {
+                Some(Some(properties)) => Kept::Some(properties),
+                Some(None) => Kept::All,
+                None => Kept::None,
+            },
+        };
+        match self.excluded.get(element) {
+            Some(None) => Selected { kept: Kept::None, excluded: None },
+            Some(Some(excluded)) => Selected { kept, excluded: Some(excluded) },
+            None => Selected { kept, excluded: None },
+        }
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Sets the elements and properties decoded, default is `Projection::all()`.
+    ///
+    /// Elements not selected are skipped and missing from the payload, properties not selected are missing from the rows.
+    /// Binary values are skipped without decoding them. The header is returned as found in the file.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+    /// Moves past an ascii value of type `data_type`.
+    pub(super) fn __skip_ascii_property(&self, elem_iter: &mut Iter<&str>, data_type: &PropertyType) -> Result<()> {
+        let s = match elem_iter.next() {
+            Some(s) => s,
+            None => return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Expected element of type '{:?}', but found nothing.", data_type)
+            )),
+        };
+        if let PropertyType::List(_, _) = *data_type {
+            let count: usize = self.parse(s)?;
+            if count > 0 && elem_iter.nth(count - 1).is_none() {
+                return Err(io::Error::new(ErrorKind::InvalidInput, format!("Expected {} list entries, but found less.", count)));
+            }
+        }
+        Ok(())
+    }
+    /// Moves past a binary value of type `data_type`.
+    pub(super) fn __skip_binary_property<T: Read, B: ByteOrder>(&self, reader: &mut T, data_type: &PropertyType) -> Result<()> {
+        let (count, scalar_type) = match *data_type {
+            PropertyType::Scalar(ref scalar_type) => (1, scalar_type),
+            PropertyType::List(ref index_type, ref scalar_type) => {
+                let mut index = [0; 8];
+                let index = &mut index[..index_type.byte_size()];
+                reader.read_exact(index)?;
+                let count = list_count::<B>(index, index_type)?;
+                self.__check_list_len(count)?;
+                (count, scalar_type)
+            },
+        };
+        let length = (count as u64).checked_mul(scalar_type.byte_size() as u64)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "List is too large."))?;
+        if io::copy(&mut reader.by_ref().take(length), &mut io::sink())? < length {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of file."));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property };
+    #[test]
+    fn projection_ok() {
+        let header = "element face 1\nproperty list uchar int i\nproperty uchar a\n\
+            element point 2\nproperty short x\nproperty list uchar uchar l\nproperty uchar y\nend_header\n";
+        let ascii = format!("ply\nformat ascii 1.0\n{}3 0 1 2 9\n1 2 5 6 7\n-1 0 8\n", header);
+        let mut binary = format!("ply\nformat binary_little_endian 1.0\n{}", header).into_bytes();
+        binary.extend_from_slice(&[3, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 9, 1, 0, 2, 5, 6, 7, 255, 255, 0, 8]);
+        let mut p = Parser::<DefaultElement>::new();
+        p.set_projection(Projection::all().select("point", &["y", "x"]));
+        for data in &[ascii.into_bytes(), binary] {
+            let ply = p.read_ply(&mut &data[..]).unwrap();
+            assert_eq!(ply.header.elements.len(), 2);
+            assert!(!ply.payload.contains_key("face"));
+            let points = &ply.payload["point"];
+            assert_eq!(points[0].len(), 2);
+            assert_eq!((&points[0]["x"], &points[0]["y"]), (&Property::Short(1), &Property::UChar(7)));
+            assert_eq!((&points[1]["x"], &points[1]["y"]), (&Property::Short(-1), &Property::UChar(8)));
+        }
+    }
+    #[test]
+    fn projection_skips_elements() {
+        let header = "element point 3\nproperty short x\nproperty uchar y\nelement face 2\nproperty list uchar uchar i\nend_header\n";
+        let mut binary = format!("ply\nformat binary_little_endian 1.0\n{}", header).into_bytes();
+        binary.extend_from_slice(&[1, 0, 7, 2, 0, 8, 3, 0, 9, 2, 4, 5, 1, 6]);
+        let mut p = Parser::<DefaultElement>::new();
+        p.set_projection(Projection::all().select("face", &[]));
+        let ply = p.read_ply(&mut &binary[..]).unwrap();
+        assert_eq!(ply.payload["face"][1]["i"], Property::ListUChar(vec![6]));
+        let err = p.read_ply(&mut &binary[..binary.len() - 10]).unwrap_err();
+        assert!(matches!(err, crate::ply::Error::Payload { ref element, index: 1, .. } if element == "point"), "{:?}", err);
+        p.set_projection(Projection::all().select("point", &[]));
+        assert_eq!(p.read_ply(&mut &binary[..]).unwrap().payload["point"][2]["y"], Property::UChar(9));
+        assert!(p.read_ply(&mut &binary[..binary.len() - 1]).is_err());
+    }
+    #[test]
+    fn projection_skips_invalid_lists() {
+        let header = "element face 1\nproperty list int int i\nproperty uchar a\nend_header\n";
+        let mut binary = format!("ply\nformat binary_little_endian 1.0\n{}", header).into_bytes();
+        binary.extend_from_slice(&[255, 255, 255, 255, 7]);
+        let mut p = Parser::<DefaultElement>::new();
+        p.set_projection(Projection::all().select("face", &["a"]));
+        assert_eq!(p.read_ply(&mut &binary[..]).unwrap_err().kind(), ErrorKind::InvalidData);
+        let end = binary.len();
+        binary[end - 5..].copy_from_slice(&[3, 0, 0, 0, 1]);
+        binary.extend_from_slice(&[0; 12]);
+        p.set_limits(crate::parser::Limits { max_list_len: 2, ..crate::parser::Limits::unlimited() });
+        assert!(matches!(p.read_ply(&mut &binary[..]), Err(crate::ply::Error::Limit(_))));
+    }
+    #[test]
+    fn projection_exclude() {
+        let header = "element face 1\nproperty list uchar int i\nproperty uchar a\n\
+            element point 2\nproperty short x\nproperty uchar y\nend_header\n";
+        let data = format!("ply\nformat ascii 1.0\n{}1 4 9\n1 7\n-1 8\n", header);
+        let mut p = Parser::<DefaultElement>::new();
+        let projection = Projection::all().exclude("face", &["a"]).exclude("face", &["i"]).exclude("point", &["x"]);
+        p.set_projection(projection.clone());
+        let ply = p.read_ply(&mut data.as_bytes()).unwrap();
+        assert!(ply.payload["face"][0].is_empty());
+        assert_eq!(ply.payload["point"][1].len(), 1);
+        let projected = projection.exclude("face", &[]).project(&ply.header);
+        assert_eq!(projected.elements.keys().collect::<Vec<_>>(), vec!["point"]);
+        assert_eq!(projected.elements["point"].properties.keys().collect::<Vec<_>>(), vec!["y"]);
+        assert!(!Projection::all().select("point", &[]).exclude("point", &[]).keeps_element("point"));
+    }
+}
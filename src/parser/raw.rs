@@ -0,0 +1,161 @@
+//! Reading the payload as undecoded bytes, for tools that copy or reorder rows.
+
+use std::io;
+use std::io::{ BufRead, BufReader, ErrorKind, Read, Result };
+use std::result;
+
+use byteorder::{ BigEndian, ByteOrder, LittleEndian };
+
+use super::{ Parser, payload_error };
+use super::incremental::list_count;
+use crate::ply::{ ElementDef, Encoding, Error, Header, KeyMap, PropertyAccess, PropertyType };
+use crate::util::LocationTracker;
+
+/// A PLY file with its payload undecoded, created by `Parser::read_raw()`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RawPly {
+    pub header: Header,
+    /// Rows of each element, in header order.
+    pub payload: KeyMap<RawElement>,
+}
+
+/// Undecoded rows of one element, as found in the file.
+///
+/// Ascii rows keep their line break, so writing `bytes()` after the header reproduces the input.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RawElement {
+    bytes: Vec<u8>,
+    /// Offset of each row in `bytes`.
+    starts: Vec<usize>,
+}
+
+impl RawElement {
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+    /// Bytes of row `index`, panics if out of bounds.
+    pub fn row(&self, index: usize) -> &[u8] {
+        let end = self.starts.get(index + 1).copied().unwrap_or(self.bytes.len());
+        &self.bytes[self.starts[index]..end]
+    }
+    /// Iterates over the bytes of all rows.
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[u8]> + '_ {
+        (0..self.len()).map(move |i| self.row(i))
+    }
+    /// Bytes of all rows.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+    /// Appends a row, e.g. one of another `RawElement` with the same properties and encoding.
+    pub fn push(&mut self, row: &[u8]) {
+        self.starts.push(self.bytes.len());
+        self.bytes.extend_from_slice(row);
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads the header and splits the payload into rows without decoding any values.
+    ///
+    /// Binary rows are split according to their properties, ascii rows at line breaks.
+    /// Only the limits set with `set_limits()` apply, the rows aren't checked otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/house_2_ok_little_endian.ply").unwrap();
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let raw = p.read_raw(&mut f).unwrap();
+    /// assert_eq!(raw.payload["vertex"].row(0).len(), 12);
+    /// // Reverse the faces without decoding them.
+    /// let mut faces = parser::RawElement::default();
+    /// for row in raw.payload["face"].rows().rev() {
+    ///     faces.push(row);
+    /// }
+    /// ```
+    pub fn read_raw<T: Read>(&self, source: &mut T) -> result::Result<RawPly, Error> {
+        let mut reader = BufReader::new(source);
+        let mut location = LocationTracker::new();
+        let header = self.__read_header(&mut reader, &mut location)?;
+        let mut payload = KeyMap::new();
+        for element_def in header.elements.values() {
+            let mut element = RawElement::default();
+            for index in 0..element_def.count {
+                element.starts.push(element.bytes.len());
+                let read = match header.encoding {
+                    Encoding::Ascii => read_raw_line(&mut reader, &mut element.bytes),
+                    Encoding::BinaryBigEndian => self.__read_raw_binary_row::<_, BigEndian>(&mut reader, element_def, &mut element.bytes),
+                    Encoding::BinaryLittleEndian => self.__read_raw_binary_row::<_, LittleEndian>(&mut reader, element_def, &mut element.bytes),
+                };
+                read.map_err(|e| payload_error(e, element_def, index))?;
+            }
+            payload.insert(element_def.name.clone(), element);
+        }
+        Ok(RawPly { header, payload })
+    }
+    fn __read_raw_binary_row<T: Read, B: ByteOrder>(&self, reader: &mut T, element_def: &ElementDef, bytes: &mut Vec<u8>) -> Result<()> {
+        for p in element_def.properties.values() {
+            match p.data_type {
+                PropertyType::Scalar(ref scalar_type) => read_raw_bytes(reader, bytes, scalar_type.byte_size())?,
+                PropertyType::List(ref index_type, ref scalar_type) => {
+                    let start = bytes.len();
+                    read_raw_bytes(reader, bytes, index_type.byte_size())?;
+                    let count = list_count::<B>(&bytes[start..], index_type)?;
+                    self.__check_list_alloc(count, scalar_type)?;
+                    let length = count.checked_mul(scalar_type.byte_size())
+                        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "List is too large."))?;
+                    read_raw_bytes(reader, bytes, length)?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_raw_line<T: BufRead>(reader: &mut T, bytes: &mut Vec<u8>) -> Result<()> {
+    if reader.read_until(b'\n', bytes)? == 0 {
+        return Err(io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of file."));
+    }
+    Ok(())
+}
+
+/// Appends exactly `n` bytes, growing `bytes` only as data arrives.
+fn read_raw_bytes<T: Read>(reader: &mut T, bytes: &mut Vec<u8>, n: usize) -> Result<()> {
+    if reader.by_ref().take(n as u64).read_to_end(bytes)? < n {
+        return Err(io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of file."));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    #[test]
+    fn read_raw_ok() {
+        let header = "element face 2\nproperty list uchar ushort i\nelement point 2\nproperty uchar x\nend_header\n";
+        let ascii = format!("ply\nformat ascii 1.0\n{}2 4 5\n0\n7\n8", header);
+        let mut binary = format!("ply\nformat binary_big_endian 1.0\n{}", header).into_bytes();
+        binary.extend_from_slice(&[2, 0, 4, 0, 5, 0, 7, 8]);
+        let p = Parser::<DefaultElement>::new();
+        let raw = p.read_raw(&mut ascii.as_bytes()).unwrap();
+        assert_eq!(raw.payload["face"].rows().collect::<Vec<_>>(), vec![&b"2 4 5\n"[..], b"0\n"]);
+        assert_eq!(raw.payload["point"].bytes(), b"7\n8");
+        let raw = p.read_raw(&mut &binary[..]).unwrap();
+        assert_eq!(raw.payload["face"].rows().collect::<Vec<_>>(), vec![&[2, 0, 4, 0, 5][..], &[0]]);
+        assert_eq!(raw.payload["point"].row(1), &[8]);
+        assert!(matches!(p.read_raw(&mut &binary[..binary.len() - 1]), Err(Error::Payload { index: 1, .. })));
+    }
+    #[test]
+    fn read_raw_negative_count() {
+        let mut binary = b"ply\nformat binary_little_endian 1.0\nelement face 1\nproperty list short int i\nend_header\n".to_vec();
+        binary.extend_from_slice(&[255, 255]);
+        let e = Parser::<DefaultElement>::new().read_raw(&mut &binary[..]).unwrap_err();
+        assert!(matches!(e, Error::Payload { index: 0, .. }));
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+    }
+}
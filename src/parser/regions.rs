@@ -0,0 +1,141 @@
+//! Concurrent reading of the elements of a binary file, each from its own file handle.
+
+use std::fs::File;
+use std::io;
+use std::io::{ BufRead, BufReader, ErrorKind, Result, Seek, SeekFrom };
+use std::path::Path;
+use std::thread;
+
+use super::Parser;
+use crate::ply::{ Encoding, Header, Payload, Ply, PropertyAccess };
+use crate::util::{ add_offset, element_byte_len, LocationTracker };
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Byte offsets of the elements of a binary payload, in header order, relative to the stream start.
+    ///
+    /// `reader` has to be positioned at the start of the payload and is left at an unspecified position.
+    /// Elements of fixed row size are stepped over, rows with list properties have to be scanned.
+    /// Offsets of elements after the last one in `until` aren't computed, `None` computes all.
+    pub fn element_offsets<T: BufRead + Seek>(&self, reader: &mut T, header: &Header, until: Option<&str>) -> Result<Vec<u64>> {
+        if header.encoding == Encoding::Ascii {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Element offsets require a binary encoding."));
+        }
+        let mut offsets = Vec::with_capacity(header.elements.len());
+        let mut offset = reader.stream_position()?;
+        let mut location = LocationTracker::new();
+        for e in header.elements.values() {
+            offsets.push(offset);
+            if until == Some(&e.name[..]) {
+                break;
+            }
+            match element_byte_len(e, e.count)? {
+                Some(len) => offset = add_offset(offset, len)?,
+                None => {
+                    reader.seek(SeekFrom::Start(offset))?;
+                    self.__skip_element(reader, &mut location, e, &header.encoding)?;
+                    offset = reader.stream_position()?;
+                },
+            }
+        }
+        Ok(offsets)
+    }
+}
+
+impl<E: PropertyAccess + Send> Parser<E> {
+    /// Reads the `elements` of the binary PLY file at `path` concurrently, one thread and file handle per element.
+    ///
+    /// The returned `Ply` has the full header, but only the requested elements in its payload.
+    /// Checksums aren't verified since only parts of the payload are read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use ply_rs::*;
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let ply = p.read_elements_concurrent("scan.ply", &["vertex", "face"]).unwrap();
+    /// ```
+    pub fn read_elements_concurrent<P: AsRef<Path>>(&self, path: P, elements: &[&str]) -> Result<Ply<E>> {
+        let path = path.as_ref();
+        let mut reader = BufReader::new(File::open(path)?);
+        let header = self.read_header(&mut reader)?;
+        let mut last = None;
+        for name in elements {
+            match header.elements.keys().position(|k| k == name) {
+                Some(i) => last = last.max(Some(i)),
+                None => return Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` declared in header.", name))),
+            }
+        }
+        let last = match last {
+            Some(i) => header.elements.keys().nth(i).map(|k| &k[..]),
+            None => return Ok(Ply { header, payload: Payload::new() }),
+        };
+        let offsets = self.element_offsets(&mut reader, &header, last)?;
+
+        let results = thread::scope(|s| {
+            let handles: Vec<_> = header.elements.values().zip(&offsets)
+                .filter(|(e, _)| elements.contains(&&e.name[..]))
+                .map(|(element_def, &offset)| {
+                    let encoding = header.encoding;
+                    s.spawn(move || {
+                        let mut file = File::open(path)?;
+                        file.seek(SeekFrom::Start(offset))?;
+                        let mut reader = BufReader::new(file);
+                        let rows = self.__read_element_list(&mut reader, &mut LocationTracker::new(), element_def, &encoding)?;
+                        Ok((element_def.name.clone(), rows))
+                    })
+                }).collect();
+            handles.into_iter()
+                .map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect::<Result<Vec<_>>>()
+        })?;
+        let mut payload = Payload::new();
+        for (name, rows) in results {
+            payload.insert(name, rows);
+        }
+        Ok(Ply { header, payload })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::ply::{ DefaultElement, Property };
+    use crate::writer::Writer;
+    #[test]
+    fn read_elements_concurrent_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut ply = p.read_ply(&mut File::open("example_plys/house_2_ok_ascii.ply").unwrap()).unwrap();
+        ply.header.encoding = Encoding::BinaryLittleEndian;
+        let path = std::env::temp_dir().join(format!("ply_rs_regions_{}.ply", std::process::id()));
+        Writer::new().write_ply(&mut File::create(&path).unwrap(), &mut ply).unwrap();
+
+        let read = p.read_elements_concurrent(&path, &["face", "vertex"]).unwrap();
+        assert_eq!(read.payload["vertex"], ply.payload["vertex"]);
+        assert_eq!(read.payload["face"], ply.payload["face"]);
+        let faces = p.read_elements_concurrent(&path, &["face"]).unwrap();
+        assert_eq!(faces.payload.keys().collect::<Vec<_>>(), vec!["face"]);
+        assert!(p.read_elements_concurrent(&path, &["edge"]).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+    #[test]
+    fn element_offsets_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut buf = b"ply\nformat binary_big_endian 1.0\nelement face 2\nproperty list uchar uchar i\n\
+            element point 2\nproperty short x\nelement edge 1\nproperty uchar a\nend_header\n".to_vec();
+        let start = buf.len() as u64;
+        buf.extend_from_slice(&[1, 7, 2, 8, 9, 0, 1, 0, 2, 3]);
+        let mut reader = Cursor::new(buf);
+        let header = p.read_header(&mut reader).unwrap();
+        assert_eq!(p.element_offsets(&mut reader, &header, None).unwrap(), vec![start, start + 5, start + 9]);
+        reader.set_position(start + 5);
+        let points = p.__read_element_list(&mut reader, &mut LocationTracker::new(), &header.elements["point"], &header.encoding).unwrap();
+        assert_eq!(points[1]["x"], Property::Short(2));
+
+        let buf = b"ply\nformat binary_big_endian 1.0\nelement a 4611686018427387904\nproperty double x\n\
+            element b 1\nproperty uchar y\nend_header\n".to_vec();
+        let mut reader = Cursor::new(buf);
+        let header = p.read_header(&mut reader).unwrap();
+        assert_eq!(p.element_offsets(&mut reader, &header, None).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
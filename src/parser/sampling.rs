@@ -0,0 +1,125 @@
+//! Reading a subset of the rows of each element.
+
+use std::io;
+use std::io::{ BufRead, ErrorKind, Result };
+
+use byteorder::{ BigEndian, LittleEndian };
+
+use super::{ Parser, element_line_rethrow };
+use crate::ply::{ ElementDef, Encoding, PropertyAccess };
+use crate::util::{ LocationTracker, SplitMix64 };
+
+/// Selects the rows of each element kept by the `Parser`, see `Parser::set_sampling()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Sampling {
+    /// Keeps every row.
+    All,
+    /// Keeps rows `0, n, 2n, ...`.
+    EveryNth(usize),
+    /// Keeps a uniform random sample of `size` rows, in file order.
+    ///
+    /// The same `seed` selects the same rows of the same file.
+    Reservoir { size: usize, seed: u64 },
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    pub(super) fn __read_sampled_payload_for_element<T: BufRead>(&self, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef, encoding: &Encoding) -> Result<Vec<E>> {
+        let mut rng = match self.sampling {
+            Sampling::EveryNth(0) => return Err(io::Error::new(ErrorKind::InvalidInput, "Sampling every 0th row is not possible.")),
+            Sampling::Reservoir { seed, .. } => SplitMix64(seed),
+            _ => SplitMix64(0),
+        };
+        let stride = element_def.fixed_stride();
+        let mut skip_buffer = vec![0; stride.unwrap_or(0)];
+        let mut line = String::new();
+        let mut non_finite = 0;
+        // Kept rows with their index, the reservoir is sorted by index in the end.
+        let mut kept = Vec::<(usize, E)>::new();
+        for i in 0..element_def.count {
+            // Reservoir slot replaced by row `i`, or `kept.len()` to append.
+            let slot = match self.sampling {
+                Sampling::All => Some(kept.len()),
+                Sampling::EveryNth(n) => if i % n == 0 { Some(kept.len()) } else { None },
+                Sampling::Reservoir { size, .. } => if i < size {
+                    Some(kept.len())
+                } else {
+                    let j = rng.below(i as u64 + 1) as usize;
+                    if j < size { Some(j) } else { None }
+                },
+            };
+            let element = match *encoding {
+                Encoding::Ascii => {
+                    line.clear();
+                    reader.read_line(&mut line)?;
+                    if slot.is_none() {
+                        location.next_line();
+                        self.__progress_rows(1);
+                        continue;
+                    }
+                    match self.__read_ascii_element(&line, element_def, &mut non_finite) {
+                        Ok(e) => e,
+                        Err(e) => return element_line_rethrow(location, &line, e),
+                    }
+                },
+                _ if slot.is_none() && stride.is_some() => {
+                    reader.read_exact(&mut skip_buffer)?;
+                    location.next_line();
+                    self.__progress_rows(1);
+                    continue;
+                },
+                Encoding::BinaryBigEndian => self.__read_binary_element::<T, BigEndian>(reader, element_def)?,
+                Encoding::BinaryLittleEndian => self.__read_binary_element::<T, LittleEndian>(reader, element_def)?,
+            };
+            location.next_line();
+            self.__progress_rows(1);
+            match slot {
+                Some(s) if s == kept.len() => kept.push((i, element)),
+                Some(s) => kept[s] = (i, element),
+                None => (),
+            }
+        }
+        self.__warn_non_finite(element_def, non_finite);
+        kept.sort_by_key(|&(i, _)| i);
+        Ok(kept.into_iter().map(|(_, e)| e).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property };
+    fn points(encoding: &str, n: usize) -> Vec<u8> {
+        let mut buf = format!("ply\nformat {} 1.0\nelement point {}\nproperty int x\nend_header\n", encoding, n).into_bytes();
+        for i in 0..n as i32 {
+            if encoding == "ascii" {
+                buf.extend_from_slice(format!("{}\n", i).as_bytes());
+            } else {
+                buf.extend_from_slice(&i.to_le_bytes());
+            }
+        }
+        buf
+    }
+    fn xs(p: &Parser<DefaultElement>, buf: &[u8]) -> Vec<i32> {
+        let ply = p.read_ply(&mut &buf[..]).unwrap();
+        ply.payload["point"].iter().map(|e| match e["x"] { Property::Int(x) => x, _ => panic!() }).collect()
+    }
+    #[test]
+    fn every_nth_ok() {
+        let mut p = Parser::<DefaultElement>::new();
+        p.set_sampling(Sampling::EveryNth(3));
+        assert_eq!(xs(&p, &points("ascii", 10)), vec![0, 3, 6, 9]);
+        assert_eq!(xs(&p, &points("binary_little_endian", 10)), vec![0, 3, 6, 9]);
+        p.set_sampling(Sampling::EveryNth(0));
+        assert!(p.read_ply(&mut &points("ascii", 10)[..]).is_err());
+    }
+    #[test]
+    fn reservoir_ok() {
+        let mut p = Parser::<DefaultElement>::new();
+        p.set_sampling(Sampling::Reservoir { size: 5, seed: 42 });
+        let ascii = xs(&p, &points("ascii", 1000));
+        assert_eq!(ascii.len(), 5);
+        assert!(ascii.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(ascii, xs(&p, &points("binary_little_endian", 1000)));
+        assert_eq!(xs(&p, &points("ascii", 3)), vec![0, 1, 2]);
+    }
+}
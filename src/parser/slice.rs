@@ -0,0 +1,70 @@
+//! Parsing PLY files held in memory as a whole.
+
+use std::result;
+
+use super::Parser;
+use crate::ply::{ Error, Ply, PropertyAccess };
+use crate::util::LocationTracker;
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Parses a complete PLY file from `bytes`, like `read_ply()` but without an intermediate buffer.
+    ///
+    /// Header lines are parsed in place, binary rows are decoded straight from the slice.
+    /// Bytes following the last row are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let data = std::fs::read("example_plys/house_2_ok_little_endian.ply").unwrap();
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let ply = p.parse_slice(&data).unwrap();
+    /// assert_eq!(ply.payload["face"].len(), 3);
+    /// ```
+    pub fn parse_slice(&self, bytes: &[u8]) -> result::Result<Ply<E>, Error> {
+        let start = self.__stats_start();
+        let mut reader = bytes;
+        let mut location = LocationTracker::new();
+        let header = self.__read_header(&mut reader, &mut location)?;
+        let header_bytes = (bytes.len() - reader.len()) as u64;
+        let progress = self.__progress_start(&header);
+        let payload = self.__read_payload(&mut reader, &mut location, &header);
+        if progress {
+            self.__progress_stop();
+        }
+        let payload = payload?;
+        self.__record_stats(start, header_bytes, (bytes.len() - reader.len()) as u64, &payload);
+        Ok(Ply { header, payload })
+    }
+    /// Parses a complete ascii PLY file from `text`, see `parse_slice()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let text = "ply\nformat ascii 1.0\nelement point 2\nproperty int x\nend_header\n1\n2\n";
+    /// let ply = parser::Parser::<ply::DefaultElement>::new().parse_str(text).unwrap();
+    /// assert_eq!(ply.payload["point"][1]["x"], ply::Property::Int(2));
+    /// ```
+    pub fn parse_str(&self, text: &str) -> result::Result<Ply<E>, Error> {
+        self.parse_slice(text.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    #[test]
+    fn parse_slice_ok() {
+        let p = Parser::<DefaultElement>::new();
+        for path in &["example_plys/greg_turk_example1_ok_ascii.ply", "example_plys/house_2_ok_little_endian.ply"] {
+            let data = std::fs::read(path).unwrap();
+            let expected = p.read_ply(&mut &data[..]).unwrap();
+            let ply = p.parse_slice(&data).unwrap();
+            assert_eq!(ply.header, expected.header);
+            assert_eq!(ply.payload, expected.payload);
+            assert!(matches!(p.parse_slice(&data[..data.len() - 3]), Err(Error::Payload { .. })));
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Metrics of the last read, e.g. for logging ingestion.
+
+use std::sync::MutexGuard;
+use std::time::{ Duration, Instant };
+
+use super::Parser;
+use crate::ply::{ KeyMap, Payload, PropertyAccess };
+
+/// Metrics of a successful `Parser::read_ply()` or `Parser::parse_slice()`, see `Parser::last_stats()`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ParseStats {
+    /// Bytes of the header, including the line break after `end_header`.
+    pub header_bytes: u64,
+    /// Bytes of header and payload consumed, bytes after the last row aren't counted.
+    pub bytes: u64,
+    /// Rows stored for each element read, in header order.
+    pub rows: KeyMap<usize>,
+    /// Time taken, if enabled with `Parser::set_measure_time()`.
+    pub elapsed: Option<Duration>,
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Enables measuring the time taken by reads for `ParseStats::elapsed`.
+    ///
+    /// Disabled by default, as the clock isn't available on all targets, e.g. `wasm32-unknown-unknown`.
+    pub fn set_measure_time(&mut self, measure: bool) {
+        self.measure_time = measure;
+    }
+    /// Metrics of the last `read_ply()` or `parse_slice()`, `None` if it failed or none was done.
+    ///
+    /// Bytes are counted as given to the parser, i.e. after decompression for `read_ply_gz()` and `read_ply_zstd()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let data = std::fs::read("example_plys/house_2_ok_little_endian.ply").unwrap();
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// p.read_ply(&mut &data[..]).unwrap();
+    /// let stats = p.last_stats().unwrap();
+    /// assert_eq!(stats.bytes, data.len() as u64);
+    /// assert_eq!(stats.rows["vertex"], 5);
+    /// ```
+    pub fn last_stats(&self) -> Option<ParseStats> {
+        self.__stats().clone()
+    }
+    fn __stats(&self) -> MutexGuard<'_, Option<ParseStats>> {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    /// Clears the metrics of the previous read, returns the start time if measured.
+    pub(super) fn __stats_start(&self) -> Option<Instant> {
+        *self.__stats() = None;
+        match self.measure_time {
+            true => Some(Instant::now()),
+            false => None,
+        }
+    }
+    pub(super) fn __record_stats(&self, start: Option<Instant>, header_bytes: u64, bytes: u64, payload: &Payload<E>) {
+        *self.__stats() = Some(ParseStats {
+            header_bytes,
+            bytes,
+            rows: payload.iter().map(|(k, rows)| (k.clone(), rows.len())).collect(),
+            elapsed: start.map(|s| s.elapsed()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Projection;
+    use crate::ply::DefaultElement;
+    #[test]
+    fn last_stats_ok() {
+        let text = "ply\r\nformat ascii 1.0\r\nelement point 2\r\nproperty int x\r\nelement face 1\r\nproperty int y\r\nend_header\r\n1\r\n2\r\n3\r\ntrailing";
+        let mut p = Parser::<DefaultElement>::new();
+        assert_eq!(p.last_stats(), None);
+        p.read_ply(&mut text.as_bytes()).unwrap();
+        let stats = p.last_stats().unwrap();
+        assert_eq!((stats.header_bytes, stats.bytes), (text.find("1\r\n2").unwrap() as u64, text.find("trailing").unwrap() as u64));
+        assert_eq!(stats.rows.into_iter().collect::<Vec<_>>(), vec![("point".to_string(), 2), ("face".to_string(), 1)]);
+        assert_eq!(stats.elapsed, None);
+        p.set_measure_time(true);
+        p.set_projection(Projection::all().select("face", &[]));
+        assert_eq!(p.parse_str(text).unwrap().payload.len(), 1);
+        let stats = p.last_stats().unwrap();
+        assert_eq!((stats.bytes, stats.rows.len()), (text.find("trailing").unwrap() as u64, 1));
+        assert!(stats.elapsed.is_some());
+        assert!(p.read_ply(&mut &text.as_bytes()[..60]).is_err());
+        assert_eq!(p.last_stats(), None);
+    }
+}
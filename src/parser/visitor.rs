@@ -0,0 +1,168 @@
+//! Reading a PLY file through callbacks, without building any elements.
+
+use std::io::{ BufRead, BufReader, Read, Result };
+use std::result;
+use std::slice::Iter;
+
+use byteorder::{ BigEndian, ByteOrder, LittleEndian };
+
+use super::{ Parser, element_line_rethrow, payload_error };
+use crate::ply::{ ElementDef, Encoding, Error, Header, Property, PropertyAccess, PropertyDef };
+use crate::util::LocationTracker;
+
+/// Receives the content of a PLY file from `Parser::read_with()`, in file order.
+///
+/// All methods do nothing by default. Returning an error stops reading and is passed on by `read_with()`.
+pub trait PlyVisitor {
+    /// Called once, before any row.
+    fn header(&mut self, _header: &Header) -> Result<()> {
+        Ok(())
+    }
+    /// Called before the values of row `index` of `element`.
+    fn element_start(&mut self, _element: &ElementDef, _index: usize) -> Result<()> {
+        Ok(())
+    }
+    /// Called for each value of a row, in the order of the properties in the header.
+    fn property(&mut self, _element: &ElementDef, _property: &PropertyDef, _value: Property) -> Result<()> {
+        Ok(())
+    }
+    /// Called after the values of row `index` of `element`.
+    fn element_end(&mut self, _element: &ElementDef, _index: usize) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads the PLY file from `source` and passes its header and values to `visitor`.
+    ///
+    /// Nothing but the current row is held in memory. Properties not selected by `set_projection()` aren't passed on,
+    /// neither are rows of elements not selected. Checksums aren't verified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// struct MaxZ(f32);
+    /// impl parser::PlyVisitor for MaxZ {
+    ///     fn property(&mut self, _: &ply::ElementDef, property: &ply::PropertyDef, value: ply::Property) -> std::io::Result<()> {
+    ///         if let ("z", ply::Property::Float(z)) = (property.name.as_str(), value) {
+    ///             self.0 = self.0.max(z);
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let mut max_z = MaxZ(f32::MIN);
+    /// parser::Parser::<ply::DefaultElement>::new().read_with(&mut f, &mut max_z).unwrap();
+    /// assert_eq!(max_z.0, 1.0);
+    /// ```
+    pub fn read_with<T: Read, V: PlyVisitor>(&self, source: &mut T, visitor: &mut V) -> result::Result<(), Error> {
+        let mut reader = BufReader::new(source);
+        let mut location = LocationTracker::new();
+        let header = self.__read_header(&mut reader, &mut location)?;
+        visitor.header(&header)?;
+        for element_def in header.elements.values() {
+            let first_line = location.line_index;
+            self.__visit_element(&mut reader, &mut location, element_def, header.encoding, visitor)
+                .map_err(|e| payload_error(e, element_def, location.line_index - first_line))?;
+        }
+        Ok(())
+    }
+    fn __visit_element<T: BufRead, V: PlyVisitor>(&self, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef, encoding: Encoding, visitor: &mut V) -> Result<()> {
+        if !self.projection.keeps_element(&element_def.name) {
+            return self.__skip_element(reader, location, element_def, &encoding);
+        }
+        let mut line = String::new();
+        let mut non_finite = 0;
+        for index in 0..element_def.count {
+            visitor.element_start(element_def, index)?;
+            match encoding {
+                Encoding::Ascii => {
+                    line.clear();
+                    reader.read_line(&mut line)?;
+                    self.__visit_ascii_row(&line, element_def, &mut non_finite, visitor)
+                        .or_else(|e| element_line_rethrow(location, &line, e))?;
+                },
+                Encoding::BinaryBigEndian => self.__visit_binary_row::<T, BigEndian, V>(reader, element_def, visitor)?,
+                Encoding::BinaryLittleEndian => self.__visit_binary_row::<T, LittleEndian, V>(reader, element_def, visitor)?,
+            }
+            visitor.element_end(element_def, index)?;
+            location.next_line();
+        }
+        self.__warn_non_finite(element_def, non_finite);
+        Ok(())
+    }
+    fn __visit_ascii_row<V: PlyVisitor>(&self, line: &str, element_def: &ElementDef, non_finite: &mut usize, visitor: &mut V) -> Result<()> {
+        let tokens = self.__tokenize_ascii_line(line)?;
+        let mut token_iter: Iter<&str> = tokens.iter();
+        let selected = self.projection.selected(&element_def.name);
+        for (k, p) in &element_def.properties {
+            if !selected.contains(k) {
+                self.__skip_ascii_property(&mut token_iter, &p.data_type)?;
+                continue;
+            }
+            let value = self.__read_ascii_property(&mut token_iter, &p.data_type, non_finite)?;
+            visitor.property(element_def, p, value)?;
+        }
+        self.__check_trailing_values(&mut token_iter)
+    }
+    fn __visit_binary_row<T: Read, B: ByteOrder, V: PlyVisitor>(&self, reader: &mut T, element_def: &ElementDef, visitor: &mut V) -> Result<()> {
+        let selected = self.projection.selected(&element_def.name);
+        for (k, p) in &element_def.properties {
+            if !selected.contains(k) {
+                self.__skip_binary_property::<T, B>(reader, &p.data_type)?;
+                continue;
+            }
+            let value = self.__read_binary_property::<T, B>(reader, &p.data_type)?;
+            visitor.property(element_def, p, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+    impl PlyVisitor for Trace {
+        fn header(&mut self, header: &Header) -> Result<()> {
+            self.0.push(format!("{} elements", header.elements.len()));
+            Ok(())
+        }
+        fn element_start(&mut self, element: &ElementDef, index: usize) -> Result<()> {
+            self.0.push(format!("{} {}", element.name, index));
+            Ok(())
+        }
+        fn property(&mut self, _: &ElementDef, property: &PropertyDef, value: Property) -> Result<()> {
+            self.0.push(format!("{}={:?}", property.name, value));
+            Ok(())
+        }
+        fn element_end(&mut self, _: &ElementDef, _: usize) -> Result<()> {
+            self.0.push("end".to_string());
+            Ok(())
+        }
+    }
+    #[test]
+    fn read_with_ok() {
+        let header = "element point 2\nproperty short x\nproperty list uchar uchar l\nelement face 1\nproperty uchar i\nend_header\n";
+        let ascii = format!("ply\nformat ascii 1.0\n{}1 2 5 6\n-1 0\n3\n", header);
+        let mut binary = format!("ply\nformat binary_big_endian 1.0\n{}", header).into_bytes();
+        binary.extend_from_slice(&[0, 1, 2, 5, 6, 255, 255, 0, 3]);
+        let mut p = Parser::<DefaultElement>::new();
+        for data in &[ascii.into_bytes(), binary] {
+            let mut trace = Trace::default();
+            p.read_with(&mut &data[..], &mut trace).unwrap();
+            assert_eq!(trace.0, vec![
+                "2 elements", "point 0", "x=Short(1)", "l=ListUChar([5, 6])", "end",
+                "point 1", "x=Short(-1)", "l=ListUChar([])", "end", "face 0", "i=UChar(3)", "end",
+            ]);
+        }
+        p.set_projection(crate::parser::Projection::all().select("point", &["l"]));
+        let mut trace = Trace::default();
+        let truncated = "ply\nformat ascii 1.0\nelement point 2\nproperty short x\nproperty list uchar uchar l\nend_header\n1 2 5 6\n";
+        assert!(matches!(p.read_with(&mut truncated.as_bytes(), &mut trace), Err(Error::Payload { index: 1, .. })));
+        assert_eq!(trace.0, vec!["1 elements", "point 0", "l=ListUChar([5, 6])", "end", "point 1"]);
+    }
+}
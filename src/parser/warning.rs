@@ -0,0 +1,60 @@
+use std::fmt;
+use std::fmt::{ Display, Formatter };
+
+use crate::ply::Encoding;
+
+/// Describes an irregularity in the input that didn't prevent reading it.
+///
+/// The `Parser` collects warnings while reading, retrieve them with `Parser::take_warnings()`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Warning {
+    /// Ascii tokens like `nan`, `inf` or `-inf` have been accepted as float values in lenient mode.
+    NonFiniteValues {
+        /// Name of the element containing the values.
+        element: String,
+        /// Number of non-finite values found.
+        count: usize,
+    },
+    /// The float values at the start of a binary payload look implausible in the declared byte order, but sane in the other.
+    ///
+    /// Only reported if enabled with `Parser::set_check_endianness()`.
+    SuspiciousEndianness {
+        /// Name of the element checked.
+        element: String,
+        /// Encoding declared in the header.
+        declared: Encoding,
+    },
+    /// A malformed header line has been read ignoring leading whitespace or trailing tokens in lenient mode.
+    RepairedHeaderLine {
+        /// Line number, starting at 1.
+        line: usize,
+        /// The line as found in the file.
+        text: String,
+    },
+    /// The header has no valid `format` line, the encoding has been guessed from the payload.
+    ///
+    /// Only reported if enabled with `Parser::set_infer_encoding()`.
+    InferredEncoding {
+        /// The encoding guessed.
+        encoding: Encoding,
+        /// The malformed `format` line dropped, if any.
+        discarded: Option<String>,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Warning::NonFiniteValues { ref element, count } =>
+                write!(f, "Element `{}` contains {} non-finite float value(s).", element, count),
+            Warning::SuspiciousEndianness { ref element, declared } =>
+                write!(f, "Float values of element `{}` look implausible as declared {}, the byte order may be swapped.", element, declared),
+            Warning::RepairedHeaderLine { line, ref text } =>
+                write!(f, "Malformed header line {} '{}' has been read partially.", line, text),
+            Warning::InferredEncoding { encoding, discarded: None } =>
+                write!(f, "No format line found, the payload has been read as {}.", encoding),
+            Warning::InferredEncoding { encoding, discarded: Some(ref text) } =>
+                write!(f, "Malformed format line '{}' dropped, the payload has been read as {}.", text, encoding),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Reading zstd compressed PLY files.
+
+use std::io::Read;
+use std::result;
+
+use zstd::stream::read::Decoder;
+
+use super::Parser;
+use crate::ply::{ Error, Ply, PropertyAccess };
+
+impl<E: PropertyAccess> Parser<E> {
+    /// Reads a complete PLY file from the zstd compressed `source`, like `read_ply()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// # let data = std::fs::read("example_plys/house_2_ok_little_endian.ply").unwrap();
+    /// # let compressed = zstd::encode_all(&data[..], 0).unwrap();
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let ply = p.read_ply_zstd(&mut &compressed[..]).unwrap();
+    /// assert_eq!(ply.payload["vertex"].len(), 5);
+    /// ```
+    pub fn read_ply_zstd<T: Read>(&self, source: &mut T) -> result::Result<Ply<E>, Error> {
+        self.read_ply(&mut Decoder::new(source)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    use crate::writer::Writer;
+    #[test]
+    fn zstd_round_trip() {
+        let p = Parser::<DefaultElement>::new();
+        let mut ply = p.read_ply_from_path("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+        let mut w = Writer::new();
+        let mut fast = Vec::new();
+        w.write_ply_zstd(&mut fast, &mut ply).unwrap();
+        w.set_zstd_level(19);
+        let mut small = Vec::new();
+        w.write_ply_zstd(&mut small, &mut ply).unwrap();
+        assert!(small.len() <= fast.len());
+        assert_eq!(small[..4], super::super::path::ZSTD_MAGIC);
+        let path = std::env::temp_dir().join(format!("ply_rs_zstd_{}.ply.zst", std::process::id()));
+        std::fs::write(&path, &small).unwrap();
+        let read = p.read_ply_from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap().payload, ply.payload);
+        assert!(p.read_ply_zstd(&mut &small[..small.len() / 2]).is_err());
+        let w = Writer::with_compression(crate::writer::Compression::Zstd(3));
+        let mut buf = Vec::new();
+        let written = w.write_ply(&mut buf, &mut ply).unwrap();
+        assert_eq!(written, Writer::new().write_ply(&mut Vec::new(), &mut ply).unwrap());
+        assert_eq!(p.read_ply_zstd(&mut &buf[..]).unwrap().payload, ply.payload);
+    }
+}
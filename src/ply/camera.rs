@@ -0,0 +1,151 @@
+//! The `camera` element of range scans, as written by VCGLib and MeshLab.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Addable, ElementDef, Ply, PropertyAccess, PropertyDef, PropertyType, ScalarType };
+use crate::util::{ element_def, get_scalar_as_f64, set_scalar_from_f64 };
+
+/// Name of the element holding one row per camera.
+pub const CAMERA_ELEMENT: &str = "camera";
+
+/// Float properties of the camera element in declaration order, `viewportx` and `viewporty` are ints.
+const PROPERTIES: [&str; 23] = [
+    "view_px", "view_py", "view_pz",
+    "x_axisx", "x_axisy", "x_axisz",
+    "y_axisx", "y_axisy", "y_axisz",
+    "z_axisx", "z_axisy", "z_axisz",
+    "focal", "scalex", "scaley", "centerx", "centery",
+    "viewportx", "viewporty",
+    "k1", "k2", "k3", "k4",
+];
+
+/// Acquisition geometry of a scan.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Camera {
+    /// Position of the camera, `view_px`, `view_py`, `view_pz`.
+    pub view_point: [f64; 3],
+    /// Right vector of the image plane.
+    pub x_axis: [f64; 3],
+    /// Up vector.
+    pub y_axis: [f64; 3],
+    /// View direction.
+    pub z_axis: [f64; 3],
+    /// Focal length, in the units of `view_point`.
+    pub focal: f64,
+    /// Size of a pixel, `scalex`, `scaley`.
+    pub scale: [f64; 2],
+    /// Principal point in pixels, `centerx`, `centery`.
+    pub center: [f64; 2],
+    /// Image dimensions in pixels, `viewportx`, `viewporty`.
+    pub viewport: [u32; 2],
+    /// Radial distortion coefficients `k1` to `k4`, zero if not declared.
+    pub distortion: [f64; 4],
+}
+
+impl Camera {
+    fn values(&self) -> [f64; 23] {
+        let mut v = [0.0; 23];
+        v[0..3].copy_from_slice(&self.view_point);
+        v[3..6].copy_from_slice(&self.x_axis);
+        v[6..9].copy_from_slice(&self.y_axis);
+        v[9..12].copy_from_slice(&self.z_axis);
+        v[12] = self.focal;
+        v[13..15].copy_from_slice(&self.scale);
+        v[15..17].copy_from_slice(&self.center);
+        v[17] = f64::from(self.viewport[0]);
+        v[18] = f64::from(self.viewport[1]);
+        v[19..23].copy_from_slice(&self.distortion);
+        v
+    }
+    fn from_values(v: &[f64; 23]) -> Self {
+        let mut c = Camera::default();
+        c.view_point.copy_from_slice(&v[0..3]);
+        c.x_axis.copy_from_slice(&v[3..6]);
+        c.y_axis.copy_from_slice(&v[6..9]);
+        c.z_axis.copy_from_slice(&v[9..12]);
+        c.focal = v[12];
+        c.scale.copy_from_slice(&v[13..15]);
+        c.center.copy_from_slice(&v[15..17]);
+        c.viewport = [v[17] as u32, v[18] as u32];
+        c.distortion.copy_from_slice(&v[19..23]);
+        c
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Reads all rows of the `camera` element.
+    ///
+    /// The distortion coefficients are optional, all other properties are required.
+    pub fn cameras(&self) -> Result<Vec<Camera>> {
+        let def = element_def(self, CAMERA_ELEMENT)?;
+        let defs = PROPERTIES.iter().map(|&n| match def.properties.get(n) {
+            Some(p) => Ok(Some(p)),
+            None if n.starts_with('k') => Ok(None),
+            None => Err(io::Error::new(ErrorKind::InvalidInput, format!("Element `camera` has no property `{}`.", n))),
+        }).collect::<Result<Vec<_>>>()?;
+        let rows = self.payload.get(CAMERA_ELEMENT).map(|l| &l[..]).unwrap_or_default();
+        rows.iter().enumerate().map(|(row, e)| {
+            let mut values = [0.0; 23];
+            for (v, def) in values.iter_mut().zip(&defs) {
+                if let Some(def) = def {
+                    *v = match get_scalar_as_f64(e, def) {
+                        Some(v) => v,
+                        None => return Err(io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Element `camera` at index {} has no scalar value for property `{}`.", row, def.name)
+                        )),
+                    };
+                }
+            }
+            Ok(Camera::from_values(&values))
+        }).collect()
+    }
+    /// Stores `cameras` as `camera` element, replacing a previous one.
+    pub fn set_cameras(&mut self, cameras: &[Camera]) {
+        let mut def = ElementDef::new(CAMERA_ELEMENT.to_string());
+        for n in PROPERTIES.iter() {
+            let scalar_type = if n.starts_with("viewport") { ScalarType::Int } else { ScalarType::Float };
+            def.properties.add(PropertyDef::new(n.to_string(), PropertyType::Scalar(scalar_type)));
+        }
+        def.count = cameras.len();
+        let rows = cameras.iter().map(|c| {
+            let mut e = E::new();
+            for (p, v) in def.properties.values().zip(c.values().iter()) {
+                set_scalar_from_f64(&mut e, p, *v);
+            }
+            e
+        }).collect();
+        self.header.elements.add(def);
+        self.payload.insert(CAMERA_ELEMENT.to_string(), rows);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    #[test]
+    fn cameras_round_trip() {
+        let mut ply = Ply::<DefaultElement>::new();
+        assert!(ply.cameras().is_err());
+        let camera = Camera {
+            view_point: [0.5, 1.0, -2.0],
+            x_axis: [1.0, 0.0, 0.0],
+            y_axis: [0.0, 1.0, 0.0],
+            z_axis: [0.0, 0.0, 1.0],
+            focal: 0.25,
+            scale: [0.0078125, 0.0078125],
+            center: [320.0, 240.0],
+            viewport: [640, 480],
+            distortion: [0.0; 4],
+        };
+        ply.set_cameras(std::slice::from_ref(&camera));
+        assert_eq!(ply.header.elements["camera"].properties["viewportx"].data_type, PropertyType::Scalar(ScalarType::Int));
+        assert_eq!(ply.cameras().unwrap(), vec![camera.clone()]);
+
+        ply.header.elements.get_mut("camera").unwrap().properties.remove("k4");
+        assert_eq!(ply.cameras().unwrap(), vec![camera]);
+        ply.header.elements.get_mut("camera").unwrap().properties.remove("focal");
+        assert!(ply.cameras().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Maps scalar properties to `red`, `green` and `blue` vertex colors.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Addable, Ply, Property, PropertyAccess, PropertyDef, PropertyType, ScalarType };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64 };
+
+/// Color ramp used by `Ply::colorize()`.
+///
+/// `Viridis` and `Turbo` are evaluated by polynomial fits, which deviate from the reference tables by a few levels.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Colormap {
+    /// Perceptually uniform blue to yellow map of matplotlib.
+    Viridis,
+    /// Rainbow-like map with improved perceptual ordering.
+    Turbo,
+    /// Linear interpolation between equally spaced colors, from low to high values.
+    Ramp(Vec<[u8; 3]>),
+}
+
+/// Defines which scalar values are mapped to both ends of the colormap.
+///
+/// Values outside the range are clamped, non-finite values get the color of the lowest value.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Normalization {
+    /// Minimum and maximum of all finite values.
+    MinMax,
+    /// Fixed range `(low, high)`.
+    Range(f64, f64),
+    /// Percentiles `(low, high)` of all finite values, between 0 and 100, e.g. `(2.0, 98.0)` to ignore outliers.
+    Percentile(f64, f64),
+}
+
+/// Evaluates `c0 + t * (c1 + t * (c2 + ...))` per channel.
+fn polynomial(coefficients: &[[f64; 3]], t: f64) -> [f64; 3] {
+    let mut c = [0.0; 3];
+    for k in coefficients.iter().rev() {
+        for (c, k) in c.iter_mut().zip(k.iter()) {
+            *c = *c * t + k;
+        }
+    }
+    c
+}
+
+const VIRIDIS: [[f64; 3]; 7] = [
+    [0.277_727_327_223_417_7, 0.005_407_344_544_966_578, 0.334_099_805_335_306_1],
+    [0.105_093_043_108_577_4, 1.404_613_529_898_575, 1.384_590_162_594_685],
+    [-0.330_861_828_725_556_3, 0.214_847_559_468_213, 0.095_095_163_028_236_59],
+    [-4.634_230_498_983_486, -5.799_100_973_351_585, -19.332_440_956_279_87],
+    [6.228_269_936_347_081, 14.179_933_366_805_09, 56.690_552_600_681_05],
+    [4.776_384_997_670_288, -13.745_145_377_746_01, -65.353_032_633_372_34],
+    [-5.435_455_855_934_631, 4.645_852_612_178_535, 26.312_435_249_583_2],
+];
+
+const TURBO: [[f64; 3]; 6] = [
+    [0.135_721_38, 0.091_402_61, 0.106_673_30],
+    [4.615_392_60, 2.194_188_39, 12.641_946_08],
+    [-42.660_322_58, 4.842_966_58, -60.582_048_36],
+    [132.131_082_34, -14.185_033_33, 110.362_767_71],
+    [-152.942_393_96, 4.277_298_57, -89.903_109_12],
+    [59.286_379_43, 2.829_566_04, 27.348_249_73],
+];
+
+impl Colormap {
+    /// Color at `t`, which is clamped to `0..=1`.
+    pub fn color(&self, t: f64) -> [u8; 3] {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let c = match *self {
+            Colormap::Viridis => polynomial(&VIRIDIS, t),
+            Colormap::Turbo => polynomial(&TURBO, t),
+            Colormap::Ramp(ref colors) => {
+                match colors.len() {
+                    0 => return [0, 0, 0],
+                    1 => return colors[0],
+                    _ => (),
+                }
+                let x = t * (colors.len() - 1) as f64;
+                let i = (x.floor() as usize).min(colors.len() - 2);
+                let f = x - i as f64;
+                let mut c = [0.0; 3];
+                for (k, c) in c.iter_mut().enumerate() {
+                    *c = (f64::from(colors[i][k]) * (1.0 - f) + f64::from(colors[i + 1][k]) * f) / 255.0;
+                }
+                c
+            },
+        };
+        let mut rgb = [0; 3];
+        for (rgb, c) in rgb.iter_mut().zip(c.iter()) {
+            *rgb = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        rgb
+    }
+}
+
+impl Normalization {
+    /// Resolves the normalization to a fixed `(low, high)` range for `values`.
+    pub fn range(&self, values: &[f64]) -> (f64, f64) {
+        match *self {
+            Normalization::Range(low, high) => (low, high),
+            Normalization::MinMax => Normalization::Percentile(0.0, 100.0).range(values),
+            Normalization::Percentile(low, high) => {
+                let mut finite = values.iter().cloned().filter(|v| v.is_finite()).collect::<Vec<_>>();
+                if finite.is_empty() {
+                    return (0.0, 1.0);
+                }
+                finite.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let at = |p: f64| {
+                    let i = (p.clamp(0.0, 100.0) / 100.0 * (finite.len() - 1) as f64).round() as usize;
+                    finite[i]
+                };
+                (at(low), at(high))
+            },
+        }
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Maps the scalar `property` of `element` through `colormap` into `uchar` properties `red`, `green` and `blue`.
+    ///
+    /// The color properties are added to the element definition if missing.
+    /// Returns the `(low, high)` range mapped to both ends of the colormap, e.g. to draw a legend.
+    pub fn colorize(&mut self, element: &str, property: &str, colormap: &Colormap, normalization: Normalization) -> Result<(f64, f64)> {
+        let def = property_defs(element_def(self, element)?, &[property])?.remove(0);
+        if let PropertyType::List(_, _) = def.data_type {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Property `{}.{}` is a list.", element, property)));
+        }
+        let element_def = self.header.elements.get_mut(element).unwrap();
+        for channel in &["red", "green", "blue"] {
+            match element_def.properties.get(*channel) {
+                None => element_def.properties.add(PropertyDef::new(channel.to_string(), PropertyType::Scalar(ScalarType::UChar))),
+                Some(p) if p.data_type == PropertyType::Scalar(ScalarType::UChar) => (),
+                Some(p) => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Property `{}.{}` has type {:?}, expected uchar.", element, channel, p.data_type)
+                )),
+            }
+        }
+
+        let list = match self.payload.get_mut(element) {
+            Some(l) => l,
+            None => return Ok(normalization.range(&[])),
+        };
+        let values = list.iter().enumerate()
+            .map(|(row, e)| require_scalar_as_f64(e, &def, element, row))
+            .collect::<Result<Vec<_>>>()?;
+        let (low, high) = normalization.range(&values);
+        let scale = if high > low { 1.0 / (high - low) } else { 0.0 };
+        for (e, v) in list.iter_mut().zip(values.iter()) {
+            let rgb = colormap.color((v - low) * scale);
+            e.set_property("red".to_string(), Property::UChar(rgb[0]));
+            e.set_property("green".to_string(), Property::UChar(rgb[1]));
+            e.set_property("blue".to_string(), Property::UChar(rgb[2]));
+        }
+        Ok((low, high))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    #[test]
+    fn colormap_ends_ok() {
+        assert_eq!(Colormap::Viridis.color(0.0), [71, 1, 85]);
+        assert_eq!(Colormap::Viridis.color(2.0), [252, 231, 33]);
+        let ramp = Colormap::Ramp(vec![[0, 0, 0], [255, 0, 0], [255, 255, 255]]);
+        assert_eq!(ramp.color(0.25), [128, 0, 0]);
+        assert_eq!(ramp.color(0.75), [255, 128, 128]);
+        assert_eq!(ramp.color(f64::NAN), [0, 0, 0]);
+    }
+    #[test]
+    fn normalization_range_ok() {
+        let values = (0..=100).map(f64::from).chain(Some(f64::NAN)).collect::<Vec<_>>();
+        assert_eq!(Normalization::MinMax.range(&values), (0.0, 100.0));
+        assert_eq!(Normalization::Percentile(5.0, 90.0).range(&values), (5.0, 90.0));
+        assert_eq!(Normalization::Range(-1.0, 1.0).range(&values), (-1.0, 1.0));
+    }
+    #[test]
+    fn colorize_ok() {
+        let mut ply = Ply::<DefaultElement>::new();
+        let properties = [("intensity", PropertyType::Scalar(ScalarType::UShort))];
+        add_test_element(&mut ply, "vertex", &properties, [0, 500, 1000].iter().map(|&i| vec![Property::UShort(i)]).collect());
+        let ramp = Colormap::Ramp(vec![[0, 0, 0], [0, 0, 200]]);
+        assert_eq!(ply.colorize("vertex", "intensity", &ramp, Normalization::MinMax).unwrap(), (0.0, 1000.0));
+        assert_eq!(ply.header.elements["vertex"].properties.len(), 4);
+        assert_eq!(ply.payload["vertex"][1]["blue"], Property::UChar(100));
+        assert_eq!(ply.payload["vertex"][2]["red"], Property::UChar(0));
+        assert!(ply.make_consistent().is_ok());
+    }
+}
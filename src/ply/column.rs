@@ -0,0 +1,378 @@
+//! Typed access to all values of a single property.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Column, ColumnarPly, Header, OutOfRange, Ply, Property, PropertyAccess, PropertyDef, PropertyType, ScalarType, ScalarValue };
+use crate::util::{ get_scalar_as_f64, header_element_def, property_defs, set_scalar_from_f64 };
+
+/// Bulk operation on the values of a scalar property, see `Ply::apply_column()` and `ColumnarPly::apply_column()`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ColumnOp {
+    /// Multiplies by the factor.
+    Scale(f64),
+    /// Adds the offset.
+    Offset(f64),
+    /// Limits to `[min, max]`.
+    Clamp(f64, f64),
+    /// Replaces each value `v` by `lut[v]`, values have to be integer indices into the table.
+    Remap(Vec<f64>),
+}
+
+impl ColumnOp {
+    fn apply(&self, value: f64) -> Option<f64> {
+        match *self {
+            ColumnOp::Scale(factor) => Some(value * factor),
+            ColumnOp::Offset(offset) => Some(value + offset),
+            ColumnOp::Clamp(min, max) => Some(value.max(min).min(max)),
+            ColumnOp::Remap(ref lut) if value >= 0.0 && value.fract() == 0.0 => lut.get(value as usize).copied(),
+            ColumnOp::Remap(_) => None,
+        }
+    }
+}
+
+/// Definition of the scalar property `property_name` of `element_name` declared in `header`.
+pub(super) fn scalar_def(header: &Header, element_name: &str, property_name: &str) -> Result<PropertyDef> {
+    let def = property_defs(header_element_def(header, element_name)?, &[property_name])?.remove(0);
+    match def.data_type {
+        PropertyType::Scalar(_) => Ok(def),
+        PropertyType::List(_, _) => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Property `{}` of element `{}` is a list.", property_name, element_name)
+        )),
+    }
+}
+
+/// Converts `value` to `T` if it keeps its value, up to rounding of floats.
+fn checked<T: ScalarValue>(value: f64) -> Option<T> {
+    let integer = !matches!(T::SCALAR_TYPE, ScalarType::Float | ScalarType::Double);
+    if integer && value.fract() != 0.0 {
+        return None;
+    }
+    Property::from_f64(value, &T::SCALAR_TYPE, OutOfRange::Error).map(|_| T::from_f64(value))
+}
+
+/// Applies `ops` in order to all `values`, rounding and saturating integers.
+///
+/// Returns the row of the first value without an entry in a lookup table, leaving `values` unchanged.
+fn apply_ops<T: ScalarValue>(values: &mut [T], ops: &[ColumnOp]) -> std::result::Result<(), usize> {
+    let integer = !matches!(T::SCALAR_TYPE, ScalarType::Float | ScalarType::Double);
+    let results = values.iter().enumerate().map(|(row, v)| {
+        let r = ops.iter().try_fold(v.to_f64(), |v, op| op.apply(v)).ok_or(row)?;
+        Ok(T::from_f64(if integer { r.round() } else { r }))
+    }).collect::<std::result::Result<Vec<T>, usize>>()?;
+    values.copy_from_slice(&results);
+    Ok(())
+}
+
+/// Converts all `values` to a column of `scalar_type`, returns the row and value of the first one out of range.
+fn cast_values<T: ScalarValue>(values: &[T], scalar_type: &ScalarType, policy: OutOfRange) -> std::result::Result<Column, (usize, f64)> {
+    let mut column = Column::with_capacity(&PropertyType::Scalar(scalar_type.clone()), values.len());
+    for (row, v) in values.iter().enumerate() {
+        let pushed = Property::from_f64(v.to_f64(), scalar_type, policy).map(|p| column.push(p).is_ok());
+        if pushed != Some(true) {
+            return Err((row, v.to_f64()));
+        }
+    }
+    Ok(column)
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Iterates over the values of the scalar property `property_name` of all `element_name` elements, converted to `T`.
+    ///
+    /// Values are converted on the fly, nothing is collected.
+    /// Rows without a value for the property are skipped. See `Parser::column_iter()` to scan a file without reading it into a `Ply`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// let top = ply.column_iter::<f32>("vertex", "z").unwrap().fold(f32::MIN, f32::max);
+    /// assert_eq!(top, 1.0);
+    /// ```
+    pub fn column_iter<T: ScalarValue>(&self, element_name: &str, property_name: &str) -> Result<impl Iterator<Item = T> + '_> {
+        let def = scalar_def(&self.header, element_name, property_name)?;
+        let elements = self.payload.get(element_name).map(|l| &l[..]).unwrap_or_default();
+        Ok(elements.iter().filter_map(move |e| get_scalar_as_f64(e, &def).map(T::from_f64)))
+    }
+    /// Iterates over the values of the scalar property `property_name` of all `element_name` elements, checked to fit into `T`.
+    ///
+    /// Unlike `column_iter()`, rows without a value yield an error, as do values out of the range of `T`
+    /// and values with a fractional part if `T` is an integer type.
+    pub fn column_iter_checked<T: ScalarValue>(&self, element_name: &str, property_name: &str) -> Result<impl Iterator<Item = Result<T>> + '_> {
+        let def = scalar_def(&self.header, element_name, property_name)?;
+        let elements = self.payload.get(element_name).map(|l| &l[..]).unwrap_or_default();
+        let element_name = element_name.to_string();
+        Ok(elements.iter().enumerate().map(move |(index, e)| match get_scalar_as_f64(e, &def) {
+            Some(v) => checked(v).ok_or_else(|| io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Value {} of property `{}` in row {} of element `{}` doesn't fit into type {:?}.", v, def.name, index, element_name, T::SCALAR_TYPE)
+            )),
+            None => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Row {} of element `{}` has no value for property `{}`.", index, element_name, def.name)
+            )),
+        }))
+    }
+    /// Collects the values of the scalar property `property_name` of all `element_name` elements as `T`, see `column_iter_checked()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// let z = ply.column::<u8>("vertex", "z").unwrap();
+    /// assert_eq!(z, [0, 1, 1, 0, 0, 1, 1, 0]);
+    /// assert!(ply.column::<f32>("vertex", "w").is_err());
+    /// ```
+    pub fn column<T: ScalarValue>(&self, element_name: &str, property_name: &str) -> Result<Vec<T>> {
+        self.column_iter_checked(element_name, property_name)?.collect()
+    }
+    /// Applies `f` to the value of the scalar property `property_name` of every `element_name` element.
+    ///
+    /// Values are passed as `T` and stored back in the declared type of the property, rounded and saturated for integers.
+    /// Rows without a value are skipped, unchanged values aren't written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// ply.column_mut("vertex", "z", |z: &mut f32| *z *= 2.0).unwrap();
+    /// assert_eq!(ply.payload["vertex"][1]["z"], ply::Property::Float(2.0));
+    /// ```
+    pub fn column_mut<T: ScalarValue, F: FnMut(&mut T)>(&mut self, element_name: &str, property_name: &str, mut f: F) -> Result<()> {
+        let def = scalar_def(&self.header, element_name, property_name)?;
+        let elements = match self.payload.get_mut(element_name) {
+            Some(l) => l,
+            None => return Ok(()),
+        };
+        for e in elements.iter_mut() {
+            let value = match get_scalar_as_f64(e, &def) {
+                Some(v) => v,
+                None => continue,
+            };
+            let mut typed = T::from_f64(value);
+            f(&mut typed);
+            if typed.to_f64() != value {
+                set_scalar_from_f64(e, &def, typed.to_f64());
+            }
+        }
+        Ok(())
+    }
+    /// Applies `ops` in order to the scalar property `property_name` of every `element_name` element, in one pass.
+    ///
+    /// Results are stored in the declared type of the property, rounded and saturated for integers.
+    /// Fails without changing anything if a value isn't a valid index of a `ColumnOp::Remap` table.
+    /// Each row is converted from and back to a `Property`, `ColumnarPly::apply_column()` works on the typed values directly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// use ply_rs::ply::ColumnOp;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// ply.apply_column("vertex", "x", &[ColumnOp::Scale(2.0), ColumnOp::Offset(-1.0)]).unwrap();
+    /// assert_eq!(ply.payload["vertex"][7]["x"], ply::Property::Float(1.0));
+    /// ```
+    pub fn apply_column(&mut self, element_name: &str, property_name: &str, ops: &[ColumnOp]) -> Result<()> {
+        let def = scalar_def(&self.header, element_name, property_name)?;
+        let elements = match self.payload.get_mut(element_name) {
+            Some(l) => l,
+            None => return Ok(()),
+        };
+        let mut results = Vec::with_capacity(elements.len());
+        for (row, e) in elements.iter().enumerate() {
+            let value = get_scalar_as_f64(e, &def);
+            let result = match value {
+                Some(v) => ops.iter().try_fold(v, |v, op| op.apply(v)).map(Some),
+                None => Some(None),
+            };
+            match result {
+                Some(r) => results.push(r.filter(|&r| Some(r) != value)),
+                None => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Element `{}` at index {} has no entry in the lookup table for property `{}`.", element_name, row, property_name)
+                )),
+            }
+        }
+        for (e, r) in elements.iter_mut().zip(results) {
+            if let Some(r) = r {
+                set_scalar_from_f64(e, &def, r);
+            }
+        }
+        Ok(())
+    }
+    /// Changes the type of the scalar property `property_name` of `element_name` to `scalar_type`, converting all values.
+    ///
+    /// Integers are rounded, values out of range are handled according to `policy`.
+    /// With `OutOfRange::Error`, fails without changing anything. See `ColumnarPly::cast_column()` for typed storage.
+    pub fn cast_column(&mut self, element_name: &str, property_name: &str, scalar_type: ScalarType, policy: OutOfRange) -> Result<()> {
+        let def = scalar_def(&self.header, element_name, property_name)?;
+        let elements = self.payload.get_mut(element_name).map(|l| &mut l[..]).unwrap_or_default();
+        let mut converted = Vec::with_capacity(elements.len());
+        for (row, e) in elements.iter().enumerate() {
+            let value = match get_scalar_as_f64(e, &def) {
+                Some(v) => v,
+                None => {
+                    converted.push(None);
+                    continue;
+                },
+            };
+            match Property::from_f64(value, &scalar_type, policy) {
+                Some(p) => converted.push(Some(p)),
+                None => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Element `{}` at index {}: value {} of property `{}` is out of range for type {:?}.", element_name, row, value, property_name, scalar_type)
+                )),
+            }
+        }
+        for (e, p) in elements.iter_mut().zip(converted) {
+            if let Some(p) = p {
+                e.set_property(property_name.to_string(), p);
+            }
+        }
+        let def = self.header.elements.get_mut(element_name).unwrap().properties.get_mut(property_name).unwrap();
+        def.data_type = PropertyType::Scalar(scalar_type);
+        Ok(())
+    }
+}
+
+/// Typed column of the scalar property `property_name` of `element_name`, checked to match its declaration.
+fn scalar_column<'a>(ply: &'a mut ColumnarPly, element_name: &str, property_name: &str) -> Result<&'a mut Column> {
+    let def = scalar_def(&ply.header, element_name, property_name)?;
+    match ply.payload.get_mut(element_name).and_then(|columns| columns.get_mut(property_name)) {
+        Some(column) if column.has_type(&def.data_type) => Ok(column),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("No column of the declared type for property `{}` of element `{}`.", property_name, element_name)
+        )),
+    }
+}
+
+/// Matches the scalar variants of `$column`, binding their values to `$v`, lists yield `$list`.
+macro_rules! with_scalars {
+    ($column:expr, $v:ident => $f:expr, _ => $list:expr) => (match $column {
+        Column::Char($v) => $f, Column::UChar($v) => $f, Column::Short($v) => $f, Column::UShort($v) => $f,
+        Column::Int($v) => $f, Column::UInt($v) => $f, Column::Float($v) => $f, Column::Double($v) => $f,
+        _ => $list,
+    })
+}
+
+impl ColumnarPly {
+    /// Applies `ops` in order to the values of the scalar property `property_name` of `element_name`.
+    ///
+    /// Works like `Ply::apply_column()`, but on the typed vector of the column without converting rows.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// use ply_rs::ply::{ Column, ColumnOp };
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_columnar_ply(&mut f).unwrap();
+    /// ply.apply_column("vertex", "x", &[ColumnOp::Scale(2.0), ColumnOp::Offset(-1.0)]).unwrap();
+    /// assert_eq!(ply.payload["vertex"]["x"].get(7), Some(ply::Property::Float(1.0)));
+    /// ```
+    pub fn apply_column(&mut self, element_name: &str, property_name: &str, ops: &[ColumnOp]) -> Result<()> {
+        let column = scalar_column(self, element_name, property_name)?;
+        let result = with_scalars!(column, v => apply_ops(v, ops), _ => unreachable!("scalar_column() checks the type"));
+        result.map_err(|row| io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Element `{}` at index {} has no entry in the lookup table for property `{}`.", element_name, row, property_name)
+        ))
+    }
+    /// Changes the type of the scalar property `property_name` of `element_name` to `scalar_type`, converting all values.
+    ///
+    /// Works like `Ply::cast_column()`, but converts the typed vector of the column into a new one.
+    pub fn cast_column(&mut self, element_name: &str, property_name: &str, scalar_type: ScalarType, policy: OutOfRange) -> Result<()> {
+        let column = scalar_column(self, element_name, property_name)?;
+        let cast = with_scalars!(&*column, v => cast_values(v, &scalar_type, policy), _ => unreachable!("scalar_column() checks the type"));
+        *column = cast.map_err(|(row, value)| io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Element `{}` at index {}: value {} of property `{}` is out of range for type {:?}.", element_name, row, value, property_name, scalar_type)
+        ))?;
+        let def = self.header.elements.get_mut(element_name).unwrap().properties.get_mut(property_name).unwrap();
+        def.data_type = PropertyType::Scalar(scalar_type);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    /// Vertices with a single scalar property `name` of type `ty`, one per value.
+    fn vertices(name: &str, ty: ScalarType, values: Vec<Property>) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        add_test_element(&mut ply, "vertex", &[(name, PropertyType::Scalar(ty))], values.into_iter().map(|v| vec![v]).collect());
+        ply
+    }
+    #[test]
+    fn column_iter_ok() {
+        let mut ply = vertices("z", ScalarType::UShort, vec![Property::UShort(3), Property::UShort(1), Property::UShort(2)]);
+        let i = PropertyDef::new("i".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int));
+        ply.header.elements.get_mut("vertex").unwrap().properties.add(i);
+        assert_eq!(ply.column_iter::<f32>("vertex", "z").unwrap().collect::<Vec<_>>(), vec![3.0, 1.0, 2.0]);
+        assert!(ply.column_iter::<f32>("vertex", "i").is_err());
+        assert!(ply.column_iter::<f32>("vertex", "w").is_err());
+
+        ply.column_mut("vertex", "z", |z: &mut f32| *z = *z * 1.5 - 4.0).unwrap();
+        assert_eq!(ply.column_iter::<i32>("vertex", "z").unwrap().collect::<Vec<_>>(), vec![1, 0, 0]);
+        assert!(ply.column_mut("vertex", "i", |_: &mut f32| ()).is_err());
+    }
+    #[test]
+    fn column_ops() {
+        let mut ply = vertices("c", ScalarType::Int, (0..4).map(Property::Int).collect());
+        let column = |ply: &Ply<DefaultElement>| ply.column_iter::<f64>("vertex", "c").unwrap().collect::<Vec<_>>();
+
+        let lut = ColumnOp::Remap(vec![10.0, 20.0, 30.0]);
+        assert!(ply.apply_column("vertex", "c", std::slice::from_ref(&lut)).is_err());
+        assert_eq!(column(&ply), vec![0.0, 1.0, 2.0, 3.0]);
+        ply.apply_column("vertex", "c", &[ColumnOp::Clamp(0.0, 2.0), lut, ColumnOp::Scale(10.0), ColumnOp::Offset(1.0)]).unwrap();
+        assert_eq!(column(&ply), vec![101.0, 201.0, 301.0, 301.0]);
+
+        assert!(ply.cast_column("vertex", "c", ScalarType::UChar, OutOfRange::Error).is_err());
+        assert_eq!(ply.header.elements["vertex"].properties["c"].data_type, PropertyType::Scalar(ScalarType::Int));
+        ply.cast_column("vertex", "c", ScalarType::UChar, OutOfRange::Clamp).unwrap();
+        assert_eq!(ply.header.elements["vertex"].properties["c"].data_type, PropertyType::Scalar(ScalarType::UChar));
+        assert_eq!(ply.payload["vertex"][0]["c"], Property::UChar(101));
+        assert_eq!(column(&ply), vec![101.0, 201.0, 255.0, 255.0]);
+    }
+    #[test]
+    fn columnar_column_ops() {
+        let ply = vertices("c", ScalarType::Int, (0..4).map(Property::Int).collect());
+        let mut columnar = ColumnarPly::from_ply(&ply).unwrap();
+        let lut = ColumnOp::Remap(vec![10.0, 20.0, 30.0]);
+        assert!(columnar.apply_column("vertex", "c", std::slice::from_ref(&lut)).is_err());
+        assert_eq!(columnar.payload["vertex"]["c"], Column::Int(vec![0, 1, 2, 3]));
+        columnar.apply_column("vertex", "c", &[ColumnOp::Clamp(0.0, 2.0), lut, ColumnOp::Scale(10.25), ColumnOp::Offset(1.0)]).unwrap();
+        assert_eq!(columnar.payload["vertex"]["c"], Column::Int(vec![104, 206, 309, 309]));
+
+        assert!(columnar.cast_column("vertex", "c", ScalarType::UChar, OutOfRange::Error).is_err());
+        assert_eq!(columnar.payload["vertex"]["c"], Column::Int(vec![104, 206, 309, 309]));
+        columnar.cast_column("vertex", "c", ScalarType::UChar, OutOfRange::Clamp).unwrap();
+        assert_eq!(columnar.header.elements["vertex"].properties["c"].data_type, PropertyType::Scalar(ScalarType::UChar));
+        assert_eq!(columnar.payload["vertex"]["c"], Column::UChar(vec![104, 206, 255, 255]));
+
+        columnar.payload.get_mut("vertex").unwrap().insert("c".to_string(), Column::Float(vec![0.0]));
+        assert!(columnar.apply_column("vertex", "c", &[ColumnOp::Scale(2.0)]).is_err());
+        assert!(columnar.cast_column("vertex", "w", ScalarType::UChar, OutOfRange::Clamp).is_err());
+    }
+    #[test]
+    fn column_checked() {
+        let mut ply = vertices("x", ScalarType::Double, vec![Property::Double(1.0), Property::Double(300.0), Property::Double(2.5)]);
+        assert_eq!(ply.column::<f32>("vertex", "x").unwrap(), vec![1.0, 300.0, 2.5]);
+        assert!(ply.column::<i32>("vertex", "x").is_err());
+        let bytes = ply.column_iter_checked::<u8>("vertex", "x").unwrap().map(|v| v.ok()).collect::<Vec<_>>();
+        assert_eq!(bytes, vec![Some(1), None, None]);
+        ply.payload.get_mut("vertex").unwrap()[1].remove("x");
+        assert!(ply.column::<f64>("vertex", "x").is_err());
+        assert_eq!(ply.column_iter::<f64>("vertex", "x").unwrap().count(), 2);
+    }
+}
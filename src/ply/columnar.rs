@@ -0,0 +1,299 @@
+//! Payload stored column-major, one typed vector per property instead of one map per row.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Header, KeyMap, Ply, Property, PropertyAccess, PropertyType, ScalarType };
+
+/// All values of one property in row order, lists are stored as one vector per row.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Column {
+    Char(Vec<i8>),
+    UChar(Vec<u8>),
+    Short(Vec<i16>),
+    UShort(Vec<u16>),
+    Int(Vec<i32>),
+    UInt(Vec<u32>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    ListChar(Vec<Vec<i8>>),
+    ListUChar(Vec<Vec<u8>>),
+    ListShort(Vec<Vec<i16>>),
+    ListUShort(Vec<Vec<u16>>),
+    ListInt(Vec<Vec<i32>>),
+    ListUInt(Vec<Vec<u32>>),
+    ListFloat(Vec<Vec<f32>>),
+    ListDouble(Vec<Vec<f64>>),
+}
+
+/// Applies `$f` to the vector of `$column` whatever its variant.
+macro_rules! with_values {
+    ($column:expr, $v:ident => $f:expr) => (match $column {
+        Column::Char($v) => $f, Column::UChar($v) => $f, Column::Short($v) => $f, Column::UShort($v) => $f,
+        Column::Int($v) => $f, Column::UInt($v) => $f, Column::Float($v) => $f, Column::Double($v) => $f,
+        Column::ListChar($v) => $f, Column::ListUChar($v) => $f, Column::ListShort($v) => $f, Column::ListUShort($v) => $f,
+        Column::ListInt($v) => $f, Column::ListUInt($v) => $f, Column::ListFloat($v) => $f, Column::ListDouble($v) => $f,
+    })
+}
+
+impl Column {
+    /// Creates an empty column for values of `data_type`, with room for `capacity` rows.
+    pub fn with_capacity(data_type: &PropertyType, capacity: usize) -> Self {
+        match *data_type {
+            PropertyType::Scalar(ScalarType::Char) => Column::Char(Vec::with_capacity(capacity)),
+            PropertyType::Scalar(ScalarType::UChar) => Column::UChar(Vec::with_capacity(capacity)),
+            PropertyType::Scalar(ScalarType::Short) => Column::Short(Vec::with_capacity(capacity)),
+            PropertyType::Scalar(ScalarType::UShort) => Column::UShort(Vec::with_capacity(capacity)),
+            PropertyType::Scalar(ScalarType::Int) => Column::Int(Vec::with_capacity(capacity)),
+            PropertyType::Scalar(ScalarType::UInt) => Column::UInt(Vec::with_capacity(capacity)),
+            PropertyType::Scalar(ScalarType::Float) => Column::Float(Vec::with_capacity(capacity)),
+            PropertyType::Scalar(ScalarType::Double) => Column::Double(Vec::with_capacity(capacity)),
+            PropertyType::List(_, ScalarType::Char) => Column::ListChar(Vec::with_capacity(capacity)),
+            PropertyType::List(_, ScalarType::UChar) => Column::ListUChar(Vec::with_capacity(capacity)),
+            PropertyType::List(_, ScalarType::Short) => Column::ListShort(Vec::with_capacity(capacity)),
+            PropertyType::List(_, ScalarType::UShort) => Column::ListUShort(Vec::with_capacity(capacity)),
+            PropertyType::List(_, ScalarType::Int) => Column::ListInt(Vec::with_capacity(capacity)),
+            PropertyType::List(_, ScalarType::UInt) => Column::ListUInt(Vec::with_capacity(capacity)),
+            PropertyType::List(_, ScalarType::Float) => Column::ListFloat(Vec::with_capacity(capacity)),
+            PropertyType::List(_, ScalarType::Double) => Column::ListDouble(Vec::with_capacity(capacity)),
+        }
+    }
+    /// Creates an empty column for values of `data_type`.
+    pub fn new(data_type: &PropertyType) -> Self {
+        Column::with_capacity(data_type, 0)
+    }
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        with_values!(self, v => v.len())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Whether the values have the scalar type of `data_type`, the index type of lists isn't stored.
+    pub fn has_type(&self, data_type: &PropertyType) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(&Column::new(data_type))
+    }
+    /// Appends `value`, which has to be of the type of the column.
+    pub fn push(&mut self, value: Property) -> Result<()> {
+        match (self, value) {
+            (Column::Char(c), Property::Char(v)) => c.push(v),
+            (Column::UChar(c), Property::UChar(v)) => c.push(v),
+            (Column::Short(c), Property::Short(v)) => c.push(v),
+            (Column::UShort(c), Property::UShort(v)) => c.push(v),
+            (Column::Int(c), Property::Int(v)) => c.push(v),
+            (Column::UInt(c), Property::UInt(v)) => c.push(v),
+            (Column::Float(c), Property::Float(v)) => c.push(v),
+            (Column::Double(c), Property::Double(v)) => c.push(v),
+            (Column::ListChar(c), Property::ListChar(v)) => c.push(v),
+            (Column::ListUChar(c), Property::ListUChar(v)) => c.push(v),
+            (Column::ListShort(c), Property::ListShort(v)) => c.push(v),
+            (Column::ListUShort(c), Property::ListUShort(v)) => c.push(v),
+            (Column::ListInt(c), Property::ListInt(v)) => c.push(v),
+            (Column::ListUInt(c), Property::ListUInt(v)) => c.push(v),
+            (Column::ListFloat(c), Property::ListFloat(v)) => c.push(v),
+            (Column::ListDouble(c), Property::ListDouble(v)) => c.push(v),
+            (_, v) => return Err(io::Error::new(ErrorKind::InvalidInput, format!("Value {:?} doesn't match the type of the column.", v))),
+        }
+        Ok(())
+    }
+    /// Value of row `index`.
+    pub fn get(&self, index: usize) -> Option<Property> {
+        match *self {
+            Column::Char(ref c) => c.get(index).map(|&v| Property::Char(v)),
+            Column::UChar(ref c) => c.get(index).map(|&v| Property::UChar(v)),
+            Column::Short(ref c) => c.get(index).map(|&v| Property::Short(v)),
+            Column::UShort(ref c) => c.get(index).map(|&v| Property::UShort(v)),
+            Column::Int(ref c) => c.get(index).map(|&v| Property::Int(v)),
+            Column::UInt(ref c) => c.get(index).map(|&v| Property::UInt(v)),
+            Column::Float(ref c) => c.get(index).map(|&v| Property::Float(v)),
+            Column::Double(ref c) => c.get(index).map(|&v| Property::Double(v)),
+            Column::ListChar(ref c) => c.get(index).map(|v| Property::ListChar(v.clone())),
+            Column::ListUChar(ref c) => c.get(index).map(|v| Property::ListUChar(v.clone())),
+            Column::ListShort(ref c) => c.get(index).map(|v| Property::ListShort(v.clone())),
+            Column::ListUShort(ref c) => c.get(index).map(|v| Property::ListUShort(v.clone())),
+            Column::ListInt(ref c) => c.get(index).map(|v| Property::ListInt(v.clone())),
+            Column::ListUInt(ref c) => c.get(index).map(|v| Property::ListUInt(v.clone())),
+            Column::ListFloat(ref c) => c.get(index).map(|v| Property::ListFloat(v.clone())),
+            Column::ListDouble(ref c) => c.get(index).map(|v| Property::ListDouble(v.clone())),
+        }
+    }
+}
+
+/// A PLY file with its payload stored column-major.
+///
+/// Each property is a `Column` of typed values, which takes far less memory than a `Ply<DefaultElement>`
+/// and keeps the values of a property together. Read it with `Parser::read_columnar_ply()`,
+/// write it with `Writer::write_columnar_ply()` or convert from and to a `Ply`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+/// let ply = parser::Parser::<ply::DefaultElement>::new().read_columnar_ply(&mut f).unwrap();
+/// assert_eq!(ply.rows("vertex"), 8);
+/// if let ply::Column::Float(ref z) = ply.payload["vertex"]["z"] {
+///     assert_eq!(z.iter().sum::<f32>(), 4.0);
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColumnarPly {
+    /// All header information found in a PLY file.
+    pub header: Header,
+    /// The columns of each element, by element and property name.
+    pub payload: KeyMap<KeyMap<Column>>,
+}
+
+impl ColumnarPly {
+    /// Creates empty columns for all properties declared in `header`, with room for the declared number of rows.
+    pub fn new(header: Header) -> Self {
+        let payload = header.elements.iter().map(|(k, e)| {
+            let columns = e.properties.iter().map(|(name, p)| (name.clone(), Column::with_capacity(&p.data_type, e.count))).collect();
+            (k.clone(), columns)
+        }).collect();
+        ColumnarPly { header, payload }
+    }
+    /// Number of rows of `element`, the length of its first column or the declared count if it has none.
+    pub fn rows(&self, element: &str) -> usize {
+        match self.payload.get(element).and_then(|columns| columns.values().next()) {
+            Some(column) => column.len(),
+            None => self.header.elements.get(element).map_or(0, |e| e.count),
+        }
+    }
+    /// View of row `index` of `element`, e.g. to write it with a `Writer<ColumnarRow>`.
+    pub fn row(&self, element: &str, index: usize) -> ColumnarRow<'_> {
+        ColumnarRow { columns: self.payload.get(element), index }
+    }
+    /// Copies the values of `ply` into columns, each row has to hold a value of the declared type for each property.
+    pub fn from_ply<E: PropertyAccess>(ply: &Ply<E>) -> Result<Self> {
+        let mut columnar = ColumnarPly::new(ply.header.clone());
+        for (k, element_def) in &ply.header.elements {
+            let rows = ply.payload.get(k).map_or(&[][..], |rows| &rows[..]);
+            let columns = columnar.payload.get_mut(k).unwrap();
+            for (name, column) in columns.iter_mut() {
+                macro_rules! fill {
+                    ($c:expr, $get:ident) => (for (index, row) in rows.iter().enumerate() {
+                        match row.$get(name) {
+                            Some(v) => $c.push(v.to_owned()),
+                            None => return Err(missing(&element_def.name, name, index)),
+                        }
+                    });
+                }
+                match *column {
+                    Column::Char(ref mut c) => fill!(c, get_char),
+                    Column::UChar(ref mut c) => fill!(c, get_uchar),
+                    Column::Short(ref mut c) => fill!(c, get_short),
+                    Column::UShort(ref mut c) => fill!(c, get_ushort),
+                    Column::Int(ref mut c) => fill!(c, get_int),
+                    Column::UInt(ref mut c) => fill!(c, get_uint),
+                    Column::Float(ref mut c) => fill!(c, get_float),
+                    Column::Double(ref mut c) => fill!(c, get_double),
+                    Column::ListChar(ref mut c) => fill!(c, get_list_char),
+                    Column::ListUChar(ref mut c) => fill!(c, get_list_uchar),
+                    Column::ListShort(ref mut c) => fill!(c, get_list_short),
+                    Column::ListUShort(ref mut c) => fill!(c, get_list_ushort),
+                    Column::ListInt(ref mut c) => fill!(c, get_list_int),
+                    Column::ListUInt(ref mut c) => fill!(c, get_list_uint),
+                    Column::ListFloat(ref mut c) => fill!(c, get_list_float),
+                    Column::ListDouble(ref mut c) => fill!(c, get_list_double),
+                }
+            }
+        }
+        Ok(columnar)
+    }
+    /// Creates one element per row, setting its values with `set_named_property()`.
+    pub fn to_ply<E: PropertyAccess>(&self) -> Ply<E> {
+        let mut ply = Ply::new();
+        ply.header = self.header.clone();
+        for (k, columns) in &self.payload {
+            let rows = (0..self.rows(k)).map(|index| {
+                let mut row = E::new();
+                for (name, column) in columns {
+                    if let Some(value) = column.get(index) {
+                        row.set_named_property(name, value);
+                    }
+                }
+                row
+            }).collect();
+            ply.payload.insert(k.clone(), rows);
+        }
+        ply
+    }
+}
+
+fn missing(element: &str, property: &str, index: usize) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, format!("Row {} of element `{}` has no property `{}` of the declared type.", index, element, property))
+}
+
+/// Row of a `ColumnarPly`, borrowing its values from the columns.
+///
+/// Only meant for reading, `new()` creates a row without values.
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnarRow<'a> {
+    columns: Option<&'a KeyMap<Column>>,
+    index: usize,
+}
+
+macro_rules! get_scalar(
+    ($name:ident, $variant:ident, $t:ty) => (fn $name(&self, property_name: &String) -> Option<$t> {
+        match self.columns?.get(property_name)? {
+            Column::$variant(c) => c.get(self.index).copied(),
+            _ => None,
+        }
+    })
+);
+
+macro_rules! get_list(
+    ($name:ident, $variant:ident, $t:ty) => (fn $name(&self, property_name: &String) -> Option<&[$t]> {
+        match self.columns?.get(property_name)? {
+            Column::$variant(c) => c.get(self.index).map(Vec::as_slice),
+            _ => None,
+        }
+    })
+);
+
+impl<'a> PropertyAccess for ColumnarRow<'a> {
+    fn new() -> Self {
+        ColumnarRow { columns: None, index: 0 }
+    }
+    get_scalar!(get_char, Char, i8);
+    get_scalar!(get_uchar, UChar, u8);
+    get_scalar!(get_short, Short, i16);
+    get_scalar!(get_ushort, UShort, u16);
+    get_scalar!(get_int, Int, i32);
+    get_scalar!(get_uint, UInt, u32);
+    get_scalar!(get_float, Float, f32);
+    get_scalar!(get_double, Double, f64);
+    get_list!(get_list_char, ListChar, i8);
+    get_list!(get_list_uchar, ListUChar, u8);
+    get_list!(get_list_short, ListShort, i16);
+    get_list!(get_list_ushort, ListUShort, u16);
+    get_list!(get_list_int, ListInt, i32);
+    get_list!(get_list_uint, ListUInt, u32);
+    get_list!(get_list_float, ListFloat, f32);
+    get_list!(get_list_double, ListDouble, f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::ply::DefaultElement;
+    #[test]
+    fn columnar_round_trip() {
+        let ply = Parser::<DefaultElement>::new().read_ply_from_path("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+        let columnar = ColumnarPly::from_ply(&ply).unwrap();
+        assert_eq!(columnar.rows("face"), 6);
+        assert_eq!(columnar.payload["face"]["vertex_index"], Column::ListInt(ply.payload["face"].iter().map(|f| f.get_list_int(&"vertex_index".to_string()).unwrap().to_vec()).collect()));
+        assert_eq!(columnar.row("vertex", 1).get_float(&"z".to_string()), Some(1.0));
+        assert_eq!(columnar.row("vertex", 1).get_double(&"z".to_string()), None);
+        assert_eq!(columnar.to_ply::<DefaultElement>(), ply);
+        let mut column = Column::new(&PropertyType::Scalar(ScalarType::UChar));
+        assert!(column.push(Property::UChar(1)).is_ok());
+        assert!(column.push(Property::Char(1)).is_err());
+        assert_eq!(column.get(0), Some(Property::UChar(1)));
+        let mut broken = ply.clone();
+        broken.payload.get_mut("vertex").unwrap()[3].insert("x".to_string(), Property::Double(0.0));
+        assert!(ColumnarPly::from_ply(&broken).is_err());
+    }
+}
@@ -3,8 +3,8 @@
 use std::fmt::{ Display, Formatter };
 use std::fmt;
 use std::error;
-use super::Ply;
-use super::PropertyAccess;
+use super::{ Header, Ply, PropertyAccess, PropertyType, ScalarType };
+use crate::util::{ get_any_list_as_f64, get_any_scalar_as_f64 };
 
 /// Contains a description, why a given `Ply` object isn't consistent and could not be made consistent.
 #[derive(Debug)]
@@ -37,11 +37,44 @@ impl error::Error for ConsistencyError {
 }
 
 fn has_white_space(s: &str) -> bool {
-    return s.contains(" ") || s.contains("\t");
+    s.contains(' ') || s.contains('\t')
 }
 
 fn has_line_break(s: &str) -> bool {
-    return s.contains("\n") || s.contains("\r");
+    s.contains('\n') || s.contains('\r')
+}
+
+/// Whether `s` can be written as a single token of a header line.
+fn is_token(s: &str) -> bool {
+    !s.is_empty() && !s.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Whether `element` holds a value of type `data_type` for property `k`, or of any type with `any_type`.
+fn has_type<E: PropertyAccess>(element: &E, k: &String, data_type: &PropertyType, any_type: bool) -> bool {
+    match *data_type {
+        PropertyType::Scalar(_) if any_type => get_any_scalar_as_f64(element, k).is_some(),
+        PropertyType::List(_, _) if any_type => get_any_list_as_f64(element, k).is_some(),
+        PropertyType::Scalar(ref scalar_type) => match *scalar_type {
+            ScalarType::Char => element.get_char(k).is_some(),
+            ScalarType::UChar => element.get_uchar(k).is_some(),
+            ScalarType::Short => element.get_short(k).is_some(),
+            ScalarType::UShort => element.get_ushort(k).is_some(),
+            ScalarType::Int => element.get_int(k).is_some(),
+            ScalarType::UInt => element.get_uint(k).is_some(),
+            ScalarType::Float => element.get_float(k).is_some(),
+            ScalarType::Double => element.get_double(k).is_some(),
+        },
+        PropertyType::List(_, ref scalar_type) => match *scalar_type {
+            ScalarType::Char => element.get_list_char(k).is_some(),
+            ScalarType::UChar => element.get_list_uchar(k).is_some(),
+            ScalarType::Short => element.get_list_short(k).is_some(),
+            ScalarType::UShort => element.get_list_ushort(k).is_some(),
+            ScalarType::Int => element.get_list_int(k).is_some(),
+            ScalarType::UInt => element.get_list_uint(k).is_some(),
+            ScalarType::Float => element.get_list_float(k).is_some(),
+            ScalarType::Double => element.get_list_double(k).is_some(),
+        },
+    }
 }
 
 impl<E: PropertyAccess> Ply<E>{
@@ -62,6 +95,10 @@ impl<E: PropertyAccess> Ply<E>{
     /// Every relevant string is checked to not contain line breaks.
     /// Identifiers are also checked to not contain white spaces.
     pub fn make_consistent(&mut self) -> Result<(), ConsistencyError>{
+        self.make_consistent_with(true)
+    }
+    /// Like `make_consistent()`, names of elements and properties are only checked with `check_names`.
+    pub(crate) fn make_consistent_with(&mut self, check_names: bool) -> Result<(), ConsistencyError> {
         for (ek, _) in &self.header.elements {
             if !self.payload.contains_key(ek) {
                 self.payload.insert(ek.clone(), Vec::new());
@@ -77,29 +114,87 @@ impl<E: PropertyAccess> Ply<E>{
             }
             ed.unwrap().count = pe.len();
         }
-        for ref oi in &self.header.obj_infos {
+        for oi in &self.header.obj_infos {
             if has_line_break(oi) {
                 return Err(ConsistencyError::new(&format!("Objection information `{}` should not contain any line breaks.", oi)));
             }
         }
-        for ref c in &self.header.comments {
-            if has_line_break(&c) {
+        for c in &self.header.comments {
+            if has_line_break(c) {
                return Err(ConsistencyError::new(&format!("Comment `{}` should not contain any line breaks.", c)));
             }
         }
-        for (_, ref e) in &self.header.elements {
-            if has_line_break(&e.name) {
-                return Err(ConsistencyError::new(&format!("Name of element `{}` should not contain any line breaks.", e.name)));
+        if check_names {
+            for (_, e) in &self.header.elements {
+                if has_line_break(&e.name) {
+                    return Err(ConsistencyError::new(&format!("Name of element `{}` should not contain any line breaks.", e.name)));
+                }
+                if has_white_space(&e.name) {
+                    return Err(ConsistencyError::new(&format!("Name of element `{}` should not contain any white spaces.", e.name)));
+                }
+                for (_, p) in &e.properties {
+                    if has_line_break(&p.name) {
+                        return Err(ConsistencyError::new(&format!("Name of property `{}` of element `{}` should not contain any line breaks.", p.name, e.name)));
+                    }
+                    if has_white_space(&p.name) {
+                        return Err(ConsistencyError::new(&format!("Name of property `{}` of element `{}` should not contain any spaces.", p.name, e.name)));
+                    }
+                }
             }
-            if has_white_space(&e.name) {
-                return Err(ConsistencyError::new(&format!("Name of element `{}` should not contain any white spaces.", e.name)));
+        }
+        Ok(())
+    }
+    /// Checks without modifying anything that the payload can be written with `header`.
+    ///
+    /// Every element of the payload has to be declared with as many rows as it has,
+    /// every row has to hold a value of the declared type for each property,
+    /// list indices have to be integers and names of elements and properties have to be valid tokens.
+    /// `Writer::write_ply()` runs this check after `make_consistent()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// let mut ply = Ply::<DefaultElement>::new();
+    /// let mut point = ElementDef::new("point".to_string());
+    /// point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)));
+    /// ply.header.elements.add(point);
+    /// let mut row = DefaultElement::new();
+    /// row.insert("x".to_string(), Property::Double(0.5));
+    /// ply.payload.insert("point".to_string(), vec![row]);
+    /// ply.make_consistent().unwrap();
+    /// assert!(ply.check(&ply.header).is_err());
+    /// ```
+    pub fn check(&self, header: &Header) -> Result<(), ConsistencyError> {
+        for k in self.payload.keys() {
+            if !header.elements.contains_key(k) {
+                return Err(ConsistencyError::new(&format!("No declaration for element `{}` found.", k)));
+            }
+        }
+        self.check_declared(header, false, true)
+    }
+    /// Like `check()`, but ignores elements of the payload missing from `header`, e.g. dropped by a `Projection`.
+    ///
+    /// With `any_type`, values may be stored in any scalar or list type, to be converted when written.
+    /// Names are only checked with `check_names`.
+    pub(crate) fn check_declared(&self, header: &Header, any_type: bool, check_names: bool) -> Result<(), ConsistencyError> {
+        for (_, e) in &header.elements {
+            if check_names && !is_token(&e.name) {
+                return Err(ConsistencyError::new(&format!("Name of element `{}` isn't a valid token.", e.name)));
             }
-            for (_, ref p) in &e.properties {
-                if has_line_break(&p.name) {
-                    return Err(ConsistencyError::new(&format!("Name of property `{}` of element `{}` should not contain any line breaks.", p.name, e.name)));
+            let rows = self.payload.get(&e.name).map_or(&[][..], |rows| &rows[..]);
+            if rows.len() != e.count {
+                return Err(ConsistencyError::new(&format!("Element `{}` is declared with {} rows but has {}.", e.name, e.count, rows.len())));
+            }
+            for (k, p) in &e.properties {
+                if check_names && !is_token(k) {
+                    return Err(ConsistencyError::new(&format!("Name of property `{}` of element `{}` isn't a valid token.", k, e.name)));
+                }
+                if let PropertyType::List(ScalarType::Float, _) | PropertyType::List(ScalarType::Double, _) = p.data_type {
+                    return Err(ConsistencyError::new(&format!("Index of list `{}` of element `{}` must be an integer type.", k, e.name)));
                 }
-                if has_white_space(&p.name) {
-                    return Err(ConsistencyError::new(&format!("Name of property `{}` of element `{}` should not contain any spaces.", p.name, e.name)));
+                if let Some(index) = rows.iter().position(|row| !has_type(row, k, &p.data_type, any_type)) {
+                    return Err(ConsistencyError::new(&format!("Row {} of element `{}` has no property `{}` of type {:?}.", index, e.name, k, p.data_type)));
                 }
             }
         }
@@ -157,4 +252,27 @@ mod tests {
         let r = p.make_consistent();
         assert!(r.is_err());
     }
+    #[test]
+    fn check_fail() {
+        let mut p = P::new();
+        let mut e = ElementDef::new("point".to_string());
+        e.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Int)));
+        p.header.elements.add(e);
+        let mut row = DefaultElement::new();
+        row.insert("x".to_string(), Property::Int(1));
+        p.payload.insert("point".to_string(), vec![row.clone()]);
+        assert!(p.check(&p.header).is_err());
+        p.make_consistent().unwrap();
+        assert!(p.check(&p.header).is_ok());
+        row.insert("x".to_string(), Property::UInt(1));
+        p.payload.get_mut("point").unwrap().push(row);
+        p.make_consistent().unwrap();
+        assert!(p.check(&p.header).is_err());
+        p.payload.get_mut("point").unwrap().pop();
+        p.make_consistent().unwrap();
+        p.header.elements.get_mut("point").unwrap().properties.add(PropertyDef::new("i".to_string(), PropertyType::List(ScalarType::Float, ScalarType::Int)));
+        assert!(p.check(&p.header).is_err());
+        p.header.elements.get_mut("point").unwrap().properties.add(PropertyDef::new("".to_string(), PropertyType::Scalar(ScalarType::Int)));
+        assert!(p.check(&p.header).is_err());
+    }
 }
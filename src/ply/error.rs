@@ -0,0 +1,121 @@
+//! Errors of reading PLY files, with the position where they occurred.
+
+use std::error;
+use std::fmt;
+use std::fmt::{ Display, Formatter };
+use std::io;
+use std::io::ErrorKind;
+
+use crate::parser::LimitError;
+
+/// Describes why and where reading a PLY file failed, returned by `Parser::read_ply()` and friends.
+///
+/// Converts to and from `io::Error`, so `?` works in functions returning either.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying reader failed, or the input ended early.
+    Io(io::Error),
+    /// A header line couldn't be parsed.
+    Syntax {
+        /// Line number, starting at 1.
+        line: usize,
+        /// Byte offset of the line in the input.
+        offset: u64,
+        message: String,
+    },
+    /// The header lines are valid on their own but don't form a valid header, e.g. a property before any element.
+    Schema {
+        /// Line number, starting at 1.
+        line: usize,
+        /// Byte offset of the line in the input.
+        offset: u64,
+        message: String,
+    },
+    /// A row of the payload couldn't be read.
+    Payload {
+        /// Name of the element.
+        element: String,
+        /// Index of the row within its element.
+        index: usize,
+        source: io::Error,
+    },
+    /// The input exceeds the limits set with `Parser::set_limits()`.
+    Limit(LimitError),
+}
+
+impl Error {
+    /// The `io::ErrorKind` the error converts to.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::Io(ref e) | Error::Payload { source: ref e, .. } => e.kind(),
+            Error::Syntax { .. } | Error::Schema { .. } => ErrorKind::InvalidInput,
+            Error::Limit(_) => ErrorKind::InvalidData,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Error::Io(ref e) => write!(f, "{}", e),
+            Error::Syntax { line, offset, ref message } | Error::Schema { line, offset, ref message } =>
+                write!(f, "Line {}: {}\n\tByte offset: {}", line, message, offset),
+            Error::Payload { ref element, index, ref source } =>
+                write!(f, "{}\n\tElement: `{}`, row {}", source, element, index),
+            Error::Limit(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref e) | Error::Payload { source: ref e, .. } => Some(e),
+            Error::Limit(ref e) => Some(e),
+            Error::Syntax { .. } | Error::Schema { .. } => None,
+        }
+    }
+}
+
+/// Unwraps errors converted with `From<Error> for io::Error` or wrapping a `LimitError`.
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        let inner = match e.get_ref() {
+            Some(inner) if inner.is::<Error>() || inner.is::<LimitError>() => e.into_inner().unwrap(),
+            _ => return Error::Io(e),
+        };
+        match inner.downcast::<Error>() {
+            Ok(e) => *e,
+            Err(inner) => Error::Limit(*inner.downcast::<LimitError>().unwrap()),
+        }
+    }
+}
+
+impl From<LimitError> for Error {
+    fn from(e: LimitError) -> Self {
+        Error::Limit(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            e => io::Error::new(e.kind(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn io_round_trip() {
+        let e = Error::Syntax { line: 3, offset: 24, message: "Couldn't parse line.".to_string() };
+        let e = Error::from(io::Error::from(e));
+        assert!(matches!(e, Error::Syntax { line: 3, offset: 24, .. }));
+        let e = Error::from(io::Error::from(LimitError::ListTooLong { length: 3, limit: 2 }));
+        assert!(matches!(e, Error::Limit(LimitError::ListTooLong { .. })));
+        assert!(matches!(Error::from(io::Error::from(ErrorKind::UnexpectedEof)), Error::Io(_)));
+    }
+}
@@ -0,0 +1,146 @@
+//! Removal of vertices while keeping the faces referencing them consistent.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Ply, PropertyAccess, PropertyDef };
+use crate::util::{ element_def, get_list_as_f64, property_defs, require_scalar_as_f64, set_list_from_f64 };
+
+/// Names of the vertex list property of faces, in order of preference.
+pub const VERTEX_INDEX_NAMES: [&str; 2] = ["vertex_indices", "vertex_index"];
+/// Names of per vertex quality properties, in order of preference.
+pub const QUALITY_NAMES: [&str; 2] = ["quality", "confidence"];
+
+/// Number of entries removed by `Ply::retain_vertices()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Removed {
+    /// Removed `vertex` elements.
+    pub vertices: usize,
+    /// Removed `face` elements, because they referenced a removed vertex.
+    pub faces: usize,
+}
+
+/// Definition of the vertex index list of faces, `None` if there is no `face` element.
+pub(super) fn face_index_def<E: PropertyAccess>(ply: &Ply<E>) -> Result<Option<PropertyDef>> {
+    match ply.header.elements.get("face") {
+        Some(face) => match VERTEX_INDEX_NAMES.iter().find_map(|n| face.properties.get(*n)) {
+            Some(p) => Ok(Some(p.clone())),
+            None => Err(io::Error::new(ErrorKind::InvalidInput, "Element `face` has no vertex index list.")),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Vertex indices of all faces, each one as given by `f(old_index)`.
+///
+/// `None` if `f` returns `None` for any vertex of the face.
+pub(super) fn map_faces<E, T, F>(ply: &Ply<E>, def: &PropertyDef, mut f: F) -> Result<Vec<Option<Vec<T>>>>
+        where E: PropertyAccess, F: FnMut(usize) -> Option<T> {
+    let faces = ply.payload.get("face").map(|l| &l[..]).unwrap_or_default();
+    faces.iter().enumerate().map(|(row, face)| match get_list_as_f64(face, def) {
+        Some(l) => Ok(l.iter().map(|&i| if i >= 0.0 { f(i as usize) } else { None }).collect()),
+        None => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Element `face` at index {} has no list value for property `{}`.", row, def.name)
+        )),
+    }).collect()
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Keeps only the vertices for which `keep(index, vertex)` returns true.
+    ///
+    /// Faces referencing a removed vertex are removed as well,
+    /// the vertex indices of the remaining faces are renumbered.
+    /// Element counts in the header are updated.
+    pub fn retain_vertices<F: FnMut(usize, &E) -> bool>(&mut self, mut keep: F) -> Result<Removed> {
+        element_def(self, "vertex")?;
+        let face_index_def = face_index_def(self)?;
+
+        // Maps old to new vertex indices, `None` for removed vertices.
+        let mut new_index = Vec::new();
+        let mut kept = 0;
+        for (i, v) in self.payload.get("vertex").map(|l| &l[..]).unwrap_or_default().iter().enumerate() {
+            if keep(i, v) {
+                new_index.push(Some(kept));
+                kept += 1;
+            } else {
+                new_index.push(None);
+            }
+        }
+        // Renumbered indices per face, `None` for removed faces. Computed before any modification.
+        let face_indices = match face_index_def {
+            Some(ref def) => map_faces(self, def, |i| new_index.get(i).cloned().flatten().map(|n| n as f64))?,
+            None => Vec::new(),
+        };
+
+        let removed = Removed {
+            vertices: new_index.len() - kept,
+            faces: face_indices.iter().filter(|f| f.is_none()).count(),
+        };
+        if let Some(vertices) = self.payload.get_mut("vertex") {
+            let mut flags = new_index.iter();
+            vertices.retain(|_| flags.next().unwrap().is_some());
+        }
+        self.header.elements.get_mut("vertex").unwrap().count = kept;
+        if let Some(def) = face_index_def {
+            let faces = self.payload.entry("face".to_string()).or_default();
+            let mut indices = face_indices.into_iter();
+            faces.retain_mut(|f| match indices.next().unwrap() {
+                Some(r) => {
+                    set_list_from_f64(f, &def, &r);
+                    true
+                },
+                None => false,
+            });
+            let count = faces.len();
+            self.header.elements.get_mut("face").unwrap().count = count;
+        }
+        Ok(removed)
+    }
+    /// Removes vertices whose `quality` or `confidence` property is below `threshold`, see `retain_vertices()`.
+    pub fn filter_by_quality(&mut self, threshold: f64) -> Result<Removed> {
+        let vertex = element_def(self, "vertex")?;
+        let name = match QUALITY_NAMES.iter().find(|n| vertex.properties.contains_key(**n)) {
+            Some(n) => n,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, "Element `vertex` has neither `quality` nor `confidence`.")),
+        };
+        let def = property_defs(vertex, &[name])?.remove(0);
+        let keep = match self.payload.get("vertex") {
+            Some(list) => list.iter().enumerate()
+                .map(|(row, v)| require_scalar_as_f64(v, &def, "vertex", row).map(|q| q >= threshold))
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        self.retain_vertices(|row, _| keep[row])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    fn quad() -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let vertices = [0.9, 0.1, 0.8, 0.7].iter().map(|&c| vec![Property::Float(c)]).collect();
+        add_test_element(&mut ply, "vertex", &[("confidence", PropertyType::Scalar(ScalarType::Float))], vertices);
+        let faces = vec![vec![Property::ListUInt(vec![0, 1, 2])], vec![Property::ListUInt(vec![0, 2, 3])]];
+        add_test_element(&mut ply, "face", &[("vertex_indices", PropertyType::List(ScalarType::UChar, ScalarType::UInt))], faces);
+        ply
+    }
+    #[test]
+    fn filter_by_quality_ok() {
+        let mut ply = quad();
+        let removed = ply.filter_by_quality(0.5).unwrap();
+        assert_eq!(removed, Removed { vertices: 1, faces: 1 });
+        assert_eq!(ply.header.elements["vertex"].count, 3);
+        assert_eq!(ply.header.elements["face"].count, 1);
+        assert_eq!(ply.payload["face"][0]["vertex_indices"], Property::ListUInt(vec![0, 1, 2]));
+        assert_eq!(ply.payload["vertex"][1]["confidence"], Property::Float(0.8));
+    }
+    #[test]
+    fn filter_by_quality_missing() {
+        let mut ply = quad();
+        ply.header.elements.get_mut("vertex").unwrap().properties.clear();
+        assert!(ply.filter_by_quality(0.5).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+//! Typed access to list properties of constant length, e.g. the vertex indices of triangles.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Addable, Ply, PropertyAccess, PropertyDef, PropertyType, ScalarType, ScalarValue };
+use crate::util::{ element_def, get_list_as_f64, property_defs, set_list_from_f64 };
+
+/// List of constant length, as a flat array `[T; N]` or an array of tuples `[[T; M]; N]`.
+pub trait FixedList: Sized {
+    type Scalar: ScalarValue;
+    /// Number of scalars in the list.
+    const LEN: usize;
+    /// Builds the list from exactly `LEN` scalars.
+    fn from_flat(values: &[Self::Scalar]) -> Self;
+    /// Appends the `LEN` scalars of the list to `out`.
+    fn flatten(&self, out: &mut Vec<Self::Scalar>);
+}
+
+impl<T: ScalarValue, const N: usize> FixedList for [T; N] {
+    type Scalar = T;
+    const LEN: usize = N;
+    fn from_flat(values: &[T]) -> Self {
+        let mut a = [T::default(); N];
+        a.copy_from_slice(values);
+        a
+    }
+    fn flatten(&self, out: &mut Vec<T>) {
+        out.extend_from_slice(self);
+    }
+}
+
+impl<T: ScalarValue, const M: usize, const N: usize> FixedList for [[T; M]; N] {
+    type Scalar = T;
+    const LEN: usize = M * N;
+    fn from_flat(values: &[T]) -> Self {
+        let mut a = [[T::default(); M]; N];
+        for (a, v) in a.iter_mut().zip(values.chunks(M)) {
+            a.copy_from_slice(v);
+        }
+        a
+    }
+    fn flatten(&self, out: &mut Vec<T>) {
+        for a in self {
+            out.extend_from_slice(a);
+        }
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Reads the list property `property_name` of all `element_name` elements as arrays of constant length.
+    ///
+    /// Fails on the first list whose length differs, e.g. a quad when reading triangles as `[u32; 3]`.
+    pub fn fixed_lists<L: FixedList>(&self, element_name: &str, property_name: &str) -> Result<Vec<L>> {
+        let def = property_defs(element_def(self, element_name)?, &[property_name])?.remove(0);
+        let elements = self.payload.get(element_name).map(|l| &l[..]).unwrap_or_default();
+        let mut lists = Vec::with_capacity(elements.len());
+        let mut flat = Vec::with_capacity(L::LEN);
+        for (row, e) in elements.iter().enumerate() {
+            let values = match get_list_as_f64(e, &def) {
+                Some(v) => v,
+                None => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Element `{}` at index {} has no list value for property `{}`.", element_name, row, property_name)
+                )),
+            };
+            if values.len() != L::LEN {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Element `{}` at index {} has a list `{}` of length {}, expected {}.", element_name, row, property_name, values.len(), L::LEN)
+                ));
+            }
+            flat.clear();
+            flat.extend(values.into_iter().map(L::Scalar::from_f64));
+            lists.push(L::from_flat(&flat));
+        }
+        Ok(lists)
+    }
+    /// Stores `lists` as the list property `property_name` of `element_name`.
+    ///
+    /// Missing elements are created, the property is declared as list of `L::Scalar` if it doesn't exist yet.
+    /// An existing property keeps its type. Fails if there are already elements, but not `lists.len()`.
+    pub fn set_fixed_lists<L: FixedList>(&mut self, element_name: &str, property_name: &str, lists: &[L]) -> Result<()> {
+        let def = element_def(self, element_name)?;
+        let property_def = match def.properties.get(property_name) {
+            Some(p) => p.clone(),
+            None => PropertyDef::new(property_name.to_string(), PropertyType::List(ScalarType::UChar, L::Scalar::SCALAR_TYPE)),
+        };
+        if let PropertyType::Scalar(_) = property_def.data_type {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Property `{}` of element `{}` is not a list.", property_name, element_name)
+            ));
+        }
+        let elements = self.payload.entry(element_name.to_string()).or_default();
+        if elements.is_empty() {
+            elements.extend((0..lists.len()).map(|_| E::new()));
+        } else if elements.len() != lists.len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` has {} entries, but {} lists are given.", element_name, elements.len(), lists.len())
+            ));
+        }
+        let mut flat = Vec::with_capacity(L::LEN);
+        for (e, l) in elements.iter_mut().zip(lists) {
+            flat.clear();
+            l.flatten(&mut flat);
+            set_list_from_f64(e, &property_def, &flat.iter().map(|v| v.to_f64()).collect::<Vec<_>>());
+        }
+        let def = self.header.elements.get_mut(element_name).unwrap();
+        def.properties.add(property_def);
+        def.count = lists.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    fn faces() -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut face = ElementDef::new("face".to_string());
+        face.properties.add(PropertyDef::new("vertex_indices".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int)));
+        ply.header.elements.add(face);
+        ply
+    }
+    #[test]
+    fn fixed_lists_round_trip() {
+        let mut ply = faces();
+        ply.set_fixed_lists("face", "vertex_indices", &[[0u32, 1, 2], [2, 3, 0]]).unwrap();
+        assert_eq!(ply.header.elements["face"].count, 2);
+        assert_eq!(ply.payload["face"][1]["vertex_indices"], Property::ListInt(vec![2, 3, 0]));
+        assert_eq!(ply.fixed_lists::<[u32; 3]>("face", "vertex_indices").unwrap(), vec![[0, 1, 2], [2, 3, 0]]);
+
+        ply.set_fixed_lists("face", "texcoord", &[[[0.0f32, 0.5], [1.0, 0.5], [1.0, 1.0]]; 2]).unwrap();
+        assert_eq!(
+            ply.header.elements["face"].properties["texcoord"].data_type,
+            PropertyType::List(ScalarType::UChar, ScalarType::Float)
+        );
+        assert_eq!(ply.fixed_lists::<[[f32; 2]; 3]>("face", "texcoord").unwrap()[1], [[0.0, 0.5], [1.0, 0.5], [1.0, 1.0]]);
+    }
+    #[test]
+    fn fixed_lists_wrong_length() {
+        let mut ply = faces();
+        ply.set_fixed_lists("face", "vertex_indices", &[[0i32, 1, 2, 3]]).unwrap();
+        let err = ply.fixed_lists::<[u32; 3]>("face", "vertex_indices").unwrap_err();
+        assert!(err.to_string().contains("length 4, expected 3"));
+        assert!(ply.set_fixed_lists("face", "vertex_indices", &[[0i32, 1, 2]; 2]).is_err());
+    }
+}
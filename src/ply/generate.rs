@@ -0,0 +1,244 @@
+//! Synthetic meshes and point clouds, e.g. as test fixtures.
+//!
+//! Positions are `float` properties `x`, `y`, `z`, faces a `vertex_indices` list of `int`.
+//! All generators produce ascii files, change `header.encoding` to write them in binary.
+
+use std::f64::consts::PI;
+
+use super::{ Addable, ElementDef, Encoding, Ply, PropertyAccess, PropertyDef, PropertyType, ScalarType };
+use crate::util::{ set_list_from_f64, set_scalar_from_f64, SplitMix64 };
+
+fn scalar(name: &str, scalar_type: ScalarType) -> PropertyDef {
+    PropertyDef::new(name.to_string(), PropertyType::Scalar(scalar_type))
+}
+
+/// Adds element `name` with one row per entry of `rows`, each holding the values of `properties` in order.
+fn add_element<E: PropertyAccess>(ply: &mut Ply<E>, name: &str, properties: Vec<PropertyDef>, rows: Vec<Vec<f64>>) {
+    let mut def = ElementDef::new(name.to_string());
+    def.count = rows.len();
+    let elements = rows.iter().map(|row| {
+        let mut e = E::new();
+        for (p, &v) in properties.iter().zip(row) {
+            set_scalar_from_f64(&mut e, p, v);
+        }
+        e
+    }).collect();
+    for p in properties {
+        def.properties.add(p);
+    }
+    ply.header.elements.add(def);
+    ply.payload.insert(name.to_string(), elements);
+}
+
+/// Mesh with `vertex` positions, optional normals, and `face` elements.
+fn mesh<E: PropertyAccess>(positions: &[[f64; 3]], normals: Option<&[[f64; 3]]>, faces: &[Vec<usize>]) -> Ply<E> {
+    let mut ply = Ply::new();
+    let mut properties = vec![scalar("x", ScalarType::Float), scalar("y", ScalarType::Float), scalar("z", ScalarType::Float)];
+    if normals.is_some() {
+        properties.extend(["nx", "ny", "nz"].iter().map(|n| scalar(n, ScalarType::Float)));
+    }
+    let rows = positions.iter().enumerate().map(|(i, p)| {
+        let mut row = p.to_vec();
+        if let Some(n) = normals {
+            row.extend_from_slice(&n[i]);
+        }
+        row
+    }).collect();
+    add_element(&mut ply, "vertex", properties, rows);
+
+    let index_def = PropertyDef::new("vertex_indices".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int));
+    let mut face = ElementDef::new("face".to_string());
+    face.count = faces.len();
+    let elements = faces.iter().map(|f| {
+        let mut e = E::new();
+        set_list_from_f64(&mut e, &index_def, &f.iter().map(|&i| i as f64).collect::<Vec<_>>());
+        e
+    }).collect();
+    face.properties.add(index_def);
+    ply.header.elements.add(face);
+    ply.payload.insert("face".to_string(), elements);
+    ply
+}
+
+/// Square of side `size` in the plane `z = 0`, centered at the origin and split into `divisions` x `divisions` quads of two triangles.
+pub fn plane<E: PropertyAccess>(size: f64, divisions: usize) -> Ply<E> {
+    let n = divisions.max(1);
+    let mut positions = Vec::with_capacity((n + 1) * (n + 1));
+    for j in 0..=n {
+        for i in 0..=n {
+            positions.push([size * (i as f64 / n as f64 - 0.5), size * (j as f64 / n as f64 - 0.5), 0.0]);
+        }
+    }
+    let mut faces = Vec::with_capacity(2 * n * n);
+    for j in 0..n {
+        for i in 0..n {
+            let v = j * (n + 1) + i;
+            faces.push(vec![v, v + 1, v + n + 2]);
+            faces.push(vec![v, v + n + 2, v + n + 1]);
+        }
+    }
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    mesh(&positions, Some(&normals), &faces)
+}
+
+/// Cube of side `size` centered at the origin, with 8 vertices and 6 outward facing quads.
+pub fn cube<E: PropertyAccess>(size: f64) -> Ply<E> {
+    let h = size / 2.0;
+    let positions = (0..8).map(|i| [
+        if i & 1 == 0 { -h } else { h },
+        if i & 2 == 0 { -h } else { h },
+        if i & 4 == 0 { -h } else { h },
+    ]).collect::<Vec<_>>();
+    let faces = vec![
+        vec![0, 2, 3, 1], vec![4, 5, 7, 6],
+        vec![0, 1, 5, 4], vec![2, 6, 7, 3],
+        vec![0, 4, 6, 2], vec![1, 3, 7, 5],
+    ];
+    mesh(&positions, None, &faces)
+}
+
+/// UV sphere of `radius` centered at the origin with normals, `rings` >= 2 latitude bands and `segments` >= 3 longitudes.
+///
+/// The poles are single vertices, the triangles are oriented outwards.
+pub fn sphere<E: PropertyAccess>(radius: f64, rings: usize, segments: usize) -> Ply<E> {
+    let (rings, segments) = (rings.max(2), segments.max(3));
+    let mut normals = vec![[0.0, 0.0, 1.0]];
+    for r in 1..rings {
+        let theta = PI * r as f64 / rings as f64;
+        for s in 0..segments {
+            let phi = 2.0 * PI * s as f64 / segments as f64;
+            normals.push([theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()]);
+        }
+    }
+    normals.push([0.0, 0.0, -1.0]);
+    let south = normals.len() - 1;
+    let ring = |r: usize, s: usize| 1 + (r - 1) * segments + s % segments;
+    let mut faces = Vec::new();
+    for s in 0..segments {
+        faces.push(vec![0, ring(1, s), ring(1, s + 1)]);
+        for r in 1..rings - 1 {
+            faces.push(vec![ring(r, s), ring(r + 1, s), ring(r + 1, s + 1)]);
+            faces.push(vec![ring(r, s), ring(r + 1, s + 1), ring(r, s + 1)]);
+        }
+        faces.push(vec![ring(rings - 1, s), south, ring(rings - 1, s + 1)]);
+    }
+    let positions = normals.iter().map(|n| [n[0] * radius, n[1] * radius, n[2] * radius]).collect::<Vec<_>>();
+    mesh(&positions, Some(&normals), &faces)
+}
+
+/// Attributes and encoding of a cloud generated by `random_cloud()`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CloudOptions {
+    /// Same seed, same cloud.
+    pub seed: u64,
+    /// Points are drawn uniformly in the cube `[-extent, extent]³`.
+    pub extent: f64,
+    /// Adds random unit normals `nx`, `ny`, `nz`.
+    pub normals: bool,
+    /// Adds `uchar` colors `red`, `green`, `blue`.
+    pub colors: bool,
+    /// Adds a `float` `intensity` in `[0, 1]`.
+    pub intensity: bool,
+    /// Encoding declared in the header.
+    pub encoding: Encoding,
+}
+
+impl Default for CloudOptions {
+    fn default() -> Self {
+        CloudOptions { seed: 0, extent: 1.0, normals: false, colors: false, intensity: false, encoding: Encoding::Ascii }
+    }
+}
+
+/// Cloud of `n_points` random points with the attributes chosen in `options`, only a `vertex` element.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// use ply_rs::ply::generate::{ random_cloud, CloudOptions };
+/// let options = CloudOptions { colors: true, encoding: ply::Encoding::BinaryLittleEndian, ..CloudOptions::default() };
+/// let mut cloud = random_cloud::<ply::DefaultElement>(1000, &options);
+/// let mut buf = Vec::new();
+/// writer::Writer::new().write_ply(&mut buf, &mut cloud).unwrap();
+/// ```
+pub fn random_cloud<E: PropertyAccess>(n_points: usize, options: &CloudOptions) -> Ply<E> {
+    let mut rng = SplitMix64(options.seed);
+    let mut properties = vec![scalar("x", ScalarType::Float), scalar("y", ScalarType::Float), scalar("z", ScalarType::Float)];
+    if options.normals {
+        properties.extend(["nx", "ny", "nz"].iter().map(|n| scalar(n, ScalarType::Float)));
+    }
+    if options.colors {
+        properties.extend(["red", "green", "blue"].iter().map(|n| scalar(n, ScalarType::UChar)));
+    }
+    if options.intensity {
+        properties.push(scalar("intensity", ScalarType::Float));
+    }
+    let rows = (0..n_points).map(|_| {
+        let mut row = (0..3).map(|_| options.extent * (2.0 * rng.unit() - 1.0)).collect::<Vec<_>>();
+        if options.normals {
+            let z = 2.0 * rng.unit() - 1.0;
+            let phi = 2.0 * PI * rng.unit();
+            let r = (1.0 - z * z).sqrt();
+            row.extend_from_slice(&[r * phi.cos(), r * phi.sin(), z]);
+        }
+        if options.colors {
+            row.extend((0..3).map(|_| rng.below(256) as f64));
+        }
+        if options.intensity {
+            row.push(rng.unit());
+        }
+        row
+    }).collect();
+    let mut ply = Ply::new();
+    ply.header.encoding = options.encoding;
+    add_element(&mut ply, "vertex", properties, rows);
+    ply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property };
+    use crate::util::get_list_as_f64;
+    /// Sum of the signed volumes of the fan triangles, positive for outward facing meshes.
+    fn volume(ply: &Ply<DefaultElement>) -> f64 {
+        let p = |i: f64| {
+            let v = &ply.payload["vertex"][i as usize];
+            let c = |k: &str| match v[k] { Property::Float(f) => f as f64, _ => unreachable!() };
+            [c("x"), c("y"), c("z")]
+        };
+        let def = &ply.header.elements["face"].properties["vertex_indices"];
+        ply.payload["face"].iter().map(|f| {
+            let l = get_list_as_f64(f, def).unwrap();
+            (1..l.len() - 1).map(|k| {
+                let (a, b, c) = (p(l[0]), p(l[k]), p(l[k + 1]));
+                (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0]) + a[2] * (b[0] * c[1] - b[1] * c[0])) / 6.0
+            }).sum::<f64>()
+        }).sum()
+    }
+    #[test]
+    fn meshes_consistent() {
+        let mut plane = plane::<DefaultElement>(2.0, 3);
+        assert_eq!(plane.header.elements["vertex"].count, 16);
+        assert_eq!(plane.header.elements["face"].count, 18);
+        assert!(plane.make_consistent().is_ok());
+
+        let cube = cube::<DefaultElement>(2.0);
+        assert!((volume(&cube) - 8.0).abs() < 1e-9);
+        let sphere = sphere::<DefaultElement>(1.0, 8, 12);
+        assert_eq!(sphere.header.elements["vertex"].count, 2 + 7 * 12);
+        assert_eq!(sphere.header.elements["face"].count, 2 * 7 * 12);
+        let v = volume(&sphere);
+        assert!(v > 3.0 && v < 4.0 / 3.0 * PI);
+    }
+    #[test]
+    fn random_cloud_attributes() {
+        let options = CloudOptions { seed: 7, normals: true, colors: true, intensity: true, ..CloudOptions::default() };
+        let cloud = random_cloud::<DefaultElement>(10, &options);
+        assert_eq!(cloud.header.elements["vertex"].properties.len(), 10);
+        assert_eq!(cloud.payload, random_cloud::<DefaultElement>(10, &options).payload);
+        assert!(matches!(cloud.payload["vertex"][3]["red"], Property::UChar(_)));
+        let plain = random_cloud::<DefaultElement>(5, &CloudOptions::default());
+        assert_eq!(plain.payload["vertex"][0].len(), 3);
+    }
+}
@@ -0,0 +1,184 @@
+//! Georeferencing metadata, stored as `comment crs <crs>` and `comment offset <x> <y> <z>`.
+
+use std::fmt;
+use std::fmt::{ Display, Formatter };
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Header, Ply, PropertyAccess };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64, set_scalar_from_f64 };
+
+/// Keyword starting a coordinate reference system comment.
+pub const CRS_COMMENT_PREFIX: &str = "crs";
+/// Keyword starting a global offset comment.
+pub const OFFSET_COMMENT_PREFIX: &str = "offset";
+
+/// Coordinate reference system of the positions.
+///
+/// Written as `comment crs EPSG:<code>` or `comment crs WKT:<wkt>`, the WKT has to fit on one line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Crs {
+    /// EPSG registry code, e.g. `Crs::Epsg(4978)`.
+    Epsg(u32),
+    /// Well-known text definition.
+    Wkt(String),
+}
+
+impl Crs {
+    fn from_comment(comment: &str) -> Option<Self> {
+        let value = comment_value(comment, CRS_COMMENT_PREFIX)?;
+        if let Some(code) = value.strip_prefix("EPSG:") {
+            code.trim().parse().ok().map(Crs::Epsg)
+        } else {
+            value.strip_prefix("WKT:").map(|wkt| Crs::Wkt(wkt.trim().to_string()))
+        }
+    }
+}
+
+impl Display for Crs {
+    fn fmt(&self, f: &mut Formatter) -> std::result::Result<(), fmt::Error> {
+        match *self {
+            Crs::Epsg(code) => write!(f, "EPSG:{}", code),
+            Crs::Wkt(ref wkt) => write!(f, "WKT:{}", wkt),
+        }
+    }
+}
+
+/// Returns the remainder of `comment` if its first token is `keyword`.
+pub(super) fn comment_value<'a>(comment: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = comment.trim_start().strip_prefix(keyword)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim())
+}
+
+fn parse_offset(comment: &str) -> Option<[f64; 3]> {
+    let mut tokens = comment_value(comment, OFFSET_COMMENT_PREFIX)?.split_whitespace();
+    let mut offset = [0.0; 3];
+    for o in offset.iter_mut() {
+        *o = tokens.next()?.parse().ok()?;
+    }
+    if tokens.next().is_some() {
+        return None;
+    }
+    Some(offset)
+}
+
+/// Replaces the first comment starting with `keyword` by `comment`, or appends it.
+fn replace_comment(header: &mut Header, keyword: &str, comment: String) {
+    match header.comments.iter().position(|c| comment_value(c, keyword).is_some()) {
+        Some(i) => header.comments[i] = comment,
+        None => header.comments.push(comment),
+    }
+}
+
+impl Header {
+    /// Coordinate reference system declared by a `crs` comment.
+    pub fn crs(&self) -> Option<Crs> {
+        self.comments.iter().find_map(|c| Crs::from_comment(c))
+    }
+    /// Declares the coordinate reference system, replaces a previous declaration.
+    pub fn set_crs(&mut self, crs: Crs) {
+        replace_comment(self, CRS_COMMENT_PREFIX, format!("{} {}", CRS_COMMENT_PREFIX, crs));
+    }
+    /// Removes the coordinate reference system declaration and returns it.
+    pub fn remove_crs(&mut self) -> Option<Crs> {
+        let crs = self.crs()?;
+        self.comments.retain(|c| comment_value(c, CRS_COMMENT_PREFIX).is_none());
+        Some(crs)
+    }
+    /// Global offset which has to be added to the stored positions to obtain coordinates in the CRS.
+    pub fn offset(&self) -> Option<[f64; 3]> {
+        self.comments.iter().find_map(|c| parse_offset(c))
+    }
+    /// Declares the global offset, replaces a previous declaration.
+    pub fn set_offset(&mut self, offset: [f64; 3]) {
+        let comment = format!("{} {} {} {}", OFFSET_COMMENT_PREFIX, offset[0], offset[1], offset[2]);
+        replace_comment(self, OFFSET_COMMENT_PREFIX, comment);
+    }
+    /// Removes the global offset declaration and returns it.
+    pub fn remove_offset(&mut self) -> Option<[f64; 3]> {
+        let offset = self.offset()?;
+        self.comments.retain(|c| comment_value(c, OFFSET_COMMENT_PREFIX).is_none());
+        Some(offset)
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Adds the declared global offset to the `x`, `y` and `z` properties of `element` and removes the declaration.
+    ///
+    /// Afterwards the positions are absolute coordinates in the CRS.
+    /// Use `double` properties, `float` can't represent typical projected coordinates to millimeter precision.
+    /// Does nothing if no offset is declared.
+    pub fn apply_offset(&mut self, element: &str) -> Result<()> {
+        let offset = match self.header.offset() {
+            Some(o) => o,
+            None => return Ok(()),
+        };
+        self.translate_positions(element, offset)?;
+        self.header.remove_offset();
+        Ok(())
+    }
+    /// Subtracts `offset` from the `x`, `y` and `z` properties of `element` and declares it as global offset.
+    ///
+    /// Fails if an offset is already declared, apply it first.
+    pub fn remove_offset(&mut self, element: &str, offset: [f64; 3]) -> Result<()> {
+        if self.header.offset().is_some() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "A global offset is already declared."));
+        }
+        self.translate_positions(element, [-offset[0], -offset[1], -offset[2]])?;
+        self.header.set_offset(offset);
+        Ok(())
+    }
+    fn translate_positions(&mut self, element: &str, delta: [f64; 3]) -> Result<()> {
+        let defs = property_defs(element_def(self, element)?, &["x", "y", "z"])?;
+        if let Some(list) = self.payload.get_mut(element) {
+            for (row, e) in list.iter_mut().enumerate() {
+                for (def, d) in defs.iter().zip(delta.iter()) {
+                    let v = require_scalar_as_f64(e, def, element, row)?;
+                    set_scalar_from_f64(e, def, v + d);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    #[test]
+    fn crs_get_set_ok() {
+        let mut h = Header::new();
+        h.comments.push("crs_like comment".to_string());
+        assert_eq!(h.crs(), None);
+        h.set_crs(Crs::Epsg(2056));
+        assert_eq!(h.comments[1], "crs EPSG:2056");
+        assert_eq!(h.crs(), Some(Crs::Epsg(2056)));
+        let wkt = "GEOGCS[\"WGS 84\", DATUM[\"WGS_1984\"]]".to_string();
+        h.set_crs(Crs::Wkt(wkt.clone()));
+        assert_eq!(h.comments.len(), 2);
+        assert_eq!(h.remove_crs(), Some(Crs::Wkt(wkt)));
+        assert_eq!(h.crs(), None);
+    }
+    #[test]
+    fn offset_roundtrip_ok() {
+        let mut ply = Ply::<DefaultElement>::new();
+        let properties = ["x", "y", "z"].iter().map(|&n| (n, PropertyType::Scalar(ScalarType::Double))).collect::<Vec<_>>();
+        add_test_element(&mut ply, "vertex", &properties, vec![vec![Property::Double(2600001.5), Property::Double(1200002.25), Property::Double(400.0)]]);
+
+        let offset = [2600000.0, 1200000.0, 0.0];
+        ply.remove_offset("vertex", offset).unwrap();
+        assert_eq!(ply.header.offset(), Some(offset));
+        assert_eq!(ply.payload["vertex"][0]["x"], Property::Double(1.5));
+        assert_eq!(ply.payload["vertex"][0]["y"], Property::Double(2.25));
+        assert!(ply.remove_offset("vertex", offset).is_err());
+
+        ply.apply_offset("vertex").unwrap();
+        assert_eq!(ply.header.offset(), None);
+        assert_eq!(ply.payload["vertex"][0]["x"], Property::Double(2600001.5));
+        assert_eq!(ply.payload["vertex"][0]["z"], Property::Double(400.0));
+    }
+}
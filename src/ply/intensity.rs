@@ -0,0 +1,116 @@
+//! Rescaling of `intensity` properties for visualization.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Normalization, Ply, PropertyAccess, PropertyDef, PropertyType, ScalarType };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64, set_scalar_from_f64 };
+
+/// Value range and type of normalized intensities.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IntensityRange {
+    /// `float` values between 0 and 1.
+    Unit,
+    /// `uchar` values between 0 and 255.
+    Byte,
+}
+
+/// Set of elements sharing the same normalization range.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IntensityScope {
+    /// All elements.
+    Global,
+    /// Square tiles of the given edge length in the `x`/`y` plane, normalized independently.
+    Tiles(f64),
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Rescales the `intensity` property of `element` to `range` and changes its type accordingly.
+    ///
+    /// `normalization` is resolved per `scope`, values outside the resolved range are clamped.
+    pub fn normalize_intensity(&mut self, element: &str, range: IntensityRange, normalization: Normalization, scope: IntensityScope) -> Result<()> {
+        let def = property_defs(element_def(self, element)?, &["intensity"])?.remove(0);
+        if let PropertyType::List(_, _) = def.data_type {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Property `{}.intensity` is a list.", element)));
+        }
+        let list = self.payload.get(element).map(|l| &l[..]).unwrap_or_default();
+        let values = list.iter().enumerate()
+            .map(|(row, e)| require_scalar_as_f64(e, &def, element, row))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Tile of every element, all elements share tile 0 in global scope.
+        let tiles = match scope {
+            IntensityScope::Global => vec![0; values.len()],
+            IntensityScope::Tiles(size) => {
+                if size <= 0.0 {
+                    return Err(io::Error::new(ErrorKind::InvalidInput, format!("Invalid tile size {}.", size)));
+                }
+                let xy = property_defs(element_def(self, element)?, &["x", "y"])?;
+                let mut ids = HashMap::new();
+                let mut tiles = Vec::with_capacity(values.len());
+                for (row, e) in list.iter().enumerate() {
+                    let x = (require_scalar_as_f64(e, &xy[0], element, row)? / size).floor() as i64;
+                    let y = (require_scalar_as_f64(e, &xy[1], element, row)? / size).floor() as i64;
+                    let next = ids.len();
+                    tiles.push(*ids.entry((x, y)).or_insert(next));
+                }
+                tiles
+            },
+        };
+        let tile_count = tiles.iter().max().map_or(0, |m| m + 1);
+        let mut tile_values = vec![Vec::new(); tile_count];
+        for (&t, &v) in tiles.iter().zip(values.iter()) {
+            tile_values[t].push(v);
+        }
+        let ranges = tile_values.iter().map(|v| normalization.range(v)).collect::<Vec<_>>();
+
+        let (scalar_type, max) = match range {
+            IntensityRange::Unit => (ScalarType::Float, 1.0),
+            IntensityRange::Byte => (ScalarType::UChar, 255.0),
+        };
+        let new_def = PropertyDef::new("intensity".to_string(), PropertyType::Scalar(scalar_type));
+        self.header.elements.get_mut(element).unwrap().properties.get_mut("intensity").unwrap().data_type = new_def.data_type.clone();
+        if let Some(list) = self.payload.get_mut(element) {
+            for ((e, &t), &v) in list.iter_mut().zip(tiles.iter()).zip(values.iter()) {
+                let (low, high) = ranges[t];
+                let n = if high > low && !v.is_nan() { ((v - low) / (high - low)).clamp(0.0, 1.0) } else { 0.0 };
+                set_scalar_from_f64(e, &new_def, n * max);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    fn cloud(points: &[(f32, u16)]) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let properties = [
+            ("x", PropertyType::Scalar(ScalarType::Float)),
+            ("y", PropertyType::Scalar(ScalarType::Float)),
+            ("intensity", PropertyType::Scalar(ScalarType::UShort)),
+        ];
+        let vertices = points.iter().map(|&(x, i)| vec![Property::Float(x), Property::Float(0.0), Property::UShort(i)]).collect();
+        add_test_element(&mut ply, "vertex", &properties, vertices);
+        ply
+    }
+    #[test]
+    fn normalize_global_ok() {
+        let mut ply = cloud(&[(0.0, 100), (1.0, 300), (2.0, 200)]);
+        ply.normalize_intensity("vertex", IntensityRange::Unit, Normalization::MinMax, IntensityScope::Global).unwrap();
+        assert_eq!(ply.header.elements["vertex"].properties["intensity"].data_type, PropertyType::Scalar(ScalarType::Float));
+        assert_eq!(ply.payload["vertex"][2]["intensity"], Property::Float(0.5));
+        assert_eq!(ply.payload["vertex"][1]["intensity"], Property::Float(1.0));
+    }
+    #[test]
+    fn normalize_tiles_ok() {
+        let mut ply = cloud(&[(0.0, 100), (1.0, 300), (10.0, 1000), (11.0, 2000)]);
+        ply.normalize_intensity("vertex", IntensityRange::Byte, Normalization::MinMax, IntensityScope::Tiles(5.0)).unwrap();
+        let i = ply.payload["vertex"].iter().map(|v| v["intensity"].clone()).collect::<Vec<_>>();
+        assert_eq!(i, vec![Property::UChar(0), Property::UChar(255), Property::UChar(0), Property::UChar(255)]);
+        assert!(ply.normalize_intensity("vertex", IntensityRange::Byte, Normalization::MinMax, IntensityScope::Tiles(0.0)).is_err());
+    }
+}
@@ -1,17 +1,73 @@
 //! Definitions used to model PLY files.
 
 
+mod camera;
+pub use self::camera::*;
+
+mod colormap;
+pub use self::colormap::*;
+
+mod column;
+pub use self::column::*;
+
+mod columnar;
+pub use self::columnar::*;
+
 mod consistency;
 pub use self::consistency::*;
 
 mod default_element;
 pub use self::default_element::*;
 
+mod error;
+pub use self::error::Error;
+
+mod filter;
+pub use self::filter::*;
+
+mod fixed_list;
+pub use self::fixed_list::*;
+
+pub mod generate;
+
+mod georef;
+pub use self::georef::*;
+
+mod intensity;
+pub use self::intensity::*;
+
 mod key_map;
 pub use self::key_map::*;
 
+mod normals;
+
+mod partition;
+
 mod ply_data_structure;
 pub use self::ply_data_structure::*;
 
 mod property;
 pub use self::property::*;
+#[cfg(feature = "derive")]
+pub use ply_derive::PlyElement;
+
+pub use crate::transcode::{ transcode, TranscodeOptions };
+
+mod range_grid;
+pub use self::range_grid::*;
+
+mod sample;
+
+mod schema;
+pub use self::schema::*;
+
+mod simplify;
+
+mod time;
+pub use self::time::*;
+
+mod units;
+pub use self::units::*;
+
+#[cfg(feature = "meshopt")]
+mod vertex_cache;
@@ -0,0 +1,88 @@
+//! Orientation of per-element normals.
+
+use std::io::Result;
+
+use super::{ Ply, PropertyAccess };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64, set_scalar_from_f64 };
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Flips the normals `nx`, `ny`, `nz` of `element_name` such that they point toward `viewpoint`.
+    ///
+    /// A normal is negated if it points away from the viewpoint, i.e. if its dot product with
+    /// the vector from the element's position `x`, `y`, `z` to the viewpoint is negative.
+    /// This is the usual fix-up for scanner output before meshing or shading.
+    ///
+    /// Returns the number of flipped normals.
+    pub fn orient_normals_toward(&mut self, element_name: &str, viewpoint: [f64; 3]) -> Result<usize> {
+        self.orient_normals_toward_each(element_name, |_| viewpoint)
+    }
+    /// Like `orient_normals_toward()`, but with an individual viewpoint per element.
+    ///
+    /// `viewpoint` is called with the row index of each element,
+    /// e.g. to look up the sensor position at acquisition time.
+    pub fn orient_normals_toward_each<F: FnMut(usize) -> [f64; 3]>(&mut self, element_name: &str, mut viewpoint: F) -> Result<usize> {
+        let defs = property_defs(element_def(self, element_name)?, &["x", "y", "z", "nx", "ny", "nz"])?;
+        let elements = match self.payload.get_mut(element_name) {
+            Some(e) => e,
+            None => return Ok(0),
+        };
+        let mut flipped = 0;
+        for (row, element) in elements.iter_mut().enumerate() {
+            let mut v = [0.0; 6];
+            for (i, def) in defs.iter().enumerate() {
+                v[i] = require_scalar_as_f64(element, def, element_name, row)?;
+            }
+            let target = viewpoint(row);
+            let dot: f64 = (0..3).map(|a| v[a + 3] * (target[a] - v[a])).sum();
+            if dot < 0.0 {
+                for a in 3..6 {
+                    set_scalar_from_f64(element, &defs[a], -v[a]);
+                }
+                flipped += 1;
+            }
+        }
+        Ok(flipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    fn cloud(points: &[[f32; 6]]) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let properties = ["x", "y", "z", "nx", "ny", "nz"].iter().map(|&n| (n, PropertyType::Scalar(ScalarType::Float))).collect::<Vec<_>>();
+        add_test_element(&mut ply, "vertex", &properties, points.iter().map(|p| p.iter().map(|&x| Property::Float(x)).collect()).collect());
+        ply
+    }
+    #[test]
+    fn orient_normals_toward_ok() {
+        let mut ply = cloud(&[
+            [0.0, 0.0, 0.0, 0.0, 0.0, -1.0],
+            [1.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 5.0, 0.0, 1.0, 0.0],
+        ]);
+        assert_eq!(ply.orient_normals_toward("vertex", [0.0, 0.0, 10.0]).unwrap(), 1);
+        assert_eq!(ply.payload["vertex"][0]["nz"], Property::Float(1.0));
+        assert_eq!(ply.payload["vertex"][1]["nz"], Property::Float(1.0));
+        assert_eq!(ply.payload["vertex"][2]["ny"], Property::Float(1.0));
+        assert_eq!(ply.orient_normals_toward("vertex", [0.0, 0.0, 10.0]).unwrap(), 0);
+    }
+    #[test]
+    fn orient_normals_toward_each_ok() {
+        let mut ply = cloud(&[
+            [0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+        ]);
+        let sensors = [[0.0, 0.0, 1.0], [0.0, 0.0, -1.0]];
+        assert_eq!(ply.orient_normals_toward_each("vertex", |row| sensors[row]).unwrap(), 1);
+        assert_eq!(ply.payload["vertex"][0]["nz"], Property::Float(1.0));
+        assert_eq!(ply.payload["vertex"][1]["nz"], Property::Float(-1.0));
+    }
+    #[test]
+    fn orient_normals_missing_property() {
+        let mut ply = cloud(&[]);
+        ply.header.elements["vertex"].properties.remove("nz");
+        assert!(ply.orient_normals_toward("vertex", [0.0; 3]).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Splitting a `Ply` into several parts.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::filter::{ face_index_def, map_faces };
+use super::{ Ply, PropertyAccess, PropertyType };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64, set_list_from_f64 };
+
+impl<E: PropertyAccess + Clone> Ply<E> {
+    /// Distributes the entries of `element` to several parts, entry `i` goes to part `parts[i]` or is dropped if `None`.
+    ///
+    /// Returns `max(parts) + 1` plys sharing the header of `self`, with updated element counts.
+    /// When splitting `vertex`, every face goes to the part containing all of its vertices and is renumbered,
+    /// faces spanning several parts are dropped.
+    /// All other elements are copied to every part.
+    pub fn into_parts(mut self, element: &str, parts: &[Option<usize>]) -> Result<Vec<Ply<E>>> {
+        element_def(&self, element)?;
+        let entries = self.payload.remove(element).unwrap_or_default();
+        if entries.len() != parts.len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` has {} entries, but {} parts have been assigned.", element, entries.len(), parts.len())
+            ));
+        }
+        let count = parts.iter().filter_map(|p| *p).max().map_or(0, |m| m + 1);
+
+        // Faces per part, if faces follow the vertices.
+        let mut faces = Vec::new();
+        let face_def = if element == "vertex" { face_index_def(&self)? } else { None };
+        if let Some(ref def) = face_def {
+            // Part and index within the part of every vertex.
+            let mut sizes = vec![0; count];
+            let local = parts.iter().map(|p| p.map(|p| {
+                sizes[p] += 1;
+                (p, sizes[p] - 1)
+            })).collect::<Vec<_>>();
+            let mapped = map_faces(&self, def, |i| local.get(i).cloned().flatten())?;
+            faces = vec![Vec::new(); count];
+            for (f, m) in self.payload.remove("face").unwrap_or_default().into_iter().zip(mapped) {
+                let m = match m {
+                    Some(m) => m,
+                    None => continue,
+                };
+                let part = match m.first() {
+                    Some(&(p, _)) => p,
+                    None => continue,
+                };
+                if m.iter().all(|&(p, _)| p == part) {
+                    let mut f = f;
+                    set_list_from_f64(&mut f, def, &m.iter().map(|&(_, i)| i as f64).collect::<Vec<_>>());
+                    faces[part].push(f);
+                }
+            }
+        }
+
+        let mut split = vec![Vec::new(); count];
+        for (e, p) in entries.into_iter().zip(parts) {
+            if let Some(p) = *p {
+                split[p].push(e);
+            }
+        }
+        let mut faces = faces.into_iter();
+        Ok(split.into_iter().map(|entries| {
+            let mut part = self.clone();
+            part.header.elements.get_mut(element).unwrap().count = entries.len();
+            part.payload.insert(element.to_string(), entries);
+            if let Some(f) = faces.next() {
+                part.header.elements.get_mut("face").unwrap().count = f.len();
+                part.payload.insert("face".to_string(), f);
+            }
+            part
+        }).collect())
+    }
+    /// Splits `element` into one ply per distinct value of its scalar `property`, e.g. `split_by("vertex", "label")`.
+    ///
+    /// Returns the value and part for every distinct value in increasing order, NaN last.
+    /// Faces follow their vertices as described in `into_parts()`,
+    /// remove the `face` element beforehand to split points only.
+    pub fn split_by(&self, element: &str, property: &str) -> Result<Vec<(f64, Ply<E>)>> {
+        let def = property_defs(element_def(self, element)?, &[property])?.remove(0);
+        if let PropertyType::List(_, _) = def.data_type {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Property `{}.{}` is a list.", element, property)));
+        }
+        let entries = self.payload.get(element).map(|l| &l[..]).unwrap_or_default();
+        // Merges all NaNs and both zeros, positive NaN sorts last in total order.
+        let canonical = |v: f64| if v.is_nan() { f64::NAN } else if v == 0.0 { 0.0 } else { v };
+        let values = entries.iter().enumerate()
+            .map(|(row, e)| require_scalar_as_f64(e, &def, element, row).map(canonical))
+            .collect::<Result<Vec<_>>>()?;
+        let mut distinct = values.clone();
+        distinct.sort_by(f64::total_cmp);
+        distinct.dedup_by(|a, b| a.to_bits() == b.to_bits());
+        let parts = values.iter()
+            .map(|v| distinct.binary_search_by(|d| d.total_cmp(v)).ok())
+            .collect::<Vec<_>>();
+        let plys = self.clone().into_parts(element, &parts)?;
+        Ok(distinct.into_iter().zip(plys).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    #[test]
+    fn split_by_ok() {
+        let mut ply = Ply::<DefaultElement>::new();
+        ply.header.comments.push("labeled".to_string());
+        let vertices = [7, 2, 7, 2, 7].iter().map(|&l| vec![Property::UChar(l)]).collect();
+        add_test_element(&mut ply, "vertex", &[("label", PropertyType::Scalar(ScalarType::UChar))], vertices);
+        let faces = [vec![0, 2, 4], vec![1, 3, 1], vec![0, 1, 2]].iter().map(|l| vec![Property::ListInt(l.clone())]).collect();
+        add_test_element(&mut ply, "face", &[("vertex_indices", PropertyType::List(ScalarType::UChar, ScalarType::Int))], faces);
+
+        let parts = ply.split_by("vertex", "label").unwrap();
+        assert_eq!(parts.len(), 2);
+        let (label, ref two) = parts[0];
+        assert_eq!(label, 2.0);
+        assert_eq!(two.header.elements["vertex"].count, 2);
+        assert_eq!(two.header.comments, vec!["labeled".to_string()]);
+        assert_eq!(two.payload["face"].len(), 1);
+        assert_eq!(two.payload["face"][0]["vertex_indices"], Property::ListInt(vec![0, 1, 0]));
+        let (label, ref seven) = parts[1];
+        assert_eq!(label, 7.0);
+        assert_eq!(seven.payload["vertex"].len(), 3);
+        assert_eq!(seven.payload["face"][0]["vertex_indices"], Property::ListInt(vec![0, 1, 2]));
+        assert!(ply.split_by("vertex", "missing").is_err());
+    }
+}
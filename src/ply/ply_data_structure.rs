@@ -39,10 +39,16 @@ impl<E: PropertyAccess> Ply<E> {
     }
 }
 
+impl<E: PropertyAccess> Default for Ply<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Header Types
 
 /// Models the header of a PLY file.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Header {
     /// In which format is the payload encoded?
     ///
@@ -74,6 +80,12 @@ impl Header {
     }
 }
 
+impl Default for Header {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Alias to give object informations an explicit type.
 pub type ObjInfo = String;
 
@@ -83,7 +95,7 @@ pub type Comment = String;
 /// Models a version number.
 ///
 /// At time of writing, the only existin version for a PLY file is "1.0".
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Version {
     pub major: u16,
     pub minor: u8,
@@ -96,7 +108,7 @@ impl Display for Version {
 }
 
 /// Models possible encoding standards for the payload.
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Encoding {
     /// Write numbers in their ascii representation (e.g. -13, 6.28, etc.).
     /// Properties are separated by spaces and elements are separated by line breaks.
@@ -125,7 +137,7 @@ impl Display for Encoding {
 /// A single point is an element.
 /// We might model it as consisting of three coordinates: x, y, and z.
 /// Usually, one finds a list of elements in a ply file.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct ElementDef {
     /// Name of the element.
     ///
@@ -156,15 +168,22 @@ impl ElementDef {
     /// No properties are set.
     pub fn new(name: String) -> Self {
         ElementDef {
-            name: name,
+            name,
             count: 0,
             properties: KeyMap::new(),
         }
     }
+    /// Number of bytes of a binary encoded element, `None` if it has list properties and hence a variable size.
+    pub fn fixed_stride(&self) -> Option<usize> {
+        self.properties.values().map(|p| match p.data_type {
+            PropertyType::Scalar(ref s) => Some(s.byte_size()),
+            PropertyType::List(_, _) => None,
+        }).sum()
+    }
 }
 
 /// Defines a property of an element.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct PropertyDef {
     /// Unique name of property.
     ///
@@ -180,8 +199,8 @@ impl PropertyDef {
     /// Creates a new property definition.
     pub fn new(name: String, data_type: PropertyType) -> Self {
         PropertyDef {
-            name: name,
-            data_type: data_type,
+            name,
+            data_type,
         }
     }
 }
@@ -1,8 +1,9 @@
+use super::ElementDef;
 
 /// Scalar type used to encode properties in the payload.
 ///
 /// For the translation to rust types, see individual documentation.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum ScalarType {
     /// Signed 8 bit integer, rust: `i8`.
     Char,
@@ -22,11 +23,77 @@ pub enum ScalarType {
     Double,
 }
 
+impl ScalarType {
+    /// Number of bytes of a binary encoded value.
+    pub fn byte_size(&self) -> usize {
+        match *self {
+            ScalarType::Char | ScalarType::UChar => 1,
+            ScalarType::Short | ScalarType::UShort => 2,
+            ScalarType::Int | ScalarType::UInt | ScalarType::Float => 4,
+            ScalarType::Double => 8,
+        }
+    }
+    /// Smallest and largest finite value of the type.
+    pub fn range(&self) -> (f64, f64) {
+        match *self {
+            ScalarType::Char => (f64::from(i8::MIN), f64::from(i8::MAX)),
+            ScalarType::UChar => (0.0, f64::from(u8::MAX)),
+            ScalarType::Short => (f64::from(i16::MIN), f64::from(i16::MAX)),
+            ScalarType::UShort => (0.0, f64::from(u16::MAX)),
+            ScalarType::Int => (f64::from(i32::MIN), f64::from(i32::MAX)),
+            ScalarType::UInt => (0.0, f64::from(u32::MAX)),
+            ScalarType::Float => (f64::from(f32::MIN), f64::from(f32::MAX)),
+            ScalarType::Double => (f64::MIN, f64::MAX),
+        }
+    }
+}
+
+/// Rust type of a scalar or list entry. All values of PLY scalar types are exactly representable as `f64`.
+pub trait ScalarValue: Copy + Default {
+    /// Type used when a new property is declared.
+    const SCALAR_TYPE: ScalarType;
+    fn from_f64(v: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! scalar_value {
+    ($t:ty, $s:ident) => {
+        impl ScalarValue for $t {
+            const SCALAR_TYPE: ScalarType = ScalarType::$s;
+            fn from_f64(v: f64) -> Self {
+                v as $t
+            }
+            fn to_f64(self) -> f64 {
+                f64::from(self)
+            }
+        }
+    };
+}
+scalar_value!(i8, Char);
+scalar_value!(u8, UChar);
+scalar_value!(i16, Short);
+scalar_value!(u16, UShort);
+scalar_value!(i32, Int);
+scalar_value!(u32, UInt);
+scalar_value!(f32, Float);
+scalar_value!(f64, Double);
+
+/// Handling of values outside the range of the type they are stored in, e.g. `300` in a `uchar` property.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutOfRange {
+    /// Reject the value.
+    Error,
+    /// Store the closest value of the type.
+    Clamp,
+    /// Keep the low bits of integers, like a two's complement cast. Floats overflow to infinity.
+    Wrap,
+}
+
 /// Data type used to encode properties in the payload.
 ///
 /// There are two possible types: scalars and lists.
 /// Lists are a sequence of scalars with a leading integer value defining how many elements the list contains.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum PropertyType {
     /// Simple, "one-number" type.
     Scalar(ScalarType),
@@ -63,6 +130,46 @@ pub enum Property {
     ListDouble(Vec<f64>),
 }
 
+impl Property {
+    /// Converts `value` to a scalar of `scalar_type`, integers are rounded.
+    ///
+    /// Returns `None` if `value` is out of range and `policy` is `OutOfRange::Error`.
+    /// NaN is out of range for integers, clamping or wrapping it gives zero.
+    /// Non-finite values are always in range for floats.
+    pub fn from_f64(value: f64, scalar_type: &ScalarType, policy: OutOfRange) -> Option<Property> {
+        let (min, max) = scalar_type.range();
+        let is_float = matches!(*scalar_type, ScalarType::Float | ScalarType::Double);
+        let value = if is_float { value } else { value.round() };
+        let in_range = (min <= value && value <= max) || (is_float && !value.is_finite());
+        let value = match policy {
+            _ if in_range => value,
+            OutOfRange::Error => return None,
+            OutOfRange::Clamp if value.is_nan() => 0.0,
+            OutOfRange::Clamp => value.clamp(min, max),
+            OutOfRange::Wrap if is_float => value.signum() * f64::INFINITY,
+            // Saturates at the range of i64 before wrapping, wide enough for all PLY integers.
+            OutOfRange::Wrap => return Some(match *scalar_type {
+                ScalarType::Char => Property::Char(value as i64 as i8),
+                ScalarType::UChar => Property::UChar(value as i64 as u8),
+                ScalarType::Short => Property::Short(value as i64 as i16),
+                ScalarType::UShort => Property::UShort(value as i64 as u16),
+                ScalarType::Int => Property::Int(value as i64 as i32),
+                _ => Property::UInt(value as i64 as u32),
+            }),
+        };
+        Some(match *scalar_type {
+            ScalarType::Char => Property::Char(value as i8),
+            ScalarType::UChar => Property::UChar(value as u8),
+            ScalarType::Short => Property::Short(value as i16),
+            ScalarType::UShort => Property::UShort(value as u16),
+            ScalarType::Int => Property::Int(value as i32),
+            ScalarType::UInt => Property::UInt(value as u32),
+            ScalarType::Float => Property::Float(value as f32),
+            ScalarType::Double => Property::Double(value),
+        })
+    }
+}
+
 /// Provides setters and getters for the Parser and the Writer.
 ///
 /// This trait allows you to create your own data structure for the case that the
@@ -80,6 +187,12 @@ pub trait PropertyAccess {
         // By default, do nothing
         // Sombody might only want to write, no point in bothering him/her with setter implementations.
     }
+    /// Called by the `Parser` for each value read, forwards to `set_property()` by default.
+    ///
+    /// Implement it instead of `set_property()` to decode values without allocating their name.
+    fn set_named_property(&mut self, property_name: &str, property: Property) {
+        self.set_property(property_name.to_string(), property);
+    }
     fn get_char(&self, _property_name: &String) -> Option<i8> {
         None
     }
@@ -129,3 +242,32 @@ pub trait PropertyAccess {
         None
     }
 }
+
+/// Element type with one property per field, usually implemented with `#[derive(PlyElement)]` of feature `derive`.
+///
+/// Fields of the types `i8`, `u8`, `i16`, `u16`, `i32`, `u32`, `f32` and `f64` are scalars, a `Vec` of them is a list.
+/// `#[ply(name = "...")]` renames the element or a property, `#[ply(index = "uint")]` sets the index type of a list, default is `uchar`.
+/// Values read in another type than the field are ignored.
+pub trait PlyElement: PropertyAccess {
+    /// Definition of the element with its properties in field order, `count` is zero.
+    fn element_def() -> ElementDef;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn from_f64_policies() {
+        let uchar = ScalarType::UChar;
+        assert_eq!(Property::from_f64(254.6, &uchar, OutOfRange::Error), Some(Property::UChar(255)));
+        assert_eq!(Property::from_f64(300.0, &uchar, OutOfRange::Error), None);
+        assert_eq!(Property::from_f64(300.0, &uchar, OutOfRange::Clamp), Some(Property::UChar(255)));
+        assert_eq!(Property::from_f64(300.0, &uchar, OutOfRange::Wrap), Some(Property::UChar(44)));
+        assert_eq!(Property::from_f64(-1.0, &uchar, OutOfRange::Wrap), Some(Property::UChar(255)));
+        assert_eq!(Property::from_f64(f64::NAN, &ScalarType::Int, OutOfRange::Clamp), Some(Property::Int(0)));
+        assert_eq!(Property::from_f64(1e40, &ScalarType::Float, OutOfRange::Error), None);
+        assert_eq!(Property::from_f64(-1e40, &ScalarType::Float, OutOfRange::Clamp), Some(Property::Float(f32::MIN)));
+        assert_eq!(Property::from_f64(1e40, &ScalarType::Float, OutOfRange::Wrap), Some(Property::Float(f32::INFINITY)));
+        assert_eq!(Property::from_f64(f64::INFINITY, &ScalarType::Float, OutOfRange::Error), Some(Property::Float(f32::INFINITY)));
+    }
+}
@@ -0,0 +1,173 @@
+//! Organized scans in the Stanford `range_grid` convention.
+//!
+//! The grid size is declared as `obj_info num_cols <n>` and `obj_info num_rows <n>`,
+//! each row of the `range_grid` element lists the vertex measured at one cell, or nothing.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::georef::comment_value;
+use super::{ Addable, ElementDef, Header, Ply, PropertyAccess, PropertyDef, PropertyType, ScalarType };
+use crate::util::{ element_def, get_list_as_f64, property_defs, require_scalar_as_f64, set_list_from_f64 };
+
+/// Name of the element holding one row per grid cell.
+pub const RANGE_GRID_ELEMENT: &str = "range_grid";
+/// Keyword of the obj_info declaring the number of columns.
+pub const NUM_COLS_OBJ_INFO: &str = "num_cols";
+/// Keyword of the obj_info declaring the number of rows.
+pub const NUM_ROWS_OBJ_INFO: &str = "num_rows";
+
+/// Vertex index of each cell of an organized scan, in row-major order.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RangeGrid {
+    pub num_cols: usize,
+    pub num_rows: usize,
+    /// `num_rows * num_cols` cells, `None` where the scanner got no return.
+    pub cells: Vec<Option<usize>>,
+}
+
+/// Number of cells of a `num_cols` x `num_rows` grid, fails if it overflows.
+fn cell_count(num_cols: usize, num_rows: usize) -> Result<usize> {
+    num_cols.checked_mul(num_rows).ok_or_else(|| io::Error::new(
+        ErrorKind::InvalidInput,
+        format!("Range grid of {} x {} cells is too large.", num_cols, num_rows)
+    ))
+}
+
+impl RangeGrid {
+    /// Vertex index at `row`, `col`, `None` for empty cells or outside the grid.
+    pub fn get(&self, row: usize, col: usize) -> Option<usize> {
+        if row >= self.num_rows || col >= self.num_cols {
+            return None;
+        }
+        self.cells[row * self.num_cols + col]
+    }
+}
+
+fn obj_info_usize(header: &Header, keyword: &str) -> Option<usize> {
+    header.obj_infos.iter().find_map(|o| comment_value(o, keyword)?.parse().ok())
+}
+
+impl Header {
+    /// Grid size as `(num_cols, num_rows)`, declared by `num_cols` and `num_rows` obj_infos.
+    pub fn range_grid_size(&self) -> Option<(usize, usize)> {
+        Some((obj_info_usize(self, NUM_COLS_OBJ_INFO)?, obj_info_usize(self, NUM_ROWS_OBJ_INFO)?))
+    }
+    /// Declares the grid size, replaces previous declarations.
+    pub fn set_range_grid_size(&mut self, num_cols: usize, num_rows: usize) {
+        self.obj_infos.retain(|o| comment_value(o, NUM_COLS_OBJ_INFO).is_none() && comment_value(o, NUM_ROWS_OBJ_INFO).is_none());
+        self.obj_infos.push(format!("{} {}", NUM_COLS_OBJ_INFO, num_cols));
+        self.obj_infos.push(format!("{} {}", NUM_ROWS_OBJ_INFO, num_rows));
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Reads the `range_grid` element.
+    ///
+    /// Fails if the grid size isn't declared, doesn't match the number of cells,
+    /// or a cell lists more than one vertex or one out of range.
+    pub fn range_grid(&self) -> Result<RangeGrid> {
+        let (num_cols, num_rows) = match self.header.range_grid_size() {
+            Some(s) => s,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, "Missing `num_cols` or `num_rows` obj_info.")),
+        };
+        let grid_def = element_def(self, RANGE_GRID_ELEMENT)?;
+        let def = match grid_def.properties.values().next() {
+            Some(p) if matches!(p.data_type, PropertyType::List(_, _)) => p,
+            _ => return Err(io::Error::new(ErrorKind::InvalidInput, "Element `range_grid` has no vertex index list.")),
+        };
+        let rows = self.payload.get(RANGE_GRID_ELEMENT).map(|l| &l[..]).unwrap_or_default();
+        if rows.len() != cell_count(num_cols, num_rows)? {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `range_grid` has {} entries, but the grid has {} x {} cells.", rows.len(), num_cols, num_rows)
+            ));
+        }
+        let vertex_count = self.payload.get("vertex").map_or(0, |l| l.len());
+        let mut cells = Vec::with_capacity(rows.len());
+        for (i, row) in rows.iter().enumerate() {
+            let indices = get_list_as_f64(row, def).unwrap_or_default();
+            let cell = match indices[..] {
+                [] => None,
+                [v] if v >= 0.0 && (v as usize) < vertex_count => Some(v as usize),
+                _ => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Cell {} of the range grid lists {:?}, expected at most one vertex below {}.", i, indices, vertex_count)
+                )),
+            };
+            cells.push(cell);
+        }
+        Ok(RangeGrid { num_cols, num_rows, cells })
+    }
+    /// Positions of the vertices of the range grid in row-major order, `None` for empty cells.
+    pub fn range_grid_points(&self) -> Result<Vec<Option<[f64; 3]>>> {
+        let grid = self.range_grid()?;
+        let defs = property_defs(element_def(self, "vertex")?, &["x", "y", "z"])?;
+        let vertices = &self.payload["vertex"];
+        grid.cells.iter().map(|c| match *c {
+            Some(i) => {
+                let mut p = [0.0; 3];
+                for (p, def) in p.iter_mut().zip(&defs) {
+                    *p = require_scalar_as_f64(&vertices[i], def, "vertex", i)?;
+                }
+                Ok(Some(p))
+            },
+            None => Ok(None),
+        }).collect()
+    }
+    /// Stores `grid` as `range_grid` element with a `vertex_indices` list and declares its size.
+    ///
+    /// A previous range grid is replaced.
+    pub fn set_range_grid(&mut self, grid: &RangeGrid) -> Result<()> {
+        if grid.cells.len() != cell_count(grid.num_cols, grid.num_rows)? {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Range grid has {} cells, expected {} x {}.", grid.cells.len(), grid.num_cols, grid.num_rows)
+            ));
+        }
+        let def = PropertyDef::new("vertex_indices".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int));
+        let rows = grid.cells.iter().map(|c| {
+            let mut e = E::new();
+            let indices = c.map(|i| vec![i as f64]).unwrap_or_default();
+            set_list_from_f64(&mut e, &def, &indices);
+            e
+        }).collect();
+        let mut element = ElementDef::new(RANGE_GRID_ELEMENT.to_string());
+        element.count = grid.cells.len();
+        element.properties.add(def);
+        self.header.elements.add(element);
+        self.payload.insert(RANGE_GRID_ELEMENT.to_string(), rows);
+        self.header.set_range_grid_size(grid.num_cols, grid.num_rows);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    #[test]
+    fn range_grid_round_trip() {
+        let mut ply = Ply::<DefaultElement>::new();
+        let properties = ["x", "y", "z"].iter().map(|&n| (n, PropertyType::Scalar(ScalarType::Float))).collect::<Vec<_>>();
+        add_test_element(&mut ply, "vertex", &properties, (0..2).map(|i| vec![Property::Float(i as f32); 3]).collect());
+        assert!(ply.range_grid().is_err());
+
+        let grid = RangeGrid { num_cols: 3, num_rows: 2, cells: vec![None, Some(1), None, None, None, Some(0)] };
+        ply.set_range_grid(&grid).unwrap();
+        assert_eq!(ply.header.obj_infos, vec!["num_cols 3", "num_rows 2"]);
+        assert_eq!(ply.payload["range_grid"][1]["vertex_indices"], Property::ListInt(vec![1]));
+        let read = ply.range_grid().unwrap();
+        assert_eq!(read, grid);
+        assert_eq!(read.get(1, 2), Some(0));
+        assert_eq!(read.get(2, 0), None);
+        assert_eq!(ply.range_grid_points().unwrap()[1], Some([1.0; 3]));
+
+        ply.header.set_range_grid_size(2, 2);
+        assert!(ply.range_grid().is_err());
+        assert!(ply.set_range_grid(&RangeGrid { num_cols: 2, num_rows: 2, cells: vec![None] }).is_err());
+        ply.header.set_range_grid_size(usize::MAX, 2);
+        assert_eq!(ply.range_grid().unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert!(ply.set_range_grid(&RangeGrid { num_cols: usize::MAX, num_rows: 2, cells: vec![None] }).is_err());
+    }
+}
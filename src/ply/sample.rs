@@ -0,0 +1,169 @@
+//! Sampling of point clouds from mesh surfaces.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::filter::{ face_index_def, map_faces };
+use super::{ Addable, ElementDef, Ply, PropertyAccess, PropertyType };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64, set_scalar_from_f64, SplitMix64 };
+
+/// Names of the vertex normal properties, renormalized after interpolation.
+const NORMAL_NAMES: [&str; 3] = ["nx", "ny", "nz"];
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Samples `n_points` points uniformly over the area of the faces, see `sample_surface_seeded()`.
+    pub fn sample_surface(&self, n_points: usize) -> Result<Ply<E>> {
+        self.sample_surface_seeded(n_points, 0)
+    }
+    /// Samples `n_points` points uniformly over the area of the faces into a new `Ply` with only a `vertex` element.
+    ///
+    /// All scalar vertex properties, e.g. normals, colors or texture coordinates,
+    /// are interpolated from the corners of the sampled face. Normals `nx`, `ny`, `nz` are renormalized.
+    /// Polygons are split into a fan of triangles. The same `seed` draws the same points.
+    /// Comments and object informations are copied.
+    pub fn sample_surface_seeded(&self, n_points: usize, seed: u64) -> Result<Ply<E>> {
+        let vertex = element_def(self, "vertex")?;
+        let position_defs = property_defs(vertex, &["x", "y", "z"])?;
+        let mut sampled_def = ElementDef::new("vertex".to_string());
+        for p in vertex.properties.values().filter(|p| matches!(p.data_type, PropertyType::Scalar(_))) {
+            sampled_def.properties.add(p.clone());
+        }
+        sampled_def.count = n_points;
+        let defs = sampled_def.properties.values().cloned().collect::<Vec<_>>();
+        let normals = NORMAL_NAMES.iter().map(|n| defs.iter().position(|d| d.name == *n)).collect::<Option<Vec<_>>>();
+
+        let vertices = self.payload.get("vertex").map(|l| &l[..]).unwrap_or_default();
+        let values = vertices.iter().enumerate()
+            .map(|(row, v)| defs.iter().map(|d| require_scalar_as_f64(v, d, "vertex", row)).collect::<Result<Vec<_>>>())
+            .collect::<Result<Vec<_>>>()?;
+        let positions = vertices.iter().enumerate()
+            .map(|(row, v)| position_defs.iter().map(|d| require_scalar_as_f64(v, d, "vertex", row)).collect::<Result<Vec<_>>>())
+            .collect::<Result<Vec<_>>>()?;
+
+        // Triangles and their cumulated areas.
+        let mut triangles = Vec::new();
+        let mut cumulated = Vec::new();
+        let mut total = 0.0;
+        if let Some(def) = face_index_def(self)? {
+            for (row, face) in map_faces(self, &def, |i| if i < vertices.len() { Some(i) } else { None })?.into_iter().enumerate() {
+                let face = match face {
+                    Some(f) => f,
+                    None => return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Element `face` at index {} references a vertex out of range.", row)
+                    )),
+                };
+                for k in 2..face.len() {
+                    let t = [face[0], face[k - 1], face[k]];
+                    let [a, b, c] = t.map(|i| &positions[i]);
+                    let e1 = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+                    let e2 = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+                    let n = [e1[1] * e2[2] - e1[2] * e2[1], e1[2] * e2[0] - e1[0] * e2[2], e1[0] * e2[1] - e1[1] * e2[0]];
+                    let area = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt() / 2.0;
+                    if area > 0.0 {
+                        total += area;
+                        triangles.push(t);
+                        cumulated.push(total);
+                    }
+                }
+            }
+        }
+        if n_points > 0 && triangles.is_empty() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "The faces have no area to sample points from."));
+        }
+
+        let mut rng = SplitMix64(seed);
+        let mut points = Vec::with_capacity(n_points);
+        for _ in 0..n_points {
+            let r = rng.unit() * total;
+            let t = triangles[cumulated.partition_point(|&c| c <= r).min(triangles.len() - 1)];
+            let s = rng.unit().sqrt();
+            let r2 = rng.unit();
+            let weights = [1.0 - s, s * (1.0 - r2), s * r2];
+            let mut v = (0..defs.len())
+                .map(|p| t.iter().zip(weights.iter()).map(|(&i, w)| values[i][p] * w).sum::<f64>())
+                .collect::<Vec<_>>();
+            if let Some(ref n) = normals {
+                let length = n.iter().map(|&p| v[p] * v[p]).sum::<f64>().sqrt();
+                if length > 0.0 {
+                    for &p in n {
+                        v[p] /= length;
+                    }
+                }
+            }
+            let mut point = E::new();
+            for (d, &x) in defs.iter().zip(v.iter()) {
+                set_scalar_from_f64(&mut point, d, x);
+            }
+            points.push(point);
+        }
+
+        let mut ply = Ply::<E>::new();
+        ply.header.encoding = self.header.encoding;
+        ply.header.version = self.header.version;
+        ply.header.comments = self.header.comments.clone();
+        ply.header.obj_infos = self.header.obj_infos.clone();
+        ply.header.elements.add(sampled_def);
+        ply.payload.insert("vertex".to_string(), points);
+        Ok(ply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    /// Unit square at z = 0 as one quad, plus a degenerate triangle.
+    fn square() -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut properties = ["x", "y", "z", "nx", "ny", "nz"].iter().map(|&p| (p, PropertyType::Scalar(ScalarType::Float))).collect::<Vec<_>>();
+        properties.push(("red", PropertyType::Scalar(ScalarType::UChar)));
+        let vertices = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]].iter().map(|p: &[f32; 2]| {
+            let mut v = [p[0], p[1], 0.0, p[0], 0.0, 1.0].iter().map(|&x| Property::Float(x)).collect::<Vec<_>>();
+            v.push(Property::UChar((p[0] * 200.0) as u8));
+            v
+        }).collect();
+        add_test_element(&mut ply, "vertex", &properties, vertices);
+        let faces = [vec![0, 1, 2, 3], vec![0, 0, 1]].iter().map(|l| vec![Property::ListInt(l.clone())]).collect();
+        add_test_element(&mut ply, "face", &[("vertex_index", PropertyType::List(ScalarType::UChar, ScalarType::Int))], faces);
+        ply
+    }
+    fn float(p: &Property) -> f32 {
+        match *p {
+            Property::Float(v) => v,
+            _ => panic!("Unexpected property {:?}", p),
+        }
+    }
+    #[test]
+    fn sample_surface_ok() {
+        let ply = square();
+        let points = ply.sample_surface(1000).unwrap();
+        assert_eq!(points.header.elements.len(), 1);
+        assert_eq!(points.header.elements["vertex"].count, 1000);
+        let mut left = 0;
+        for p in &points.payload["vertex"] {
+            let (x, y) = (float(&p["x"]), float(&p["y"]));
+            assert!((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y));
+            assert_eq!(float(&p["z"]), 0.0);
+            let n = [float(&p["nx"]), float(&p["ny"]), float(&p["nz"])];
+            assert!(((n[0] * n[0] + n[1] * n[1] + n[2] * n[2]) - 1.0).abs() < 1e-5);
+            match p["red"] {
+                Property::UChar(r) => assert!((f32::from(r) - x * 200.0).abs() <= 1.0),
+                _ => panic!(),
+            }
+            if x < 0.5 {
+                left += 1;
+            }
+        }
+        // Both triangles of the quad are sampled in proportion to their area.
+        assert!((400..600).contains(&left), "{} points on the left", left);
+        assert_eq!(ply.sample_surface(1000).unwrap(), points);
+    }
+    #[test]
+    fn sample_surface_no_area() {
+        let mut ply = square();
+        ply.payload.get_mut("face").unwrap().remove(0);
+        assert!(ply.sample_surface(1).is_err());
+        assert!(ply.sample_surface(0).unwrap().payload["vertex"].is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+//! Sharing identical schemas between many PLY files.
+
+use std::collections::HashSet;
+use std::sync::{ Arc, Mutex };
+
+use super::{ ElementDef, Header };
+
+/// Interns schemas, so files with identical schemas share one allocation.
+///
+/// The schema of a header consists of its encoding, version and element definitions,
+/// element counts, comments and object information are per file and not part of it.
+/// Interned schemas can be compared with `Arc::ptr_eq()` instead of comparing all properties.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// use std::sync::Arc;
+/// let registry = ply::SchemaRegistry::new();
+/// let p = parser::Parser::<ply::DefaultElement>::new();
+/// let mut schemas = Vec::new();
+/// let paths = ["example_plys/house_2_ok_ascii.ply", "example_plys/house_2_ok_ascii.ply", "example_plys/house_ok_ascii.ply"];
+/// for path in &paths {
+///     let mut f = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+///     let header = p.read_header(&mut f).unwrap();
+///     schemas.push(registry.intern(&header));
+/// }
+/// assert_eq!(registry.len(), 2);
+/// assert!(Arc::ptr_eq(&schemas[0], &schemas[1]));
+/// assert!(!Arc::ptr_eq(&schemas[0], &schemas[2]));
+/// ```
+#[derive(Debug, Default)]
+pub struct SchemaRegistry {
+    headers: Mutex<HashSet<Arc<Header>>>,
+    elements: Mutex<HashSet<Arc<ElementDef>>>,
+}
+
+/// Inserts `value` unless an equal value is present and returns the shared instance.
+fn intern<T: Eq + std::hash::Hash>(set: &Mutex<HashSet<Arc<T>>>, value: T) -> Arc<T> {
+    let mut set = set.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(existing) = set.get(&value) {
+        return existing.clone();
+    }
+    let value = Arc::new(value);
+    set.insert(value.clone());
+    value
+}
+
+impl SchemaRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        SchemaRegistry::default()
+    }
+    /// Returns the shared schema of `header`: a header with all element counts set to zero, without comments and object information.
+    pub fn intern(&self, header: &Header) -> Arc<Header> {
+        let mut schema = Header::new();
+        schema.encoding = header.encoding;
+        schema.version = header.version;
+        schema.elements = header.elements.iter().map(|(k, e)| {
+            let mut e = e.clone();
+            e.count = 0;
+            (k.clone(), e)
+        }).collect();
+        intern(&self.headers, schema)
+    }
+    /// Returns the shared schema of a single element, its definition with `count` set to zero.
+    pub fn intern_element(&self, element_def: &ElementDef) -> Arc<ElementDef> {
+        let mut schema = element_def.clone();
+        schema.count = 0;
+        intern(&self.elements, schema)
+    }
+    /// Number of distinct header schemas.
+    pub fn len(&self) -> usize {
+        self.headers.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+    /// True if no header schema has been interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use std::sync::Arc;
+    #[test]
+    fn intern_ok() {
+        let registry = SchemaRegistry::new();
+        let mut a = Header::new();
+        let mut e = ElementDef::new("vertex".to_string());
+        e.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)));
+        e.count = 3;
+        a.elements.add(e.clone());
+        let mut b = a.clone();
+        b.comments.push("other file".to_string());
+        b.elements.get_mut("vertex").unwrap().count = 7;
+        assert!(Arc::ptr_eq(&registry.intern(&a), &registry.intern(&b)));
+        b.encoding = Encoding::BinaryLittleEndian;
+        assert!(!Arc::ptr_eq(&registry.intern(&a), &registry.intern(&b)));
+        assert_eq!(registry.len(), 2);
+        let mut f = e.clone();
+        f.count = 1;
+        assert!(Arc::ptr_eq(&registry.intern_element(&e), &registry.intern_element(&f)));
+        assert_eq!(registry.intern_element(&e).count, 0);
+    }
+}
@@ -0,0 +1,426 @@
+//! Mesh decimation by edge collapse with the quadric error metric of Garland and Heckbert.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::filter::{ face_index_def, map_faces };
+use super::{ Ply, PropertyAccess, Removed };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64, set_list_from_f64, set_scalar_from_f64 };
+
+/// Weight of the planes keeping open boundaries in place, relative to the faces.
+const BOUNDARY_WEIGHT: f64 = 10.0;
+
+/// Symmetric 4x4 matrix `[a², ab, ac, ad, b², bc, bd, c², cd, d²]` of the squared distance to a set of planes.
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Squared distance to the plane `n·p + d = 0`, scaled by `weight`.
+    fn plane(n: [f64; 3], d: f64, weight: f64) -> Self {
+        let [a, b, c] = n;
+        Quadric([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d].map(|v| v * weight))
+    }
+    fn add(&mut self, o: &Quadric) {
+        for (a, b) in self.0.iter_mut().zip(o.0.iter()) {
+            *a += b;
+        }
+    }
+    fn error(&self, p: [f64; 3]) -> f64 {
+        let q = &self.0;
+        let [x, y, z] = p;
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z + q[9]
+    }
+    /// Position of minimal error, `None` if not unique.
+    fn minimum(&self) -> Option<[f64; 3]> {
+        let q = &self.0;
+        let m = [[q[0], q[1], q[2]], [q[1], q[4], q[5]], [q[2], q[5], q[7]]];
+        let b = [-q[3], -q[6], -q[8]];
+        let det = dot(m[0], cross(m[1], m[2]));
+        let scale = (q[0] + q[4] + q[7]).powi(3);
+        if det.abs() <= 1e-9 * scale || scale == 0.0 {
+            return None;
+        }
+        // Cramer's rule, `m` is symmetric.
+        let x = dot(b, cross(m[1], m[2])) / det;
+        let y = dot(m[0], cross(b, m[2])) / det;
+        let z = dot(m[0], cross(m[1], b)) / det;
+        Some([x, y, z])
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+fn normal(p: [[f64; 3]; 3]) -> [f64; 3] {
+    cross(sub(p[1], p[0]), sub(p[2], p[0]))
+}
+
+/// Collapse of the edge `u`, `v` into `u` at `position`, valid while neither vertex changed.
+struct Candidate {
+    cost: f64,
+    u: usize,
+    v: usize,
+    versions: (usize, usize),
+    position: [f64; 3],
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Candidate {}
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Candidate {
+    /// Reversed, such that `BinaryHeap` pops the cheapest collapse first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then((other.u, other.v).cmp(&(self.u, self.v)))
+    }
+}
+
+struct Mesh {
+    positions: Vec<[f64; 3]>,
+    attributes: Vec<Vec<f64>>,
+    quadrics: Vec<Quadric>,
+    /// Merged vertices point to the vertex they were collapsed into.
+    merged: Vec<Option<usize>>,
+    versions: Vec<usize>,
+    triangles: Vec<[usize; 3]>,
+    face_alive: Vec<bool>,
+    /// Faces around each vertex, may contain removed faces.
+    vertex_faces: Vec<Vec<usize>>,
+}
+
+impl Mesh {
+    fn neighbors(&self, v: usize) -> Vec<usize> {
+        let mut n = self.vertex_faces[v].iter()
+            .filter(|&&f| self.face_alive[f])
+            .flat_map(|&f| self.triangles[f].iter().cloned())
+            .filter(|&w| w != v)
+            .collect::<Vec<_>>();
+        n.sort_unstable();
+        n.dedup();
+        n
+    }
+    fn candidate(&self, u: usize, v: usize) -> Candidate {
+        let mut q = self.quadrics[u];
+        q.add(&self.quadrics[v]);
+        let (pu, pv) = (self.positions[u], self.positions[v]);
+        let mid = [(pu[0] + pv[0]) / 2.0, (pu[1] + pv[1]) / 2.0, (pu[2] + pv[2]) / 2.0];
+        let position = match q.minimum() {
+            Some(p) => p,
+            None => *[pu, pv, mid].iter().min_by(|a, b| q.error(**a).total_cmp(&q.error(**b))).unwrap(),
+        };
+        Candidate { cost: q.error(position), u, v, versions: (self.versions[u], self.versions[v]), position }
+    }
+    /// True if the collapse keeps the mesh manifold and flips no face.
+    fn is_valid(&self, c: &Candidate) -> bool {
+        let nu = self.neighbors(c.u);
+        let shared = self.neighbors(c.v).iter().filter(|w| nu.binary_search(w).is_ok()).count();
+        if shared > 2 {
+            return false;
+        }
+        for &f in self.vertex_faces[c.u].iter().chain(self.vertex_faces[c.v].iter()) {
+            let t = self.triangles[f];
+            if !self.face_alive[f] || (t.contains(&c.u) && t.contains(&c.v)) {
+                continue;
+            }
+            let before = t.map(|i| self.positions[i]);
+            let after = t.map(|i| if i == c.u || i == c.v { c.position } else { self.positions[i] });
+            if dot(normal(before), normal(after)) <= 0.0 {
+                return false;
+            }
+        }
+        true
+    }
+    /// Merges `c.v` into `c.u`, returns the number of removed faces.
+    fn collapse(&mut self, c: &Candidate) -> usize {
+        let (u, v) = (c.u, c.v);
+        let mut removed = 0;
+        for f in std::mem::take(&mut self.vertex_faces[v]) {
+            if !self.face_alive[f] {
+                continue;
+            }
+            if self.triangles[f].contains(&u) {
+                self.face_alive[f] = false;
+                removed += 1;
+            } else {
+                for i in self.triangles[f].iter_mut().filter(|i| **i == v) {
+                    *i = u;
+                }
+                self.vertex_faces[u].push(f);
+            }
+        }
+        let alive = &self.face_alive;
+        self.vertex_faces[u].retain(|&f| alive[f]);
+
+        // Attributes are interpolated at the projection of the new position onto the edge.
+        let (pu, pv) = (self.positions[u], self.positions[v]);
+        let edge = sub(pv, pu);
+        let length = dot(edge, edge);
+        let t = if length > 0.0 { (dot(sub(c.position, pu), edge) / length).clamp(0.0, 1.0) } else { 0.5 };
+        let av = std::mem::take(&mut self.attributes[v]);
+        for (a, b) in self.attributes[u].iter_mut().zip(av) {
+            *a += (b - *a) * t;
+        }
+        self.positions[u] = c.position;
+        let qv = self.quadrics[v];
+        self.quadrics[u].add(&qv);
+        self.merged[v] = Some(u);
+        self.versions[u] += 1;
+        removed
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Reduces the number of faces to about `target_ratio` times the current count.
+    ///
+    /// Edges are collapsed in order of increasing quadric error, the merged vertex is moved
+    /// to the position of least error. The scalar vertex properties named in `attributes`,
+    /// e.g. colors or normals, are interpolated along the collapsed edge.
+    /// Other properties keep the values of one of the merged vertices.
+    /// Open boundaries are kept in place, collapses flipping a face are skipped.
+    ///
+    /// All faces must be triangles. Returns the number of removed vertices and faces.
+    pub fn simplify(&mut self, target_ratio: f64, attributes: &[&str]) -> Result<Removed> {
+        let vertex = element_def(self, "vertex")?;
+        let position_defs = property_defs(vertex, &["x", "y", "z"])?;
+        let attribute_names = attributes.iter().cloned().filter(|n| !["x", "y", "z"].contains(n)).collect::<Vec<_>>();
+        let attribute_defs = property_defs(vertex, &attribute_names)?;
+        let def = match face_index_def(self)? {
+            Some(d) => d,
+            None => return Ok(Removed::default()),
+        };
+        let vertices = self.payload.get("vertex").map(|l| &l[..]).unwrap_or_default();
+        let vertex_count = vertices.len();
+        let read = |defs: &[_]| vertices.iter().enumerate()
+            .map(|(row, v)| defs.iter().map(|d| require_scalar_as_f64(v, d, "vertex", row)).collect::<Result<Vec<_>>>())
+            .collect::<Result<Vec<_>>>();
+        let positions = read(&position_defs)?.into_iter().map(|p| [p[0], p[1], p[2]]).collect::<Vec<_>>();
+        let attribute_values = read(&attribute_defs)?;
+        let mut triangles = Vec::new();
+        for (row, face) in map_faces(self, &def, |i| if i < vertex_count { Some(i) } else { None })?.into_iter().enumerate() {
+            match face {
+                Some(l) if l.len() == 3 => triangles.push([l[0], l[1], l[2]]),
+                Some(l) => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Element `face` at index {} has {} vertices, only triangles can be simplified.", row, l.len())
+                )),
+                None => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Element `face` at index {} references a vertex out of range.", row)
+                )),
+            }
+        }
+
+        let mut mesh = Mesh {
+            quadrics: vec![Quadric::default(); vertex_count],
+            merged: vec![None; vertex_count],
+            versions: vec![0; vertex_count],
+            face_alive: triangles.iter().map(|t| t[0] != t[1] && t[1] != t[2] && t[2] != t[0]).collect(),
+            vertex_faces: vec![Vec::new(); vertex_count],
+            positions,
+            attributes: attribute_values,
+            triangles,
+        };
+        let mut edges = Vec::new();
+        for (f, t) in mesh.triangles.iter().enumerate() {
+            if !mesh.face_alive[f] {
+                continue;
+            }
+            let n = normal(t.map(|i| mesh.positions[i]));
+            let area2 = dot(n, n).sqrt();
+            if area2 > 0.0 {
+                let n = n.map(|c| c / area2);
+                let q = Quadric::plane(n, -dot(n, mesh.positions[t[0]]), area2 / 2.0);
+                for &i in t.iter() {
+                    mesh.quadrics[i].add(&q);
+                }
+            }
+            for k in 0..3 {
+                mesh.vertex_faces[t[k]].push(f);
+                let (a, b) = (t[k], t[(k + 1) % 3]);
+                edges.push((a.min(b), a.max(b), f, n));
+            }
+        }
+        // Edges with a single face are boundaries, constrained by a plane perpendicular to the face.
+        edges.sort_unstable_by_key(|e| (e.0, e.1));
+        for (i, &(a, b, _, n)) in edges.iter().enumerate() {
+            let shared = |j: usize| edges.get(j).is_some_and(|e| (e.0, e.1) == (a, b));
+            if (i > 0 && shared(i - 1)) || shared(i + 1) {
+                continue;
+            }
+            let e = sub(mesh.positions[b], mesh.positions[a]);
+            let p = cross(e, n);
+            let length = dot(p, p).sqrt();
+            if length > 0.0 {
+                let p = p.map(|c| c / length);
+                let q = Quadric::plane(p, -dot(p, mesh.positions[a]), BOUNDARY_WEIGHT * dot(e, e));
+                mesh.quadrics[a].add(&q);
+                mesh.quadrics[b].add(&q);
+            }
+        }
+        edges.dedup_by_key(|e| (e.0, e.1));
+        let original = mesh.triangles.clone();
+        let mut heap = edges.iter().map(|&(a, b, _, _)| mesh.candidate(a, b)).collect::<BinaryHeap<_>>();
+
+        let mut faces = mesh.face_alive.iter().filter(|&&a| a).count();
+        let target = (faces as f64 * target_ratio.clamp(0.0, 1.0)).ceil() as usize;
+        while faces > target {
+            let c = match heap.pop() {
+                Some(c) => c,
+                None => break,
+            };
+            if mesh.merged[c.u].is_some() || mesh.merged[c.v].is_some()
+                    || (mesh.versions[c.u], mesh.versions[c.v]) != c.versions || !mesh.is_valid(&c) {
+                continue;
+            }
+            faces -= mesh.collapse(&c);
+            for w in mesh.neighbors(c.u) {
+                heap.push(mesh.candidate(c.u, w));
+            }
+        }
+
+        // Faces reference the surviving vertices by their old index, `retain_vertices()` renumbers.
+        // Only modified elements are written.
+        let mut removed_faces = 0;
+        if let Some(list) = self.payload.get_mut("face") {
+            let mut alive = mesh.face_alive.iter();
+            let mut triangles = mesh.triangles.iter().zip(original.iter());
+            list.retain_mut(|f| {
+                let (t, o) = triangles.next().unwrap();
+                if *alive.next().unwrap() {
+                    if t != o {
+                        set_list_from_f64(f, &def, &t.map(|i| i as f64));
+                    }
+                    true
+                } else {
+                    removed_faces += 1;
+                    false
+                }
+            });
+            let count = list.len();
+            self.header.elements.get_mut("face").unwrap().count = count;
+        }
+        if let Some(list) = self.payload.get_mut("vertex") {
+            for (i, v) in list.iter_mut().enumerate().filter(|(i, _)| mesh.merged[*i].is_none() && mesh.versions[*i] > 0) {
+                for (d, &p) in position_defs.iter().zip(mesh.positions[i].iter()) {
+                    set_scalar_from_f64(v, d, p);
+                }
+                for (d, &a) in attribute_defs.iter().zip(mesh.attributes[i].iter()) {
+                    set_scalar_from_f64(v, d, a);
+                }
+            }
+        }
+        let removed = self.retain_vertices(|i, _| mesh.merged[i].is_none())?;
+        Ok(Removed { vertices: removed.vertices, faces: removed_faces + removed.faces })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    /// Flat `n` x `n` grid of triangles with a red/blue gradient along x.
+    fn grid(n: usize) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut vertex = ElementDef::new("vertex".to_string());
+        for p in &["x", "y", "z"] {
+            vertex.properties.add(PropertyDef::new(p.to_string(), PropertyType::Scalar(ScalarType::Float)));
+        }
+        vertex.properties.add(PropertyDef::new("red".to_string(), PropertyType::Scalar(ScalarType::UChar)));
+        vertex.count = (n + 1) * (n + 1);
+        ply.header.elements.add(vertex);
+        let mut face = ElementDef::new("face".to_string());
+        face.properties.add(PropertyDef::new("vertex_indices".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int)));
+        face.count = 2 * n * n;
+        ply.header.elements.add(face);
+        let mut vertices = Vec::new();
+        for y in 0..=n {
+            for x in 0..=n {
+                let mut v = DefaultElement::new();
+                v.insert("x".to_string(), Property::Float(x as f32));
+                v.insert("y".to_string(), Property::Float(y as f32));
+                v.insert("z".to_string(), Property::Float(0.0));
+                v.insert("red".to_string(), Property::UChar((x * 255 / n) as u8));
+                vertices.push(v);
+            }
+        }
+        ply.payload.insert("vertex".to_string(), vertices);
+        let mut faces = Vec::new();
+        let w = (n + 1) as i32;
+        for y in 0..n as i32 {
+            for x in 0..n as i32 {
+                let i = y * w + x;
+                for l in &[vec![i, i + 1, i + w + 1], vec![i, i + w + 1, i + w]] {
+                    let mut f = DefaultElement::new();
+                    f.insert("vertex_indices".to_string(), Property::ListInt(l.clone()));
+                    faces.push(f);
+                }
+            }
+        }
+        ply.payload.insert("face".to_string(), faces);
+        ply
+    }
+    fn float(p: &Property) -> f32 {
+        match *p {
+            Property::Float(v) => v,
+            _ => panic!("Unexpected property {:?}", p),
+        }
+    }
+    #[test]
+    fn simplify_plane() {
+        let mut ply = grid(8);
+        let removed = ply.simplify(0.25, &["red"]).unwrap();
+        let faces = ply.payload["face"].len();
+        assert!(faces <= 32, "{} faces left", faces);
+        assert_eq!(removed.faces, 128 - faces);
+        assert_eq!(ply.header.elements["vertex"].count, 81 - removed.vertices);
+        let vertices = ply.payload["vertex"].len() as i32;
+        for f in &ply.payload["face"] {
+            match f["vertex_indices"] {
+                Property::ListInt(ref l) => assert!(l.iter().all(|&i| i >= 0 && i < vertices)),
+                _ => panic!(),
+            }
+        }
+        let mut corners = 0;
+        for v in &ply.payload["vertex"] {
+            let (x, y) = (float(&v["x"]), float(&v["y"]));
+            // Vertices stay in the plane and inside the boundary, the gradient follows them.
+            assert_eq!(float(&v["z"]), 0.0);
+            assert!((0.0..=8.0).contains(&x) && (0.0..=8.0).contains(&y));
+            match v["red"] {
+                Property::UChar(r) => assert!((f32::from(r) - x * 255.0 / 8.0).abs() <= 1.0, "red {} at x {}", r, x),
+                _ => panic!(),
+            }
+            if (x == 0.0 || x == 8.0) && (y == 0.0 || y == 8.0) {
+                corners += 1;
+            }
+        }
+        assert_eq!(corners, 4);
+    }
+    #[test]
+    fn simplify_keep_all() {
+        let mut ply = grid(2);
+        let before = ply.clone();
+        assert_eq!(ply.simplify(1.0, &[]).unwrap(), Removed::default());
+        assert_eq!(ply, before);
+    }
+    #[test]
+    fn simplify_missing_attribute() {
+        assert!(grid(2).simplify(0.5, &["blue"]).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Handling of per vertex timestamps, as recorded by mobile LiDAR scanners.
+
+use std::cmp::Ordering;
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::filter::{ face_index_def, map_faces };
+use super::{ Ply, PropertyAccess, PropertyDef, Removed };
+use crate::util::{ element_def, require_scalar_as_f64, set_list_from_f64 };
+
+/// Names of the vertex timestamp property, in order of preference.
+pub const TIME_NAMES: [&str; 2] = ["time", "gps_time"];
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Definition of the `time` or `gps_time` property of `vertex`.
+    pub fn time_property(&self) -> Result<PropertyDef> {
+        let vertex = element_def(self, "vertex")?;
+        match TIME_NAMES.iter().find_map(|n| vertex.properties.get(*n)) {
+            Some(p) => Ok(p.clone()),
+            None => Err(io::Error::new(ErrorKind::InvalidInput, "Element `vertex` has neither `time` nor `gps_time`.")),
+        }
+    }
+    /// Timestamps of all vertices.
+    fn vertex_times(&self) -> Result<Vec<f64>> {
+        let def = self.time_property()?;
+        let vertices = self.payload.get("vertex").map(|l| &l[..]).unwrap_or_default();
+        vertices.iter().enumerate().map(|(row, v)| require_scalar_as_f64(v, &def, "vertex", row)).collect()
+    }
+    /// Sorts the vertices by increasing timestamp, keeping the order of equal timestamps.
+    ///
+    /// Vertices with a NaN timestamp are moved to the end. Faces are renumbered.
+    pub fn sort_by_time(&mut self) -> Result<()> {
+        let times = self.vertex_times()?;
+        let mut order = (0..times.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| times[a].partial_cmp(&times[b]).unwrap_or_else(|| times[a].is_nan().cmp(&times[b].is_nan())));
+        let mut new_index = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+        let faces = match face_index_def(self)? {
+            Some(def) => Some((map_faces(self, &def, |i| new_index.get(i).map(|&n| n as f64))?, def)),
+            None => None,
+        };
+
+        if let Some(vertices) = self.payload.get_mut("vertex") {
+            let mut old = vertices.drain(..).map(Some).collect::<Vec<_>>();
+            vertices.extend(order.iter().map(|&i| old[i].take().unwrap()));
+        }
+        if let Some((indices, def)) = faces {
+            for (f, i) in self.payload.get_mut("face").unwrap().iter_mut().zip(indices) {
+                // Indices out of range are kept as they are for the consistency check to report.
+                if let Some(i) = i {
+                    set_list_from_f64(f, &def, &i);
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Keeps only vertices with `start <= time < end`, see `retain_vertices()`.
+    pub fn slice_by_time(&mut self, start: f64, end: f64) -> Result<Removed> {
+        let times = self.vertex_times()?;
+        self.retain_vertices(|i, _| start <= times[i] && times[i] < end)
+    }
+}
+
+impl<E: PropertyAccess + Clone> Ply<E> {
+    /// Splits the vertices into chunks of `duration`, starting at the earliest timestamp.
+    ///
+    /// Returns the start time and contents of all non-empty chunks in temporal order, see `into_parts()`.
+    /// Vertices with a NaN timestamp are dropped.
+    pub fn into_time_chunks(self, duration: f64) -> Result<Vec<(f64, Ply<E>)>> {
+        if duration.partial_cmp(&0.0) != Some(Ordering::Greater) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Invalid chunk duration {}.", duration)));
+        }
+        let times = self.vertex_times()?;
+        let start = times.iter().cloned().filter(|t| !t.is_nan()).fold(f64::INFINITY, f64::min);
+        let chunks = times.iter().map(|&t| if t.is_nan() { None } else { Some(((t - start) / duration).floor() as usize) }).collect::<Vec<_>>();
+        // Compacts the chunk indices to skip empty chunks.
+        let mut used = chunks.iter().filter_map(|c| *c).collect::<Vec<_>>();
+        used.sort_unstable();
+        used.dedup();
+        let parts = chunks.iter().map(|c| c.map(|c| used.binary_search(&c).unwrap())).collect::<Vec<_>>();
+        let plys = self.into_parts("vertex", &parts)?;
+        Ok(used.iter().map(|&c| start + c as f64 * duration).zip(plys).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    fn track() -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let vertices = [3.5, 0.5, 1.0, 3.0].iter().map(|&t| vec![Property::Double(t)]).collect();
+        add_test_element(&mut ply, "vertex", &[("gps_time", PropertyType::Scalar(ScalarType::Double))], vertices);
+        let faces = vec![vec![Property::ListInt(vec![0, 3, 3])], vec![Property::ListInt(vec![1, 2, 0])]];
+        add_test_element(&mut ply, "face", &[("vertex_index", PropertyType::List(ScalarType::UChar, ScalarType::Int))], faces);
+        ply
+    }
+    fn times(ply: &Ply<DefaultElement>) -> Vec<Property> {
+        ply.payload["vertex"].iter().map(|v| v["gps_time"].clone()).collect()
+    }
+    #[test]
+    fn sort_by_time_ok() {
+        let mut ply = track();
+        ply.sort_by_time().unwrap();
+        assert_eq!(times(&ply), vec![Property::Double(0.5), Property::Double(1.0), Property::Double(3.0), Property::Double(3.5)]);
+        assert_eq!(ply.payload["face"][0]["vertex_index"], Property::ListInt(vec![3, 2, 2]));
+        assert_eq!(ply.payload["face"][1]["vertex_index"], Property::ListInt(vec![0, 1, 3]));
+    }
+    #[test]
+    fn slice_by_time_ok() {
+        let mut ply = track();
+        assert_eq!(ply.slice_by_time(2.0, 4.0).unwrap(), Removed { vertices: 2, faces: 1 });
+        assert_eq!(times(&ply), vec![Property::Double(3.5), Property::Double(3.0)]);
+        assert_eq!(ply.payload["face"][0]["vertex_index"], Property::ListInt(vec![0, 1, 1]));
+    }
+    #[test]
+    fn into_time_chunks_ok() {
+        let chunks = track().into_time_chunks(2.0).unwrap();
+        assert_eq!(chunks.iter().map(|c| c.0).collect::<Vec<_>>(), vec![0.5, 2.5]);
+        assert_eq!(times(&chunks[0].1), vec![Property::Double(0.5), Property::Double(1.0)]);
+        assert_eq!(chunks[0].1.header.elements["vertex"].count, 2);
+        assert!(chunks[0].1.payload["face"].is_empty());
+        assert_eq!(chunks[1].1.payload["face"][0]["vertex_index"], Property::ListInt(vec![0, 1, 1]));
+        assert!(track().into_time_chunks(0.0).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! Unit metadata of properties, stored as `comment unit <element>.<property> <unit>`.
+
+use std::fmt;
+use std::fmt::{ Display, Formatter };
+use std::io;
+use std::io::{ ErrorKind, Result };
+use std::str::FromStr;
+
+use super::{ Header, Ply, PropertyAccess, PropertyType };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64, set_scalar_from_f64 };
+
+/// Keyword starting a unit comment.
+pub const UNIT_COMMENT_PREFIX: &str = "unit";
+
+/// Physical unit of a property.
+///
+/// Length units can be converted into each other, see `Ply::convert_unit()`.
+/// Any other unit is kept verbatim as `Unit::Other`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Unit {
+    /// `km`
+    Kilometer,
+    /// `m`
+    Meter,
+    /// `cm`
+    Centimeter,
+    /// `mm`
+    Millimeter,
+    /// `um`
+    Micrometer,
+    /// `in`
+    Inch,
+    /// `ft`
+    Foot,
+    /// Any other unit, e.g. `s` or `deg`.
+    Other(String),
+}
+
+impl Unit {
+    /// Length of one unit in meters, `None` for units that aren't lengths.
+    pub fn meters(&self) -> Option<f64> {
+        match *self {
+            Unit::Kilometer => Some(1000.0),
+            Unit::Meter => Some(1.0),
+            Unit::Centimeter => Some(0.01),
+            Unit::Millimeter => Some(0.001),
+            Unit::Micrometer => Some(1e-6),
+            Unit::Inch => Some(0.0254),
+            Unit::Foot => Some(0.3048),
+            Unit::Other(_) => None,
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = std::convert::Infallible;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "km" => Unit::Kilometer,
+            "m" => Unit::Meter,
+            "cm" => Unit::Centimeter,
+            "mm" => Unit::Millimeter,
+            "um" => Unit::Micrometer,
+            "in" => Unit::Inch,
+            "ft" => Unit::Foot,
+            other => Unit::Other(other.to_string()),
+        })
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter) -> std::result::Result<(), fmt::Error> {
+        f.write_str(
+            match *self {
+                Unit::Kilometer => "km",
+                Unit::Meter => "m",
+                Unit::Centimeter => "cm",
+                Unit::Millimeter => "mm",
+                Unit::Micrometer => "um",
+                Unit::Inch => "in",
+                Unit::Foot => "ft",
+                Unit::Other(ref s) => s,
+            }
+        )
+    }
+}
+
+/// Splits `unit <element>.<property> <unit>` into its parts.
+fn parse_unit_comment(comment: &str) -> Option<(&str, &str, &str)> {
+    let mut tokens = comment.split_whitespace();
+    if tokens.next() != Some(UNIT_COMMENT_PREFIX) {
+        return None;
+    }
+    let (element, property) = tokens.next()?.split_once('.')?;
+    let unit = tokens.next()?;
+    if tokens.next().is_some() {
+        return None;
+    }
+    Some((element, property, unit))
+}
+
+impl Header {
+    /// Unit of `property` of `element` as declared by a comment `unit <element>.<property> <unit>`.
+    pub fn unit(&self, element: &str, property: &str) -> Option<Unit> {
+        self.comments.iter()
+            .filter_map(|c| parse_unit_comment(c))
+            .find(|&(e, p, _)| e == element && p == property)
+            .map(|(_, _, u)| u.parse().unwrap())
+    }
+    /// Declares the unit of `property` of `element`, replaces a previous declaration.
+    pub fn set_unit(&mut self, element: &str, property: &str, unit: Unit) {
+        let comment = format!("{} {}.{} {}", UNIT_COMMENT_PREFIX, element, property, unit);
+        let existing = self.comments.iter().position(|c| match parse_unit_comment(c) {
+            Some((e, p, _)) => e == element && p == property,
+            None => false,
+        });
+        match existing {
+            Some(i) => self.comments[i] = comment,
+            None => self.comments.push(comment),
+        }
+    }
+    /// Removes the unit declaration of `property` of `element` and returns the removed unit.
+    pub fn remove_unit(&mut self, element: &str, property: &str) -> Option<Unit> {
+        let unit = self.unit(element, property)?;
+        self.comments.retain(|c| match parse_unit_comment(c) {
+            Some((e, p, _)) => e != element || p != property,
+            None => true,
+        });
+        Some(unit)
+    }
+}
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Converts all values of a scalar `property` of `element` from its declared length unit to `unit`
+    /// and updates the declaration.
+    ///
+    /// Integer properties are rounded to the nearest representable value.
+    /// Fails if no unit is declared or if one of the units isn't a length.
+    pub fn convert_unit(&mut self, element: &str, property: &str, unit: Unit) -> Result<()> {
+        let from = match self.header.unit(element, property) {
+            Some(u) => u,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, format!("No unit declared for `{}.{}`.", element, property))),
+        };
+        let factor = match (from.meters(), unit.meters()) {
+            (Some(f), Some(t)) => f / t,
+            _ => return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Can't convert `{}.{}` from `{}` to `{}`.", element, property, from, unit)
+            )),
+        };
+        let def = property_defs(element_def(self, element)?, &[property])?.remove(0);
+        if let PropertyType::List(_, _) = def.data_type {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Property `{}.{}` is a list.", element, property)));
+        }
+        if let Some(list) = self.payload.get_mut(element) {
+            for (row, e) in list.iter_mut().enumerate() {
+                let v = require_scalar_as_f64(e, &def, element, row)?;
+                set_scalar_from_f64(e, &def, v * factor);
+            }
+        }
+        self.header.set_unit(element, property, unit);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use crate::util::add_test_element;
+    #[test]
+    fn unit_get_set_ok() {
+        let mut h = Header::new();
+        h.comments.push("some comment".to_string());
+        assert_eq!(h.unit("vertex", "x"), None);
+        h.set_unit("vertex", "x", Unit::Millimeter);
+        h.set_unit("vertex", "t", "s".parse().unwrap());
+        assert_eq!(h.comments[1], "unit vertex.x mm");
+        assert_eq!(h.unit("vertex", "x"), Some(Unit::Millimeter));
+        assert_eq!(h.unit("vertex", "t"), Some(Unit::Other("s".to_string())));
+        h.set_unit("vertex", "x", Unit::Meter);
+        assert_eq!(h.comments.len(), 3);
+        assert_eq!(h.remove_unit("vertex", "x"), Some(Unit::Meter));
+        assert_eq!(h.unit("vertex", "x"), None);
+        assert_eq!(h.comments.len(), 2);
+    }
+    #[test]
+    fn convert_unit_ok() {
+        let mut ply = Ply::<DefaultElement>::new();
+        let properties = [("x", PropertyType::Scalar(ScalarType::Double)), ("y", PropertyType::Scalar(ScalarType::Int))];
+        add_test_element(&mut ply, "vertex", &properties, vec![vec![Property::Double(1500.0), Property::Int(2)]]);
+        ply.header.set_unit("vertex", "x", Unit::Millimeter);
+        ply.header.set_unit("vertex", "y", Unit::Meter);
+
+        ply.convert_unit("vertex", "x", Unit::Meter).unwrap();
+        assert_eq!(ply.payload["vertex"][0]["x"], Property::Double(1.5));
+        assert_eq!(ply.header.unit("vertex", "x"), Some(Unit::Meter));
+        ply.convert_unit("vertex", "y", Unit::Millimeter).unwrap();
+        assert_eq!(ply.payload["vertex"][0]["y"], Property::Int(2000));
+        assert!(ply.convert_unit("vertex", "x", Unit::Other("s".to_string())).is_err());
+        ply.header.remove_unit("vertex", "x");
+        assert!(ply.convert_unit("vertex", "x", Unit::Meter).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Reordering of triangle meshes for real-time rendering, using meshoptimizer.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ Ply, PropertyAccess };
+use super::filter::{ face_index_def, map_faces };
+use crate::util::{ element_def, set_list_from_f64 };
+
+impl<E: PropertyAccess> Ply<E> {
+    /// Reorders faces for the post-transform vertex cache and vertices in order of first use.
+    ///
+    /// Call before writing meshes meant for GPU rendering.
+    /// All faces must be triangles, their vertex indices are renumbered
+    /// and their other properties move along.
+    /// Vertices not referenced by any face are kept after all referenced ones.
+    pub fn optimize_vertex_cache(&mut self) -> Result<()> {
+        element_def(self, "vertex")?;
+        let def = match face_index_def(self)? {
+            Some(d) => d,
+            None => return Ok(()),
+        };
+        let vertex_count = self.payload.get("vertex").map_or(0, |l| l.len());
+        let faces = map_faces(self, &def, |i| if i < vertex_count { Some(i as u32) } else { None })?;
+        let mut indices = Vec::with_capacity(faces.len() * 3);
+        for (row, face) in faces.iter().enumerate() {
+            match face {
+                Some(l) if l.len() == 3 => indices.extend_from_slice(l),
+                Some(l) => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Element `face` at index {} has {} vertices, only triangles can be optimized.", row, l.len())
+                )),
+                None => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Element `face` at index {} references a vertex out of range.", row)
+                )),
+            }
+        }
+        let optimized = meshopt::optimize_vertex_cache(&indices, vertex_count);
+
+        // Original rows of the optimized triangles, the optimizer keeps each triangle's winding.
+        let mut rows = HashMap::<&[u32], Vec<usize>>::new();
+        for (row, t) in indices.chunks(3).enumerate().rev() {
+            rows.entry(t).or_default().push(row);
+        }
+        let face_order = optimized.chunks(3).map(|t| rows.get_mut(t).and_then(|r| r.pop()).unwrap()).collect::<Vec<_>>();
+
+        let mut new_index = vec![None; vertex_count];
+        let mut next = 0;
+        for &i in optimized.iter() {
+            if new_index[i as usize].is_none() {
+                new_index[i as usize] = Some(next);
+                next += 1;
+            }
+        }
+        for n in new_index.iter_mut().filter(|n| n.is_none()) {
+            *n = Some(next);
+            next += 1;
+        }
+        let new_index = new_index.into_iter().map(Option::unwrap).collect::<Vec<usize>>();
+
+        if let Some(vertices) = self.payload.get_mut("vertex") {
+            let mut slots = (0..vertex_count).map(|_| None).collect::<Vec<Option<E>>>();
+            for (old, v) in vertices.drain(..).enumerate() {
+                slots[new_index[old]] = Some(v);
+            }
+            *vertices = slots.into_iter().map(Option::unwrap).collect();
+        }
+        let faces = self.payload.entry("face".to_string()).or_default();
+        let mut old = faces.drain(..).map(Some).collect::<Vec<_>>();
+        for (row, t) in face_order.into_iter().zip(optimized.chunks(3)) {
+            let mut f = old[row].take().unwrap();
+            let renumbered = t.iter().map(|&i| new_index[i as usize] as f64).collect::<Vec<_>>();
+            set_list_from_f64(&mut f, &def, &renumbered);
+            faces.push(f);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    fn grid(n: usize) -> Ply<DefaultElement> {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut vertex = ElementDef::new("vertex".to_string());
+        vertex.properties.add(PropertyDef::new("id".to_string(), PropertyType::Scalar(ScalarType::Int)));
+        ply.header.elements.add(vertex);
+        let mut face = ElementDef::new("face".to_string());
+        face.properties.add(PropertyDef::new("vertex_indices".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int)));
+        face.properties.add(PropertyDef::new("id".to_string(), PropertyType::Scalar(ScalarType::Int)));
+        ply.header.elements.add(face);
+        let vertices = (0..n * n + 1).map(|i| {
+            let mut v = DefaultElement::new();
+            v.insert("id".to_string(), Property::Int(i as i32));
+            v
+        }).collect();
+        ply.payload.insert("vertex".to_string(), vertices);
+        let mut faces = Vec::new();
+        for y in 0..n - 1 {
+            for x in 0..n - 1 {
+                let i = (y * n + x) as i32;
+                let n = n as i32;
+                for l in &[vec![i, i + 1, i + n], vec![i + 1, i + n + 1, i + n]] {
+                    let mut f = DefaultElement::new();
+                    f.insert("vertex_indices".to_string(), Property::ListInt(l.clone()));
+                    f.insert("id".to_string(), Property::Int(faces.len() as i32));
+                    faces.push(f);
+                }
+            }
+        }
+        ply.payload.insert("face".to_string(), faces);
+        ply
+    }
+    /// Triangles by face id, given as vertex ids.
+    fn triangles(ply: &Ply<DefaultElement>) -> Vec<(i32, Vec<i32>)> {
+        let id = |p: &Property| match *p { Property::Int(v) => v, _ => panic!() };
+        let mut t = ply.payload["face"].iter().map(|f| match f["vertex_indices"] {
+            Property::ListInt(ref l) => (id(&f["id"]), l.iter().map(|&i| id(&ply.payload["vertex"][i as usize]["id"])).collect()),
+            _ => panic!(),
+        }).collect::<Vec<_>>();
+        t.sort();
+        t
+    }
+    #[test]
+    fn optimize_vertex_cache_ok() {
+        let mut ply = grid(8);
+        let before = triangles(&ply);
+        ply.optimize_vertex_cache().unwrap();
+        assert_eq!(triangles(&ply), before);
+        assert_eq!(ply.payload["vertex"].len(), 65);
+        // The unreferenced vertex comes last.
+        assert_eq!(ply.payload["vertex"][64]["id"], Property::Int(64));
+        assert_eq!(ply.payload["face"][0]["vertex_indices"], Property::ListInt(vec![0, 1, 2]));
+    }
+    #[test]
+    fn optimize_vertex_cache_quad() {
+        let mut ply = grid(2);
+        ply.payload.get_mut("face").unwrap()[0].insert("vertex_indices".to_string(), Property::ListInt(vec![0, 1, 3, 2]));
+        assert!(ply.optimize_vertex_cache().is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! Partial reads of PLY files served over HTTP, fetching only the byte ranges needed.
+//!
+//! The server has to support range requests, as static file servers and object stores do.
+
+use std::io;
+use std::io::{ BufRead, ErrorKind, Read, Result, Seek, SeekFrom };
+
+use crate::parser::Parser;
+use crate::ply::{ Payload, Ply, PropertyAccess };
+use crate::util::LocationTracker;
+
+/// Default number of bytes fetched per request.
+pub const DEFAULT_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Seekable reader of a remote file, fetching blocks on demand with HTTP range requests.
+///
+/// Reading the header costs one request, seeking is free, reading fetches the blocks covering the bytes read.
+pub struct HttpRangeReader {
+    agent: ureq::Agent,
+    url: String,
+    block_size: usize,
+    position: u64,
+    /// Total size, known after the first request.
+    len: Option<u64>,
+    block: Vec<u8>,
+    block_start: u64,
+    requests: usize,
+}
+
+/// Parses the total size from a `Content-Range: bytes <first>-<last>/<total>` header.
+fn content_range_total(value: &str) -> Option<u64> {
+    value.strip_prefix("bytes ")?.split('/').nth(1)?.trim().parse().ok()
+}
+
+impl HttpRangeReader {
+    /// Reader of the file at `url` fetching blocks of `DEFAULT_BLOCK_SIZE` bytes. Nothing is fetched yet.
+    pub fn new(url: &str) -> Self {
+        Self::with_block_size(url, DEFAULT_BLOCK_SIZE)
+    }
+    /// Reader fetching blocks of `block_size` bytes, smaller blocks waste less transfer on sparse reads.
+    pub fn with_block_size(url: &str, block_size: usize) -> Self {
+        HttpRangeReader {
+            agent: ureq::Agent::new_with_defaults(),
+            url: url.to_string(),
+            block_size: block_size.max(1),
+            position: 0,
+            len: None,
+            block: Vec::new(),
+            block_start: 0,
+            requests: 0,
+        }
+    }
+    /// Number of range requests issued so far.
+    pub fn requests(&self) -> usize {
+        self.requests
+    }
+    /// Total size of the remote file, fetched with a one byte request if not known yet.
+    pub fn size(&mut self) -> Result<u64> {
+        if self.len.is_none() {
+            self.fetch(0, 1)?;
+        }
+        Ok(self.len.unwrap_or(0))
+    }
+    /// Fetches `length` bytes at `start`, less at the end of the file.
+    fn fetch(&mut self, start: u64, length: usize) -> Result<Vec<u8>> {
+        let range = format!("bytes={}-{}", start, start + length as u64 - 1);
+        self.requests += 1;
+        let response = match self.agent.get(&self.url).header("Range", &range).call() {
+            Ok(r) => r,
+            Err(ureq::Error::StatusCode(416)) => return Ok(Vec::new()),
+            Err(e) => return Err(io::Error::other(e)),
+        };
+        if response.status() != 206 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Server answered {} to a range request, partial reads aren't supported.", response.status())
+            ));
+        }
+        self.len = response.headers().get("content-range").and_then(|v| v.to_str().ok()).and_then(content_range_total).or(self.len);
+        let data = response.into_body().read_to_vec().map_err(io::Error::other)?;
+        Ok(data)
+    }
+}
+
+impl BufRead for HttpRangeReader {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        let in_block = self.position >= self.block_start && self.position < self.block_start + self.block.len() as u64;
+        if !in_block {
+            if self.len.is_some_and(|len| self.position >= len) {
+                return Ok(&[]);
+            }
+            self.block = self.fetch(self.position, self.block_size)?;
+            self.block_start = self.position;
+        }
+        let offset = (self.position - self.block_start) as usize;
+        Ok(&self.block[offset.min(self.block.len())..])
+    }
+    fn consume(&mut self, amount: usize) {
+        self.position += amount as u64;
+    }
+}
+
+impl Read for HttpRangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl Seek for HttpRangeReader {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::Current(d) => self.position.checked_add_signed(d),
+            SeekFrom::End(d) => self.size()?.checked_add_signed(d),
+        };
+        match position {
+            Some(p) => {
+                self.position = p;
+                Ok(p)
+            },
+            None => Err(io::Error::new(ErrorKind::InvalidInput, "Seek to a negative position.")),
+        }
+    }
+}
+
+/// Reads the header and the `elements` of the binary PLY file at `url`, fetching only the bytes needed.
+///
+/// Elements of fixed row size are skipped without fetching them, rows with list properties preceding
+/// a requested element have to be scanned. The returned `Ply` has only the requested elements in its payload.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ply_rs::*;
+/// let p = parser::Parser::<ply::DefaultElement>::new();
+/// let ply = remote::read_elements(&p, "https://example.com/scans/statue.ply", &["vertex"]).unwrap();
+/// ```
+pub fn read_elements<E: PropertyAccess>(parser: &Parser<E>, url: &str, elements: &[&str]) -> Result<Ply<E>> {
+    let mut reader = HttpRangeReader::new(url);
+    let header = parser.read_header(&mut reader)?;
+    for name in elements {
+        if !header.elements.contains_key(*name) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` declared in header.", name)));
+        }
+    }
+    let last = header.elements.keys().rev().find(|k| elements.contains(&&k[..])).map(|k| &k[..]);
+    let mut payload = Payload::new();
+    if last.is_none() {
+        return Ok(Ply { header, payload });
+    }
+    let offsets = parser.element_offsets(&mut reader, &header, last)?;
+    for (element_def, &offset) in header.elements.values().zip(&offsets) {
+        if !elements.contains(&&element_def.name[..]) {
+            continue;
+        }
+        reader.seek(SeekFrom::Start(offset))?;
+        let rows = parser.__read_element_list(&mut reader, &mut LocationTracker::new(), element_def, &header.encoding)?;
+        payload.insert(element_def.name.clone(), rows);
+    }
+    Ok(Ply { header, payload })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+    use crate::ply::{ DefaultElement, Property };
+
+    /// Serves `data` to `connections` range requests on a local port, returns the url.
+    fn serve(data: Vec<u8>, connections: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/scan.ply", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                let mut stream = stream.unwrap();
+                let mut request = io::BufReader::new(stream.try_clone().unwrap());
+                let mut range = None;
+                let mut line = String::new();
+                while request.read_line(&mut line).unwrap() > 2 {
+                    if let Some(r) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        let (a, b) = r.trim().split_once('-').unwrap();
+                        range = Some((a.parse::<usize>().unwrap(), b.parse::<usize>().unwrap()));
+                    }
+                    line.clear();
+                }
+                let (first, last) = range.unwrap();
+                if first >= data.len() {
+                    write!(stream, "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+                    continue;
+                }
+                let last = last.min(data.len() - 1);
+                write!(stream, "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    first, last, data.len(), last + 1 - first).unwrap();
+                stream.write_all(&data[first..=last]).unwrap();
+            }
+        });
+        url
+    }
+    #[test]
+    fn read_elements_ok() {
+        let mut data = b"ply\nformat binary_little_endian 1.0\nelement point 1000\nproperty int x\n\
+            element face 1\nproperty list uchar uchar i\nend_header\n".to_vec();
+        for x in 0..1000i32 {
+            data.extend_from_slice(&x.to_le_bytes());
+        }
+        data.extend_from_slice(&[2, 5, 6]);
+        let url = serve(data, 8);
+        let p = Parser::<DefaultElement>::new();
+        let ply = read_elements(&p, &url, &["face"]).unwrap();
+        assert_eq!(ply.header.elements["point"].count, 1000);
+        assert!(!ply.payload.contains_key("point"));
+        assert_eq!(ply.payload["face"][0]["i"], Property::ListUChar(vec![5, 6]));
+    }
+    #[test]
+    fn range_reader_blocks() {
+        let data = (0..100u8).collect::<Vec<_>>();
+        let url = serve(data, 4);
+        let mut reader = HttpRangeReader::with_block_size(&url, 16);
+        let mut buf = [0; 4];
+        reader.seek(SeekFrom::Start(40)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [40, 41, 42, 43]);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(reader.requests(), 1);
+        assert_eq!(reader.size().unwrap(), 100);
+        reader.seek(SeekFrom::End(-2)).unwrap();
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], &[98, 99]);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert_eq!(reader.requests(), 2);
+    }
+}
@@ -0,0 +1,107 @@
+//! Logging of a `Ply` to a [rerun](https://rerun.io) recording stream, enabled by the `rerun` feature.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use rerun::{ Color, Mesh3D, Points3D, RecordingStream };
+
+use crate::ply::{ ElementDef, Ply, PropertyAccess, VERTEX_INDEX_NAMES };
+use crate::util::{ element_def, get_list_as_f64, get_scalar_as_f64 };
+
+fn rerun_error<D: std::fmt::Display>(e: D) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// Reads the named scalar properties of all `elements` as `f32`, `None` if any of them is missing.
+fn columns<E: PropertyAccess>(element_def: &ElementDef, elements: &[E], names: &[&str; 3]) -> Option<Vec<[f32; 3]>> {
+    let defs = names.iter().map(|n| element_def.properties.get(*n)).collect::<Option<Vec<_>>>()?;
+    elements.iter().map(|e| {
+        let mut v = [0.0; 3];
+        for (v, d) in v.iter_mut().zip(defs.iter()) {
+            *v = get_scalar_as_f64(e, d)? as f32;
+        }
+        Some(v)
+    }).collect()
+}
+
+/// Logs `ply` to `rec` under `entity_path`.
+///
+/// Vertices are logged as `Mesh3D` if the ply has faces, polygons are triangulated as fans,
+/// otherwise as `Points3D`. Colors are taken from `red`, `green` and `blue`, normals from `nx`, `ny` and `nz`.
+/// Requires `x`, `y` and `z` properties on `vertex`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ply_rs::*;
+/// let mut f = std::fs::File::open("example_plys/house_ok_ascii.ply").unwrap();
+/// let ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+/// let rec = rerun::RecordingStreamBuilder::new("ply_inspect").spawn().unwrap();
+/// rerun_log::log_ply(&rec, "house", &ply).unwrap();
+/// ```
+pub fn log_ply<E: PropertyAccess>(rec: &RecordingStream, entity_path: &str, ply: &Ply<E>) -> Result<()> {
+    let vertex = element_def(ply, "vertex")?;
+    let vertices = ply.payload.get("vertex").map(|l| &l[..]).unwrap_or_default();
+    let positions = match columns(vertex, vertices, &["x", "y", "z"]) {
+        Some(p) => p,
+        None => return Err(io::Error::new(ErrorKind::InvalidInput, "Every vertex needs `x`, `y` and `z`.")),
+    };
+    let colors = columns(vertex, vertices, &["red", "green", "blue"])
+        .map(|c| c.iter().map(|c| Color::from_rgb(c[0] as u8, c[1] as u8, c[2] as u8)).collect::<Vec<_>>());
+    let normals = columns(vertex, vertices, &["nx", "ny", "nz"]);
+
+    let face_index = ply.header.elements.get("face")
+        .and_then(|f| VERTEX_INDEX_NAMES.iter().find_map(|n| f.properties.get(*n)));
+    let face_index = match face_index {
+        Some(f) => f,
+        None => {
+            let mut points = Points3D::new(positions);
+            if let Some(c) = colors {
+                points = points.with_colors(c);
+            }
+            return rec.log(entity_path, &points).map_err(rerun_error);
+        },
+    };
+    let mut triangles = Vec::new();
+    for (row, f) in ply.payload.get("face").map(|l| &l[..]).unwrap_or_default().iter().enumerate() {
+        let polygon = match get_list_as_f64(f, face_index) {
+            Some(p) => p,
+            None => return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `face` at index {} has no list value for property `{}`.", row, face_index.name)
+            )),
+        };
+        for i in 2..polygon.len() {
+            triangles.push([polygon[0] as u32, polygon[i - 1] as u32, polygon[i] as u32]);
+        }
+    }
+    let mut mesh = Mesh3D::new(positions).with_triangle_indices(triangles);
+    if let Some(c) = colors {
+        mesh = mesh.with_vertex_colors(c);
+    }
+    if let Some(n) = normals {
+        mesh = mesh.with_vertex_normals(n);
+    }
+    rec.log(entity_path, &mesh).map_err(rerun_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::ply::DefaultElement;
+    #[test]
+    fn log_ply_ok() {
+        let (rec, storage) = rerun::RecordingStreamBuilder::new("ply_rs_test").memory().unwrap();
+        let mut f = std::fs::File::open("example_plys/house_ok_ascii.ply").unwrap();
+        let mut ply = Parser::<DefaultElement>::new().read_ply(&mut f).unwrap();
+        let before = storage.num_msgs();
+        log_ply(&rec, "house", &ply).unwrap();
+        ply.header.elements.remove("face");
+        log_ply(&rec, "points", &ply).unwrap();
+        rec.flush_blocking().unwrap();
+        assert!(storage.num_msgs() >= before + 2);
+        ply.header.elements.get_mut("vertex").unwrap().properties.remove("z");
+        assert!(log_ply(&rec, "invalid", &ply).is_err());
+    }
+}
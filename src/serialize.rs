@@ -0,0 +1,188 @@
+//! Streaming PLY rows into any `serde` serializer, e.g. JSON Lines, CBOR or MessagePack.
+//!
+//! Rows are read in chunks and serialized as they come, the payload is never held in memory as a whole.
+
+use std::cell::RefCell;
+use std::io;
+use std::io::{ BufRead, Result };
+
+use serde::ser::{ Error, SerializeMap, SerializeSeq };
+use serde::{ Serialize, Serializer };
+
+use crate::parser::Parser;
+use crate::ply::{ DefaultElement, ElementDef, Header, Property };
+use crate::transcode::CHUNK_ROWS;
+use crate::util::LocationTracker;
+
+/// Scalars as numbers, lists as sequences of numbers.
+impl Serialize for Property {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match *self {
+            Property::Char(v) => serializer.serialize_i8(v),
+            Property::UChar(v) => serializer.serialize_u8(v),
+            Property::Short(v) => serializer.serialize_i16(v),
+            Property::UShort(v) => serializer.serialize_u16(v),
+            Property::Int(v) => serializer.serialize_i32(v),
+            Property::UInt(v) => serializer.serialize_u32(v),
+            Property::Float(v) => serializer.serialize_f32(v),
+            Property::Double(v) => serializer.serialize_f64(v),
+            Property::ListChar(ref v) => v.serialize(serializer),
+            Property::ListUChar(ref v) => v.serialize(serializer),
+            Property::ListShort(ref v) => v.serialize(serializer),
+            Property::ListUShort(ref v) => v.serialize(serializer),
+            Property::ListInt(ref v) => v.serialize(serializer),
+            Property::ListUInt(ref v) => v.serialize(serializer),
+            Property::ListFloat(ref v) => v.serialize(serializer),
+            Property::ListDouble(ref v) => v.serialize(serializer),
+        }
+    }
+}
+
+/// One row of an element, serialized as a map from property names to values in header order.
+pub struct Row<'a> {
+    element_def: &'a ElementDef,
+    element: &'a DefaultElement,
+}
+
+impl<'a> Row<'a> {
+    /// Declaration of the element the row belongs to.
+    pub fn element_def(&self) -> &ElementDef {
+        self.element_def
+    }
+}
+
+impl<'a> Serialize for Row<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.element_def.properties.len()))?;
+        for name in self.element_def.properties.keys() {
+            map.serialize_entry(name, &self.element[name])?;
+        }
+        map.end()
+    }
+}
+
+/// Reads the rows of `element_def` in chunks and passes them to `f`.
+fn read_rows<T: BufRead, F, X>(parser: &Parser<DefaultElement>, reader: &mut T, location: &mut LocationTracker, element_def: &ElementDef, header: &Header, mut f: F) -> std::result::Result<(), X>
+    where F: FnMut(Row) -> std::result::Result<(), X>, X: From<io::Error> {
+    let mut chunk = element_def.clone();
+    let mut remaining = element_def.count;
+    while remaining > 0 {
+        chunk.count = remaining.min(CHUNK_ROWS);
+        remaining -= chunk.count;
+        let rows = parser.__read_element_list(reader, location, &chunk, &header.encoding)?;
+        for element in &rows {
+            f(Row { element_def, element })?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a PLY document from `reader` and passes each row to `f` with the name of its element, e.g. to write JSON Lines.
+///
+/// Returns the header.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// use std::io::Write;
+/// let mut f = std::io::BufReader::new(std::fs::File::open("example_plys/house_2_ok_ascii.ply").unwrap());
+/// let mut lines = Vec::new();
+/// serialize::for_each_row(&mut f, |element, row| {
+///     write!(lines, "{{\"{}\":", element)?;
+///     serde_json::to_writer(&mut lines, &row)?;
+///     writeln!(lines, "}}")
+/// }).unwrap();
+/// assert!(String::from_utf8(lines).unwrap().starts_with("{\"vertex\":{\"x\":1.0,\"y\":-1.0,\"z\":0.0}}\n"));
+/// ```
+pub fn for_each_row<T: BufRead, F: FnMut(&str, Row) -> Result<()>>(mut reader: T, mut f: F) -> Result<Header> {
+    let parser = Parser::<DefaultElement>::new();
+    let header = parser.read_header(&mut reader)?;
+    let mut location = LocationTracker::new();
+    for element_def in header.elements.values() {
+        read_rows(&parser, &mut reader, &mut location, element_def, &header, |row| f(&element_def.name, row))?;
+    }
+    Ok(header)
+}
+
+/// Rows of one element, read while serializing.
+struct Rows<'a, T: BufRead> {
+    state: &'a RefCell<(T, LocationTracker)>,
+    parser: &'a Parser<DefaultElement>,
+    element_def: &'a ElementDef,
+    header: &'a Header,
+}
+
+impl<'a, T: BufRead> Serialize for Rows<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.element_def.count))?;
+        let mut state = self.state.borrow_mut();
+        let (ref mut reader, ref mut location) = *state;
+        read_rows(self.parser, reader, location, self.element_def, self.header, |row| {
+            seq.serialize_element(&row).map_err(Failure::Serializer)
+        }).map_err(|e| match e {
+            Failure::Io(e) => S::Error::custom(e),
+            Failure::Serializer(e) => e,
+        })?;
+        seq.end()
+    }
+}
+
+enum Failure<X> {
+    Io(io::Error),
+    Serializer(X),
+}
+
+impl<X> From<io::Error> for Failure<X> {
+    fn from(e: io::Error) -> Self {
+        Failure::Io(e)
+    }
+}
+
+/// Reads a PLY document from `reader` and serializes its payload to `serializer` as map from element names to sequences of rows.
+///
+/// Read errors are reported as custom errors of the serializer.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// let ply = "ply\nformat ascii 1.0\nelement point 2\nproperty int x\nproperty list uchar uchar c\nend_header\n1 0\n2 2 7 8\n";
+/// let mut json = Vec::new();
+/// serialize::transcode_to_serializer(ply.as_bytes(), &mut serde_json::Serializer::new(&mut json)).unwrap();
+/// assert_eq!(String::from_utf8(json).unwrap(), r#"{"point":[{"x":1,"c":[]},{"x":2,"c":[7,8]}]}"#);
+/// ```
+pub fn transcode_to_serializer<T: BufRead, S: Serializer>(mut reader: T, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    let parser = Parser::<DefaultElement>::new();
+    let header = parser.read_header(&mut reader).map_err(S::Error::custom)?;
+    let state = RefCell::new((reader, LocationTracker::new()));
+    let mut map = serializer.serialize_map(Some(header.elements.len()))?;
+    for element_def in header.elements.values() {
+        map.serialize_entry(&element_def.name, &Rows { state: &state, parser: &parser, element_def, header: &header })?;
+    }
+    map.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn transcode_to_serializer_err() {
+        let truncated = "ply\nformat binary_little_endian 1.0\nelement point 2\nproperty int x\nend_header\n\x01\x00\x00\x00";
+        let mut json = Vec::new();
+        let err = transcode_to_serializer(truncated.as_bytes(), &mut serde_json::Serializer::new(&mut json)).unwrap_err();
+        assert!(err.is_io() || err.is_data());
+        assert!(transcode_to_serializer("ply\nformat ascii 2.0\n".as_bytes(), &mut serde_json::Serializer::new(&mut json)).is_err());
+    }
+    #[test]
+    fn for_each_row_ok() {
+        let ply = "ply\nformat ascii 1.0\nelement a 1\nproperty float x\nelement b 2\nproperty char y\nend_header\n0.5\n-1\n2\n";
+        let mut rows = Vec::new();
+        let header = for_each_row(ply.as_bytes(), |element, row| {
+            rows.push(format!("{} {}", element, serde_json::to_string(&row).unwrap()));
+            Ok(())
+        }).unwrap();
+        assert_eq!(header.elements.len(), 2);
+        assert_eq!(rows, vec![r#"a {"x":0.5}"#, r#"b {"y":-1}"#, r#"b {"y":2}"#]);
+    }
+}
@@ -0,0 +1,371 @@
+//! Spatial index over vertex positions.
+//!
+//! A `KdTree` is built once from the positions of an element (usually `vertex` with properties `x`, `y` and `z`)
+//! and keeps the row index of every point, so results always refer back to the payload.
+//! It is the basis for spatial queries and tiling.
+//!
+//! Only available with the `spatial` feature.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # use ply_rs::*;
+//! let path = "example_plys/greg_turk_example1_ok_ascii.ply";
+//! let mut f = std::fs::File::open(path).unwrap();
+//! let ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+//!
+//! let tree = spatial::KdTree::from_ply(&ply, "vertex").unwrap();
+//! assert_eq!(tree.len(), ply.payload["vertex"].len());
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::io::Result;
+
+use crate::ply::{ ElementDef, Ply, PropertyAccess };
+use crate::util::{ element_def, property_defs, require_scalar_as_f64 };
+
+/// Axis aligned bounding box.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl BoundingBox {
+    /// Creates a box containing only `point`.
+    pub fn from_point(point: [f64; 3]) -> Self {
+        BoundingBox {
+            min: point,
+            max: point,
+        }
+    }
+    /// Grows the box such that it contains `point`.
+    pub fn extend(&mut self, point: [f64; 3]) {
+        for (a, v) in point.iter().enumerate() {
+            self.min[a] = self.min[a].min(*v);
+            self.max[a] = self.max[a].max(*v);
+        }
+    }
+    /// Checks if `point` lies within the box, borders included.
+    pub fn contains(&self, point: [f64; 3]) -> bool {
+        (0..3).all(|a| self.min[a] <= point[a] && point[a] <= self.max[a])
+    }
+}
+
+/// A point stored in the tree together with its row index in the element list.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct KdPoint {
+    pub position: [f64; 3],
+    pub row: usize,
+}
+
+/// Collects points one by one, e.g. while elements are being parsed, and builds a `KdTree`.
+#[derive(Debug, Default, Clone)]
+pub struct KdTreeBuilder {
+    points: Vec<KdPoint>,
+}
+
+impl KdTreeBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        KdTreeBuilder {
+            points: Vec::new(),
+        }
+    }
+    /// Adds the point of element `row`.
+    pub fn push(&mut self, row: usize, position: [f64; 3]) {
+        self.points.push(KdPoint { position, row });
+    }
+    /// Builds the tree from all points added so far.
+    pub fn build(self) -> KdTree {
+        KdTree::from_kd_points(self.points)
+    }
+}
+
+/// Implicit, balanced kd-tree.
+///
+/// The points are stored in a single vector: For every range, the median along the splitting axis
+/// sits in the middle, smaller coordinates to its left and larger ones to its right.
+/// The splitting axis cycles through x, y, and z with the depth of the node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KdTree {
+    points: Vec<KdPoint>,
+    bounds: Option<BoundingBox>,
+}
+
+impl KdTree {
+    /// Builds a tree over positions, rows are numbered in iteration order.
+    pub fn from_points<I: IntoIterator<Item = [f64; 3]>>(positions: I) -> Self {
+        let points = positions.into_iter()
+            .enumerate()
+            .map(|(row, position)| KdPoint { position, row })
+            .collect();
+        KdTree::from_kd_points(points)
+    }
+    /// Builds a tree over the properties `x`, `y`, and `z` of element `element_name`.
+    pub fn from_ply<E: PropertyAccess>(ply: &Ply<E>, element_name: &str) -> Result<Self> {
+        let element_def = element_def(ply, element_name)?;
+        let elements = match ply.payload.get(element_name) {
+            Some(e) => e.as_slice(),
+            None => &[],
+        };
+        KdTree::from_elements(elements, element_def, ["x", "y", "z"])
+    }
+    /// Builds a tree over the scalar properties named in `axes`.
+    ///
+    /// The properties may have any scalar type, they are converted to `f64`.
+    pub fn from_elements<E: PropertyAccess>(elements: &[E], element_def: &ElementDef, axes: [&str; 3]) -> Result<Self> {
+        let defs = property_defs(element_def, &axes)?;
+        let mut builder = KdTreeBuilder::new();
+        for (row, element) in elements.iter().enumerate() {
+            let mut position = [0.0; 3];
+            for (a, def) in defs.iter().enumerate() {
+                position[a] = require_scalar_as_f64(element, def, &element_def.name, row)?;
+            }
+            builder.push(row, position);
+        }
+        Ok(builder.build())
+    }
+    fn from_kd_points(mut points: Vec<KdPoint>) -> Self {
+        let mut bounds: Option<BoundingBox> = None;
+        for p in &points {
+            match bounds {
+                None => bounds = Some(BoundingBox::from_point(p.position)),
+                Some(ref mut b) => b.extend(p.position),
+            }
+        }
+        build_recursive(&mut points, 0);
+        KdTree {
+            points,
+            bounds,
+        }
+    }
+    /// Number of points in the tree.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+    /// True if the tree contains no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+    /// Bounding box of all points, `None` for an empty tree.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        self.bounds
+    }
+    /// All points in tree order.
+    pub fn points(&self) -> &[KdPoint] {
+        &self.points
+    }
+    /// Returns the rows of the `k` points closest to `point`, nearest first.
+    ///
+    /// Returns fewer rows if the tree contains less than `k` points.
+    pub fn nearest(&self, point: [f64; 3], k: usize) -> Vec<usize> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        nearest_recursive(&self.points, 0, point, k, &mut heap);
+        heap.into_sorted_vec().into_iter().map(|c| c.row).collect()
+    }
+    /// Returns the rows of all points within distance `radius` of `point`, nearest first.
+    pub fn within_radius(&self, point: [f64; 3], radius: f64) -> Vec<usize> {
+        let mut found = Vec::new();
+        within_radius_recursive(&self.points, 0, point, radius * radius, &mut found);
+        found.sort();
+        found.into_iter().map(|c| c.row).collect()
+    }
+}
+
+/// Entry of a query result, ordered by distance and row.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Candidate {
+    distance_squared: f64,
+    row: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_squared.total_cmp(&other.distance_squared).then(self.row.cmp(&other.row))
+    }
+}
+
+fn distance_squared(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (0..3).map(|i| (a[i] - b[i]) * (a[i] - b[i])).sum()
+}
+
+/// Splits `points` into the subtree on the side of `point` and the one on the other side.
+///
+/// Returns the median, both subtrees and the signed distance of `point` to the splitting plane.
+fn split(points: &[KdPoint], depth: usize, point: [f64; 3]) -> (&KdPoint, &[KdPoint], &[KdPoint], f64) {
+    let axis = depth % 3;
+    let mid = points.len() / 2;
+    let median = &points[mid];
+    let delta = point[axis] - median.position[axis];
+    let (left, right) = (&points[..mid], &points[mid + 1..]);
+    if delta < 0.0 {
+        (median, left, right, delta)
+    } else {
+        (median, right, left, delta)
+    }
+}
+
+fn nearest_recursive(points: &[KdPoint], depth: usize, point: [f64; 3], k: usize, heap: &mut BinaryHeap<Candidate>) {
+    if points.is_empty() {
+        return;
+    }
+    let (median, near, far, delta) = split(points, depth, point);
+    heap.push(Candidate { distance_squared: distance_squared(point, median.position), row: median.row });
+    if heap.len() > k {
+        heap.pop();
+    }
+    nearest_recursive(near, depth + 1, point, k, heap);
+    let worst = heap.peek().map(|c| c.distance_squared).unwrap_or(f64::INFINITY);
+    if heap.len() < k || delta * delta <= worst {
+        nearest_recursive(far, depth + 1, point, k, heap);
+    }
+}
+
+fn within_radius_recursive(points: &[KdPoint], depth: usize, point: [f64; 3], radius_squared: f64, found: &mut Vec<Candidate>) {
+    if points.is_empty() {
+        return;
+    }
+    let (median, near, far, delta) = split(points, depth, point);
+    let d = distance_squared(point, median.position);
+    if d <= radius_squared {
+        found.push(Candidate { distance_squared: d, row: median.row });
+    }
+    within_radius_recursive(near, depth + 1, point, radius_squared, found);
+    if delta * delta <= radius_squared {
+        within_radius_recursive(far, depth + 1, point, radius_squared, found);
+    }
+}
+
+fn cmp_axis(a: &KdPoint, b: &KdPoint, axis: usize) -> Ordering {
+    a.position[axis].total_cmp(&b.position[axis])
+}
+
+fn build_recursive(points: &mut [KdPoint], depth: usize) {
+    if points.len() <= 1 {
+        return;
+    }
+    let axis = depth % 3;
+    let mid = points.len() / 2;
+    points.select_nth_unstable_by(mid, |a, b| cmp_axis(a, b, axis));
+    let (left, right) = points.split_at_mut(mid);
+    build_recursive(left, depth + 1);
+    build_recursive(&mut right[1..], depth + 1);
+}
+
+/// A `Ply` stored together with a spatial index over one of its elements.
+#[derive(Debug, Clone)]
+pub struct IndexedPly<E: PropertyAccess> {
+    pub ply: Ply<E>,
+    /// Name of the indexed element.
+    pub element_name: String,
+    /// Index over the positions of `element_name`, rows refer to `ply.payload[element_name]`.
+    pub index: KdTree,
+}
+
+impl<E: PropertyAccess> IndexedPly<E> {
+    /// Builds an index over `x`, `y`, and `z` of `element_name` and keeps it with `ply`.
+    ///
+    /// The index must be rebuilt, if the payload of the element is modified.
+    pub fn new(ply: Ply<E>, element_name: &str) -> Result<Self> {
+        let index = KdTree::from_ply(&ply, element_name)?;
+        Ok(IndexedPly {
+            ply,
+            element_name: element_name.to_string(),
+            index,
+        })
+    }
+    /// Rows of the `k` elements closest to `point`, see `KdTree::nearest()`.
+    pub fn nearest(&self, point: [f64; 3], k: usize) -> Vec<usize> {
+        self.index.nearest(point, k)
+    }
+    /// Rows of all elements within `radius` of `point`, see `KdTree::within_radius()`.
+    pub fn within_radius(&self, point: [f64; 3], radius: f64) -> Vec<usize> {
+        self.index.within_radius(point, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn check_invariant(points: &[KdPoint], depth: usize) {
+        if points.len() <= 1 {
+            return;
+        }
+        let axis = depth % 3;
+        let mid = points.len() / 2;
+        let m = points[mid].position[axis];
+        assert!(points[..mid].iter().all(|p| p.position[axis] <= m));
+        assert!(points[mid + 1..].iter().all(|p| p.position[axis] >= m));
+        check_invariant(&points[..mid], depth + 1);
+        check_invariant(&points[mid + 1..], depth + 1);
+    }
+    #[test]
+    fn build_ok() {
+        let positions: Vec<[f64; 3]> = (0..100)
+            .map(|i| [(i * 37 % 101) as f64, (i * 59 % 103) as f64, (i % 7) as f64])
+            .collect();
+        let tree = KdTree::from_points(positions.clone());
+        assert_eq!(tree.len(), 100);
+        check_invariant(tree.points(), 0);
+        for p in tree.points() {
+            assert_eq!(positions[p.row], p.position);
+        }
+        let b = tree.bounds().unwrap();
+        assert_eq!(b.min, [0.0, 0.0, 0.0]);
+        assert_eq!(b.max, [100.0, 102.0, 6.0]);
+    }
+    fn brute_force(positions: &[[f64; 3]], point: [f64; 3]) -> Vec<Candidate> {
+        let mut all: Vec<Candidate> = positions.iter().enumerate()
+            .map(|(row, p)| Candidate { distance_squared: distance_squared(point, *p), row })
+            .collect();
+        all.sort();
+        all
+    }
+    #[test]
+    fn nearest_ok() {
+        let positions: Vec<[f64; 3]> = (0..500)
+            .map(|i| [(i * 37 % 101) as f64 * 0.1, (i * 59 % 103) as f64 * 0.2, (i * 13 % 17) as f64])
+            .collect();
+        let tree = KdTree::from_points(positions.clone());
+        for q in &[[0.0, 0.0, 0.0], [5.0, 10.0, 8.0], [-3.0, 30.0, 2.5]] {
+            let expected: Vec<usize> = brute_force(&positions, *q).iter().take(7).map(|c| c.row).collect();
+            assert_eq!(tree.nearest(*q, 7), expected);
+        }
+        assert_eq!(tree.nearest([0.0; 3], 0), Vec::<usize>::new());
+        assert_eq!(tree.nearest([0.0; 3], 1000).len(), 500);
+    }
+    #[test]
+    fn within_radius_ok() {
+        let positions: Vec<[f64; 3]> = (0..500)
+            .map(|i| [(i * 37 % 101) as f64 * 0.1, (i * 59 % 103) as f64 * 0.2, (i * 13 % 17) as f64])
+            .collect();
+        let tree = KdTree::from_points(positions.clone());
+        let q = [5.0, 10.0, 8.0];
+        let expected: Vec<usize> = brute_force(&positions, q).iter()
+            .take_while(|c| c.distance_squared <= 9.0)
+            .map(|c| c.row)
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(tree.within_radius(q, 3.0), expected);
+    }
+    #[test]
+    fn build_empty() {
+        let tree = KdTree::from_points(Vec::new());
+        assert!(tree.is_empty());
+        assert!(tree.nearest([0.0; 3], 3).is_empty());
+        assert_eq!(tree.bounds(), None);
+    }
+}
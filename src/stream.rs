@@ -0,0 +1,432 @@
+//! Adapters for the streams read by the `Parser` and written by the `Writer`.
+
+use std::io;
+use std::io::{ BufRead, ErrorKind, Read, Result, Seek, SeekFrom, Write };
+
+#[cfg(feature = "checksum")]
+pub use sha2::digest;
+#[cfg(feature = "checksum")]
+pub use sha2::Sha256;
+#[cfg(feature = "checksum")]
+use self::digest::{ Digest, Output };
+
+/// Restricts a stream to the range `offset..offset + length`, e.g. a PLY embedded in an archive.
+///
+/// Positions are relative to the start of the window:
+/// Seeking to `SeekFrom::Start(0)` moves to `offset` in the underlying stream,
+/// `SeekFrom::End(0)` to `offset + length`. Reading stops at the end of the window.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// use std::io::Cursor;
+/// let mut container = b"HEADER OF SOME ARCHIVE".to_vec();
+/// let offset = container.len() as u64;
+/// container.extend_from_slice(b"ply\nformat ascii 1.0\nend_header\n");
+/// let length = container.len() as u64 - offset;
+/// container.extend_from_slice(b"MORE ARCHIVE DATA");
+///
+/// let mut window = stream::Window::new(Cursor::new(container), offset, length).unwrap();
+/// let ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut window);
+/// assert!(ply.is_ok());
+/// ```
+#[derive(Debug)]
+pub struct Window<R> {
+    inner: R,
+    offset: u64,
+    length: u64,
+    /// Position relative to `offset`.
+    position: u64,
+}
+
+impl<R: Seek> Window<R> {
+    /// Creates a window over `inner` and moves to its start.
+    pub fn new(mut inner: R, offset: u64, length: u64) -> Result<Self> {
+        inner.seek(SeekFrom::Start(offset))?;
+        Ok(Window {
+            inner,
+            offset,
+            length,
+            position: 0,
+        })
+    }
+}
+
+impl<R> Window<R> {
+    /// Start of the window in the underlying stream.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    /// Length of the window in bytes.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+    /// True if the window has length zero.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+    /// Returns the underlying stream, its position is not restored.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    fn remaining(&self) -> u64 {
+        self.length.saturating_sub(self.position)
+    }
+}
+
+impl<R: Read> Read for Window<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let max = (buf.len() as u64).min(self.remaining()) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Window<R> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        let max = self.remaining();
+        let buf = self.inner.fill_buf()?;
+        let max = (buf.len() as u64).min(max) as usize;
+        Ok(&buf[..max])
+    }
+    fn consume(&mut self, amt: usize) {
+        let amt = (amt as u64).min(self.remaining());
+        self.inner.consume(amt as usize);
+        self.position += amt;
+    }
+}
+
+impl<R: Seek> Seek for Window<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.length.checked_add_signed(d),
+            SeekFrom::Current(d) => self.position.checked_add_signed(d),
+        };
+        let target = match target {
+            Some(t) => t,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, "Invalid seek to a negative or overflowing position.")),
+        };
+        self.inner.seek(SeekFrom::Start(self.offset + target))?;
+        self.position = target;
+        Ok(target)
+    }
+}
+
+/// Hashes all bytes read through it with the digest `D`, e.g. `Sha256`.
+///
+/// Reading via `BufRead` only hashes consumed bytes, hence the header and the payload can be hashed separately:
+///
+/// ```rust
+/// # use ply_rs::*;
+/// use ply_rs::stream::{ HashingReader, Sha256 };
+/// let path = "example_plys/house_2_ok_little_endian.ply";
+/// let f = std::io::BufReader::new(std::fs::File::open(path).unwrap());
+/// let mut reader = HashingReader::<_, Sha256>::new(f);
+///
+/// let p = parser::Parser::<ply::DefaultElement>::new();
+/// let header = p.read_header(&mut reader).unwrap();
+/// reader.reset_digest();
+/// let payload = p.read_payload(&mut reader, &header).unwrap();
+/// let (_, payload_hash) = reader.finalize();
+/// # assert_eq!(payload_hash.len(), 32);
+/// ```
+///
+/// `Parser::read_ply()` buffers internally and may read ahead of the payload's end:
+/// Only rely on the hash of the complete stream in that case.
+#[cfg(feature = "checksum")]
+pub struct HashingReader<R, D: Digest> {
+    inner: R,
+    digest: D,
+    hashed: u64,
+}
+
+#[cfg(feature = "checksum")]
+impl<R, D: Digest> HashingReader<R, D> {
+    /// Wraps `inner`, the digest starts empty.
+    pub fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            digest: D::new(),
+            hashed: 0,
+        }
+    }
+    /// Discards all bytes hashed so far.
+    pub fn reset_digest(&mut self) {
+        self.digest = D::new();
+        self.hashed = 0;
+    }
+    /// Number of bytes hashed since creation or the last reset.
+    pub fn bytes_hashed(&self) -> u64 {
+        self.hashed
+    }
+    /// The underlying stream.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Returns the underlying stream and the hash of all bytes read since creation or the last reset.
+    pub fn finalize(self) -> (R, Output<D>) {
+        (self.inner, self.digest.finalize())
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<R: Read, D: Digest> Read for HashingReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.digest.update(&buf[..n]);
+        self.hashed += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<R: BufRead, D: Digest> BufRead for HashingReader<R, D> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+    fn consume(&mut self, amt: usize) {
+        // The inner buffer still holds the bytes handed out by `fill_buf`, no read happens here.
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.digest.update(&buf[..amt]);
+            self.hashed += amt as u64;
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Hashes all bytes written through it with the digest `D`, e.g. `Sha256`.
+///
+/// Call `reset_digest()` after writing the header to obtain a hash of the payload only.
+#[cfg(feature = "checksum")]
+pub struct HashingWriter<W, D: Digest> {
+    inner: W,
+    digest: D,
+    hashed: u64,
+}
+
+#[cfg(feature = "checksum")]
+impl<W, D: Digest> HashingWriter<W, D> {
+    /// Wraps `inner`, the digest starts empty.
+    pub fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            digest: D::new(),
+            hashed: 0,
+        }
+    }
+    /// Discards all bytes hashed so far.
+    pub fn reset_digest(&mut self) {
+        self.digest = D::new();
+        self.hashed = 0;
+    }
+    /// Number of bytes hashed since creation or the last reset.
+    pub fn bytes_hashed(&self) -> u64 {
+        self.hashed
+    }
+    /// The underlying target.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    /// Returns the underlying target and the hash of all bytes written since creation or the last reset.
+    pub fn finalize(self) -> (W, Output<D>) {
+        (self.inner, self.digest.finalize())
+    }
+}
+
+#[cfg(feature = "checksum")]
+impl<W: Write, D: Digest> Write for HashingWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.digest.update(&buf[..n]);
+        self.hashed += n as u64;
+        Ok(n)
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Destination receiving a stream in parts, e.g. a multipart upload to S3 or GCS.
+pub trait ChunkSink {
+    /// Receives part `index`, counting from zero. All parts but the last one have the part size of the `ChunkedWriter`.
+    fn put_part(&mut self, index: usize, data: &[u8]) -> Result<()>;
+    /// Completes the upload after the last part has been put.
+    fn finalize(&mut self) -> Result<()>;
+}
+
+/// Buffers bytes written through it and passes them to a `ChunkSink` in parts of fixed size.
+///
+/// Only one part is held in memory. `flush()` doesn't emit partial parts since object stores
+/// require a minimum part size, call `finish()` after writing to emit the last part and finalize.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::*;
+/// use ply_rs::stream::{ ChunkSink, ChunkedWriter };
+/// struct Parts(Vec<Vec<u8>>);
+/// impl ChunkSink for Parts {
+///     fn put_part(&mut self, _index: usize, data: &[u8]) -> std::io::Result<()> {
+///         self.0.push(data.to_vec());
+///         Ok(())
+///     }
+///     fn finalize(&mut self) -> std::io::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// let mut f = std::fs::File::open("example_plys/house_2_ok_ascii.ply").unwrap();
+/// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+/// let mut out = ChunkedWriter::new(Parts(Vec::new()), 64);
+/// writer::Writer::new().write_ply(&mut out, &mut ply).unwrap();
+/// let parts = out.finish().unwrap();
+/// assert!(parts.0.iter().rev().skip(1).all(|p| p.len() == 64));
+/// ```
+pub struct ChunkedWriter<S: ChunkSink> {
+    sink: S,
+    part_size: usize,
+    buffer: Vec<u8>,
+    parts: usize,
+}
+
+impl<S: ChunkSink> ChunkedWriter<S> {
+    /// Writes to `sink` in parts of `part_size` bytes, at least one.
+    pub fn new(sink: S, part_size: usize) -> Self {
+        let part_size = part_size.max(1);
+        ChunkedWriter {
+            sink,
+            part_size,
+            buffer: Vec::with_capacity(part_size),
+            parts: 0,
+        }
+    }
+    /// Number of parts passed to the sink so far.
+    pub fn parts(&self) -> usize {
+        self.parts
+    }
+    /// The underlying sink.
+    pub fn get_ref(&self) -> &S {
+        &self.sink
+    }
+    fn put_part(&mut self) -> Result<()> {
+        self.sink.put_part(self.parts, &self.buffer)?;
+        self.parts += 1;
+        self.buffer.clear();
+        Ok(())
+    }
+    /// Emits the remaining bytes as last part, finalizes the sink and returns it.
+    ///
+    /// An empty stream is emitted as a single empty part.
+    pub fn finish(mut self) -> Result<S> {
+        if !self.buffer.is_empty() || self.parts == 0 {
+            self.put_part()?;
+        }
+        self.sink.finalize()?;
+        Ok(self.sink)
+    }
+}
+
+impl<S: ChunkSink> Write for ChunkedWriter<S> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = buf.len().min(self.part_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        if self.buffer.len() == self.part_size {
+            self.put_part()?;
+        }
+        Ok(n)
+    }
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    #[test]
+    fn window_read_ok() {
+        let mut w = Window::new(Cursor::new(b"0123456789".to_vec()), 2, 5).unwrap();
+        let mut s = String::new();
+        w.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "23456");
+        assert_eq!(w.read(&mut [0; 4]).unwrap(), 0);
+    }
+    #[test]
+    fn window_seek_ok() {
+        let mut w = Window::new(Cursor::new(b"0123456789".to_vec()), 2, 5).unwrap();
+        let mut b = [0; 2];
+        assert_eq!(w.seek(SeekFrom::End(-2)).unwrap(), 3);
+        w.read_exact(&mut b).unwrap();
+        assert_eq!(&b, b"56");
+        assert_eq!(w.seek(SeekFrom::Current(-4)).unwrap(), 1);
+        w.read_exact(&mut b).unwrap();
+        assert_eq!(&b, b"34");
+        assert_eq!(w.seek(SeekFrom::Start(0)).unwrap(), 0);
+        w.read_exact(&mut b).unwrap();
+        assert_eq!(&b, b"23");
+        assert!(w.seek(SeekFrom::Current(-3)).is_err());
+    }
+    #[cfg(feature = "checksum")]
+    #[test]
+    fn hashing_reader_writer_equal() {
+        let mut w = HashingWriter::<_, Sha256>::new(Vec::new());
+        w.write_all(b"header\n").unwrap();
+        w.reset_digest();
+        w.write_all(b"payload").unwrap();
+        assert_eq!(w.bytes_hashed(), 7);
+        let (buf, written_hash) = w.finalize();
+
+        let mut r = HashingReader::<_, Sha256>::new(&buf[..]);
+        let mut line = String::new();
+        r.read_line(&mut line).unwrap();
+        r.reset_digest();
+        r.read_to_string(&mut line).unwrap();
+        let (_, read_hash) = r.finalize();
+        assert_eq!(written_hash, read_hash);
+        assert_eq!(read_hash, Sha256::digest(b"payload"));
+    }
+    #[test]
+    fn window_buf_read_ok() {
+        let mut w = Window::new(Cursor::new(b"ab\ncd\nef\n".to_vec()), 3, 4).unwrap();
+        let mut line = String::new();
+        w.read_line(&mut line).unwrap();
+        assert_eq!(line, "cd\n");
+        line.clear();
+        w.read_line(&mut line).unwrap();
+        assert_eq!(line, "e");
+    }
+    #[derive(Default)]
+    struct Parts {
+        parts: Vec<(usize, Vec<u8>)>,
+        finalized: bool,
+    }
+    impl ChunkSink for Parts {
+        fn put_part(&mut self, index: usize, data: &[u8]) -> Result<()> {
+            assert!(!self.finalized);
+            self.parts.push((index, data.to_vec()));
+            Ok(())
+        }
+        fn finalize(&mut self) -> Result<()> {
+            self.finalized = true;
+            Ok(())
+        }
+    }
+    #[test]
+    fn chunked_writer_parts() {
+        let mut w = ChunkedWriter::new(Parts::default(), 4);
+        w.write_all(b"0123456789").unwrap();
+        w.flush().unwrap();
+        assert_eq!(w.parts(), 2);
+        let sink = w.finish().unwrap();
+        assert!(sink.finalized);
+        assert_eq!(sink.parts, vec![(0, b"0123".to_vec()), (1, b"4567".to_vec()), (2, b"89".to_vec())]);
+
+        let mut w = ChunkedWriter::new(Parts::default(), 2);
+        w.write_all(b"01").unwrap();
+        assert_eq!(w.finish().unwrap().parts.len(), 1);
+        assert_eq!(ChunkedWriter::new(Parts::default(), 2).finish().unwrap().parts, vec![(0, Vec::new())]);
+    }
+}
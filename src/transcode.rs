@@ -0,0 +1,321 @@
+//! Conversion of PLY files between encodings, streaming rows with bounded memory.
+
+use std::fs::File;
+use std::io;
+use std::io::{ BufRead, BufReader, BufWriter, ErrorKind, Result, Seek, SeekFrom, Write };
+use std::path::Path;
+
+use crate::checksum::Checksum;
+use crate::parser::Parser;
+use crate::ply::{ DefaultElement, Encoding, Header };
+use crate::util::LocationTracker;
+use crate::writer::{ Compression, Writer };
+
+/// Number of rows held in memory at once.
+pub(crate) const CHUNK_ROWS: usize = 4096;
+
+/// Changes applied by `transcode()`, the default copies the file as is.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TranscodeOptions {
+    /// Encoding of the output, `None` keeps the input encoding.
+    pub encoding: Option<Encoding>,
+    /// Line terminator of the output, `None` writes `"\n"`, see `Writer::set_new_line()`.
+    pub new_line: Option<String>,
+    /// Compression of the output, `None` writes it uncompressed, see `Writer::set_compression()`.
+    pub compression: Option<Compression>,
+    /// Names of the elements left out.
+    pub drop_elements: Vec<String>,
+    /// Properties left out, as `(element, property)` names.
+    pub drop_properties: Vec<(String, String)>,
+}
+
+/// Output header: `header` with the changes of `options` applied.
+fn output_header(header: &Header, options: &TranscodeOptions) -> Result<Header> {
+    let mut out = header.clone();
+    if let Some(encoding) = options.encoding {
+        out.encoding = encoding;
+    }
+    // The payload changes, a checksum of the input would be wrong.
+    out.comments.retain(|c| Checksum::from_comment(c).is_none());
+    for name in &options.drop_elements {
+        if out.elements.remove(name).is_none() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` to drop.", name)));
+        }
+    }
+    for (element, property) in &options.drop_properties {
+        let def = match out.elements.get_mut(element) {
+            Some(d) => d,
+            None if options.drop_elements.contains(element) => continue,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` to drop properties from.", element))),
+        };
+        if def.properties.remove(property).is_none() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Element `{}` has no property `{}` to drop.", element, property)));
+        }
+        if def.properties.is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` has no properties left, drop the element instead.", element)
+            ));
+        }
+    }
+    Ok(out)
+}
+
+/// Reads the PLY file at `input_path` and writes it to `output_path` with the changes of `options`.
+///
+/// Rows are read and written in chunks, the memory used doesn't depend on the file size.
+/// The input is read uncompressed. Checksum comments are removed since the payload changes. Returns the header written.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ply_rs::*;
+/// use ply_rs::ply::{ transcode, TranscodeOptions };
+/// let options = TranscodeOptions {
+///     encoding: Some(ply::Encoding::BinaryLittleEndian),
+///     drop_properties: vec![("vertex".to_string(), "confidence".to_string())],
+///     ..TranscodeOptions::default()
+/// };
+/// transcode("scan_ascii.ply", "scan.ply", &options).unwrap();
+/// ```
+pub fn transcode<P: AsRef<Path>, Q: AsRef<Path>>(input_path: P, output_path: Q, options: &TranscodeOptions) -> Result<Header> {
+    let mut reader = BufReader::new(File::open(input_path)?);
+    let parser = Parser::<DefaultElement>::new();
+    let header = parser.read_header(&mut reader)?;
+    let out_header = output_header(&header, options)?;
+
+    let mut writer = Writer::<DefaultElement>::new();
+    if let Some(ref new_line) = options.new_line {
+        writer.set_new_line(new_line);
+    }
+    if let Some(compression) = options.compression {
+        writer.set_compression(compression);
+    }
+    let mut out = BufWriter::new(File::create(output_path)?);
+    writer.__compressed(&mut out, |mut out| {
+        let mut written = writer.write_header(&mut out, &out_header)?;
+        let mut location = LocationTracker::new();
+        for element_def in header.elements.values() {
+            let out_def = out_header.elements.get(&element_def.name);
+            let mut chunk = element_def.clone();
+            let mut remaining = element_def.count;
+            while remaining > 0 {
+                chunk.count = remaining.min(CHUNK_ROWS);
+                remaining -= chunk.count;
+                let out_def = match out_def {
+                    Some(d) => d,
+                    None => {
+                        parser.__skip_element(&mut reader, &mut location, &chunk, &header.encoding)?;
+                        continue;
+                    },
+                };
+                let rows = parser.__read_element_list(&mut reader, &mut location, &chunk, &header.encoding)?;
+                written += writer.write_payload_of_element(&mut out, &rows, out_def, &out_header)?;
+            }
+        }
+        Ok(written)
+    })?;
+    out.flush()?;
+    Ok(out_header)
+}
+
+/// Header line in canonical spelling: keywords and type names in lower case, tokens separated by single spaces.
+///
+/// The text of comments and obj_infos is kept as is, apart from leading whitespace.
+fn normalize_header_line(line: &str) -> String {
+    let line = line.trim_start();
+    let keyword_end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let keyword = line[..keyword_end].to_ascii_lowercase();
+    let rest = line[keyword_end..].trim_end_matches(['\r', '\n']);
+    let tokens: Vec<&str> = rest.split_whitespace().collect();
+    match &keyword[..] {
+        "comment" | "obj_info" if rest.trim().is_empty() => keyword,
+        "comment" | "obj_info" => format!("{} {}", keyword, rest.trim_start()),
+        "element" => std::iter::once(&keyword[..]).chain(tokens).collect::<Vec<_>>().join(" "),
+        "property" => {
+            let mut normalized = vec![keyword.clone()];
+            if let Some((name, types)) = tokens.split_last() {
+                normalized.extend(types.iter().map(|t| t.to_ascii_lowercase()));
+                normalized.push(name.to_string());
+            }
+            normalized.join(" ")
+        },
+        _ => std::iter::once(keyword.clone()).chain(tokens.iter().map(|t| t.to_ascii_lowercase())).collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// Sets the count of the last element to the number of rows following the rows of the preceding elements.
+fn count_last_rows<T: BufRead>(parser: &Parser<DefaultElement>, reader: &mut T, header: &mut Header) -> Result<()> {
+    let mut location = LocationTracker::new();
+    let encoding = header.encoding;
+    let preceding = header.elements.len().saturating_sub(1);
+    for e in header.elements.values().take(preceding) {
+        parser.__skip_element(reader, &mut location, e, &encoding)?;
+    }
+    let last = match header.elements.iter_mut().last() {
+        Some((_, e)) => e,
+        None => return Ok(()),
+    };
+    let mut count = 0;
+    match (encoding, last.fixed_stride()) {
+        (Encoding::Ascii, _) => {
+            let mut line = String::new();
+            while reader.read_line(&mut line)? > 0 {
+                if !line.trim().is_empty() {
+                    count += 1;
+                }
+                line.clear();
+            }
+        },
+        (_, Some(0)) => return Ok(()),
+        (_, Some(stride)) => {
+            let rest = io::copy(reader, &mut io::sink())? as usize;
+            if rest % stride != 0 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Payload ends with a partial row of element `{}`.", last.name)
+                ));
+            }
+            count = rest / stride;
+        },
+        (_, None) => while !reader.fill_buf()?.is_empty() {
+            parser.__skip_row(reader, &mut location, last, &encoding)?;
+            count += 1;
+        },
+    }
+    last.count = count;
+    Ok(())
+}
+
+/// Rewrites the header of the PLY file at `input_path` in canonical form and copies the payload bytes unchanged to `output_path`.
+///
+/// Keyword case, whitespace and type names are normalized, e.g. `Property  FLOAT32 x` becomes `property float x`,
+/// and obj_infos are written after the comments.
+/// With `fix_counts`, the count of the last element is recomputed from the payload, the counts of the others are kept.
+/// Returns the header written.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ply_rs::*;
+/// let header = transcode::normalize_header("truncated_scan.ply", "fixed_scan.ply", true).unwrap();
+/// println!("{} points", header.elements["vertex"].count);
+/// ```
+pub fn normalize_header<P: AsRef<Path>, Q: AsRef<Path>>(input_path: P, output_path: Q, fix_counts: bool) -> Result<Header> {
+    let mut reader = BufReader::new(File::open(input_path)?);
+    let mut header_text = String::new();
+    let mut line = String::new();
+    let mut payload_start = 0;
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Missing 'end_header' line."));
+        }
+        payload_start += read as u64;
+        let normalized = normalize_header_line(&line);
+        header_text.push_str(&normalized);
+        header_text.push('\n');
+        if normalized == "end_header" {
+            break;
+        }
+    }
+    let parser = Parser::<DefaultElement>::new();
+    let mut header = parser.read_header(&mut header_text.as_bytes())?;
+    if fix_counts {
+        count_last_rows(&parser, &mut reader, &mut header)?;
+        reader.seek(SeekFrom::Start(payload_start))?;
+    }
+
+    let mut out = BufWriter::new(File::create(output_path)?);
+    Writer::<DefaultElement>::new().write_header(&mut out, &header)?;
+    io::copy(&mut reader, &mut out)?;
+    out.flush()?;
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::Ply;
+    fn read(path: &Path) -> Ply<DefaultElement> {
+        Parser::<DefaultElement>::new().read_ply(&mut File::open(path).unwrap()).unwrap()
+    }
+    #[test]
+    fn transcode_round_trip() {
+        let dir = std::env::temp_dir();
+        let binary = dir.join(format!("ply_rs_transcode_{}.ply", std::process::id()));
+        let ascii = dir.join(format!("ply_rs_transcode_{}_ascii.ply", std::process::id()));
+        let input = Path::new("example_plys/house_2_ok_ascii.ply");
+
+        let options = TranscodeOptions { encoding: Some(Encoding::BinaryBigEndian), ..TranscodeOptions::default() };
+        transcode(input, &binary, &options).unwrap();
+        let options = TranscodeOptions {
+            encoding: Some(Encoding::Ascii),
+            new_line: Some("\r\n".to_string()),
+            drop_properties: vec![("vertex".to_string(), "z".to_string())],
+            ..TranscodeOptions::default()
+        };
+        let header = transcode(&binary, &ascii, &options).unwrap();
+        assert!(!header.elements["vertex"].properties.contains_key("z"));
+        assert!(std::fs::read_to_string(&ascii).unwrap().contains("end_header\r\n"));
+
+        let (original, transcoded) = (read(input), read(&binary));
+        assert_eq!(transcoded.header.encoding, Encoding::BinaryBigEndian);
+        assert_eq!(original.payload, transcoded.payload);
+        let stripped = read(&ascii);
+        assert_eq!(stripped.payload["face"], original.payload["face"]);
+        for (a, b) in stripped.payload["vertex"].iter().zip(original.payload["vertex"].iter()) {
+            assert_eq!(a["x"], b["x"]);
+            assert!(!a.contains_key("z"));
+        }
+        std::fs::remove_file(binary).unwrap();
+        std::fs::remove_file(ascii).unwrap();
+    }
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn transcode_gzip() {
+        let output = std::env::temp_dir().join(format!("ply_rs_transcode_{}.ply.gz", std::process::id()));
+        let input = Path::new("example_plys/house_2_ok_ascii.ply");
+        let options = TranscodeOptions { compression: Some(Compression::Gzip(6)), ..TranscodeOptions::default() };
+        crate::ply::transcode(input, &output, &options).unwrap();
+
+        let mut decoder = flate2::read::GzDecoder::new(File::open(&output).unwrap());
+        let transcoded = Parser::<DefaultElement>::new().read_ply(&mut decoder).unwrap();
+        assert_eq!(read(input).payload, transcoded.payload);
+        std::fs::remove_file(output).unwrap();
+    }
+    #[test]
+    fn transcode_drop_unknown() {
+        let mut header = Header::new();
+        header.comments.push("checksum crc32:1c291ca3".to_string());
+        let options = TranscodeOptions { drop_elements: vec!["vertex".to_string()], ..TranscodeOptions::default() };
+        assert!(output_header(&header, &options).is_err());
+        assert!(output_header(&header, &TranscodeOptions::default()).unwrap().comments.is_empty());
+    }
+    #[test]
+    fn normalize_header_ok() {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("ply_rs_normalize_{}.ply", std::process::id()));
+        let output = dir.join(format!("ply_rs_normalize_{}_out.ply", std::process::id()));
+        let mut file = b"PLY\r\n FORMAT  Binary_Little_Endian 1.0\r\nComment  two  spaces \r\nElement face 1\r\n\
+            property LIST uint8 INT32 vertex_Index\r\nelement vertex 7\r\nproperty Float32 x\r\nEnd_Header\r\n".to_vec();
+        let payload = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 63];
+        file.extend_from_slice(&payload);
+        std::fs::write(&input, &file).unwrap();
+
+        let header = normalize_header(&input, &output, true).unwrap();
+        assert_eq!(header.elements["vertex"].count, 2);
+        let written = std::fs::read(&output).unwrap();
+        let text = "ply\nformat binary_little_endian 1.0\ncomment two  spaces \nelement face 1\n\
+            property list uchar int vertex_Index\nelement vertex 2\nproperty float x\nend_header\n";
+        assert_eq!(&written[..text.len()], text.as_bytes());
+        assert_eq!(&written[text.len()..], &payload);
+
+        std::fs::write(&input, &file[..file.len() - 1]).unwrap();
+        assert!(normalize_header(&input, &output, true).is_err());
+        assert_eq!(normalize_header(&input, &output, false).unwrap().elements["vertex"].count, 7);
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+}
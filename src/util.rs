@@ -13,3 +13,200 @@ impl LocationTracker {
         self.line_index += 1;
     }
 }
+
+use std::io;
+use std::io::ErrorKind;
+
+use crate::ply::{ ElementDef, Header, Ply, Property, PropertyAccess, PropertyDef, PropertyType, ScalarType, ScalarValue };
+
+/// Reads the scalar property described by `property_def` from `element`, converted to `f64`.
+///
+/// Returns `None` for list properties or if the element doesn't provide the value.
+pub fn get_scalar_as_f64<E: PropertyAccess>(element: &E, property_def: &PropertyDef) -> Option<f64> {
+    let k = &property_def.name;
+    match property_def.data_type {
+        PropertyType::Scalar(ScalarType::Char) => element.get_char(k).map(f64::from),
+        PropertyType::Scalar(ScalarType::UChar) => element.get_uchar(k).map(f64::from),
+        PropertyType::Scalar(ScalarType::Short) => element.get_short(k).map(f64::from),
+        PropertyType::Scalar(ScalarType::UShort) => element.get_ushort(k).map(f64::from),
+        PropertyType::Scalar(ScalarType::Int) => element.get_int(k).map(f64::from),
+        PropertyType::Scalar(ScalarType::UInt) => element.get_uint(k).map(f64::from),
+        PropertyType::Scalar(ScalarType::Float) => element.get_float(k).map(f64::from),
+        PropertyType::Scalar(ScalarType::Double) => element.get_double(k),
+        PropertyType::List(_, _) => None,
+    }
+}
+
+/// Converts `value` to the scalar type of `property_def` (rounded and saturating for integers) and stores it in `element`.
+///
+/// List properties are left untouched.
+pub fn set_scalar_from_f64<E: PropertyAccess>(element: &mut E, property_def: &PropertyDef, value: f64) {
+    let property = match property_def.data_type {
+        PropertyType::Scalar(ScalarType::Char) => Property::Char(value.round() as i8),
+        PropertyType::Scalar(ScalarType::UChar) => Property::UChar(value.round() as u8),
+        PropertyType::Scalar(ScalarType::Short) => Property::Short(value.round() as i16),
+        PropertyType::Scalar(ScalarType::UShort) => Property::UShort(value.round() as u16),
+        PropertyType::Scalar(ScalarType::Int) => Property::Int(value.round() as i32),
+        PropertyType::Scalar(ScalarType::UInt) => Property::UInt(value.round() as u32),
+        PropertyType::Scalar(ScalarType::Float) => Property::Float(value as f32),
+        PropertyType::Scalar(ScalarType::Double) => Property::Double(value),
+        PropertyType::List(_, _) => return,
+    };
+    element.set_property(property_def.name.clone(), property);
+}
+
+/// Looks up the definition of `element_name` in the header of `ply`.
+pub fn element_def<'a, E: PropertyAccess>(ply: &'a Ply<E>, element_name: &str) -> io::Result<&'a ElementDef> {
+    header_element_def(&ply.header, element_name)
+}
+
+/// Looks up the definition of `element_name` in `header`.
+pub fn header_element_def<'a>(header: &'a Header, element_name: &str) -> io::Result<&'a ElementDef> {
+    match header.elements.get(element_name) {
+        Some(e) => Ok(e),
+        None => Err(io::Error::new(ErrorKind::InvalidInput, format!("No element `{}` declared in header.", element_name))),
+    }
+}
+
+/// Number of bytes of `rows` binary encoded rows of `element_def`, `None` if it has list properties.
+///
+/// Fails with `ErrorKind::InvalidData` if the size overflows, e.g. for a huge count declared in a header.
+pub fn element_byte_len(element_def: &ElementDef, rows: usize) -> io::Result<Option<u64>> {
+    let stride = match element_def.fixed_stride() {
+        Some(s) => s as u64,
+        None => return Ok(None),
+    };
+    match stride.checked_mul(rows as u64) {
+        Some(len) => Ok(Some(len)),
+        None => Err(io::Error::new(ErrorKind::InvalidData, format!("Size of {} rows of element `{}` overflows.", rows, element_def.name))),
+    }
+}
+
+/// Adds `len` bytes to the payload position `offset`, failing with `ErrorKind::InvalidData` on overflow.
+pub fn add_offset(offset: u64, len: u64) -> io::Result<u64> {
+    offset.checked_add(len).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Payload offset overflows."))
+}
+
+/// Looks up the definitions of the properties `names` of `element_def`, in the given order.
+pub fn property_defs(element_def: &ElementDef, names: &[&str]) -> io::Result<Vec<PropertyDef>> {
+    names.iter().map(|name| match element_def.properties.get(*name) {
+        Some(p) => Ok(p.clone()),
+        None => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Element `{}` has no property `{}`.", element_def.name, name)
+        )),
+    }).collect()
+}
+
+/// Like `get_scalar_as_f64()`, but reports a missing value as error.
+pub fn require_scalar_as_f64<E: PropertyAccess>(element: &E, property_def: &PropertyDef, element_name: &str, row: usize) -> io::Result<f64> {
+    match get_scalar_as_f64(element, property_def) {
+        Some(v) => Ok(v),
+        None => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Element `{}` at index {} has no scalar value for property `{}`.", element_name, row, property_def.name)
+        )),
+    }
+}
+
+/// Reads the list property described by `property_def` from `element`, converted to `f64`.
+///
+/// Returns `None` for scalar properties or if the element doesn't provide the value.
+pub fn get_list_as_f64<E: PropertyAccess>(element: &E, property_def: &PropertyDef) -> Option<Vec<f64>> {
+    let k = &property_def.name;
+    match property_def.data_type {
+        PropertyType::List(_, ScalarType::Char) => element.get_list_char(k).map(|l| l.iter().map(|&v| f64::from(v)).collect()),
+        PropertyType::List(_, ScalarType::UChar) => element.get_list_uchar(k).map(|l| l.iter().map(|&v| f64::from(v)).collect()),
+        PropertyType::List(_, ScalarType::Short) => element.get_list_short(k).map(|l| l.iter().map(|&v| f64::from(v)).collect()),
+        PropertyType::List(_, ScalarType::UShort) => element.get_list_ushort(k).map(|l| l.iter().map(|&v| f64::from(v)).collect()),
+        PropertyType::List(_, ScalarType::Int) => element.get_list_int(k).map(|l| l.iter().map(|&v| f64::from(v)).collect()),
+        PropertyType::List(_, ScalarType::UInt) => element.get_list_uint(k).map(|l| l.iter().map(|&v| f64::from(v)).collect()),
+        PropertyType::List(_, ScalarType::Float) => element.get_list_float(k).map(|l| l.iter().map(|&v| f64::from(v)).collect()),
+        PropertyType::List(_, ScalarType::Double) => element.get_list_double(k).map(|l| l.to_vec()),
+        PropertyType::Scalar(_) => None,
+    }
+}
+
+/// Reads the scalar property `k` from `element` in whichever type it is stored, converted to `f64`.
+pub fn get_any_scalar_as_f64<E: PropertyAccess>(element: &E, k: &String) -> Option<f64> {
+    element.get_double(k)
+        .or_else(|| element.get_float(k).map(f64::from))
+        .or_else(|| element.get_int(k).map(f64::from))
+        .or_else(|| element.get_uint(k).map(f64::from))
+        .or_else(|| element.get_short(k).map(f64::from))
+        .or_else(|| element.get_ushort(k).map(f64::from))
+        .or_else(|| element.get_char(k).map(f64::from))
+        .or_else(|| element.get_uchar(k).map(f64::from))
+}
+
+/// Reads the list property `k` from `element` in whichever type it is stored, converted to `f64`.
+pub fn get_any_list_as_f64<E: PropertyAccess>(element: &E, k: &String) -> Option<Vec<f64>> {
+    fn to_f64<V: ScalarValue>(list: &[V]) -> Vec<f64> {
+        list.iter().map(|v| v.to_f64()).collect()
+    }
+    element.get_list_double(k).map(to_f64)
+        .or_else(|| element.get_list_float(k).map(to_f64))
+        .or_else(|| element.get_list_int(k).map(to_f64))
+        .or_else(|| element.get_list_uint(k).map(to_f64))
+        .or_else(|| element.get_list_short(k).map(to_f64))
+        .or_else(|| element.get_list_ushort(k).map(to_f64))
+        .or_else(|| element.get_list_char(k).map(to_f64))
+        .or_else(|| element.get_list_uchar(k).map(to_f64))
+}
+
+/// Converts `values` to the list type of `property_def` (rounded and saturating for integers) and stores them in `element`.
+///
+/// Scalar properties are left untouched.
+pub fn set_list_from_f64<E: PropertyAccess>(element: &mut E, property_def: &PropertyDef, values: &[f64]) {
+    let property = match property_def.data_type {
+        PropertyType::List(_, ScalarType::Char) => Property::ListChar(values.iter().map(|v| v.round() as i8).collect()),
+        PropertyType::List(_, ScalarType::UChar) => Property::ListUChar(values.iter().map(|v| v.round() as u8).collect()),
+        PropertyType::List(_, ScalarType::Short) => Property::ListShort(values.iter().map(|v| v.round() as i16).collect()),
+        PropertyType::List(_, ScalarType::UShort) => Property::ListUShort(values.iter().map(|v| v.round() as u16).collect()),
+        PropertyType::List(_, ScalarType::Int) => Property::ListInt(values.iter().map(|v| v.round() as i32).collect()),
+        PropertyType::List(_, ScalarType::UInt) => Property::ListUInt(values.iter().map(|v| v.round() as u32).collect()),
+        PropertyType::List(_, ScalarType::Float) => Property::ListFloat(values.iter().map(|&v| v as f32).collect()),
+        PropertyType::List(_, ScalarType::Double) => Property::ListDouble(values.to_vec()),
+        PropertyType::Scalar(_) => return,
+    };
+    element.set_property(property_def.name.clone(), property);
+}
+
+/// SplitMix64, sufficient to draw reservoir slots or sample positions.
+pub struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// Uniform value in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((u128::from(self.next()) * u128::from(n)) >> 64) as u64
+    }
+    /// Uniform value in `0.0..1.0`.
+    pub fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Declares element `name` with `properties` in `ply` and adds one row per entry of `rows`, for tests.
+///
+/// Values are assigned to the properties in order, a row may leave trailing properties unset.
+#[cfg(test)]
+pub fn add_test_element(ply: &mut Ply<crate::ply::DefaultElement>, name: &str, properties: &[(&str, PropertyType)], rows: Vec<Vec<Property>>) {
+    use crate::ply::Addable;
+    let mut element_def = ElementDef::new(name.to_string());
+    for (property, data_type) in properties {
+        element_def.properties.add(PropertyDef::new(property.to_string(), data_type.clone()));
+    }
+    element_def.count = rows.len();
+    ply.header.elements.add(element_def);
+    let list = rows.into_iter().map(|row| {
+        properties.iter().zip(row).map(|(&(property, _), value)| (property.to_string(), value)).collect()
+    }).collect();
+    ply.payload.insert(name.to_string(), list);
+}
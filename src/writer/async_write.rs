@@ -0,0 +1,252 @@
+//! Writing PLY files to asynchronous targets.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use tokio::io::{ AsyncWrite, AsyncWriteExt };
+
+use super::{ BATCH_BYTES, Writer, row_error };
+use super::stream::current;
+use crate::ply::{ ElementDef, Encoding, Header, Payload, Ply, PropertyAccess };
+
+/// Writes to a tokio `AsyncWrite` with the settings of a `Writer`.
+///
+/// Rows are encoded in memory like by `Writer` and written in batches of about 64 KiB,
+/// so no thread is blocked while the target isn't ready.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ply_rs::*;
+/// # async fn serve(ply: &mut ply::Ply<ply::DefaultElement>) -> std::io::Result<()> {
+/// let mut out = tokio::fs::File::create("scan.ply").await?;
+/// let w = writer::AsyncWriter::new(writer::Writer::new());
+/// w.write_ply(&mut out, ply).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncWriter<E: PropertyAccess> {
+    writer: Writer<E>,
+}
+
+impl<E: PropertyAccess> AsyncWriter<E> {
+    /// Writes with the settings of `writer`.
+    pub fn new(writer: Writer<E>) -> Self {
+        AsyncWriter { writer }
+    }
+    /// The settings used.
+    pub fn writer(&self) -> &Writer<E> {
+        &self.writer
+    }
+    /// The settings used, to change them.
+    pub fn writer_mut(&mut self) -> &mut Writer<E> {
+        &mut self.writer
+    }
+    /// Writes an entire PLY file, see `Writer::write_ply()`.
+    pub async fn write_ply<T: AsyncWrite + Unpin>(&self, out: &mut T, ply: &mut Ply<E>) -> Result<u64> {
+        self.writer.__prepare(ply)?;
+        self.write_ply_unchecked(out, ply).await
+    }
+    /// Writes an entire PLY file without checking it, see `Writer::write_ply_unchecked()`.
+    pub async fn write_ply_unchecked<T: AsyncWrite + Unpin>(&self, out: &mut T, ply: &Ply<E>) -> Result<u64> {
+        let mut written = self.write_header(out, &ply.header).await?;
+        written += self.write_payload(out, &ply.payload, &ply.header).await?;
+        out.flush().await?;
+        Ok(written)
+    }
+    /// Writes the header, see `Writer::write_header()`.
+    pub async fn write_header<T: AsyncWrite + Unpin>(&self, out: &mut T, header: &Header) -> Result<u64> {
+        let mut buf = Vec::new();
+        self.writer.write_header(&mut buf, header)?;
+        out.write_all(&buf).await?;
+        Ok(buf.len() as u64)
+    }
+    /// Writes the payload, see `Writer::write_payload()`.
+    pub async fn write_payload<T: AsyncWrite + Unpin>(&self, out: &mut T, payload: &Payload<E>, header: &Header) -> Result<u64> {
+        let header = &*self.writer.__projected(header);
+        let mut written = 0;
+        for (k, element_def) in &header.elements {
+            if let Some(element_list) = payload.get(k) {
+                written += self.write_payload_of_element(out, element_list, element_def, header).await?;
+            }
+        }
+        Ok(written)
+    }
+    /// Writes all rows of one element, see `Writer::write_payload_of_element()`.
+    pub async fn write_payload_of_element<T: AsyncWrite + Unpin>(&self, out: &mut T, element_list: &[E], element_def: &ElementDef, header: &Header) -> Result<u64> {
+        let mut buf = Vec::with_capacity(BATCH_BYTES);
+        if header.encoding == Encoding::Ascii && self.writer.align_columns && !self.writer.canonical {
+            self.writer.__write_aligned_elements(&mut buf, element_list, element_def)?;
+            out.write_all(&buf).await?;
+            return Ok(buf.len() as u64);
+        }
+        let mut written = 0;
+        for (index, element) in element_list.iter().enumerate() {
+            self.writer.__encode_element(&mut buf, element, element_def, &header.encoding).map_err(|e| row_error(e, index))?;
+            if buf.len() >= BATCH_BYTES {
+                out.write_all(&buf).await?;
+                written += buf.len() as u64;
+                buf.clear();
+            }
+        }
+        out.write_all(&buf).await?;
+        Ok(written + buf.len() as u64)
+    }
+    /// Writes a single element, see `Writer::write_element()`.
+    pub async fn write_element<T: AsyncWrite + Unpin>(&self, out: &mut T, element: &E, element_def: &ElementDef, encoding: &Encoding) -> Result<u64> {
+        let mut buf = Vec::new();
+        self.writer.__encode_element(&mut buf, element, element_def, encoding)?;
+        out.write_all(&buf).await?;
+        Ok(buf.len() as u64)
+    }
+    /// Starts a streaming write to `out`, see `AsyncStreamWriter`.
+    pub fn stream<T: AsyncWrite + Unpin>(&self, out: T) -> AsyncStreamWriter<'_, T, E> {
+        AsyncStreamWriter {
+            writer: self,
+            out,
+            header: None,
+            element: 0,
+            count: 0,
+            written: 0,
+        }
+    }
+}
+
+/// Writes a PLY file element by element to an asynchronous target, like `StreamWriter`.
+///
+/// Created by `AsyncWriter::stream()`. The order and counts declared in the header are checked the same way,
+/// the counts have to be known in advance. Like there, the projection of `Writer::set_projection()` applies.
+pub struct AsyncStreamWriter<'a, T: AsyncWrite + Unpin, E: PropertyAccess> {
+    writer: &'a AsyncWriter<E>,
+    out: T,
+    /// Header written by `begin()`.
+    header: Option<Header>,
+    /// Index of the element written next.
+    element: usize,
+    /// Rows of the current element already written.
+    count: usize,
+    written: u64,
+}
+
+impl<'a, T: AsyncWrite + Unpin, E: PropertyAccess> AsyncStreamWriter<'a, T, E> {
+    /// Writes `header`, the payload has to follow it.
+    pub async fn begin(&mut self, header: &Header) -> Result<u64> {
+        if self.header.is_some() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "The header has already been written."));
+        }
+        let written = self.writer.write_header(&mut self.out, header).await?;
+        self.written += written;
+        self.header = Some(self.writer.writer.__projected(header).into_owned());
+        Ok(written)
+    }
+    /// Writes a row of element `name`, which has to be the next element of the header not ended yet.
+    pub async fn append_element(&mut self, name: &str, element: &E) -> Result<u64> {
+        if self.header.is_some() && !self.writer.writer.projection.keeps_element(name) {
+            return Ok(0);
+        }
+        let (encoding, element_def) = current(&self.header, self.element, name)?;
+        if self.count == element_def.count {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` declares {} entries, all have already been written.", name, element_def.count)
+            ));
+        }
+        let written = self.writer.write_element(&mut self.out, element, element_def, &encoding).await?;
+        self.written += written;
+        self.count += 1;
+        Ok(written)
+    }
+    /// Ends element `name`, fails unless all rows declared have been written.
+    pub fn end_element(&mut self, name: &str) -> Result<()> {
+        if self.header.is_some() && !self.writer.writer.projection.keeps_element(name) {
+            return Ok(());
+        }
+        let (_, element_def) = current(&self.header, self.element, name)?;
+        if self.count != element_def.count {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` declares {} entries, but only {} have been written.", name, element_def.count, self.count)
+            ));
+        }
+        self.element += 1;
+        self.count = 0;
+        Ok(())
+    }
+    /// Number of bytes written so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+    /// Completes the file, flushes and returns the underlying target, fails if elements haven't been ended.
+    pub async fn finish(mut self) -> Result<T> {
+        let header = match self.header {
+            Some(ref h) => h,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, "The header hasn't been written.")),
+        };
+        if let Some(e) = header.elements.values().nth(self.element) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Element `{}` hasn't been ended.", e.name)));
+        }
+        self.out.flush().await?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::ply::DefaultElement;
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+    }
+    #[test]
+    fn async_writer_ok() {
+        let p = Parser::<DefaultElement>::new();
+        for path in &["example_plys/greg_turk_example1_ok_ascii.ply", "example_plys/house_2_ok_little_endian.ply"] {
+            let mut ply = p.read_ply_from_path(path).unwrap();
+            let mut expected = Vec::new();
+            Writer::new().write_ply(&mut expected, &mut ply).unwrap();
+            let w = AsyncWriter::new(Writer::new());
+            let mut buf = Vec::new();
+            assert_eq!(block_on(w.write_ply(&mut buf, &mut ply)).unwrap(), expected.len() as u64);
+            assert_eq!(buf, expected);
+
+            let mut stream = w.stream(Vec::new());
+            block_on(async {
+                assert!(stream.append_element("vertex", &ply.payload["vertex"][0]).await.is_err());
+                stream.begin(&ply.header).await.unwrap();
+                for (k, rows) in &ply.payload {
+                    for row in rows {
+                        stream.append_element(k, row).await.unwrap();
+                    }
+                    assert!(stream.append_element(k, &rows[0]).await.is_err());
+                    stream.end_element(k).unwrap();
+                }
+            });
+            assert_eq!(stream.written(), expected.len() as u64);
+            assert_eq!(block_on(stream.finish()).unwrap(), expected);
+        }
+    }
+    #[test]
+    fn async_stream_projection_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut ply = p.read_ply_from_path("example_plys/house_2_ok_little_endian.ply").unwrap();
+        let mut writer = Writer::new();
+        writer.set_projection(crate::parser::Projection::all().exclude("vertex", &["z"]).exclude("face", &[]));
+        let mut expected = Vec::new();
+        writer.write_ply(&mut expected, &mut ply).unwrap();
+        let w = AsyncWriter::new(writer);
+        let mut stream = w.stream(Vec::new());
+        block_on(async {
+            stream.begin(&ply.header).await.unwrap();
+            for (k, rows) in &ply.payload {
+                for row in rows {
+                    stream.append_element(k, row).await.unwrap();
+                }
+                stream.end_element(k).unwrap();
+            }
+        });
+        let buf = block_on(stream.finish()).unwrap();
+        assert_eq!(buf, expected);
+        assert!(!p.parse_slice(&buf).unwrap().payload["vertex"][0].contains_key("z"));
+    }
+}
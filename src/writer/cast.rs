@@ -0,0 +1,126 @@
+//! Converting values stored in another type than declared in the header while writing.
+
+use std::borrow::Cow;
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::Writer;
+use crate::ply::{ OutOfRange, Property, PropertyAccess, ScalarValue };
+use crate::util::{ get_any_list_as_f64, get_any_scalar_as_f64 };
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Converts values stored in another type than declared in the header, default is `None`.
+    ///
+    /// With `None`, a value not stored in its declared type fails the write.
+    /// Otherwise the header is taken as target schema: values are converted like by `Ply::cast_column()`,
+    /// integers are rounded and values out of range are handled according to the policy.
+    /// Lists are converted entry by entry, their index type is always taken from the header.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::Writer;
+    /// let mut point = ElementDef::new("point".to_string());
+    /// point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)));
+    /// point.properties.add(PropertyDef::new("i".to_string(), PropertyType::Scalar(ScalarType::UChar)));
+    /// let mut element = DefaultElement::new();
+    /// element.insert("x".to_string(), Property::Double(0.5));
+    /// element.insert("i".to_string(), Property::Int(300));
+    ///
+    /// let mut w = Writer::new();
+    /// let mut buf = Vec::<u8>::new();
+    /// assert!(w.write_little_endian_element(&mut buf, &element, &point).is_err());
+    /// w.set_cast_policy(Some(OutOfRange::Clamp));
+    /// w.write_little_endian_element(&mut buf, &element, &point).unwrap();
+    /// assert_eq!(buf, [0, 0, 0, 63, 255]);
+    /// ```
+    pub fn set_cast_policy(&mut self, policy: Option<OutOfRange>) {
+        self.cast_policy = policy;
+    }
+    /// Rejects converted values that don't keep their exact value, e.g. `0.1` as `float` or `1.5` as `int`, default is `false`.
+    ///
+    /// Only applies to values converted with a policy set with `set_cast_policy()`.
+    pub fn set_exact_casts(&mut self, exact: bool) {
+        self.exact_casts = exact;
+    }
+    /// Converts the scalar `k` of `element` from the type it is stored in to `V`.
+    pub(super) fn __cast_scalar<V: ScalarValue>(&self, element: &E, k: &String) -> Result<V> {
+        let policy = match self.cast_policy {
+            Some(policy) => policy,
+            None => return Err(missing()),
+        };
+        match get_any_scalar_as_f64(element, k) {
+            Some(value) => cast(value, policy, self.exact_casts),
+            None => Err(missing()),
+        }
+    }
+    /// Converts the list `k` of `element` from the type it is stored in to a list of `V`.
+    pub(super) fn __cast_list<'a, V: ScalarValue>(&self, element: &'a E, k: &String) -> Result<Cow<'a, [V]>> {
+        let policy = match self.cast_policy {
+            Some(policy) => policy,
+            None => return Err(missing()),
+        };
+        match get_any_list_as_f64(element, k) {
+            Some(values) => values.into_iter().map(|v| cast(v, policy, self.exact_casts)).collect::<Result<Vec<V>>>().map(Cow::Owned),
+            None => Err(missing()),
+        }
+    }
+}
+
+fn missing() -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, "No property available for given key.")
+}
+
+/// Converts `value` to `V`, handling values out of range according to `policy`.
+///
+/// With `exact`, values in range have to be converted without loss.
+fn cast<V: ScalarValue>(value: f64, policy: OutOfRange, exact: bool) -> Result<V> {
+    let in_range = Property::from_f64(value, &V::SCALAR_TYPE, OutOfRange::Error);
+    let converted = match in_range.as_ref().map(as_f64).or_else(|| Property::from_f64(value, &V::SCALAR_TYPE, policy).as_ref().map(as_f64)) {
+        Some(v) => V::from_f64(v),
+        None => return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Value {} is out of range for type {:?}.", value, V::SCALAR_TYPE)
+        )),
+    };
+    if exact && in_range.is_some() && converted.to_f64() != value && !value.is_nan() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Value {} can't be stored exactly as type {:?}.", value, V::SCALAR_TYPE)
+        ));
+    }
+    Ok(converted)
+}
+
+/// Value of a scalar created by `Property::from_f64()`.
+fn as_f64(property: &Property) -> f64 {
+    match *property {
+        Property::Char(v) => f64::from(v),
+        Property::UChar(v) => f64::from(v),
+        Property::Short(v) => f64::from(v),
+        Property::UShort(v) => f64::from(v),
+        Property::Int(v) => f64::from(v),
+        Property::UInt(v) => f64::from(v),
+        Property::Float(v) => f64::from(v),
+        Property::Double(v) => v,
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn cast_ok() {
+        assert_eq!(cast::<f32>(0.1, OutOfRange::Error, false).unwrap(), 0.1f32);
+        assert!(cast::<f32>(0.1, OutOfRange::Error, true).is_err());
+        assert_eq!(cast::<f32>(0.5, OutOfRange::Error, true).unwrap(), 0.5);
+        assert_eq!(cast::<i16>(-2.6, OutOfRange::Error, false).unwrap(), -3);
+        assert!(cast::<i16>(-2.6, OutOfRange::Error, true).is_err());
+        assert!(cast::<u8>(256.0, OutOfRange::Error, false).is_err());
+        assert_eq!(cast::<u8>(256.0, OutOfRange::Clamp, true).unwrap(), 255);
+        assert_eq!(cast::<u8>(256.0, OutOfRange::Wrap, false).unwrap(), 0);
+        assert!(cast::<f64>(f64::NAN, OutOfRange::Error, true).unwrap().is_nan());
+    }
+}
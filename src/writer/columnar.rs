@@ -0,0 +1,62 @@
+//! Writing a payload stored in columns.
+
+use std::io;
+use std::io::{ ErrorKind, Result, Write };
+
+use super::Writer;
+use crate::ply::{ ColumnarPly, ColumnarRow, Header };
+
+impl<'a> Writer<ColumnarRow<'a>> {
+    /// Writes an entire PLY file stored in columns to `out`, like `write_ply()`.
+    ///
+    /// Element counts are taken from the columns, which have to hold one column of the declared type
+    /// per property, all of the same length. Returns number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let mut ply = p.read_ply_from_path("example_plys/house_2_ok_ascii.ply").unwrap();
+    /// let columnar = ply::ColumnarPly::from_ply(&ply).unwrap();
+    /// let mut buf = Vec::<u8>::new();
+    /// writer::Writer::new().write_columnar_ply(&mut buf, &columnar).unwrap();
+    /// let mut expected = Vec::<u8>::new();
+    /// writer::Writer::new().write_ply(&mut expected, &mut ply).unwrap();
+    /// assert_eq!(buf, expected);
+    /// ```
+    pub fn write_columnar_ply<T: Write>(&self, out: &mut T, ply: &'a ColumnarPly) -> Result<u64> {
+        let header = counted_header(ply)?;
+        self.__compressed(out, |mut out| {
+            let mut written = self.write_header(&mut out, &header)?;
+            let projected = self.__projected(&header);
+            for element_def in projected.elements.values() {
+                let rows = (0..element_def.count).map(|index| ply.row(&element_def.name, index)).collect::<Vec<_>>();
+                written += self.write_payload_of_element(&mut out, &rows, element_def, &projected)?;
+            }
+            out.flush()?;
+            Ok(written)
+        })
+    }
+}
+
+/// The header of `ply` with the counts of its columns, checking that they match the declared properties.
+fn counted_header(ply: &ColumnarPly) -> Result<Header> {
+    let mut header = ply.header.clone();
+    for (k, element_def) in header.elements.iter_mut() {
+        element_def.count = ply.rows(k);
+        for (name, p) in &element_def.properties {
+            match ply.payload.get(k).and_then(|columns| columns.get(name)) {
+                Some(column) if !column.has_type(&p.data_type) => return Err(inconsistent(format!("Column `{}` of element `{}` doesn't have the declared type {:?}.", name, k, p.data_type))),
+                Some(column) if column.len() != element_def.count => return Err(inconsistent(format!("Columns of element `{}` have different lengths.", k))),
+                Some(_) => (),
+                None => return Err(inconsistent(format!("No column for property `{}` of element `{}` found.", name, k))),
+            }
+        }
+    }
+    Ok(header)
+}
+
+fn inconsistent(description: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput, format!("The given ply isn't consistent: {}", description))
+}
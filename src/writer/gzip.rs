@@ -0,0 +1,33 @@
+//! Writing gzip compressed PLY files.
+
+use std::io::{ Result, Write };
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use super::Writer;
+use crate::ply::{ Ply, PropertyAccess };
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Writes `ply` gzip compressed to `out`, like `write_ply()`, and finishes the gzip stream.
+    ///
+    /// Returns the number of bytes written before compression. The compression set with `set_compression()` is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::{ Ply, DefaultElement };
+    /// # use ply_rs::writer::Writer;
+    /// # let mut ply = Ply::<DefaultElement>::new();
+    /// let mut buf = Vec::<u8>::new();
+    /// Writer::new().write_ply_gz(&mut buf, &mut ply).unwrap();
+    /// assert_eq!(buf[..2], [0x1f, 0x8b]);
+    /// ```
+    pub fn write_ply_gz<T: Write>(&self, out: &mut T, ply: &mut Ply<E>) -> Result<u64> {
+        let mut encoder = GzEncoder::new(out, Compression::default());
+        self.__prepare(ply)?;
+        let written = self.__write_ply_unchecked(&mut encoder, ply)?;
+        encoder.finish()?;
+        Ok(written)
+    }
+}
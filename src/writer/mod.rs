@@ -3,6 +3,39 @@
 use std::marker::PhantomData;
 use crate::ply::PropertyAccess;
 
+#[cfg(feature = "async")]
+mod async_write;
+#[cfg(feature = "async")]
+pub use self::async_write::{ AsyncStreamWriter, AsyncWriter };
+mod cast;
+mod columnar;
+#[cfg(feature = "gzip")]
+mod gzip;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod path;
+#[cfg(feature = "bytemuck")]
+mod pod;
+mod progress;
+mod provenance;
+mod size;
+mod staged;
+pub use self::staged::*;
+mod stream;
+pub use self::stream::StreamWriter;
+mod text;
+#[cfg(feature = "zstd")]
+mod zstd;
+
+/// Writes all of `bytes` to `out`, returns their number.
+fn write_bytes<T: Write + ?Sized>(out: &mut T, bytes: &[u8]) -> Result<u64> {
+    out.write_all(bytes)?;
+    Ok(bytes.len() as u64)
+}
+
+/// Number of bytes of encoded rows collected before they are written to the output.
+const BATCH_BYTES: usize = 1 << 16;
+
 /// Writes a `Ply` to a `Write` trait.
 ///
 /// The simplest function to start with is `write_ply()`.
@@ -39,19 +72,122 @@ use crate::ply::PropertyAccess;
 pub struct Writer<E: PropertyAccess> {
     /// Should be fairly efficient, se `as_bytes()` in https://doc.rust-lang.org/src/collections/string.rs.html#1001
     new_line: String,
+    line_breaks: LineBreakPolicy,
+    names: NamePolicy,
+    align_columns: bool,
+    separator: Separator,
+    type_names: TypeNames,
+    non_finite: NonFinitePolicy,
+    float_notation: FloatNotation,
+    float_precision: Option<FloatPrecision>,
+    /// Precision of individual properties, by element and property name.
+    property_float_precision: HashMap<String, HashMap<String, Option<FloatPrecision>>>,
+    consistency_check: bool,
+    canonical: bool,
+    projection: Projection,
+    cast_policy: Option<OutOfRange>,
+    exact_casts: bool,
+    generator_comment: bool,
+    /// Lines added with `add_provenance()`.
+    provenance: Vec<(String, String)>,
+    progress: Option<progress::Progress>,
+    compression: Compression,
+    #[cfg(feature = "zstd")]
+    zstd_level: i32,
     phantom: PhantomData<E>,
 }
 
+/// Handling of line breaks in comments and object informations, which would corrupt the header.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineBreakPolicy {
+    /// Fail to write the line.
+    Reject,
+    /// Write `\n` and `\r` as the two characters `\\n` and `\\r`.
+    Escape,
+}
+
+/// Compression of the files written by `Writer::write_ply()`, see `Writer::set_compression()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Compression {
+    /// Writes the file as is.
+    None,
+    /// gzip with a level from `0` (none) to `9` (smallest).
+    #[cfg(feature = "gzip")]
+    Gzip(u32),
+    /// zstd with a level from `1` (fastest) to `22` (smallest), `0` selects the default level.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+/// Handling of element and property names containing white space or control characters, which would corrupt the header.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NamePolicy {
+    /// Fail to write the definition.
+    Reject,
+    /// Write each invalid character as this character, e.g. `'_'`. Values are still looked up by the original name.
+    Replace(char),
+}
+
+/// Character between the values of ascii rows, see `Writer::set_separator()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Separator {
+    /// A single space, as written by most exporters.
+    Space,
+    /// A tab, for pasting rows into spreadsheet tools.
+    Tab,
+}
+
+/// Spelling of scalar types in `property` lines, the parser accepts both.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TypeNames {
+    /// Names of the original specification: `char`, `uchar`, `short`, `ushort`, `int`, `uint`, `float`, `double`.
+    Classic,
+    /// Names with bit sizes: `int8`, `uint8`, `int16`, `uint16`, `int32`, `uint32`, `float32`, `float64`.
+    Sized,
+}
+
+/// Handling of `NaN` and infinite float values in ascii output, which many readers reject.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NonFinitePolicy {
+    /// Fail to write the element, the default since the default `Strictness::Strict` of the parser rejects the tokens.
+    Error,
+    /// Write the tokens `nan`, `inf` and `-inf`, as accepted by `Strictness::Lenient`.
+    Tokens,
+    /// Write this value instead.
+    Substitute(f64),
+}
+
+/// Notation of `float` and `double` values in ascii output, see `Writer::set_float_notation()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FloatNotation {
+    /// Decimal or exponent notation, whichever is shorter, e.g. `0.25` and `1e-7`.
+    Shortest,
+    /// Always exponent notation, e.g. `2.5e-1`, as written by MeshLab or PCL.
+    Scientific,
+}
+
+/// Precision of `float` and `double` values in ascii output, see `Writer::set_float_precision()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FloatPrecision {
+    /// Rounds to this many significant digits, e.g. `123.5` for `123.456` with 4 digits.
+    Significant(usize),
+    /// Writes this many digits after the decimal point, e.g. `123.46` for `123.456` with 2 decimals.
+    Decimals(usize),
+}
+
 /*
 use std::marker::PhantomData;
 use writer::Writer;
 use ply::PropertyAccess;
 // */
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io;
 use std::io::{ Write, Result, ErrorKind };
 
-use crate::ply::Ply;
+use crate::parser::Projection;
+use crate::ply::{ OutOfRange, Ply };
 
 // ////////////////////////////
 // General
@@ -61,21 +197,295 @@ impl<E: PropertyAccess> Writer<E> {
     pub fn new() -> Self {
         Writer {
             new_line: "\n".to_string(),
+            line_breaks: LineBreakPolicy::Reject,
+            names: NamePolicy::Reject,
+            align_columns: false,
+            separator: Separator::Space,
+            type_names: TypeNames::Classic,
+            non_finite: NonFinitePolicy::Error,
+            float_notation: FloatNotation::Shortest,
+            float_precision: None,
+            property_float_precision: HashMap::new(),
+            consistency_check: true,
+            canonical: false,
+            projection: Projection::all(),
+            cast_policy: None,
+            exact_casts: false,
+            generator_comment: false,
+            provenance: Vec::new(),
+            progress: None,
+            compression: Compression::None,
+            #[cfg(feature = "zstd")]
+            zstd_level: ::zstd::DEFAULT_COMPRESSION_LEVEL,
             phantom: PhantomData,
         }
     }
-    /// Writes an entire PLY file modeled by `ply` to `out`, performs consistency chekc.
+    /// Create a new `Writer<E>` compressing the files it writes, see `set_compression()`.
+    pub fn with_compression(compression: Compression) -> Self {
+        let mut writer = Self::new();
+        writer.set_compression(compression);
+        writer
+    }
+    /// Sets the compression of whole files written by `write_ply()`, `write_ply_unchecked()` and `write_ply_parallel()`, default is `Compression::None`.
+    ///
+    /// The compressed stream is finished before they return, so `out` holds a complete file.
+    /// They still return the number of bytes written before compression.
+    /// The other `write_` functions write parts of a file and aren't affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// let w = writer::Writer::with_compression(writer::Compression::None);
+    /// let mut buf = Vec::<u8>::new();
+    /// assert_eq!(w.write_ply(&mut buf, &mut ply).unwrap(), buf.len() as u64);
+    /// ```
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+    /// Calls `write` with `out` wrapped in the encoder of the compression, then finishes the compressed stream.
+    pub(crate) fn __compressed<T: Write>(&self, out: &mut T, write: impl FnOnce(&mut dyn Write) -> Result<u64>) -> Result<u64> {
+        match self.compression {
+            Compression::None => write(out),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip(level) => {
+                let mut encoder = flate2::write::GzEncoder::new(out, flate2::Compression::new(level));
+                let written = write(&mut encoder)?;
+                encoder.finish()?.flush()?;
+                Ok(written)
+            },
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => {
+                let mut encoder = ::zstd::stream::write::Encoder::new(out, level)?;
+                let written = write(&mut encoder)?;
+                encoder.finish()?.flush()?;
+                Ok(written)
+            },
+        }
+    }
+    /// Sets the line terminator of header lines and ascii elements, default is `"\n"`.
+    ///
+    /// Use `"\r\n"` for tools expecting Windows line endings.
+    pub fn set_new_line(&mut self, new_line: &str) {
+        self.new_line = new_line.to_string();
+    }
+    /// Sets how line breaks in comments and object informations are handled, default is `LineBreakPolicy::Reject`.
+    pub fn set_line_break_policy(&mut self, policy: LineBreakPolicy) {
+        self.line_breaks = policy;
+    }
+    /// Sets how names of elements and properties that aren't valid header tokens are handled, default is `NamePolicy::Reject`.
+    ///
+    /// Empty names are always rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::{ NamePolicy, Writer };
+    /// let p = PropertyDef::new("red value".to_string(), PropertyType::Scalar(ScalarType::UChar));
+    /// let mut w = Writer::<DefaultElement>::new();
+    /// let mut buf = Vec::<u8>::new();
+    /// assert!(w.write_line_property_definition(&mut buf, &p).is_err());
+    /// w.set_name_policy(NamePolicy::Replace('_'));
+    /// w.write_line_property_definition(&mut buf, &p).unwrap();
+    /// assert_eq!(buf, b"property uchar red_value\n");
+    /// ```
+    pub fn set_name_policy(&mut self, policy: NamePolicy) {
+        self.names = policy;
+    }
+    /// Pads ascii values to aligned columns, right-aligned to the widest value of each property.
+    ///
+    /// Applies to `write_ply()`, `write_payload()` and `write_payload_of_element()`,
+    /// which format all rows of an element before writing them. Meant for small, human readable exports.
+    pub fn set_align_columns(&mut self, align: bool) {
+        self.align_columns = align;
+    }
+    /// Sets the character between values of ascii rows, including the entries of lists, default is `Separator::Space`.
+    ///
+    /// Aligned columns are still padded with spaces, after the separator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::{ Separator, Writer };
+    /// let mut face = ElementDef::new("face".to_string());
+    /// face.properties.add(PropertyDef::new("i".to_string(), PropertyType::Scalar(ScalarType::Int)));
+    /// face.properties.add(PropertyDef::new("v".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int)));
+    /// let mut element = DefaultElement::new();
+    /// element.insert("i".to_string(), Property::Int(7));
+    /// element.insert("v".to_string(), Property::ListInt(vec![1, 2]));
+    ///
+    /// let mut w = Writer::new();
+    /// w.set_separator(Separator::Tab);
+    /// let mut buf = Vec::<u8>::new();
+    /// w.write_ascii_element(&mut buf, &element, &face).unwrap();
+    /// assert_eq!(buf, b"7\t2\t1\t2\t\n");
+    /// ```
+    pub fn set_separator(&mut self, separator: Separator) {
+        self.separator = separator;
+    }
+    fn __separator(&self) -> &'static [u8] {
+        match self.separator {
+            _ if self.canonical => b" ",
+            Separator::Space => b" ",
+            Separator::Tab => b"\t",
+        }
+    }
+    /// Sets the spelling of scalar types in the header, default is `TypeNames::Classic`.
+    ///
+    /// Use `TypeNames::Sized` to match files of exporters like Open3D.
+    pub fn set_type_names(&mut self, type_names: TypeNames) {
+        self.type_names = type_names;
+    }
+    /// Sets how `NaN` and infinite values are written in ascii output, default is `NonFinitePolicy::Error`.
+    ///
+    /// Binary output stores them as they are.
+    pub fn set_non_finite_policy(&mut self, policy: NonFinitePolicy) {
+        self.non_finite = policy;
+    }
+    /// Sets the notation of `float` and `double` values in ascii output, default is `FloatNotation::Shortest`.
+    ///
+    /// With `FloatNotation::Scientific`, a `FloatPrecision` applies to the digits of the mantissa.
+    pub fn set_float_notation(&mut self, notation: FloatNotation) {
+        self.float_notation = notation;
+    }
+    /// Sets the precision of `float` and `double` values in ascii output, default is `None`.
+    ///
+    /// `None` writes the shortest representation that reads back to the same value.
+    /// Non-finite values aren't affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::{ FloatPrecision, Writer };
+    /// let mut point = ElementDef::new("point".to_string());
+    /// point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Double)));
+    /// point.properties.add(PropertyDef::new("y".to_string(), PropertyType::Scalar(ScalarType::Double)));
+    /// let mut element = DefaultElement::new();
+    /// element.insert("x".to_string(), Property::Double(0.1 + 0.2));
+    /// element.insert("y".to_string(), Property::Double(0.1 + 0.2));
+    ///
+    /// let mut w = Writer::new();
+    /// w.set_float_precision(Some(FloatPrecision::Significant(3)));
+    /// w.set_property_float_precision("point", "y", Some(FloatPrecision::Decimals(2)));
+    /// let mut buf = Vec::<u8>::new();
+    /// w.write_ascii_element(&mut buf, &element, &point).unwrap();
+    /// assert_eq!(buf, b"0.3 0.30 \n");
+    /// ```
+    pub fn set_float_precision(&mut self, precision: Option<FloatPrecision>) {
+        self.float_precision = precision;
+    }
+    /// Overrides the precision set with `set_float_precision()` for `property` of `element`.
+    pub fn set_property_float_precision(&mut self, element: &str, property: &str, precision: Option<FloatPrecision>) {
+        self.property_float_precision.entry(element.to_string()).or_default().insert(property.to_string(), precision);
+    }
+    fn __float_precision(&self, element: &str, property: &str) -> Option<FloatPrecision> {
+        if self.canonical {
+            return None;
+        }
+        match self.property_float_precision.get(element).and_then(|p| p.get(property)) {
+            Some(&precision) => precision,
+            None => self.float_precision,
+        }
+    }
+    /// Writes byte-identical output for equal inputs, default is `false`.
+    ///
+    /// Overrides the settings affecting the layout: lines end with `"\n"`, floats are written in their shortest
+    /// round-trip form, scalar types get their classic names, values are separated by single spaces without alignment
+    /// and ascii rows have no trailing space.
+    /// Elements and properties are always written in header order. Values are written as stored, so `-0.0` stays distinct from `0.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::Writer;
+    /// let mut point = ElementDef::new("point".to_string());
+    /// point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)));
+    /// let mut element = DefaultElement::new();
+    /// element.insert("x".to_string(), Property::Float(0.5));
+    ///
+    /// let mut w = Writer::new();
+    /// w.set_new_line("\r\n");
+    /// w.set_canonical(true);
+    /// let mut buf = Vec::<u8>::new();
+    /// w.write_ascii_element(&mut buf, &element, &point).unwrap();
+    /// assert_eq!(buf, b"0.5\n");
+    /// ```
+    pub fn set_canonical(&mut self, canonical: bool) {
+        self.canonical = canonical;
+    }
+    /// Sets the elements and properties written, default is `Projection::all()`.
+    ///
+    /// Applies to `write_ply()`, `write_header()` and `write_payload()`, which write the header reduced by
+    /// `Projection::project()` and only the matching values. The `Ply` itself isn't modified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new()
+    ///     .read_ply(&mut std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap()).unwrap();
+    /// let mut w = writer::Writer::new();
+    /// w.set_projection(parser::Projection::all().exclude("vertex", &["z"]).exclude("face", &[]));
+    /// let mut buf = Vec::<u8>::new();
+    /// w.write_ply(&mut buf, &mut ply).unwrap();
+    /// let written = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut &buf[..]).unwrap();
+    /// assert_eq!(written.header.elements["vertex"].properties.len(), 2);
+    /// assert!(!written.payload.contains_key("face"));
+    /// ```
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+    /// The header as written with the projection set with `set_projection()`.
+    fn __projected<'h>(&self, header: &'h Header) -> Cow<'h, Header> {
+        match self.projection.is_all() {
+            true => Cow::Borrowed(header),
+            false => Cow::Owned(self.projection.project(header)),
+        }
+    }
+    /// Sets whether `write_ply()` runs `Ply::check()` before writing, ignoring elements dropped by `set_projection()`, default is `true`.
+    ///
+    /// Disabling it saves a pass over the payload for data known to be consistent.
+    pub fn set_consistency_check(&mut self, check: bool) {
+        self.consistency_check = check;
+    }
+    /// Writes an entire PLY file modeled by `ply` to `out`, performs consistency check.
     ///
     /// `ply` must be mutable since a consistency check is performed.
     /// If problems can be corrected automatically, `ply` will be modified accordingly.
+    /// Problems that can't are reported before anything is written, see `Ply::check()` and `set_consistency_check()`.
+    /// This includes escaping line breaks in comments and object informations with `LineBreakPolicy::Escape`.
     ///
     /// Returns number of bytes written.
-    pub fn write_ply<T: Write>(&self, out: &mut T, ply: &mut Ply<E>) -> Result<usize> {
-        match ply.make_consistent() {
+    pub fn write_ply<T: Write>(&self, out: &mut T, ply: &mut Ply<E>) -> Result<u64> {
+        self.__prepare(ply)?;
+        self.write_ply_unchecked(out, ply)
+    }
+    /// Makes `ply` consistent and checks it as described for `write_ply()`.
+    fn __prepare(&self, ply: &mut Ply<E>) -> Result<()> {
+        if self.line_breaks == LineBreakPolicy::Escape {
+            for text in ply.header.comments.iter_mut().chain(ply.header.obj_infos.iter_mut()) {
+                if let Cow::Owned(escaped) = escape_line_breaks(text) {
+                    *text = escaped;
+                }
+            }
+        }
+        let check_names = self.names == NamePolicy::Reject;
+        match ply.make_consistent_with(check_names) {
             Ok(()) => (),
             Err(e) => return Err(io::Error::new(ErrorKind::InvalidInput, format!("The given ply isn't consistent: {:?}", e))),
         };
-        self.write_ply_unchecked(out, ply)
+        if self.consistency_check {
+            if let Err(e) = ply.check_declared(&self.__projected(&ply.header), self.cast_policy.is_some(), check_names) {
+                return Err(io::Error::new(ErrorKind::InvalidInput, format!("The given ply isn't consistent: {:?}", e)));
+            }
+        }
+        Ok(())
     }
     /// Writes an entire PLY file modeled by `ply` to `out`, performes no consistency check.
     ///
@@ -83,15 +493,73 @@ impl<E: PropertyAccess> Writer<E> {
     /// The user is responsible to provide a consistent `Ply`,
     /// if not, behaviour is undefined and might result
     /// in a corrupted output.
-    pub fn write_ply_unchecked<T: Write>(&self, out: &mut T, ply: &Ply<E>) -> Result<usize> {
+    pub fn write_ply_unchecked<T: Write>(&self, out: &mut T, ply: &Ply<E>) -> Result<u64> {
+        self.__compressed(out, |mut out| self.__write_ply_unchecked(&mut out, ply))
+    }
+    /// Writes `ply` without checking and without compression.
+    fn __write_ply_unchecked<T: Write>(&self, out: &mut T, ply: &Ply<E>) -> Result<u64> {
         let mut written = 0;
         written += self.write_header(out, &ply.header)?;
-        written += self.write_payload(out, &ply.payload, &ply.header)?;
-        out.flush().unwrap();
+        let progress = self.__progress_start(&ply.payload, &ply.header, written);
+        let result = self.write_payload(out, &ply.payload, &ply.header);
+        if progress {
+            self.__progress_stop();
+        }
+        written += result?;
+        out.flush()?;
+        Ok(written)
+    }
+    /// Writes each of `plys` with `write_ply()`, back to back as read by `Parser::documents()`.
+    pub fn write_documents<T: Write>(&self, out: &mut T, plys: &mut [Ply<E>]) -> Result<u64> {
+        let mut written = 0;
+        for ply in plys {
+            written += self.write_ply(out, ply)?;
+        }
         Ok(written)
     }
-    fn write_new_line<T: Write>(&self, out: &mut T) -> Result<usize> {
-        out.write(self.new_line.as_bytes())
+    fn write_new_line<T: Write>(&self, out: &mut T) -> Result<u64> {
+        match self.canonical {
+            true => write_bytes(out, b"\n"),
+            false => write_bytes(out, self.new_line.as_bytes()),
+        }
+    }
+    /// Applies the name policy to the name of an element or property.
+    fn __name<'n>(&self, name: &'n str, kind: &str) -> Result<Cow<'n, str>> {
+        let invalid = |c: char| c.is_whitespace() || c.is_control();
+        match self.names {
+            _ if name.is_empty() => Err(io::Error::new(ErrorKind::InvalidInput, format!("Name of {} is empty.", kind))),
+            _ if !name.contains(invalid) => Ok(Cow::Borrowed(name)),
+            NamePolicy::Reject => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Name of {} `{}` contains white space or control characters.", kind, name.escape_default())
+            )),
+            NamePolicy::Replace(c) => Ok(Cow::Owned(name.replace(invalid, &c.to_string()))),
+        }
+    }
+    /// Applies the line break policy to the content of a comment or object information line.
+    fn __header_text<'t>(&self, text: &'t str, kind: &str) -> Result<Cow<'t, str>> {
+        match self.line_breaks {
+            LineBreakPolicy::Escape => Ok(escape_line_breaks(text)),
+            LineBreakPolicy::Reject if text.contains(['\n', '\r']) => Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} `{}` contains a line break.", kind, text.escape_default())
+            )),
+            LineBreakPolicy::Reject => Ok(Cow::Borrowed(text)),
+        }
+    }
+}
+
+fn escape_line_breaks(text: &str) -> Cow<'_, str> {
+    if text.contains(['\n', '\r']) {
+        Cow::Owned(text.replace('\n', "\\n").replace('\r', "\\r"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+impl<E: PropertyAccess> Default for Writer<E> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -111,38 +579,42 @@ impl<E: PropertyAccess> Writer<E> {
     /// Writes the magic number "ply" and a new line.
     ///
     /// Each PLY file must start with "ply\n".
-    pub fn write_line_magic_number<T: Write>(&self, out: &mut T) -> Result<usize> {
+    pub fn write_line_magic_number<T: Write>(&self, out: &mut T) -> Result<u64> {
         let mut written = 0;
-        written += out.write("ply".as_bytes())?;
+        written += write_bytes(out, "ply".as_bytes())?;
         written += self.write_new_line(out)?;
         Ok(written)
     }
     /// Writes "format <encoding> <version>".
     ///
     /// Each PLY file must define its format.
-    pub fn write_line_format<T: Write>(&self, out: &mut T, encoding: &Encoding, version: &Version) -> Result<usize> {
+    pub fn write_line_format<T: Write>(&self, out: &mut T, encoding: &Encoding, version: &Version) -> Result<u64> {
         let mut written = 0;
-        written += out.write("format ".as_bytes())?;
+        written += write_bytes(out, "format ".as_bytes())?;
         written += self.write_encoding(out, encoding)?;
-        written += out.write(format!(" {}.{}", version.major, version.minor).as_bytes())?;
+        written += write_bytes(out, format!(" {}.{}", version.major, version.minor).as_bytes())?;
         written += self.write_new_line(out)?;
         Ok(written)
     }
     /// Writes a comment line.
     ///
     /// A comment must not contain a line break and only consist of ascii characters.
-    pub fn write_line_comment<T: Write>(&self, out: &mut T, comment: &Comment) -> Result<usize> {
+    /// Line breaks are handled according to `set_line_break_policy()`.
+    pub fn write_line_comment<T: Write>(&self, out: &mut T, comment: &Comment) -> Result<u64> {
+        let comment = self.__header_text(comment, "Comment")?;
         let mut written = 0;
-        written += out.write(format!("comment {}", comment).as_bytes())?;
+        written += write_bytes(out, format!("comment {}", comment).as_bytes())?;
         written += self.write_new_line(out)?;
         Ok(written)
     }
     /// Writes an object information line.
     ///
     /// An object informatio line must not contain a line break an only consist of ascii characters.
-    pub fn write_line_obj_info<T: Write>(&self, out: &mut T, obj_info: &ObjInfo) -> Result<usize> {
+    /// Line breaks are handled according to `set_line_break_policy()`.
+    pub fn write_line_obj_info<T: Write>(&self, out: &mut T, obj_info: &ObjInfo) -> Result<u64> {
+        let obj_info = self.__header_text(obj_info, "Object information")?;
         let mut written = 0;
-        written += out.write(format!("obj_info {}", obj_info).as_bytes())?;
+        written += write_bytes(out, format!("obj_info {}", obj_info).as_bytes())?;
         written += self.write_new_line(out)?;
         Ok(written)
     }
@@ -152,21 +624,23 @@ impl<E: PropertyAccess> Writer<E> {
     /// It is directly followed by its property definitions.
     ///
     /// Make sure the header is consistent with the payload.
-    pub fn write_line_element_definition<T: Write>(&self, out: &mut T, element: &ElementDef) -> Result<usize> {
+    pub fn write_line_element_definition<T: Write>(&self, out: &mut T, element: &ElementDef) -> Result<u64> {
         let mut written = 0;
-        written += out.write(format!("element {} {}", element.name, element.count).as_bytes())?;
+        let name = self.__name(&element.name, "element")?;
+        written += write_bytes(out, format!("element {} {}", name, element.count).as_bytes())?;
         written += self.write_new_line(out)?;
         Ok(written)
     }
     /// Writes a property line form the header: "property [list <index_type> <scalar_type> | <scalar_type> ]"
     ///
     /// Make sure the property definition is consistent with the payload.
-    pub fn write_line_property_definition<T: Write>(&self, out: &mut T, property: &PropertyDef) -> Result<usize> {
+    pub fn write_line_property_definition<T: Write>(&self, out: &mut T, property: &PropertyDef) -> Result<u64> {
+        let name = self.__name(&property.name, "property")?;
         let mut written = 0;
-        written += out.write("property ".as_bytes())?;
+        written += write_bytes(out, "property ".as_bytes())?;
         written += self.write_property_type(out, &property.data_type)?;
-        written += out.write(" ".as_bytes())?;
-        written += out.write(property.name.as_bytes())?;
+        written += write_bytes(out, " ".as_bytes())?;
+        written += write_bytes(out, name.as_bytes())?;
         written += self.write_new_line(out)?;
         Ok(written)
     }
@@ -175,77 +649,81 @@ impl<E: PropertyAccess> Writer<E> {
     /// Convenience method to call `write_line_element_definition` and `write_line_property_definition` in the correct way.
     ///
     /// Make sure the element definition is consistent with the payload.
-    pub fn write_element_definition<T: Write>(&self, out: &mut T, element: &ElementDef) -> Result<usize> {
+    pub fn write_element_definition<T: Write>(&self, out: &mut T, element: &ElementDef) -> Result<u64> {
         let mut written = 0;
-        written += self.write_line_element_definition(out, &element)?;
+        written += self.write_line_element_definition(out, element)?;
         for (_, p) in &element.properties {
-            written += self.write_line_property_definition(out, &p)?;
+            written += self.write_line_property_definition(out, p)?;
         }
         Ok(written)
     }
     /// Writes `end_header\n`. This terminates the header. Each following byte belongs to the payload.
-    pub fn write_line_end_header<T: Write>(&self, out: &mut T) -> Result<usize> {
+    pub fn write_line_end_header<T: Write>(&self, out: &mut T) -> Result<u64> {
         let mut written = 0;
-        written += out.write("end_header".as_bytes())?;
+        written += write_bytes(out, "end_header".as_bytes())?;
         written += self.write_new_line(out)?;
         Ok(written)
     }
     /// Convenience method to write all header elements.
     ///
     /// It starts with writing the magic number "ply\n" and ends with "end_header".
+    /// The header is written to `out` at once.
     ///
     /// Make sure the header is consistent with the payload.
-    pub fn write_header<T: Write>(&self, out: &mut T, header: &Header) -> Result<usize> {
-        let mut written = 0;
-        written += self.write_line_magic_number(out)?;
-        written += self.write_line_format(out, &header.encoding, &header.version)?;
-        for c in &header.comments {
-            written += self.write_line_comment(out, c)?;
+    pub fn write_header<T: Write>(&self, out: &mut T, header: &Header) -> Result<u64> {
+        let header = &*self.__projected(header);
+        let mut buf = Vec::new();
+        self.write_line_magic_number(&mut buf)?;
+        self.write_line_format(&mut buf, &header.encoding, &header.version)?;
+        for c in header.comments.iter().chain(&self.__provenance_comments()) {
+            self.write_line_comment(&mut buf, c)?;
         }
         for oi in &header.obj_infos {
-            written += self.write_line_obj_info(out, oi)?;
+            self.write_line_obj_info(&mut buf, oi)?;
         }
         for (_, e) in &header.elements {
-            written += self.write_element_definition(out, &e)?;
+            self.write_element_definition(&mut buf, e)?;
         }
-        written += self.write_line_end_header(out)?;
-        Ok(written)
+        self.write_line_end_header(&mut buf)?;
+        out.write_all(&buf)?;
+        Ok(buf.len() as u64)
     }
-    fn write_encoding<T: Write>(&self, out: &mut T, encoding: &Encoding) -> Result<usize> {
+    fn write_encoding<T: Write>(&self, out: &mut T, encoding: &Encoding) -> Result<u64> {
         let s = match *encoding {
             Encoding::Ascii => "ascii",
             Encoding::BinaryBigEndian => "binary_big_endian",
             Encoding::BinaryLittleEndian => "binary_little_endian",
         };
-        out.write(s.as_bytes())
+        write_bytes(out, s.as_bytes())
     }
-    fn write_property_type<T: Write>(&self, out: &mut T, data_type: &PropertyType) -> Result<usize> {
+    fn write_property_type<T: Write>(&self, out: &mut T, data_type: &PropertyType) -> Result<u64> {
         match *data_type {
-            PropertyType::Scalar(ref scalar_type) => self.write_scalar_type(out, &scalar_type),
+            PropertyType::Scalar(ref scalar_type) => self.write_scalar_type(out, scalar_type),
             PropertyType::List(ref index_type, ref content_type) => {
-                let mut written = out.write("list ".as_bytes())?;
+                let mut written = write_bytes(out, "list ".as_bytes())?;
                 match *index_type {
                     ScalarType::Float => return Err(io::Error::new(ErrorKind::InvalidInput, "List index can not be of type float.")),
                     ScalarType::Double => return Err(io::Error::new(ErrorKind::InvalidInput, "List index can not be of type double.")),
                     _ => (),
                 };
-                written += self.write_scalar_type(out, &index_type)?;
-                written += out.write(" ".as_bytes())?;
-                written += self.write_scalar_type(out, &content_type)?;
+                written += self.write_scalar_type(out, index_type)?;
+                written += write_bytes(out, " ".as_bytes())?;
+                written += self.write_scalar_type(out, content_type)?;
                 Ok(written)
             }
         }
     }
-    fn write_scalar_type<T: Write>(&self, out: &mut T, scalar_type: &ScalarType) -> Result<usize> {
+    fn write_scalar_type<T: Write>(&self, out: &mut T, scalar_type: &ScalarType) -> Result<u64> {
+        let sized = self.type_names == TypeNames::Sized && !self.canonical;
         match *scalar_type {
-            ScalarType::Char => out.write("char".as_bytes()),
-            ScalarType::UChar => out.write("uchar".as_bytes()),
-            ScalarType::Short => out.write("short".as_bytes()),
-            ScalarType::UShort => out.write("ushort".as_bytes()),
-            ScalarType::Int => out.write("int".as_bytes()),
-            ScalarType::UInt => out.write("uint".as_bytes()),
-            ScalarType::Float => out.write("float".as_bytes()),
-            ScalarType::Double => out.write("double".as_bytes()),
+            ScalarType::Char => write_bytes(out, if sized { "int8" } else { "char" }.as_bytes()),
+            ScalarType::UChar => write_bytes(out, if sized { "uint8" } else { "uchar" }.as_bytes()),
+            ScalarType::Short => write_bytes(out, if sized { "int16" } else { "short" }.as_bytes()),
+            ScalarType::UShort => write_bytes(out, if sized { "uint16" } else { "ushort" }.as_bytes()),
+            ScalarType::Int => write_bytes(out, if sized { "int32" } else { "int" }.as_bytes()),
+            ScalarType::UInt => write_bytes(out, if sized { "uint32" } else { "uint" }.as_bytes()),
+            ScalarType::Float => write_bytes(out, if sized { "float32" } else { "float" }.as_bytes()),
+            ScalarType::Double => write_bytes(out, if sized { "float64" } else { "double" }.as_bytes()),
         }
     }
 }
@@ -262,33 +740,84 @@ use crate::ply::Payload;
 impl<E: PropertyAccess> Writer<E> {
     /// Writes the payload of a `ply` (`ply.playload`).
     ///
+    /// Elements are written in the order of the header, elements missing from the payload are skipped.
+    ///
     /// Make sure the Header is consistent with the payload.
-    pub fn write_payload<T: Write>(&self, out: &mut T, payload: &Payload<E>, header: &Header) -> Result<usize> {
+    pub fn write_payload<T: Write>(&self, out: &mut T, payload: &Payload<E>, header: &Header) -> Result<u64> {
+        let progress = self.__progress_start(payload, header, 0);
+        let result = self.__write_payload(out, payload, header);
+        if progress {
+            self.__progress_stop();
+        }
+        result
+    }
+    fn __write_payload<T: Write>(&self, out: &mut T, payload: &Payload<E>, header: &Header) -> Result<u64> {
+        let header = &*self.__projected(header);
         let mut written = 0;
-        let element_defs = &header.elements;
-        for (k, element_list) in payload {
-            let element_def = &element_defs[k];
-            written += self.write_payload_of_element(out, element_list, element_def, header)?;
+        for (k, element_def) in &header.elements {
+            if let Some(element_list) = payload.get(k) {
+                written += self.write_payload_of_element(out, element_list, element_def, header)?;
+            }
         }
         Ok(written)
     }
     /// Write all elments as stored in the `element_list`.
     ///
+    /// Rows are encoded in memory and written to `out` in batches of about 64 KiB,
+    /// so `out` doesn't need to be buffered.
+    ///
     /// Make sure the header and the element definition is consistent with the payload.
-    pub fn write_payload_of_element<T: Write>(&self, out: &mut T, element_list: &Vec<E>, element_def: &ElementDef, header: &Header) -> Result<usize> {
+    pub fn write_payload_of_element<T: Write>(&self, out: &mut T, element_list: &Vec<E>, element_def: &ElementDef, header: &Header) -> Result<u64> {
         let mut written = 0;
+        let mut buf = Vec::with_capacity(BATCH_BYTES);
         match header.encoding {
-            Encoding::Ascii => for element in element_list {
-                written += self.write_ascii_element(out, element, &element_def)?;
+            Encoding::Ascii if self.align_columns && !self.canonical => {
+                let n = self.__write_aligned_elements(&mut buf, element_list, element_def)?;
+                self.__progress_rows(element_list.len(), n);
             },
-            Encoding::BinaryBigEndian => for element in element_list {
-                written += self.write_big_endian_element(out, element, &element_def)?;
+            _ => for (index, element) in (0..).zip(element_list) {
+                let n = self.__encode_element(&mut buf, element, element_def, &header.encoding).map_err(|e| row_error(e, index))?;
+                self.__progress_rows(1, n);
+                if buf.len() >= BATCH_BYTES {
+                    out.write_all(&buf)?;
+                    written += buf.len() as u64;
+                    buf.clear();
+                }
             },
-            Encoding::BinaryLittleEndian => for element in element_list {
-                written += self.write_little_endian_element(out, element, &element_def)?;
-            }
         }
-        Ok(written)
+        out.write_all(&buf)?;
+        Ok(written + buf.len() as u64)
+    }
+    /// Writes a single element in `encoding`, e.g. the encoding of the header.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::Writer;
+    /// let mut point = ElementDef::new("point".to_string());
+    /// point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::UShort)));
+    /// let mut element = DefaultElement::new();
+    /// element.insert("x".to_string(), Property::UShort(258));
+    ///
+    /// let w = Writer::new();
+    /// let mut buf = Vec::<u8>::new();
+    /// w.write_element(&mut buf, &element, &point, &Encoding::BinaryLittleEndian).unwrap();
+    /// assert_eq!(buf, [2, 1]);
+    /// ```
+    pub fn write_element<T: Write>(&self, out: &mut T, element: &E, element_def: &ElementDef, encoding: &Encoding) -> Result<u64> {
+        let mut buf = Vec::new();
+        self.__encode_element(&mut buf, element, element_def, encoding)?;
+        out.write_all(&buf)?;
+        Ok(buf.len() as u64)
+    }
+    /// Appends a single element in `encoding` to `buf`.
+    fn __encode_element(&self, buf: &mut Vec<u8>, element: &E, element_def: &ElementDef, encoding: &Encoding) -> Result<u64> {
+        match *encoding {
+            Encoding::Ascii => self.__write_ascii_element(buf, element, element_def),
+            Encoding::BinaryBigEndian => self.__write_binary_element::<_, BigEndian>(buf, element, element_def),
+            Encoding::BinaryLittleEndian => self.__write_binary_element::<_, LittleEndian>(buf, element, element_def),
+        }
     }
 }
 /*
@@ -296,73 +825,175 @@ use std::io::{ Write, Result, ErrorKind };
 use ply::{ PropertyAccess, ElementDef, PropertyDef, PropertyType, ScalarType };
 use super::Writer;
 // */
-use std::fmt::Display;
 
-macro_rules! get_prop(
-    ($e:expr) => (match $e {None => return Err(io::Error::new(ErrorKind::InvalidInput, "No property available for given key.")), Some(x) => x})
+/// Gets a scalar with `$get`, converting it with the policy of `Writer::set_cast_policy()` if stored in another type.
+macro_rules! get_scalar(
+    ($w:expr, $e:expr, $get:ident, $k:expr) => (match $e.$get($k) {Some(x) => x, None => $w.__cast_scalar($e, $k)?})
+);
+
+/// Gets a list with `$get`, converting it with the policy of `Writer::set_cast_policy()` if stored in another type.
+macro_rules! get_list(
+    ($w:expr, $e:expr, $get:ident, $k:expr) => (match $e.$get($k) {Some(x) => Cow::Borrowed(x), None => $w.__cast_list($e, $k)?})
 );
 
 /// # Ascii
 impl<E: PropertyAccess> Writer<E> {
 
     /// Write a single ascii formatted element.
-    pub fn write_ascii_element<T: Write>(&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<usize> {
+    pub fn write_ascii_element<T: Write>(&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<u64> {
+        self.write_element(out, element, element_def, &Encoding::Ascii)
+    }
+    fn __write_ascii_element<T: Write>(&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<u64> {
         let mut written = 0;
         let mut p_iter = element_def.properties.iter();
         let (_k, prop_type) = p_iter.next().unwrap();
-        written += self.write_ascii_property(out, element, &prop_type)?;
+        written += self.write_ascii_property(out, element, &element_def.name, prop_type)?;
         loop {
-            written += out.write(" ".as_bytes())?;
             let n = p_iter.next();
-            if n == None {
+            if n.is_none() && self.canonical {
+                break;
+            }
+            written += write_bytes(out, self.__separator())?;
+            if n.is_none() {
                 break;
             }
             let (_name, prop_type) = n.unwrap();
-            written += self.write_ascii_property(out, element, prop_type)?;
+            written += self.write_ascii_property(out, element, &element_def.name, prop_type)?;
         }
         written += self.write_new_line(out)?;
         Ok(written)
     }
-    fn write_ascii_property<T: Write>(&self, out: &mut T, element: &E, prop_type: &PropertyDef) -> Result<usize> {
+    /// Writes all `elements` with each property padded to the width of its widest value.
+    fn __write_aligned_elements<T: Write>(&self, out: &mut T, elements: &[E], element_def: &ElementDef) -> Result<u64> {
+        let mut widths = vec![0; element_def.properties.len()];
+        let mut rows = Vec::with_capacity(elements.len());
+        for (index, element) in elements.iter().enumerate() {
+            let mut row = Vec::with_capacity(widths.len());
+            for (width, property_def) in widths.iter_mut().zip(element_def.properties.values()) {
+                let mut cell = Vec::new();
+                self.write_ascii_property(&mut cell, element, &element_def.name, property_def).map_err(|e| row_error(e, index))?;
+                *width = cell.len().max(*width);
+                row.push(cell);
+            }
+            rows.push(row);
+        }
+        let mut written = 0;
+        for row in rows {
+            for (i, (cell, width)) in row.iter().zip(widths.iter()).enumerate() {
+                if i > 0 {
+                    written += write_bytes(out, self.__separator())?;
+                }
+                written += write_bytes(out, &b" ".repeat(width - cell.len()))?;
+                written += write_bytes(out, cell)?;
+            }
+            written += self.write_new_line(out)?;
+        }
+        Ok(written)
+    }
+    fn write_ascii_property<T: Write>(&self, out: &mut T, element: &E, element_name: &str, prop_type: &PropertyDef) -> Result<u64> {
         let k = &prop_type.name;
+        let format = FloatFormat { precision: self.__float_precision(element_name, k), notation: if self.canonical { FloatNotation::Shortest } else { self.float_notation }, non_finite: self.non_finite };
+
         let result = match prop_type.data_type {
             PropertyType::Scalar(ref scalar_type) => match *scalar_type {
-                ScalarType::Char => self.write_ascii_scalar(out, get_prop!(element.get_char(k))),
-                ScalarType::UChar => self.write_ascii_scalar(out, get_prop!(element.get_uchar(k))),
-                ScalarType::Short => self.write_ascii_scalar(out, get_prop!(element.get_short(k))),
-                ScalarType::UShort => self.write_ascii_scalar(out, get_prop!(element.get_ushort(k))),
-                ScalarType::Int => self.write_ascii_scalar(out, get_prop!(element.get_int(k))),
-                ScalarType::UInt => self.write_ascii_scalar(out, get_prop!(element.get_uint(k))),
-                ScalarType::Float => self.write_ascii_scalar(out, get_prop!(element.get_float(k))),
-                ScalarType::Double => self.write_ascii_scalar(out, get_prop!(element.get_double(k))),
+                ScalarType::Char => self.write_ascii_scalar(out, get_scalar!(self, element, get_char, k), format),
+                ScalarType::UChar => self.write_ascii_scalar(out, get_scalar!(self, element, get_uchar, k), format),
+                ScalarType::Short => self.write_ascii_scalar(out, get_scalar!(self, element, get_short, k), format),
+                ScalarType::UShort => self.write_ascii_scalar(out, get_scalar!(self, element, get_ushort, k), format),
+                ScalarType::Int => self.write_ascii_scalar(out, get_scalar!(self, element, get_int, k), format),
+                ScalarType::UInt => self.write_ascii_scalar(out, get_scalar!(self, element, get_uint, k), format),
+                ScalarType::Float => self.write_ascii_scalar(out, get_scalar!(self, element, get_float, k), format),
+                ScalarType::Double => self.write_ascii_scalar(out, get_scalar!(self, element, get_double, k), format),
             },
-            PropertyType::List(_, ref scalar_type) => match *scalar_type {
-                ScalarType::Char => self.write_ascii_list(get_prop!(element.get_list_char(k)), out),
-                ScalarType::UChar => self.write_ascii_list(get_prop!(element.get_list_uchar(k)), out),
-                ScalarType::Short => self.write_ascii_list(get_prop!(element.get_list_short(k)), out),
-                ScalarType::UShort => self.write_ascii_list(get_prop!(element.get_list_ushort(k)), out),
-                ScalarType::Int => self.write_ascii_list(get_prop!(element.get_list_int(k)), out),
-                ScalarType::UInt => self.write_ascii_list(get_prop!(element.get_list_uint(k)), out),
-                ScalarType::Float => self.write_ascii_list(get_prop!(element.get_list_float(k)), out),
-                ScalarType::Double => self.write_ascii_list(get_prop!(element.get_list_double(k)), out),
+            PropertyType::List(ref index_type, ref scalar_type) => match *scalar_type {
+                ScalarType::Char => self.write_ascii_list(&get_list!(self, element, get_list_char, k), index_type, out, format),
+                ScalarType::UChar => self.write_ascii_list(&get_list!(self, element, get_list_uchar, k), index_type, out, format),
+                ScalarType::Short => self.write_ascii_list(&get_list!(self, element, get_list_short, k), index_type, out, format),
+                ScalarType::UShort => self.write_ascii_list(&get_list!(self, element, get_list_ushort, k), index_type, out, format),
+                ScalarType::Int => self.write_ascii_list(&get_list!(self, element, get_list_int, k), index_type, out, format),
+                ScalarType::UInt => self.write_ascii_list(&get_list!(self, element, get_list_uint, k), index_type, out, format),
+                ScalarType::Float => self.write_ascii_list(&get_list!(self, element, get_list_float, k), index_type, out, format),
+                ScalarType::Double => self.write_ascii_list(&get_list!(self, element, get_list_double, k), index_type, out, format),
             }
         };
-        result
+        result.map_err(|e| match e.kind() {
+            ErrorKind::InvalidInput => io::Error::new(e.kind(), format!("Property `{}` of element `{}`: {}", k, element_name, e)),
+            _ => e,
+        })
     }
-    fn write_ascii_scalar<T: Write, V: ToString>(&self, out: &mut T, value: V) -> Result<usize> {
-        out.write(value.to_string().as_bytes())
+    fn write_ascii_scalar<T: Write, V: AsciiValue>(&self, out: &mut T, value: V, format: FloatFormat) -> Result<u64> {
+        value.write_ascii(out, format)
     }
-    fn write_ascii_list<T: Write, D: Clone + Display>(&self, list: &[D], out: &mut T) -> Result<usize> {
+    fn write_ascii_list<T: Write, D: AsciiValue>(&self, list: &[D], index_type: &ScalarType, out: &mut T, format: FloatFormat) -> Result<u64> {
+        check_list_len(list.len(), index_type)?;
         let mut written = 0;
-        written += out.write(&list.len().to_string().as_bytes())?;
-        let b = " ".as_bytes();
+        written += write_bytes(out, itoa::Buffer::new().format(list.len()).as_bytes())?;
+        let b = self.__separator();
         for v in list {
-            written += out.write(b)?;
-            written += out.write(v.to_string().as_bytes())?;
+            written += write_bytes(out, b)?;
+            written += v.write_ascii(out, format)?;
         }
         Ok(written)
     }
 }
+
+/// Formatting of float values, as set for the `Writer` and the property written.
+#[derive(Debug, Clone, Copy)]
+struct FloatFormat {
+    precision: Option<FloatPrecision>,
+    notation: FloatNotation,
+    non_finite: NonFinitePolicy,
+}
+
+/// Scalar formatted into a buffer on the stack, without allocating unless a float precision is given.
+trait AsciiValue: Copy {
+    fn write_ascii<T: Write>(self, out: &mut T, format: FloatFormat) -> Result<u64>;
+}
+
+macro_rules! ascii_int {
+    ($($t:ty),*) => {$(
+        impl AsciiValue for $t {
+            fn write_ascii<T: Write>(self, out: &mut T, _format: FloatFormat) -> Result<u64> {
+                write_bytes(out, itoa::Buffer::new().format(self).as_bytes())
+            }
+        }
+    )*};
+}
+ascii_int!(i8, u8, i16, u16, i32, u32);
+
+macro_rules! ascii_float {
+    ($($t:ty),*) => {$(
+        impl AsciiValue for $t {
+            fn write_ascii<T: Write>(self, out: &mut T, format: FloatFormat) -> Result<u64> {
+                if !self.is_finite() {
+                    return match format.non_finite {
+                        NonFinitePolicy::Error => Err(io::Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Value {} isn't finite, see `Writer::set_non_finite_policy()`.", self)
+                        )),
+                        NonFinitePolicy::Tokens if self.is_nan() => write_bytes(out, b"nan"),
+                        NonFinitePolicy::Tokens if self > 0.0 => write_bytes(out, b"inf"),
+                        NonFinitePolicy::Tokens => write_bytes(out, b"-inf"),
+                        NonFinitePolicy::Substitute(v) => (v as $t).write_ascii(out, FloatFormat { non_finite: NonFinitePolicy::Tokens, ..format }),
+                    };
+                }
+                match (format.notation, format.precision) {
+                    (FloatNotation::Scientific, None) => write_bytes(out, format!("{:e}", self).as_bytes()),
+                    (FloatNotation::Scientific, Some(FloatPrecision::Significant(n))) => write_bytes(out, format!("{:.*e}", n.max(1) - 1, self).as_bytes()),
+                    (FloatNotation::Scientific, Some(FloatPrecision::Decimals(n))) => write_bytes(out, format!("{:.*e}", n, self).as_bytes()),
+                    (FloatNotation::Shortest, Some(FloatPrecision::Decimals(n))) => write_bytes(out, format!("{:.*}", n, self).as_bytes()),
+                    (FloatNotation::Shortest, Some(FloatPrecision::Significant(n))) => {
+                        // Rounded in scientific notation, then written as short as possible.
+                        let rounded = format!("{:.*e}", n.max(1) - 1, self).parse::<$t>().unwrap_or(self);
+                        write_bytes(out, ryu::Buffer::new().format(rounded).as_bytes())
+                    },
+                    (FloatNotation::Shortest, None) => write_bytes(out, ryu::Buffer::new().format(self).as_bytes()),
+                }
+            }
+        }
+    )*};
+}
+ascii_float!(f32, f64);
 /*
 use ply::{ PropertyAccess, ElementDef, PropertyType, ScalarType };
 use super::Writer;
@@ -382,60 +1013,65 @@ macro_rules! get_prop(
 impl<E: PropertyAccess> Writer<E> {
     // private payload
     /// Write a single binary formatted element in big endian.
-    pub fn write_big_endian_element<T: Write> (&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<usize> {
-        self.__write_binary_element::<T, BigEndian>(out, element, element_def)
+    pub fn write_big_endian_element<T: Write> (&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<u64> {
+        self.write_element(out, element, element_def, &Encoding::BinaryBigEndian)
     }
     /// Write a single binary formatted element in little endian.
-    pub fn write_little_endian_element<T: Write> (&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<usize> {
-        self.__write_binary_element::<T, LittleEndian>(out, element, element_def)
+    pub fn write_little_endian_element<T: Write> (&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<u64> {
+        self.write_element(out, element, element_def, &Encoding::BinaryLittleEndian)
     }
-    fn __write_binary_element<T: Write, B: ByteOrder>(&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<usize> {
+    fn __write_binary_element<T: Write, B: ByteOrder>(&self, out: &mut T, element: &E, element_def: &ElementDef) -> Result<u64> {
         let mut written = 0;
-        for (k, property_def) in &element_def.properties {
-            match property_def.data_type {
-                PropertyType::Scalar(ref scalar_type) => {
-                    written += match *scalar_type {
-                        ScalarType::Char => {out.write_i8(get_prop!(element.get_char(k)))?; 1},
-                        ScalarType::UChar => {out.write_u8(get_prop!(element.get_uchar(k)))?; 1},
-                        ScalarType::Short => {out.write_i16::<B>(get_prop!(element.get_short(k)))?; 2},
-                        ScalarType::UShort => {out.write_u16::<B>(get_prop!(element.get_ushort(k)))?; 2},
-                        ScalarType::Int => {out.write_i32::<B>(get_prop!(element.get_int(k)))?; 4},
-                        ScalarType::UInt => {out.write_u32::<B>(get_prop!(element.get_uint(k)))?; 4},
-                        ScalarType::Float => {out.write_f32::<B>(get_prop!(element.get_float(k)))?; 4},
-                        ScalarType::Double => {out.write_f64::<B>(get_prop!(element.get_double(k)))?; 8},
+        for property_def in element_def.properties.values() {
+            written += self.__write_binary_property::<T, B>(out, element, property_def).map_err(|e| match e.kind() {
+                ErrorKind::InvalidInput => io::Error::new(e.kind(), format!("Property `{}` of element `{}`: {}", property_def.name, element_def.name, e)),
+                _ => e,
+            })?;
+        }
+        Ok(written)
+    }
+    fn __write_binary_property<T: Write, B: ByteOrder>(&self, out: &mut T, element: &E, property_def: &PropertyDef) -> Result<u64> {
+        let k = &property_def.name;
+        Ok(match property_def.data_type {
+            PropertyType::Scalar(ref scalar_type) => match *scalar_type {
+                ScalarType::Char => {out.write_i8(get_scalar!(self, element, get_char, k))?; 1},
+                ScalarType::UChar => {out.write_u8(get_scalar!(self, element, get_uchar, k))?; 1},
+                ScalarType::Short => {out.write_i16::<B>(get_scalar!(self, element, get_short, k))?; 2},
+                ScalarType::UShort => {out.write_u16::<B>(get_scalar!(self, element, get_ushort, k))?; 2},
+                ScalarType::Int => {out.write_i32::<B>(get_scalar!(self, element, get_int, k))?; 4},
+                ScalarType::UInt => {out.write_u32::<B>(get_scalar!(self, element, get_uint, k))?; 4},
+                ScalarType::Float => {out.write_f32::<B>(get_scalar!(self, element, get_float, k))?; 4},
+                ScalarType::Double => {out.write_f64::<B>(get_scalar!(self, element, get_double, k))?; 8},
+            },
+            PropertyType::List(ref len_type, ref scalar_type) => {
+                let write_len = |len: usize, out: &mut T| -> Result<u64> {
+                    check_list_len(len, len_type)?;
+                    let written = match *len_type {
+                        ScalarType::Char => {out.write_i8(len as i8)?; 1},
+                        ScalarType::UChar => {out.write_u8(len as u8)?; 1},
+                        ScalarType::Short => {out.write_i16::<B>(len as i16)?; 2},
+                        ScalarType::UShort => {out.write_u16::<B>(len as u16)?; 2},
+                        ScalarType::Int => {out.write_i32::<B>(len as i32)?; 4},
+                        ScalarType::UInt => {out.write_u32::<B>(len as u32)?; 4},
+                        ScalarType::Float | ScalarType::Double => unreachable!(),
                     };
-                },
-                PropertyType::List(ref len_type, ref scalar_type) => {
-                    let write_len = |len, out: &mut T| {
-						let written = match *len_type {
-							ScalarType::Char => {out.write_i8(len as i8)?; 1},
-							ScalarType::UChar => {out.write_u8(len as u8)?; 1},
-							ScalarType::Short => {out.write_i16::<B>(len as i16)?; 2},
-							ScalarType::UShort => {out.write_u16::<B>(len as u16)?; 2},
-							ScalarType::Int => {out.write_i32::<B>(len as i32)?; 4},
-							ScalarType::UInt => {out.write_u32::<B>(len as u32)?; 4},
-							ScalarType::Float => return Err(io::Error::new(ErrorKind::InvalidInput, "Index of list must be an integer type, float declared in PropertyType.")),
-							ScalarType::Double => return Err(io::Error::new(ErrorKind::InvalidInput, "Index of list must be an integer type, double declared in PropertyType.")),
-						};
-						Ok(written)
-					};
-
-                    written += match *scalar_type {
-                        ScalarType::Char => {let list = get_prop!(element.get_list_char(k)); write_len(list.len(), out)?+self.write_binary_list::<T, i8, B>(list, out, &|o, x| {o.write_i8(*x)?; Ok(1)} )?},
-                        ScalarType::UChar => {let list = get_prop!(element.get_list_uchar(k)); write_len(list.len(), out)?+self.write_binary_list::<T, u8, B>(list, out, &|o, x| {o.write_u8(*x)?; Ok(1)} )?},
-                        ScalarType::Short => {let list = get_prop!(element.get_list_short(k)); write_len(list.len(), out)?+self.write_binary_list::<T, i16, B>(list, out, &|o, x| {o.write_i16::<B>(*x)?; Ok(2)} )?},
-                        ScalarType::UShort => {let list = get_prop!(element.get_list_ushort(k)); write_len(list.len(), out)?+self.write_binary_list::<T, u16, B>(list, out, &|o, x| {o.write_u16::<B>(*x)?; Ok(2)} )?},
-                        ScalarType::Int => {let list = get_prop!(element.get_list_int(k)); write_len(list.len(), out)?+self.write_binary_list::<T, i32, B>(list, out, &|o, x| {o.write_i32::<B>(*x)?; Ok(4)} )?},
-                        ScalarType::UInt => {let list = get_prop!(element.get_list_uint(k)); write_len(list.len(), out)?+self.write_binary_list::<T, u32, B>(list, out, &|o, x| {o.write_u32::<B>(*x)?; Ok(4)} )?},
-                        ScalarType::Float => {let list = get_prop!(element.get_list_float(k)); write_len(list.len(), out)?+self.write_binary_list::<T, f32, B>(list, out, &|o, x| {o.write_f32::<B>(*x)?; Ok(4)} )?},
-                        ScalarType::Double => {let list = get_prop!(element.get_list_double(k)); write_len(list.len(), out)?+self.write_binary_list::<T, f64, B>(list, out, &|o, x| {o.write_f64::<B>(*x)?; Ok(8)} )?},
-                    }
+                    Ok(written)
+                };
+
+                match *scalar_type {
+                    ScalarType::Char => {let list = get_list!(self, element, get_list_char, k); write_len(list.len(), out)?+self.write_binary_list::<T, i8>(&list, out, &|o, x| {o.write_i8(*x)?; Ok(1)} )?},
+                    ScalarType::UChar => {let list = get_list!(self, element, get_list_uchar, k); write_len(list.len(), out)?+self.write_binary_list::<T, u8>(&list, out, &|o, x| {o.write_u8(*x)?; Ok(1)} )?},
+                    ScalarType::Short => {let list = get_list!(self, element, get_list_short, k); write_len(list.len(), out)?+self.write_binary_list::<T, i16>(&list, out, &|o, x| {o.write_i16::<B>(*x)?; Ok(2)} )?},
+                    ScalarType::UShort => {let list = get_list!(self, element, get_list_ushort, k); write_len(list.len(), out)?+self.write_binary_list::<T, u16>(&list, out, &|o, x| {o.write_u16::<B>(*x)?; Ok(2)} )?},
+                    ScalarType::Int => {let list = get_list!(self, element, get_list_int, k); write_len(list.len(), out)?+self.write_binary_list::<T, i32>(&list, out, &|o, x| {o.write_i32::<B>(*x)?; Ok(4)} )?},
+                    ScalarType::UInt => {let list = get_list!(self, element, get_list_uint, k); write_len(list.len(), out)?+self.write_binary_list::<T, u32>(&list, out, &|o, x| {o.write_u32::<B>(*x)?; Ok(4)} )?},
+                    ScalarType::Float => {let list = get_list!(self, element, get_list_float, k); write_len(list.len(), out)?+self.write_binary_list::<T, f32>(&list, out, &|o, x| {o.write_f32::<B>(*x)?; Ok(4)} )?},
+                    ScalarType::Double => {let list = get_list!(self, element, get_list_double, k); write_len(list.len(), out)?+self.write_binary_list::<T, f64>(&list, out, &|o, x| {o.write_f64::<B>(*x)?; Ok(8)} )?},
                 }
             }
-        };
-        Ok(written)
+        })
     }
-    fn write_binary_list<T: Write, D, B: ByteOrder>(&self, list: &[D], out: &mut T, out_val: &dyn Fn(&mut T, &D) -> Result<usize>) -> Result<usize> {
+    fn write_binary_list<T: Write, D>(&self, list: &[D], out: &mut T, out_val: &dyn Fn(&mut T, &D) -> Result<u64>) -> Result<u64> {
         let mut written = 0;
         for v in list {
             written += out_val(out, v)?;
@@ -443,3 +1079,63 @@ impl<E: PropertyAccess> Writer<E> {
         Ok(written)
     }
 }
+
+/// Adds the index of the row to errors encoding it, which name the element and property.
+fn row_error(e: io::Error, index: usize) -> io::Error {
+    match e.kind() {
+        ErrorKind::InvalidInput => io::Error::new(e.kind(), format!("Row {}: {}", index, e)),
+        _ => e,
+    }
+}
+
+/// Fails if the length of a list of `len` entries doesn't fit `index_type`, instead of truncating it.
+fn check_list_len(len: usize, index_type: &ScalarType) -> Result<()> {
+    let max = match *index_type {
+        ScalarType::Char => i8::MAX as u64,
+        ScalarType::UChar => u8::MAX as u64,
+        ScalarType::Short => i16::MAX as u64,
+        ScalarType::UShort => u16::MAX as u64,
+        ScalarType::Int => i32::MAX as u64,
+        ScalarType::UInt => u32::MAX as u64,
+        ScalarType::Float | ScalarType::Double => return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Index of list must be an integer type, {:?} declared.", index_type)
+        )),
+    };
+    if len as u64 > max {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("List has {} entries, more than its index type {:?} can count (at most {}).", len, index_type, max)
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn write_ascii_value() {
+        let format = |v: &dyn Fn(&mut Vec<u8>) -> Result<u64>| {
+            let mut buf = Vec::new();
+            v(&mut buf).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        assert_eq!(format(&|b| (-128i8).write_ascii(b, FloatFormat { precision: None, notation: FloatNotation::Shortest, non_finite: NonFinitePolicy::Tokens })), "-128");
+        assert_eq!(format(&|b| u32::MAX.write_ascii(b, FloatFormat { precision: Some(FloatPrecision::Decimals(2)), notation: FloatNotation::Shortest, non_finite: NonFinitePolicy::Tokens })), "4294967295");
+        assert_eq!(format(&|b| 0.1f32.write_ascii(b, FloatFormat { precision: None, notation: FloatNotation::Shortest, non_finite: NonFinitePolicy::Tokens })), "0.1");
+        assert_eq!(format(&|b| 1e-7f64.write_ascii(b, FloatFormat { precision: None, notation: FloatNotation::Shortest, non_finite: NonFinitePolicy::Tokens })), "1e-7");
+        assert_eq!(format(&|b| f32::NEG_INFINITY.write_ascii(b, FloatFormat { precision: Some(FloatPrecision::Decimals(2)), notation: FloatNotation::Shortest, non_finite: NonFinitePolicy::Tokens })), "-inf");
+        assert_eq!(format(&|b| 2.0f32.write_ascii(b, FloatFormat { precision: Some(FloatPrecision::Decimals(3)), notation: FloatNotation::Shortest, non_finite: NonFinitePolicy::Tokens })), "2.000");
+        assert_eq!(format(&|b| 123.456f64.write_ascii(b, FloatFormat { precision: Some(FloatPrecision::Significant(4)), notation: FloatNotation::Shortest, non_finite: NonFinitePolicy::Tokens })), "123.5");
+        assert_eq!(format(&|b| 0.000123456f32.write_ascii(b, FloatFormat { precision: Some(FloatPrecision::Significant(2)), notation: FloatNotation::Shortest, non_finite: NonFinitePolicy::Tokens })), "0.00012");
+        let scientific = |precision| FloatFormat { precision, notation: FloatNotation::Scientific, non_finite: NonFinitePolicy::Tokens };
+        assert_eq!(format(&|b| 1.5e-7f32.write_ascii(b, scientific(None))), "1.5e-7");
+        assert_eq!(format(&|b| 1234.5f64.write_ascii(b, scientific(Some(FloatPrecision::Significant(3))))), "1.23e3");
+        assert_eq!(format(&|b| 1234.5f64.write_ascii(b, scientific(Some(FloatPrecision::Decimals(1))))), "1.2e3");
+        let non_finite = |non_finite| FloatFormat { precision: None, notation: FloatNotation::Shortest, non_finite };
+        assert_eq!(format(&|b| f64::NAN.write_ascii(b, non_finite(NonFinitePolicy::Tokens))), "nan");
+        assert_eq!(format(&|b| f32::INFINITY.write_ascii(b, non_finite(NonFinitePolicy::Substitute(-1.0)))), "-1.0");
+        assert!(f32::NAN.write_ascii(&mut Vec::new(), non_finite(NonFinitePolicy::Error)).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Encoding rows on the rayon thread pool.
+
+use std::io::{ Result, Write };
+
+use rayon::prelude::*;
+
+use super::{ Writer, row_error };
+use crate::ply::{ ElementDef, Encoding, Header, Payload, Ply, PropertyAccess };
+
+/// Number of rows each task of the thread pool encodes.
+const ROWS_PER_TASK: usize = 16384;
+
+impl<E: PropertyAccess + Sync> Writer<E> {
+    /// Like `write_ply()`, but encodes the rows of each element on the rayon thread pool.
+    ///
+    /// Rows are encoded in batches, one buffer per task, and written to `out` in order,
+    /// so the output is identical to `write_ply()`. Meant for large binary files, where encoding dominates.
+    /// Ascii elements with aligned columns are written sequentially.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/house_2_ok_little_endian.ply").unwrap();
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// let w = writer::Writer::new();
+    /// let (mut parallel, mut sequential) = (Vec::<u8>::new(), Vec::<u8>::new());
+    /// w.write_ply_parallel(&mut parallel, &mut ply).unwrap();
+    /// w.write_ply(&mut sequential, &mut ply).unwrap();
+    /// assert_eq!(parallel, sequential);
+    /// ```
+    pub fn write_ply_parallel<T: Write>(&self, out: &mut T, ply: &mut Ply<E>) -> Result<u64> {
+        self.__prepare(ply)?;
+        self.__compressed(out, |mut out| self.__write_ply_parallel(&mut out, ply))
+    }
+    fn __write_ply_parallel<T: Write>(&self, out: &mut T, ply: &Ply<E>) -> Result<u64> {
+        let mut written = self.write_header(out, &ply.header)?;
+        let progress = self.__progress_start(&ply.payload, &ply.header, written);
+        let result = self.write_payload_parallel(out, &ply.payload, &ply.header);
+        if progress {
+            self.__progress_stop();
+        }
+        written += result?;
+        out.flush()?;
+        Ok(written)
+    }
+    /// Like `write_payload()`, but encodes the rows on the rayon thread pool, see `write_ply_parallel()`.
+    pub fn write_payload_parallel<T: Write>(&self, out: &mut T, payload: &Payload<E>, header: &Header) -> Result<u64> {
+        let progress = self.__progress_start(payload, header, 0);
+        let result = self.__write_payload_parallel(out, payload, header);
+        if progress {
+            self.__progress_stop();
+        }
+        result
+    }
+    fn __write_payload_parallel<T: Write>(&self, out: &mut T, payload: &Payload<E>, header: &Header) -> Result<u64> {
+        let header = &*self.__projected(header);
+        let mut written = 0;
+        for (k, element_def) in &header.elements {
+            if let Some(element_list) = payload.get(k) {
+                written += self.write_payload_of_element_parallel(out, element_list, element_def, header)?;
+            }
+        }
+        Ok(written)
+    }
+    /// Like `write_payload_of_element()`, but encodes the rows on the rayon thread pool, see `write_ply_parallel()`.
+    pub fn write_payload_of_element_parallel<T: Write>(&self, out: &mut T, element_list: &[E], element_def: &ElementDef, header: &Header) -> Result<u64> {
+        if header.encoding == Encoding::Ascii && self.align_columns && !self.canonical {
+            let n = self.__write_aligned_elements(out, element_list, element_def)?;
+            self.__progress_rows(element_list.len(), n);
+            return Ok(n);
+        }
+        let batch = ROWS_PER_TASK * rayon::current_num_threads();
+        let mut written = 0;
+        for (b, rows) in element_list.chunks(batch).enumerate() {
+            let buffers = rows.par_chunks(ROWS_PER_TASK)
+                .enumerate()
+                .map(|(c, chunk)| {
+                    let mut buf = Vec::new();
+                    for (i, element) in chunk.iter().enumerate() {
+                        let index = b * batch + c * ROWS_PER_TASK + i;
+                        self.__encode_element(&mut buf, element, element_def, &header.encoding).map_err(|e| row_error(e, index))?;
+                    }
+                    Ok(buf)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let before = written;
+            for buf in buffers {
+                out.write_all(&buf)?;
+                written += buf.len() as u64;
+            }
+            self.__progress_rows(rows.len(), written - before);
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ DefaultElement, Property, PropertyDef, PropertyType, ScalarType, Addable };
+    #[test]
+    fn write_parallel_ok() {
+        let mut ply = Ply::<DefaultElement>::new();
+        let mut point = ElementDef::new("point".to_string());
+        point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)));
+        point.properties.add(PropertyDef::new("i".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Int)));
+        ply.header.elements.add(point);
+        let rows = (0..ROWS_PER_TASK * 3 + 5).map(|i| {
+            let mut row = DefaultElement::new();
+            row.insert("x".to_string(), Property::Float(i as f32 * 0.5));
+            row.insert("i".to_string(), Property::ListInt(vec![i as i32; i % 4]));
+            row
+        }).collect();
+        ply.payload.insert("point".to_string(), rows);
+        let w = Writer::new();
+        for encoding in &[Encoding::BinaryLittleEndian, Encoding::Ascii] {
+            ply.header.encoding = *encoding;
+            let (mut parallel, mut sequential) = (Vec::new(), Vec::new());
+            let written = w.write_ply_parallel(&mut parallel, &mut ply).unwrap();
+            w.write_ply(&mut sequential, &mut ply).unwrap();
+            assert_eq!(written, parallel.len() as u64);
+            assert!(parallel == sequential);
+        }
+        ply.payload.get_mut("point").unwrap()[ROWS_PER_TASK + 1].insert("x".to_string(), Property::Double(0.0));
+        let mut w = Writer::new();
+        w.set_consistency_check(false);
+        assert!(w.write_ply_parallel(&mut Vec::new(), &mut ply).is_err());
+    }
+}
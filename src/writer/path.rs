@@ -0,0 +1,45 @@
+//! Writing PLY files by path.
+
+use std::fs;
+use std::fs::File;
+use std::io::{ BufWriter, Result };
+use std::path::Path;
+
+use super::Writer;
+use crate::ply::{ Ply, PropertyAccess };
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Writes `ply` with `write_ply()` to a temporary file next to `path`, which replaces `path` once complete.
+    ///
+    /// The temporary file is named like `path` with `.tmp` appended and synced to disk before the rename,
+    /// so `path` holds either its previous content or the whole new file, also after a crash. On errors it's removed again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let p = parser::Parser::<ply::DefaultElement>::new();
+    /// let mut ply = p.read_ply_from_path("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let path = std::env::temp_dir().join(format!("ply_rs_doc_{}.ply", std::process::id()));
+    /// writer::Writer::new().write_ply_to_path(&path, &mut ply).unwrap();
+    /// assert_eq!(p.read_ply_from_path(&path).unwrap().payload, ply.payload);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn write_ply_to_path<P: AsRef<Path>>(&self, path: P, ply: &mut Ply<E>) -> Result<u64> {
+        let path = path.as_ref();
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".tmp");
+        let temporary = path.with_file_name(name);
+        let result = self.__write_ply_to_file(&temporary, ply).and_then(|written| fs::rename(&temporary, path).map(|()| written));
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+        result
+    }
+    fn __write_ply_to_file(&self, path: &Path, ply: &mut Ply<E>) -> Result<u64> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let written = self.write_ply(&mut out, ply)?;
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        Ok(written)
+    }
+}
@@ -0,0 +1,114 @@
+//! Writing rows from plain old data without encoding each value.
+
+use std::io;
+use std::io::{ ErrorKind, Result, Write };
+use std::mem::size_of;
+
+use super::{ BATCH_BYTES, Writer };
+use crate::ply::{ ElementDef, Encoding, PropertyAccess, PropertyType };
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Writes `rows` as the binary payload of `element_def`, copying their bytes instead of encoding each value.
+    ///
+    /// `P` has to lay out the properties of `element_def` in order and without padding, so `element_def` mustn't have lists.
+    /// In the byte order of the host the rows are written with a single `write_all()`,
+    /// otherwise each value is swapped in a buffer first. Ascii isn't supported.
+    ///
+    /// Make sure the count of `element_def` in the header is the length of `rows`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::Writer;
+    /// #[repr(C)]
+    /// #[derive(Clone, Copy)]
+    /// struct Vertex { x: f32, y: f32, z: f32 }
+    /// unsafe impl bytemuck::Zeroable for Vertex {}
+    /// unsafe impl bytemuck::Pod for Vertex {}
+    ///
+    /// let mut vertex = ElementDef::new("vertex".to_string());
+    /// for name in &["x", "y", "z"] {
+    ///     vertex.properties.add(PropertyDef::new(name.to_string(), PropertyType::Scalar(ScalarType::Float)));
+    /// }
+    /// let rows = vec![Vertex { x: 1.0, y: 2.0, z: 3.0 }; 2];
+    /// let w = Writer::<DefaultElement>::new();
+    /// let mut buf = Vec::<u8>::new();
+    /// assert_eq!(w.write_pod_element(&mut buf, &rows, &vertex, &Encoding::BinaryBigEndian).unwrap(), 24);
+    /// assert_eq!(buf[..4], 1f32.to_be_bytes());
+    /// ```
+    pub fn write_pod_element<T: Write, P: bytemuck::Pod>(&self, out: &mut T, rows: &[P], element_def: &ElementDef, encoding: &Encoding) -> Result<u64> {
+        match element_def.fixed_stride() {
+            Some(stride) if stride == size_of::<P>() => (),
+            Some(stride) => return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Rows of element `{}` have {} bytes, but the given type has {}.", element_def.name, stride, size_of::<P>())
+            )),
+            None => return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` has list properties, its rows don't have a fixed size.", element_def.name)
+            )),
+        }
+        let host = if cfg!(target_endian = "big") { Encoding::BinaryBigEndian } else { Encoding::BinaryLittleEndian };
+        let bytes: &[u8] = bytemuck::cast_slice(rows);
+        match *encoding {
+            Encoding::Ascii => Err(io::Error::new(ErrorKind::InvalidInput, "Rows can only be copied into binary encodings.")),
+            e if e == host => {
+                out.write_all(bytes)?;
+                Ok(bytes.len() as u64)
+            },
+            _ => {
+                let sizes = element_def.properties.values().map(|p| match p.data_type {
+                    PropertyType::Scalar(ref s) => s.byte_size(),
+                    PropertyType::List(_, _) => unreachable!(),
+                }).collect::<Vec<_>>();
+                let rows_per_batch = (BATCH_BYTES / size_of::<P>().max(1)).max(1);
+                let mut buf = Vec::with_capacity(rows_per_batch * size_of::<P>());
+                for batch in bytes.chunks(rows_per_batch * size_of::<P>().max(1)) {
+                    buf.clear();
+                    buf.extend_from_slice(batch);
+                    for row in buf.chunks_mut(size_of::<P>().max(1)) {
+                        let mut start = 0;
+                        for &size in &sizes {
+                            row[start..start + size].reverse();
+                            start += size;
+                        }
+                    }
+                    out.write_all(&buf)?;
+                }
+                Ok(bytes.len() as u64)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::{ Addable, DefaultElement, Property, PropertyDef, ScalarType };
+    #[test]
+    fn write_pod_element_ok() {
+        let mut point = ElementDef::new("point".to_string());
+        point.properties.add(PropertyDef::new("i".to_string(), PropertyType::Scalar(ScalarType::UShort)));
+        point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)));
+        let rows: Vec<[u16; 3]> = (0..20000u16).map(|i| {
+            let x = (f32::from(i) * 0.25).to_ne_bytes();
+            [i, u16::from_ne_bytes([x[0], x[1]]), u16::from_ne_bytes([x[2], x[3]])]
+        }).collect();
+        let w = Writer::<DefaultElement>::new();
+        for encoding in &[Encoding::BinaryBigEndian, Encoding::BinaryLittleEndian] {
+            let mut copied = Vec::new();
+            assert_eq!(w.write_pod_element(&mut copied, &rows, &point, encoding).unwrap(), 6 * rows.len() as u64);
+            let mut encoded = Vec::new();
+            for i in 0..rows.len() {
+                let mut e = DefaultElement::new();
+                e.insert("i".to_string(), Property::UShort(i as u16));
+                e.insert("x".to_string(), Property::Float(i as f32 * 0.25));
+                w.write_element(&mut encoded, &e, &point, encoding).unwrap();
+            }
+            assert!(copied == encoded);
+        }
+        assert!(w.write_pod_element(&mut Vec::new(), &rows, &point, &Encoding::Ascii).is_err());
+        assert!(w.write_pod_element(&mut Vec::new(), &[0u32], &point, &Encoding::BinaryBigEndian).is_err());
+    }
+}
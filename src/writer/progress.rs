@@ -0,0 +1,109 @@
+//! Progress reports while writing the payload, e.g. for a progress bar.
+
+use std::sync::atomic::{ AtomicBool, AtomicU64, AtomicUsize, Ordering };
+
+use super::Writer;
+use crate::ply::{ Header, Payload, PropertyAccess };
+
+/// State of the callback set with `Writer::set_progress()`.
+pub(super) struct Progress {
+    callback: Box<dyn Fn(u64, usize, usize) + Send + Sync>,
+    every: usize,
+    /// Whether a write reporting progress is running.
+    active: AtomicBool,
+    bytes: AtomicU64,
+    rows: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Calls `callback` with `(bytes_written, rows_done, rows_total)` while `write_ply()` or `write_payload()` write the payload.
+    ///
+    /// It is called once every `every` rows of all elements together and after the last row.
+    /// `bytes_written` counts the bytes encoded so far including the header, which is left out by `write_payload()`.
+    /// The parallel variants report whenever a batch of rows is written. Without a callback, writing isn't slowed down.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// let mut w = writer::Writer::new();
+    /// w.set_progress(5, |bytes, done, total| println!("{} bytes, {}/{} rows", bytes, done, total));
+    /// w.write_ply(&mut std::io::sink(), &mut ply).unwrap();
+    /// ```
+    pub fn set_progress<F: Fn(u64, usize, usize) + Send + Sync + 'static>(&mut self, every: usize, callback: F) {
+        self.progress = Some(Progress {
+            callback: Box::new(callback),
+            every: every.max(1),
+            active: AtomicBool::new(false),
+            bytes: AtomicU64::new(0),
+            rows: AtomicUsize::new(0),
+            total: AtomicUsize::new(0),
+        });
+    }
+    /// Removes the callback set with `set_progress()`.
+    pub fn clear_progress(&mut self) {
+        self.progress = None;
+    }
+    /// Starts reporting for the rows of `payload` written with `header`, after `bytes` of header, returns whether it did.
+    pub(super) fn __progress_start(&self, payload: &Payload<E>, header: &Header, bytes: u64) -> bool {
+        match self.progress {
+            Some(ref p) if !p.active.swap(true, Ordering::Relaxed) => {
+                let header = self.__projected(header);
+                let total = header.elements.keys().filter_map(|k| payload.get(k)).map(Vec::len).sum();
+                p.bytes.store(bytes, Ordering::Relaxed);
+                p.rows.store(0, Ordering::Relaxed);
+                p.total.store(total, Ordering::Relaxed);
+                true
+            },
+            _ => false,
+        }
+    }
+    pub(super) fn __progress_stop(&self) {
+        if let Some(ref p) = self.progress {
+            p.active.store(false, Ordering::Relaxed);
+        }
+    }
+    /// Adds `n` rows done encoded into `bytes`, calling the callback if a multiple of `every` or the last row has been reached.
+    pub(super) fn __progress_rows(&self, n: usize, bytes: u64) {
+        let p = match self.progress {
+            Some(ref p) if p.active.load(Ordering::Relaxed) => p,
+            _ => return,
+        };
+        let bytes = p.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        let before = p.rows.fetch_add(n, Ordering::Relaxed);
+        let done = before + n;
+        let total = p.total.load(Ordering::Relaxed);
+        if done / p.every != before / p.every || (done == total && before < total) {
+            (p.callback)(bytes, done, total);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{ Arc, Mutex };
+    use crate::parser::{ Parser, Projection };
+    use crate::ply::DefaultElement;
+    #[test]
+    fn progress_ok() {
+        let data = std::fs::read("example_plys/house_2_ok_little_endian.ply").unwrap();
+        let mut ply = Parser::<DefaultElement>::new().read_ply(&mut &data[..]).unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let mut w = Writer::new();
+        let r = reports.clone();
+        w.set_progress(3, move |bytes, done, total| r.lock().unwrap().push((bytes, done, total)));
+        let len = w.write_ply(&mut Vec::new(), &mut ply).unwrap();
+        let reported = reports.lock().unwrap().iter().map(|&(_, done, total)| (done, total)).collect::<Vec<_>>();
+        assert_eq!(reported, vec![(3, 8), (6, 8), (8, 8)]);
+        assert_eq!(reports.lock().unwrap().last().unwrap().0, len);
+        reports.lock().unwrap().clear();
+        w.set_projection(Projection::all().exclude("face", &[]));
+        w.write_payload(&mut Vec::new(), &ply.payload, &ply.header).unwrap();
+        assert_eq!(reports.lock().unwrap().last().unwrap().1, 5);
+        assert_eq!(reports.lock().unwrap().last().unwrap().2, 5);
+    }
+}
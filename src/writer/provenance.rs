@@ -0,0 +1,95 @@
+//! Comments recording how a file was generated.
+
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use super::Writer;
+use crate::ply::PropertyAccess;
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Appends `comment generated by ply-rs <version> on <date>` to the comments of every header written, default is `false`.
+    ///
+    /// The date is the current UTC date as `YYYY-MM-DD`. With `set_canonical()` it's left out, so equal inputs still give equal output.
+    pub fn set_generator_comment(&mut self, enabled: bool) {
+        self.generator_comment = enabled;
+    }
+    /// Appends `comment <key>: <value>` to the comments of every header written, after the generator comment.
+    ///
+    /// Lines are written in the order they were added. Line breaks are handled according to `set_line_break_policy()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::Writer;
+    /// let mut w = Writer::<DefaultElement>::new();
+    /// w.add_provenance("source", "scan-0042");
+    /// let mut buf = Vec::<u8>::new();
+    /// w.write_header(&mut buf, &Header::new()).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().contains("\ncomment source: scan-0042\n"));
+    /// ```
+    pub fn add_provenance(&mut self, key: &str, value: &str) {
+        self.provenance.push((key.to_string(), value.to_string()));
+    }
+    /// Removes the lines added with `add_provenance()`.
+    pub fn clear_provenance(&mut self) {
+        self.provenance.clear();
+    }
+    /// Comments appended to the comments of the header.
+    pub(super) fn __provenance_comments(&self) -> Vec<String> {
+        let mut comments = Vec::new();
+        if self.generator_comment {
+            let mut comment = format!("generated by ply-rs {}", env!("CARGO_PKG_VERSION"));
+            if !self.canonical {
+                let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86400);
+                let (year, month, day) = civil_from_days(days as i64);
+                comment += &format!(" on {:04}-{:02}-{:02}", year, month, day);
+            }
+            comments.push(comment);
+        }
+        comments.extend(self.provenance.iter().map(|(k, v)| format!("{}: {}", k, v)));
+        comments
+    }
+}
+
+/// Gregorian date of the day `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ply::DefaultElement;
+    #[test]
+    fn civil_from_days_ok() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_376), (2025, 10, 15));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+    #[test]
+    fn provenance_comments() {
+        let mut w = Writer::<DefaultElement>::new();
+        assert!(w.__provenance_comments().is_empty());
+        w.set_generator_comment(true);
+        w.add_provenance("tool", "scanner 2");
+        let comments = w.__provenance_comments();
+        assert!(comments[0].starts_with(&format!("generated by ply-rs {} on ", env!("CARGO_PKG_VERSION"))));
+        assert_eq!(comments[1], "tool: scanner 2");
+        w.set_canonical(true);
+        assert_eq!(w.__provenance_comments()[0], format!("generated by ply-rs {}", env!("CARGO_PKG_VERSION")));
+        w.clear_provenance();
+        assert_eq!(w.__provenance_comments().len(), 1);
+    }
+}
@@ -0,0 +1,94 @@
+//! Computing the size of the output without writing it.
+
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::Writer;
+use crate::ply::{ ElementDef, Encoding, Header, Ply, PropertyAccess, PropertyType };
+
+/// Number of rows of an ascii element formatted by `Writer::payload_size()` to estimate its size.
+const SAMPLE_ROWS: usize = 1024;
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Number of bytes `write_header()` writes for `header`.
+    pub fn header_size(&self, header: &Header) -> Result<u64> {
+        self.write_header(&mut io::sink(), header)
+    }
+    /// Number of bytes `write_payload()` writes for `ply`, exact for binary encodings and estimated for ascii.
+    ///
+    /// The ascii size is extrapolated from up to 1024 rows per element spread over all rows,
+    /// it's exact for elements with fewer rows. Settings of the writer are taken into account.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/house_2_ok_little_endian.ply").unwrap();
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// let w = writer::Writer::new();
+    /// let size = w.header_size(&ply.header).unwrap() + w.payload_size(&ply).unwrap();
+    /// let mut buf = Vec::with_capacity(size as usize);
+    /// assert_eq!(w.write_ply(&mut buf, &mut ply).unwrap(), size);
+    /// ```
+    pub fn payload_size(&self, ply: &Ply<E>) -> Result<u64> {
+        let header = self.__projected(&ply.header);
+        let mut size = 0;
+        for (k, element_def) in &header.elements {
+            let rows = match ply.payload.get(k) {
+                Some(rows) => rows,
+                None => continue,
+            };
+            size += match header.encoding {
+                Encoding::Ascii => self.__ascii_size(rows, element_def)?,
+                _ => rows.iter().map(|row| binary_row_size(row, element_def)).sum::<Result<u64>>()?,
+            };
+        }
+        Ok(size)
+    }
+    fn __ascii_size(&self, rows: &[E], element_def: &ElementDef) -> Result<u64> {
+        if self.align_columns && !self.canonical {
+            return self.__write_aligned_elements(&mut io::sink(), rows, element_def);
+        }
+        let step = rows.len().div_ceil(SAMPLE_ROWS).max(1);
+        let mut buf = Vec::new();
+        let mut sampled = 0;
+        for row in rows.iter().step_by(step) {
+            self.__write_ascii_element(&mut buf, row, element_def)?;
+            sampled += 1;
+        }
+        Ok(match step {
+            1 => buf.len() as u64,
+            _ => (buf.len() as f64 / sampled as f64 * rows.len() as f64).round() as u64,
+        })
+    }
+}
+
+/// Number of bytes of `row` encoded in binary.
+fn binary_row_size<E: PropertyAccess>(row: &E, element_def: &ElementDef) -> Result<u64> {
+    let mut size = 0;
+    for (k, p) in &element_def.properties {
+        size += match p.data_type {
+            PropertyType::Scalar(ref scalar_type) => scalar_type.byte_size() as u64,
+            PropertyType::List(ref index_type, ref scalar_type) => match list_len(row, k) {
+                Some(len) => (index_type.byte_size() + len * scalar_type.byte_size()) as u64,
+                None => return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Property `{}` of element `{}`: No property available for given key.", k, element_def.name)
+                )),
+            },
+        };
+    }
+    Ok(size)
+}
+
+/// Number of entries of the list `k` of `row` in whichever type it is stored.
+fn list_len<E: PropertyAccess>(row: &E, k: &String) -> Option<usize> {
+    row.get_list_int(k).map(<[_]>::len)
+        .or_else(|| row.get_list_uint(k).map(<[_]>::len))
+        .or_else(|| row.get_list_float(k).map(<[_]>::len))
+        .or_else(|| row.get_list_double(k).map(<[_]>::len))
+        .or_else(|| row.get_list_short(k).map(<[_]>::len))
+        .or_else(|| row.get_list_ushort(k).map(<[_]>::len))
+        .or_else(|| row.get_list_char(k).map(<[_]>::len))
+        .or_else(|| row.get_list_uchar(k).map(<[_]>::len))
+}
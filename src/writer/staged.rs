@@ -0,0 +1,185 @@
+//! Staged writing, the order of header and payload is enforced by the type system.
+
+use std::collections::VecDeque;
+use std::io;
+use std::io::{ Write, Result, ErrorKind };
+
+use super::Writer;
+use crate::ply::{ Comment, ElementDef, Encoding, ObjInfo, PropertyAccess, Version };
+
+/// First stage of a staged write: magic number and format have been written, the rest of the header follows.
+///
+/// Created by `Writer::staged()`. Only header lines can be written,
+/// `end_header()` moves on to the payload.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::ply::*;
+/// # use ply_rs::writer::Writer;
+/// let mut point = ElementDef::new("point".to_string());
+/// point.count = 1;
+/// point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Int)));
+/// let mut element = DefaultElement::new();
+/// element.insert("x".to_string(), Property::Int(7));
+///
+/// let w = Writer::<DefaultElement>::new();
+/// let mut payload = w.staged(Vec::<u8>::new(), Encoding::Ascii, Version { major: 1, minor: 0 }).unwrap()
+///     .comment(&"written in stages".to_string()).unwrap()
+///     .element(&point).unwrap()
+///     .end_header().unwrap();
+/// payload.write(&element).unwrap();
+/// let buf = payload.finish().unwrap().into_inner();
+/// assert!(String::from_utf8(buf).unwrap().trim_end().ends_with("end_header\n7"));
+/// ```
+///
+/// Payload can't be written before the header is terminated:
+///
+/// ```rust,compile_fail
+/// # use ply_rs::ply::*;
+/// # use ply_rs::writer::Writer;
+/// let w = Writer::<DefaultElement>::new();
+/// let header = w.staged(Vec::<u8>::new(), Encoding::Ascii, Version { major: 1, minor: 0 }).unwrap();
+/// header.write(&DefaultElement::new());
+/// ```
+pub struct HeaderStage<'a, T: Write, E: PropertyAccess> {
+    writer: &'a Writer<E>,
+    out: T,
+    encoding: Encoding,
+    elements: VecDeque<ElementDef>,
+    written: u64,
+}
+
+/// Second stage of a staged write: the header is complete, elements are written in the declared order.
+pub struct PayloadStage<'a, T: Write, E: PropertyAccess> {
+    writer: &'a Writer<E>,
+    out: T,
+    encoding: Encoding,
+    elements: VecDeque<ElementDef>,
+    /// Number of elements of `elements.front()` already written.
+    current_count: usize,
+    written: u64,
+}
+
+/// Final stage of a staged write: all declared elements have been written.
+pub struct Finished<T: Write> {
+    out: T,
+    written: u64,
+}
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Starts a staged write to `out` by writing the magic number and the format line.
+    ///
+    /// See `HeaderStage` for an example.
+    pub fn staged<T: Write>(&self, mut out: T, encoding: Encoding, version: Version) -> Result<HeaderStage<'_, T, E>> {
+        let mut written = self.write_line_magic_number(&mut out)?;
+        written += self.write_line_format(&mut out, &encoding, &version)?;
+        Ok(HeaderStage {
+            writer: self,
+            out,
+            encoding,
+            elements: VecDeque::new(),
+            written,
+        })
+    }
+}
+
+impl<'a, T: Write, E: PropertyAccess> HeaderStage<'a, T, E> {
+    /// Writes a comment line.
+    pub fn comment(mut self, comment: &Comment) -> Result<Self> {
+        self.written += self.writer.write_line_comment(&mut self.out, comment)?;
+        Ok(self)
+    }
+    /// Writes an object information line.
+    pub fn obj_info(mut self, obj_info: &ObjInfo) -> Result<Self> {
+        self.written += self.writer.write_line_obj_info(&mut self.out, obj_info)?;
+        Ok(self)
+    }
+    /// Writes the definition of an element and its properties.
+    ///
+    /// The payload stage expects exactly `element_def.count` elements of this type,
+    /// in the order the definitions have been written.
+    pub fn element(mut self, element_def: &ElementDef) -> Result<Self> {
+        self.written += self.writer.write_element_definition(&mut self.out, element_def)?;
+        self.elements.push_back(element_def.clone());
+        Ok(self)
+    }
+    /// Writes `end_header` and moves on to the payload.
+    pub fn end_header(mut self) -> Result<PayloadStage<'a, T, E>> {
+        self.written += self.writer.write_line_end_header(&mut self.out)?;
+        let mut payload = PayloadStage {
+            writer: self.writer,
+            out: self.out,
+            encoding: self.encoding,
+            elements: self.elements,
+            current_count: 0,
+            written: self.written,
+        };
+        payload.skip_complete();
+        Ok(payload)
+    }
+}
+
+impl<'a, T: Write, E: PropertyAccess> PayloadStage<'a, T, E> {
+    /// Definition of the element expected by the next call to `write()`, `None` if all elements have been written.
+    pub fn next_element(&self) -> Option<&ElementDef> {
+        self.elements.front()
+    }
+    /// Writes a single element using the definition returned by `next_element()`.
+    ///
+    /// Once `count` elements of a type have been written, the next declared element type follows.
+    pub fn write(&mut self, element: &E) -> Result<u64> {
+        let element_def = match self.elements.front() {
+            Some(e) => e,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, "All declared elements have already been written.")),
+        };
+        let written = self.writer.write_element(&mut self.out, element, element_def, &self.encoding)?;
+        self.written += written;
+        self.current_count += 1;
+        self.skip_complete();
+        Ok(written)
+    }
+    /// Writes all elements in `elements`, see `write()`.
+    pub fn write_all(&mut self, elements: &[E]) -> Result<u64> {
+        let mut written = 0;
+        for e in elements {
+            written += self.write(e)?;
+        }
+        Ok(written)
+    }
+    /// Completes the payload, fails if declared elements are missing.
+    pub fn finish(mut self) -> Result<Finished<T>> {
+        if let Some(e) = self.elements.front() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` declares {} entries, but only {} have been written.", e.name, e.count, self.current_count)
+            ));
+        }
+        self.out.flush()?;
+        Ok(Finished {
+            out: self.out,
+            written: self.written,
+        })
+    }
+    /// Moves past element types whose count has been reached.
+    fn skip_complete(&mut self) {
+        while let Some(e) = self.elements.front() {
+            if self.current_count < e.count {
+                break;
+            }
+            self.elements.pop_front();
+            self.current_count = 0;
+        }
+    }
+}
+
+impl<T: Write> Finished<T> {
+    /// Total number of bytes written in all stages.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+    /// Returns the underlying target.
+    pub fn into_inner(self) -> T {
+        self.out
+    }
+}
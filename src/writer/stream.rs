@@ -0,0 +1,363 @@
+//! Streaming writes checked at runtime against the header.
+
+use std::io;
+use std::io::{ Write, Result, ErrorKind, Seek, SeekFrom };
+
+use super::Writer;
+use crate::ply::{ ElementDef, Encoding, Header, PropertyAccess };
+
+/// Overwrites bytes of the target at an offset.
+type Patch<T> = fn(&mut T, u64, &[u8]) -> Result<()>;
+
+/// Writes a PLY file element by element, checking the order and counts declared in the header.
+///
+/// Created by `Writer::stream()`. Call `begin()` with the header, then for each element of the header in order
+/// `append_element()` once per row and `end_element()`, and finally `finish()`.
+/// Anything out of order, e.g. rows of an element before the previous one has been ended,
+/// fails with an error of kind `InvalidInput` and writes nothing.
+///
+/// Unlike `HeaderStage` the order is checked at runtime, so the header can be a value built elsewhere.
+/// Ascii columns aren't aligned, see `Writer::set_align_columns()`.
+/// The projection of `Writer::set_projection()` applies: rows are written with the kept properties only,
+/// rows of dropped elements are ignored.
+/// If the number of rows isn't known in advance, start with `begin_unknown_counts()` on a seekable target.
+///
+/// # Examples
+///
+/// ```rust
+/// # use ply_rs::ply::*;
+/// # use ply_rs::writer::Writer;
+/// let mut header = Header::new();
+/// let mut point = ElementDef::new("point".to_string());
+/// point.count = 2;
+/// point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Int)));
+/// header.elements.add(point);
+/// let mut element = DefaultElement::new();
+/// element.insert("x".to_string(), Property::Int(7));
+///
+/// let w = Writer::<DefaultElement>::new();
+/// let mut stream = w.stream(Vec::<u8>::new());
+/// stream.begin(&header).unwrap();
+/// stream.append_element("point", &element).unwrap();
+/// assert!(stream.end_element("point").is_err());
+/// stream.append_element("point", &element).unwrap();
+/// stream.end_element("point").unwrap();
+/// let buf = stream.finish().unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with("end_header\n7 \n7 \n"));
+/// ```
+pub struct StreamWriter<'a, T: Write, E: PropertyAccess> {
+    writer: &'a Writer<E>,
+    out: T,
+    /// Header written by `begin()`.
+    header: Option<Header>,
+    /// Index of the element written next.
+    element: usize,
+    /// Rows of the current element already written.
+    count: usize,
+    written: u64,
+    /// Offsets of the placeholder counts written by `begin_unknown_counts()`.
+    placeholders: Vec<u64>,
+    /// Overwrites bytes at an offset, set by `begin_unknown_counts()`.
+    patch: Option<Patch<T>>,
+}
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Starts a streaming write to `out`, see `StreamWriter`.
+    pub fn stream<T: Write>(&self, out: T) -> StreamWriter<'_, T, E> {
+        StreamWriter {
+            writer: self,
+            out,
+            header: None,
+            element: 0,
+            count: 0,
+            written: 0,
+            placeholders: Vec::new(),
+            patch: None,
+        }
+    }
+}
+
+impl<'a, T: Write, E: PropertyAccess> StreamWriter<'a, T, E> {
+    /// Writes `header`, the payload has to follow it.
+    pub fn begin(&mut self, header: &Header) -> Result<u64> {
+        if self.header.is_some() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "The header has already been written."));
+        }
+        let written = self.writer.write_header(&mut self.out, header)?;
+        self.written += written;
+        self.header = Some(self.writer.__projected(header).into_owned());
+        Ok(written)
+    }
+    /// Writes a row of element `name`, which has to be the next element of the header not ended yet.
+    pub fn append_element(&mut self, name: &str, element: &E) -> Result<u64> {
+        if self.header.is_some() && !self.writer.projection.keeps_element(name) {
+            return Ok(0);
+        }
+        let (encoding, element_def) = current(&self.header, self.element, name)?;
+        if self.count == element_def.count && self.patch.is_none() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` declares {} entries, all have already been written.", name, element_def.count)
+            ));
+        }
+        let written = self.writer.write_element(&mut self.out, element, element_def, &encoding)?;
+        self.written += written;
+        self.count += 1;
+        Ok(written)
+    }
+    /// Ends element `name`, fails unless all rows declared have been written.
+    ///
+    /// After `begin_unknown_counts()` the rows written so far are taken as the count of the element.
+    pub fn end_element(&mut self, name: &str) -> Result<()> {
+        if self.header.is_some() && !self.writer.projection.keeps_element(name) {
+            return Ok(());
+        }
+        let (_, element_def) = current(&self.header, self.element, name)?;
+        if self.patch.is_some() {
+            let (count, index) = (self.count, self.element);
+            if let Some((_, e)) = self.header.as_mut().and_then(|h| h.elements.iter_mut().nth(index)) {
+                e.count = count;
+            }
+        } else if self.count != element_def.count {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Element `{}` declares {} entries, but only {} have been written.", name, element_def.count, self.count)
+            ));
+        }
+        self.element += 1;
+        self.count = 0;
+        Ok(())
+    }
+    /// Number of bytes written so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+    /// Completes the file and returns the underlying target, fails if elements haven't been ended.
+    ///
+    /// After `begin_unknown_counts()` the placeholders are replaced by the counts of the elements.
+    pub fn finish(mut self) -> Result<T> {
+        let header = match self.header {
+            Some(ref h) => h,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, "The header hasn't been written.")),
+        };
+        if let Some(e) = header.elements.values().nth(self.element) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, format!("Element `{}` hasn't been ended.", e.name)));
+        }
+        if let Some(patch) = self.patch {
+            // Placeholders are the digits of `usize::MAX`.
+            let width = usize::MAX.to_string().len();
+            for (&offset, e) in self.placeholders.iter().zip(header.elements.values()) {
+                patch(&mut self.out, offset, format!("{:<1$}", e.count, width).as_bytes())?;
+            }
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<'a, T: Write + Seek, E: PropertyAccess> StreamWriter<'a, T, E> {
+    /// Writes `header` with placeholders for the element counts, `finish()` replaces them by the rows written.
+    ///
+    /// The counts declared in `header` are ignored, any number of rows can be appended to each element.
+    /// Placeholders are padded with spaces after the count once replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::*;
+    /// # use ply_rs::writer::Writer;
+    /// let mut header = Header::new();
+    /// let mut point = ElementDef::new("point".to_string());
+    /// point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Int)));
+    /// header.elements.add(point);
+    ///
+    /// let w = Writer::<DefaultElement>::new();
+    /// let mut stream = w.stream(std::io::Cursor::new(Vec::<u8>::new()));
+    /// stream.begin_unknown_counts(&header).unwrap();
+    /// for x in 0..3 {
+    ///     let mut element = DefaultElement::new();
+    ///     element.insert("x".to_string(), Property::Int(x));
+    ///     stream.append_element("point", &element).unwrap();
+    /// }
+    /// stream.end_element("point").unwrap();
+    /// let buf = stream.finish().unwrap().into_inner();
+    /// let ply = ply_rs::parser::Parser::<DefaultElement>::new().parse_slice(&buf).unwrap();
+    /// assert_eq!(ply.header.elements["point"].count, 3);
+    /// ```
+    pub fn begin_unknown_counts(&mut self, header: &Header) -> Result<u64> {
+        if self.header.is_some() {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "The header has already been written."));
+        }
+        let start = self.out.stream_position()?;
+        let mut header = self.writer.__projected(header).into_owned();
+        let mut buf = Vec::new();
+        self.writer.write_line_magic_number(&mut buf)?;
+        self.writer.write_line_format(&mut buf, &header.encoding, &header.version)?;
+        for c in header.comments.iter().chain(&self.writer.__provenance_comments()) {
+            self.writer.write_line_comment(&mut buf, c)?;
+        }
+        for oi in &header.obj_infos {
+            self.writer.write_line_obj_info(&mut buf, oi)?;
+        }
+        let mut placeholders = Vec::new();
+        let placeholder = usize::MAX.to_string();
+        for (_, e) in header.elements.iter_mut() {
+            e.count = usize::MAX;
+            // The name may have been changed by the name policy, the count is the last token of the line.
+            let line_start = buf.len();
+            self.writer.write_line_element_definition(&mut buf, e)?;
+            let at = buf[line_start..].windows(placeholder.len()).rposition(|w| w == placeholder.as_bytes()).unwrap_or(0);
+            placeholders.push(start + (line_start + at) as u64);
+            for (_, p) in &e.properties {
+                self.writer.write_line_property_definition(&mut buf, p)?;
+            }
+        }
+        self.writer.write_line_end_header(&mut buf)?;
+        self.out.write_all(&buf)?;
+        self.written += buf.len() as u64;
+        self.header = Some(header);
+        self.placeholders = placeholders;
+        self.patch = Some(patch_at::<T>);
+        Ok(buf.len() as u64)
+    }
+}
+
+/// Overwrites the bytes at `offset` with `bytes` and moves back to the end.
+fn patch_at<T: Write + Seek>(out: &mut T, offset: u64, bytes: &[u8]) -> Result<()> {
+    let end = out.stream_position()?;
+    out.seek(SeekFrom::Start(offset))?;
+    out.write_all(bytes)?;
+    out.seek(SeekFrom::Start(end))?;
+    Ok(())
+}
+
+/// Encoding and definition of the element at index `element`, fails if it isn't `name`.
+pub(super) fn current<'h>(header: &'h Option<Header>, element: usize, name: &str) -> Result<(Encoding, &'h ElementDef)> {
+    let header = match *header {
+        Some(ref h) => h,
+        None => return Err(io::Error::new(ErrorKind::InvalidInput, "The header has to be written before the payload.")),
+    };
+    let position = header.elements.keys().position(|k| k == name);
+    let message = match (header.elements.values().nth(element), position) {
+        (Some(e), Some(p)) if p == element => return Ok((header.encoding, e)),
+        (_, None) => format!("Element `{}` isn't declared in the header.", name),
+        (Some(e), Some(p)) if p > element => format!("Element `{}` follows `{}`, which hasn't been ended.", name, e.name),
+        _ => format!("Element `{}` has already been ended.", name),
+    };
+    Err(io::Error::new(ErrorKind::InvalidInput, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::ply::{ Addable, DefaultElement, Property, PropertyDef, PropertyType, ScalarType };
+    use crate::writer::NamePolicy;
+    #[test]
+    fn stream_writer_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let ply = p.read_ply_from_path("example_plys/house_2_ok_little_endian.ply").unwrap();
+        let w = Writer::new();
+        let mut stream = w.stream(Vec::new());
+        let vertex = &ply.payload["vertex"][0];
+        assert!(stream.append_element("vertex", vertex).is_err());
+        stream.begin(&ply.header).unwrap();
+        assert!(stream.begin(&ply.header).is_err());
+        assert!(stream.append_element("face", &ply.payload["face"][0]).is_err());
+        assert!(stream.append_element("edge", vertex).is_err());
+        for v in &ply.payload["vertex"] {
+            stream.append_element("vertex", v).unwrap();
+        }
+        assert!(stream.append_element("vertex", vertex).is_err());
+        stream.end_element("vertex").unwrap();
+        assert!(stream.append_element("vertex", vertex).is_err());
+        for f in &ply.payload["face"] {
+            stream.append_element("face", f).unwrap();
+        }
+        let written = stream.written();
+        let mut unfinished = w.stream(Vec::new());
+        unfinished.begin(&ply.header).unwrap();
+        assert!(unfinished.finish().is_err());
+        stream.end_element("face").unwrap();
+        let buf = stream.finish().unwrap();
+        assert_eq!(buf.len() as u64, written);
+        let read = p.parse_slice(&buf).unwrap();
+        assert_eq!(read.header.encoding, Encoding::BinaryLittleEndian);
+        assert_eq!(read.payload, ply.payload);
+    }
+    #[test]
+    fn stream_projection_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut ply = p.read_ply_from_path("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+        let mut w = Writer::new();
+        w.set_projection(crate::parser::Projection::all().exclude("vertex", &["z"]).exclude("face", &[]));
+        let mut expected = Vec::new();
+        w.write_ply(&mut expected, &mut ply).unwrap();
+        let mut stream = w.stream(Vec::new());
+        stream.begin(&ply.header).unwrap();
+        for (name, rows) in &ply.payload {
+            for row in rows {
+                stream.append_element(name, row).unwrap();
+            }
+            stream.end_element(name).unwrap();
+        }
+        let buf = stream.finish().unwrap();
+        assert_eq!(buf, expected);
+        let read = p.parse_slice(&buf).unwrap();
+        assert_eq!(read.header.elements.keys().collect::<Vec<_>>(), vec!["vertex"]);
+        assert_eq!(read.payload["vertex"][7].keys().collect::<Vec<_>>(), vec!["x", "y"]);
+
+        let mut stream = w.stream(std::io::Cursor::new(Vec::new()));
+        stream.begin_unknown_counts(&ply.header).unwrap();
+        for (name, rows) in &ply.payload {
+            for row in rows {
+                stream.append_element(name, row).unwrap();
+            }
+            stream.end_element(name).unwrap();
+        }
+        assert_eq!(p.parse_slice(&stream.finish().unwrap().into_inner()).unwrap().payload, read.payload);
+    }
+    #[test]
+    fn unknown_counts_replaced_names() {
+        let mut header = Header::new();
+        let mut e = ElementDef::new("point\u{3000}cloud".to_string());
+        e.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Int)));
+        header.elements.add(e);
+        let mut w = Writer::new();
+        w.set_name_policy(NamePolicy::Replace('_'));
+        let mut stream = w.stream(std::io::Cursor::new(Vec::new()));
+        stream.begin_unknown_counts(&header).unwrap();
+        let mut row = DefaultElement::new();
+        row.insert("x".to_string(), Property::Int(7));
+        stream.append_element("point\u{3000}cloud", &row).unwrap();
+        stream.end_element("point\u{3000}cloud").unwrap();
+        let buf = stream.finish().unwrap().into_inner();
+        let read = Parser::<DefaultElement>::new().parse_slice(&buf).unwrap();
+        assert_eq!(read.header.elements["point_cloud"].count, 1);
+        assert_eq!(read.payload["point_cloud"][0]["x"], Property::Int(7));
+    }
+    #[test]
+    fn unknown_counts_ok() {
+        let p = Parser::<DefaultElement>::new();
+        let mut ply = p.read_ply_from_path("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+        let w = Writer::new();
+        let mut stream = w.stream(std::io::Cursor::new(b"prefix".to_vec()));
+        stream.out.set_position(6);
+        stream.begin_unknown_counts(&ply.header).unwrap();
+        assert!(stream.begin_unknown_counts(&ply.header).is_err());
+        for (name, rows) in &ply.payload {
+            for row in rows.iter().take(3) {
+                stream.append_element(name, row).unwrap();
+            }
+            stream.end_element(name).unwrap();
+        }
+        let buf = stream.finish().unwrap().into_inner();
+        assert!(buf.starts_with(b"prefixply\n"));
+        let read = p.parse_slice(&buf[6..]).unwrap();
+        for (_, rows) in ply.payload.iter_mut() {
+            rows.truncate(3);
+        }
+        assert_eq!(read.header.elements["vertex"].count, 3);
+        assert_eq!(read.payload, ply.payload);
+    }
+}
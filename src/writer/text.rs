@@ -0,0 +1,63 @@
+//! Writing ascii PLY files to `fmt::Write` targets such as `String`.
+
+use std::fmt;
+use std::io;
+use std::io::{ ErrorKind, Result };
+
+use super::{ BATCH_BYTES, Writer, row_error };
+use crate::ply::{ Encoding, Ply, PropertyAccess };
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Writes `ply` as ascii PLY text to `out`, like `write_ply()`.
+    ///
+    /// Fails if the header doesn't declare the ascii encoding, binary encodings need an `io::Write`.
+    /// Returns the number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::*;
+    /// let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    /// let mut ply = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut f).unwrap();
+    /// let mut text = String::new();
+    /// writer::Writer::new().write_ply_fmt(&mut text, &mut ply).unwrap();
+    /// assert!(text.starts_with("ply\nformat ascii 1.0\n"));
+    /// ```
+    pub fn write_ply_fmt<W: fmt::Write>(&self, out: &mut W, ply: &mut Ply<E>) -> Result<u64> {
+        if ply.header.encoding != Encoding::Ascii {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Only the ascii encoding can be written as text."));
+        }
+        self.__prepare(ply)?;
+        let header = &*self.__projected(&ply.header);
+        let mut buf = Vec::with_capacity(BATCH_BYTES);
+        self.write_header(&mut buf, header)?;
+        let mut written = 0;
+        for (k, element_def) in &header.elements {
+            let element_list = match ply.payload.get(k) {
+                Some(l) => l,
+                None => continue,
+            };
+            if self.align_columns && !self.canonical {
+                self.__write_aligned_elements(&mut buf, element_list, element_def)?;
+                continue;
+            }
+            for (index, element) in element_list.iter().enumerate() {
+                self.__encode_element(&mut buf, element, element_def, &Encoding::Ascii).map_err(|e| row_error(e, index))?;
+                if buf.len() >= BATCH_BYTES {
+                    written += write_text(out, &mut buf)?;
+                }
+            }
+        }
+        written += write_text(out, &mut buf)?;
+        Ok(written)
+    }
+}
+
+/// Moves the complete lines in `buf` to `out`.
+fn write_text<W: fmt::Write>(out: &mut W, buf: &mut Vec<u8>) -> Result<u64> {
+    let text = std::str::from_utf8(buf).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    out.write_str(text).map_err(|_| io::Error::other("Formatter returned an error."))?;
+    let written = buf.len() as u64;
+    buf.clear();
+    Ok(written)
+}
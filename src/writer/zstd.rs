@@ -0,0 +1,39 @@
+//! Writing zstd compressed PLY files.
+
+use std::io::{ Result, Write };
+
+use zstd::stream::write::Encoder;
+
+use super::Writer;
+use crate::ply::{ Ply, PropertyAccess };
+
+impl<E: PropertyAccess> Writer<E> {
+    /// Sets the level used by `write_ply_zstd()`, from `1` (fastest) to `22` (smallest), default is `zstd::DEFAULT_COMPRESSION_LEVEL`.
+    ///
+    /// `0` selects the default level, negative levels trade even more size for speed.
+    pub fn set_zstd_level(&mut self, level: i32) {
+        self.zstd_level = level;
+    }
+    /// Writes `ply` zstd compressed to `out`, like `write_ply()`, and finishes the zstd frame.
+    ///
+    /// Returns the number of bytes written before compression. The compression set with `set_compression()` is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use ply_rs::ply::{ Ply, DefaultElement };
+    /// # use ply_rs::writer::Writer;
+    /// # let mut ply = Ply::<DefaultElement>::new();
+    /// let mut w = Writer::new();
+    /// w.set_zstd_level(19);
+    /// let mut buf = Vec::<u8>::new();
+    /// w.write_ply_zstd(&mut buf, &mut ply).unwrap();
+    /// ```
+    pub fn write_ply_zstd<T: Write>(&self, out: &mut T, ply: &mut Ply<E>) -> Result<u64> {
+        let mut encoder = Encoder::new(out, self.zstd_level)?;
+        self.__prepare(ply)?;
+        let written = self.__write_ply_unchecked(&mut encoder, ply)?;
+        encoder.finish()?;
+        Ok(written)
+    }
+}
@@ -7,7 +7,7 @@ fn read_file(path: &str) -> Ply {
     let mut f = std::fs::File::open(path).unwrap();
     let p = parser::Parser::new();
     let ply = p.read_ply(&mut f);
-    assert!(ply.is_ok(), format!("failed: {}", ply.err().unwrap()));
+    assert!(ply.is_ok(), "failed: {}", ply.err().unwrap());
     ply.unwrap()
 }
 
@@ -66,10 +66,154 @@ fn read_all_atomic_types_ok() {
     println!("Created ply: {:?}", ply);
 }
 
+#[cfg(feature = "checksum")]
+fn with_checksum(algorithm: checksum::ChecksumAlgorithm, payload: &str) -> String {
+    let mut hasher = checksum::ChecksumHasher::new(algorithm);
+    hasher.update(payload.as_bytes());
+    format!("ply\nformat ascii 1.0\ncomment {}\nelement point 2\nproperty int x\nproperty int y\nend_header\n",
+        hasher.finish().to_comment())
+}
+#[cfg(feature = "checksum")]
+#[test]
+fn read_checksum_ok() {
+    let mut p = parser::Parser::<ply::DefaultElement>::new();
+    p.set_verify_checksum(true);
+    for algorithm in &[checksum::ChecksumAlgorithm::Crc32, checksum::ChecksumAlgorithm::Sha256] {
+        let txt = with_checksum(*algorithm, "-7 5\n2 4\n") + "-7 5\n2 4\n";
+        let ply = p.read_ply(&mut txt.as_bytes());
+        assert!(ply.is_ok(), "failed: {}", ply.err().unwrap());
+    }
+}
+#[cfg(feature = "checksum")]
+#[test]
+fn read_checksum_mismatch() {
+    let txt = with_checksum(checksum::ChecksumAlgorithm::Sha256, "-7 5\n2 4\n") + "-7 5\n2 3\n";
+    let mut p = parser::Parser::<ply::DefaultElement>::new();
+    assert!(p.read_ply(&mut txt.as_bytes()).is_ok());
+    p.set_verify_checksum(true);
+    let err = p.read_ply(&mut txt.as_bytes()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(matches!(err, ply::Error::Io(ref e) if e.get_ref().unwrap().is::<checksum::ChecksumError>()));
+}
+#[test]
+fn read_ascii_parallel_equal() {
+    let mut txt = String::from("ply\nformat ascii 1.0\nelement point 40000\nproperty int x\nproperty float y\n\
+        element face 1\nproperty list uchar int vertex_index\nend_header\n");
+    for i in 0..40000 {
+        txt += &format!("{} {}.5\n", i, -i);
+    }
+    txt += "3 0 1 2\n";
+    let sequential = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut txt.as_bytes()).unwrap();
+    let mut p = parser::Parser::<ply::DefaultElement>::new();
+    p.set_ascii_threads(3);
+    let parallel = p.read_ply(&mut txt.as_bytes()).unwrap();
+    assert_eq!(sequential, parallel);
+}
+#[test]
+fn read_ascii_parallel_error_location() {
+    let mut txt = String::from("ply\nformat ascii 1.0\nelement point 30000\nproperty int x\nend_header\n");
+    for i in 0..30000 {
+        txt += if i == 20000 { "x\n" } else { "1\n" };
+    }
+    let sequential = parser::Parser::<ply::DefaultElement>::new().read_ply(&mut txt.as_bytes()).unwrap_err();
+    let mut p = parser::Parser::<ply::DefaultElement>::new();
+    p.set_ascii_threads(4);
+    let parallel = p.read_ply(&mut txt.as_bytes()).unwrap_err();
+    assert!(parallel.to_string().starts_with("Line 20006:"), "unexpected error: {}", parallel);
+    assert_eq!(sequential.to_string(), parallel.to_string());
+}
+#[test]
+fn read_special_floats() {
+    let txt = "ply\nformat ascii 1.0\nelement point 3\nproperty float x\nproperty double y\n\
+        property list uchar float z\nend_header\n\
+        nan 1.5 0\n-INF +Infinity 2 NaN 1\n1 2 0\n";
+    let mut p = parser::Parser::<ply::DefaultElement>::new();
+    assert!(p.read_ply(&mut txt.as_bytes()).is_err());
+    assert!(p.take_warnings().is_empty());
+
+    p.set_strictness(parser::Strictness::Lenient);
+    let ply = p.read_ply(&mut txt.as_bytes()).unwrap();
+    match ply.payload["point"][0]["x"] {
+        ply::Property::Float(v) => assert!(v.is_nan()),
+        ref v => panic!("Unexpected property {:?}", v),
+    }
+    assert_eq!(ply.payload["point"][1]["x"], ply::Property::Float(f32::NEG_INFINITY));
+    assert_eq!(ply.payload["point"][1]["y"], ply::Property::Double(f64::INFINITY));
+    assert_eq!(
+        p.take_warnings(),
+        vec![parser::Warning::NonFiniteValues { element: "point".to_string(), count: 4 }]
+    );
+    assert!(p.take_warnings().is_empty());
+}
+#[test]
+fn read_out_of_range() {
+    let txt = "ply\nformat ascii 1.0\nelement point 2\nproperty uchar x\nproperty float y\n\
+        property list uchar char z\nend_header\n\
+        7 1 1 -3\n300 -1e40 2 200 -129\n";
+    let mut p = parser::Parser::<ply::DefaultElement>::new();
+    let err = p.read_ply(&mut txt.as_bytes()).unwrap_err().to_string();
+    assert!(err.starts_with("Line 9:"), "unexpected error: {}", err);
+    assert!(err.contains("Value '300' is out of range for type UChar"), "unexpected error: {}", err);
+
+    p.set_out_of_range(ply::OutOfRange::Clamp);
+    let ply = p.read_ply(&mut txt.as_bytes()).unwrap();
+    let point = &ply.payload["point"][1];
+    assert_eq!(point["x"], ply::Property::UChar(255));
+    assert_eq!(point["y"], ply::Property::Float(f32::MIN));
+    assert_eq!(point["z"], ply::Property::ListChar(vec![127, -128]));
+
+    p.set_out_of_range(ply::OutOfRange::Wrap);
+    let ply = p.read_ply(&mut txt.as_bytes()).unwrap();
+    let point = &ply.payload["point"][1];
+    assert_eq!(point["x"], ply::Property::UChar(44));
+    assert_eq!(point["y"], ply::Property::Float(f32::NEG_INFINITY));
+    assert_eq!(point["z"], ply::Property::ListChar(vec![-56, 127]));
+    assert_eq!(ply.payload["point"][0]["z"], ply::Property::ListChar(vec![-3]));
+}
+#[test]
+fn read_exponent_range_policy() {
+    let read = |value: &str, ty: &str, policy: ply::OutOfRange| {
+        let txt = format!("ply\nformat ascii 1.0\nelement point 1\nproperty {} x\nend_header\n{}\n", ty, value);
+        let mut p = parser::Parser::<ply::DefaultElement>::new();
+        p.set_out_of_range(policy);
+        p.read_ply(&mut txt.as_bytes()).map(|ply| ply.payload["point"][0]["x"].clone())
+    };
+    // Integers in exponent form used to be rejected, now they are accepted if they are whole numbers.
+    assert_eq!(read("1e3", "int", ply::OutOfRange::Error).unwrap(), ply::Property::Int(1000));
+    assert_eq!(read("2 2.5e1 -0e0", "list uchar ushort", ply::OutOfRange::Error).unwrap(), ply::Property::ListUShort(vec![25, 0]));
+    assert!(read("1.5e0", "int", ply::OutOfRange::Clamp).is_err());
+    assert!(read("1e10", "int", ply::OutOfRange::Error).is_err());
+    assert_eq!(read("1e10", "int", ply::OutOfRange::Clamp).unwrap(), ply::Property::Int(i32::MAX));
+    // Float overflow used to parse to infinity, now it is an error unless wrapped.
+    assert!(read("1e40", "float", ply::OutOfRange::Error).is_err());
+    assert_eq!(read("1e40", "float", ply::OutOfRange::Wrap).unwrap(), ply::Property::Float(f32::INFINITY));
+}
+#[test]
+fn read_header_suggestion() {
+    let txt = "ply\nformat ascii 1.0\nelement point 1\nproperty flaot x\nend_header\n1\n";
+    let p = parser::Parser::<ply::DefaultElement>::new();
+    let err = p.read_ply(&mut txt.as_bytes()).unwrap_err().to_string();
+    assert!(err.starts_with("Line 4: Couldn't parse line. Unknown type `flaot`, did you mean `float`?"), "unexpected error: {}", err);
+    assert!(err.contains("property flaot x"));
+}
+#[test]
+fn read_error_location() {
+    let p = parser::Parser::<ply::DefaultElement>::new();
+    let txt = "ply\nformat ascii 1.0\nproperty int x\nend_header\n";
+    match p.read_ply(&mut txt.as_bytes()).unwrap_err() {
+        ply::Error::Schema { line, offset, .. } => assert_eq!((line, offset), (3, 21)),
+        e => panic!("unexpected error: {}", e),
+    }
+    let txt = "ply\nformat ascii 1.0\nelement a 1\nproperty int x\nelement b 3\nproperty int y\nend_header\n1\n2\n3\n";
+    match p.read_ply(&mut txt.as_bytes()).unwrap_err() {
+        ply::Error::Payload { element, index, .. } => assert_eq!((&element[..], index), ("b", 2)),
+        e => panic!("unexpected error: {}", e),
+    }
+}
+
 mod struct_test_1 {
     use super::ply;
     use super::parser::Parser;
-    use std;
     use super::read_file;
     #[derive(Debug)]
     struct Vertex {
@@ -142,8 +286,8 @@ mod struct_test_1 {
         for (_ignore_key, element) in &header.elements {
             // we could also just parse them in sequence, but the file format might change
             match element.name.as_ref() {
-                "vertex" => {vertex_list = vertex_parser.read_payload_for_element(&mut f, &element, &header).unwrap();},
-                "face" => {face_list = face_parser.read_payload_for_element(&mut f, &element, &header).unwrap();},
+                "vertex" => {vertex_list = vertex_parser.read_payload_for_element(&mut f, element, &header).unwrap();},
+                "face" => {face_list = face_parser.read_payload_for_element(&mut f, element, &header).unwrap();},
                 _ => panic!("Enexpeced element!"),
             }
         }
@@ -152,32 +296,72 @@ mod struct_test_1 {
         println!("vertex list: {:#?}", vertex_list);
         println!("face list: {:#?}", face_list);
 
-        let ply = read_file(&path);
+        let ply = read_file(path);
 
-        for i in 0..vertex_list.len() {
+        for (i, vertex) in vertex_list.iter().enumerate() {
             let x = match ply.payload["vertex"][i]["x"] {
                 ply::Property::Float(v) => v,
                 _ => panic!("Unexpected property."),
             };
-            assert_eq!(vertex_list[i].x, x);
+            assert_eq!(vertex.x, x);
             let y = match ply.payload["vertex"][i]["y"] {
                 ply::Property::Float(v) => v,
                 _ => panic!("Unexpected property."),
             };
-            assert_eq!(vertex_list[i].y, y);
+            assert_eq!(vertex.y, y);
             let z = match ply.payload["vertex"][i]["z"] {
                 ply::Property::Float(v) => v,
                 _ => panic!("Unexpected property."),
             };
-            assert_eq!(vertex_list[i].z, z);
+            assert_eq!(vertex.z, z);
         }
 
-        for i in 0..face_list.len() {
+        for (i, face) in face_list.iter().enumerate() {
             let v = match ply.payload["face"][i]["vertex_index"] {
                 ply::Property::ListInt(ref v) => v,
                 _ => panic!("Unexpected property."),
             };
-            assert_eq!(face_list[i].vertex_index, *v);
+            assert_eq!(face.vertex_index, *v);
         }
     }
 }
+#[test]
+fn read_binary_after_crlf_header() {
+    let header = "ply\nformat binary_little_endian 1.0\nelement point 2\nproperty uchar x\nproperty uchar y\nend_header\n";
+    let p = parser::Parser::<ply::DefaultElement>::new();
+    for line_break in &["\r\n", "\r"] {
+        let mut data = header.replace('\n', line_break).into_bytes();
+        data.extend_from_slice(&[10, 13, 10, 7]);
+        let ply = p.read_ply(&mut &data[..]).unwrap();
+        let points = &ply.payload["point"];
+        assert_eq!((&points[0]["x"], &points[0]["y"]), (&ply::Property::UChar(10), &ply::Property::UChar(13)));
+        assert_eq!((&points[1]["x"], &points[1]["y"]), (&ply::Property::UChar(10), &ply::Property::UChar(7)));
+        let header_only = p.read_header_only(&mut &data[..]).unwrap();
+        assert_eq!(header_only.elements["point"].count, 2);
+    }
+}
+#[derive(Debug, Default, PartialEq)]
+struct Position([f32; 3]);
+
+impl ply::PropertyAccess for Position {
+    fn new() -> Self {
+        Self::default()
+    }
+    fn set_named_property(&mut self, name: &str, property: ply::Property) {
+        match (name, property) {
+            ("x", ply::Property::Float(v)) => self.0[0] = v,
+            ("y", ply::Property::Float(v)) => self.0[1] = v,
+            ("z", ply::Property::Float(v)) => self.0[2] = v,
+            _ => (),
+        }
+    }
+}
+#[test]
+fn read_named_properties_into_struct() {
+    let mut f = std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap();
+    let mut p = parser::Parser::<Position>::new();
+    p.set_projection(parser::Projection::all().select("vertex", &[]));
+    let ply = p.read_ply(&mut f).unwrap();
+    assert_eq!(ply.payload["vertex"].len(), 8);
+    assert_eq!(ply.payload["vertex"][1], Position([0.0, 0.0, 1.0]));
+}
@@ -8,7 +8,7 @@ type Ply = ply::Ply<ply::DefaultElement>;
 fn read_buff<T: Read>(mut buf: &mut T) -> Ply {
     let p = parser::Parser::new();
     let ply = p.read_ply(&mut buf);
-    assert!(ply.is_ok(), format!("failed: {}", ply.err().unwrap()));
+    assert!(ply.is_ok(), "failed: {}", ply.err().unwrap());
     ply.unwrap()
 }
 
@@ -21,7 +21,7 @@ fn write_buff(ply: &Ply) -> Vec<u8> {
 
 fn read_write_ply(ply: &Ply) -> Ply {
     println!("writing ply:\n{:?}", ply);
-    let ve : Vec<u8> = write_buff(&ply);
+    let ve : Vec<u8> = write_buff(ply);
     let txt = String::from_utf8(ve.clone()).unwrap();
     println!("written ply:\n{}", txt);
     let mut buff = BufReader::new(&(*ve));
@@ -129,3 +129,392 @@ fn write_list_elements() {
     let new_ply = read_write_ply(&ply);
     assert_eq!(ply, new_ply);
 }
+#[test]
+fn write_staged_equal() {
+    let mut ply = create_list_elements();
+    ply.header.encoding = Encoding::BinaryLittleEndian;
+    let w = writer::Writer::new();
+    let mut staged = w.staged(Vec::<u8>::new(), ply.header.encoding, ply.header.version).unwrap();
+    for c in &ply.header.comments {
+        staged = staged.comment(c).unwrap();
+    }
+    for oi in &ply.header.obj_infos {
+        staged = staged.obj_info(oi).unwrap();
+    }
+    for (_, e) in &ply.header.elements {
+        staged = staged.element(e).unwrap();
+    }
+    let mut payload = staged.end_header().unwrap();
+    assert_eq!(payload.next_element().unwrap().name, "aList");
+    payload.write_all(&ply.payload["aList"]).unwrap();
+    assert!(payload.next_element().is_none());
+    assert!(payload.write(&ply.payload["aList"][0]).is_err());
+    let finished = payload.finish().unwrap();
+    assert_eq!(finished.written(), write_buff(&ply).len() as u64);
+    assert_eq!(finished.into_inner(), write_buff(&ply));
+}
+#[test]
+fn write_staged_missing_elements() {
+    let ply = create_single_elements();
+    let w = writer::Writer::new();
+    let mut payload = w.staged(Vec::<u8>::new(), ply.header.encoding, ply.header.version).unwrap()
+        .element(&ply.header.elements["point"]).unwrap()
+        .end_header().unwrap();
+    payload.write(&ply.payload["point"][0]).unwrap();
+    assert!(payload.finish().is_err());
+}
+#[test]
+fn write_comment_line_breaks() {
+    let mut ply = create_basic_header();
+    ply.header.comments.push("first\nsecond".to_string());
+    let mut w = writer::Writer::new();
+    assert!(w.write_ply_unchecked(&mut Vec::<u8>::new(), &ply).is_err());
+    assert!(w.write_ply(&mut Vec::<u8>::new(), &mut ply.clone()).is_err());
+
+    w.set_line_break_policy(writer::LineBreakPolicy::Escape);
+    let mut buf = Vec::<u8>::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    assert_eq!(ply.header.comments[1], "first\\nsecond");
+    let new_ply = read_buff(&mut &buf[..]);
+    assert_eq!(new_ply.header, ply.header);
+}
+#[test]
+fn write_sized_type_names() {
+    let mut ply = create_list_elements();
+    let mut w = writer::Writer::new();
+    w.set_type_names(writer::TypeNames::Sized);
+    let mut buf = Vec::<u8>::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    let txt = String::from_utf8(buf.clone()).unwrap();
+    assert!(txt.contains("property list int32 int32 x"), "unexpected header: {}", txt);
+    let new_ply = read_buff(&mut &buf[..]);
+    assert_eq!(new_ply, ply);
+}
+#[test]
+fn write_documents_round_trip() {
+    let mut plys = vec![create_single_elements(), create_list_elements(), create_min()];
+    plys[1].header.encoding = Encoding::BinaryLittleEndian;
+    let mut buf = Vec::<u8>::new();
+    writer::Writer::new().write_documents(&mut buf, &mut plys).unwrap();
+    let p = parser::Parser::<DefaultElement>::new();
+    let read = p.read_all(&mut &buf[..]).unwrap();
+    assert_eq!(read, plys);
+
+    buf.extend_from_slice(b"ply\nformat ascii 1.0\nelement point 1\nproperty int x\nend_header\n");
+    let mut documents = p.documents(&buf[..]);
+    assert_eq!(documents.by_ref().take(3).count(), 3);
+    assert!(documents.next().unwrap().is_err());
+    assert!(documents.next().is_none());
+}
+#[test]
+fn write_aligned_columns() {
+    let mut ply = create_single_elements();
+    ply.payload.get_mut("point").unwrap()[1].insert("y".to_string(), Property::UInt(1234));
+    let mut w = writer::Writer::new();
+    w.set_align_columns(true);
+    let mut buf = Vec::<u8>::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    let txt = String::from_utf8(buf.clone()).unwrap();
+    assert!(txt.ends_with("end_header\n-7    5\n 2 1234\n"), "unaligned rows:\n{}", txt);
+    assert_eq!(read_buff(&mut &buf[..]), ply);
+}
+#[test]
+fn write_tab_separated() {
+    let mut ply = create_single_elements();
+    ply.payload.get_mut("point").unwrap()[1].insert("y".to_string(), Property::UInt(1234));
+    let mut w = writer::Writer::new();
+    w.set_separator(writer::Separator::Tab);
+    let mut buf = Vec::<u8>::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    let txt = String::from_utf8(buf.clone()).unwrap();
+    assert!(txt.ends_with("end_header\n-7\t5\t\n2\t1234\t\n"), "rows:\n{}", txt);
+    assert_eq!(read_buff(&mut &buf[..]), ply);
+    w.set_align_columns(true);
+    let mut buf = Vec::<u8>::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    let txt = String::from_utf8(buf.clone()).unwrap();
+    assert!(txt.ends_with("end_header\n-7\t   5\n 2\t1234\n"), "unaligned rows:\n{}", txt);
+    assert_eq!(read_buff(&mut &buf[..]), ply);
+}
+#[test]
+fn write_name_policy() {
+    let mut ply = Ply::new();
+    let mut e = ElementDef::new("sample point".to_string());
+    e.properties.add(PropertyDef::new("x\u{7}".to_string(), PropertyType::Scalar(ScalarType::Int)));
+    ply.header.elements.add(e);
+    let mut row = DefaultElement::new();
+    row.insert("x\u{7}".to_string(), Property::Int(3));
+    ply.payload.insert("sample point".to_string(), vec![row]);
+    let mut w = writer::Writer::new();
+    assert!(w.write_ply(&mut Vec::new(), &mut ply).is_err());
+    assert!(w.write_header(&mut Vec::new(), &ply.header).is_err());
+    w.set_name_policy(writer::NamePolicy::Replace('_'));
+    let mut buf = Vec::<u8>::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    let written = read_buff(&mut &buf[..]);
+    assert_eq!(written.payload["sample_point"][0]["x_"], Property::Int(3));
+}
+#[test]
+fn write_to_path() {
+    let path = std::env::temp_dir().join(format!("ply_rs_write_to_path_{}.ply", std::process::id()));
+    let temporary = path.with_file_name(format!("ply_rs_write_to_path_{}.ply.tmp", std::process::id()));
+    let w = writer::Writer::new();
+    let mut ply = create_list_elements();
+    let written = w.write_ply_to_path(&path, &mut ply).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), written as u64);
+    assert!(!temporary.exists());
+    let mut broken = ply.clone();
+    broken.payload.get_mut("aList").unwrap()[0].insert("x".to_string(), Property::Int(1));
+    assert!(w.write_ply_to_path(&path, &mut broken).is_err());
+    assert!(!temporary.exists());
+    let read = parser::Parser::<DefaultElement>::new().read_ply_from_path(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.unwrap(), ply);
+}
+#[test]
+fn write_short_writes() {
+    struct OneByte(Vec<u8>);
+    impl std::io::Write for OneByte {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.extend(buf.first());
+            Ok(buf.len().min(1))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let ply = create_single_elements();
+    let w = writer::Writer::new();
+    let mut out = OneByte(Vec::new());
+    assert_eq!(w.write_line_comment(&mut out, &ply.header.comments[0]).unwrap(), out.0.len() as u64);
+    assert_eq!(out.0, b"comment Hi, I'm your friendly comment.\n");
+    let mut out = OneByte(Vec::new());
+    w.write_ply_unchecked(&mut out, &ply).unwrap();
+    assert_eq!(out.0, write_buff(&ply));
+}
+#[test]
+fn write_batched() {
+    struct CountWrites(Vec<u8>, usize);
+    impl std::io::Write for CountWrites {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.1 += 1;
+            self.0.write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let ply = create_list_elements();
+    let mut out = CountWrites(Vec::new(), 0);
+    writer::Writer::new().write_ply_unchecked(&mut out, &ply).unwrap();
+    assert_eq!(out.1, 1 + ply.payload.len());
+    assert_eq!(out.0, write_buff(&ply));
+}
+#[test]
+fn write_scientific_floats() {
+    let mut ply = Ply::new();
+    let mut point = ElementDef::new("point".to_string());
+    point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Double)));
+    ply.header.elements.add(point);
+    let points = [1.5e-7, -2.0, 6.02e23].iter().map(|&x| {
+        let mut e = DefaultElement::new();
+        e.insert("x".to_string(), Property::Double(x));
+        e
+    }).collect();
+    ply.payload.insert("point".to_string(), points);
+    let mut w = writer::Writer::new();
+    w.set_float_notation(writer::FloatNotation::Scientific);
+    let mut buf = Vec::<u8>::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    let txt = String::from_utf8(buf.clone()).unwrap();
+    assert!(txt.ends_with("end_header\n1.5e-7 \n-2e0 \n6.02e23 \n"), "{}", txt);
+    assert_eq!(read_buff(&mut &buf[..]), ply);
+}
+#[test]
+fn write_non_finite_policy() {
+    let mut ply = create_single_elements();
+    let mut point = ElementDef::new("sample".to_string());
+    point.properties.add(PropertyDef::new("v".to_string(), PropertyType::Scalar(ScalarType::Float)));
+    ply.header.elements.add(point);
+    let mut e = DefaultElement::new();
+    e.insert("v".to_string(), Property::Float(f32::NAN));
+    ply.payload.insert("sample".to_string(), vec![e]);
+    let mut w = writer::Writer::new();
+    let err = w.write_ply(&mut Vec::new(), &mut ply).unwrap_err();
+    assert!(err.to_string().contains("Property `v` of element `sample`"), "{}", err);
+    w.set_non_finite_policy(writer::NonFinitePolicy::Tokens);
+    let mut buf = Vec::<u8>::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    assert!(String::from_utf8(buf).unwrap().ends_with("\nnan \n"));
+}
+#[test]
+fn write_non_finite_default_round_trip() {
+    let mut ply = create_single_elements();
+    let mut point = ElementDef::new("sample".to_string());
+    point.properties.add(PropertyDef::new("v".to_string(), PropertyType::Scalar(ScalarType::Float)));
+    ply.header.elements.add(point);
+    let mut e = DefaultElement::new();
+    e.insert("v".to_string(), Property::Float(f32::NAN));
+    ply.payload.insert("sample".to_string(), vec![e]);
+    // Ascii output fails when writing rather than when reading back.
+    assert!(writer::Writer::new().write_ply(&mut Vec::new(), &mut ply).is_err());
+    ply.header.encoding = Encoding::BinaryLittleEndian;
+    let mut buf = Vec::<u8>::new();
+    writer::Writer::new().write_ply(&mut buf, &mut ply).unwrap();
+    let read = parser::Parser::<DefaultElement>::new().read_ply(&mut &buf[..]).unwrap();
+    match read.payload["sample"][0]["v"] {
+        Property::Float(v) => assert!(v.is_nan()),
+        ref v => panic!("Unexpected property {:?}", v),
+    }
+}
+#[test]
+fn write_list_too_long() {
+    let mut ply = create_list_elements();
+    let name = ply.header.elements.keys().next().unwrap().clone();
+    let (key, prop) = ply.header.elements[&name].properties.iter().next().map(|(k, p)| (k.clone(), p.clone())).unwrap();
+    let mut prop = prop;
+    prop.data_type = PropertyType::List(ScalarType::UChar, ScalarType::Int);
+    ply.header.elements.get_mut(&name).unwrap().properties.insert(key.clone(), prop);
+    ply.payload.get_mut(&name).unwrap()[1].insert(key.clone(), Property::ListInt(vec![0; 256]));
+    for &(encoding, align) in &[(Encoding::Ascii, false), (Encoding::Ascii, true), (Encoding::BinaryLittleEndian, false)] {
+        ply.header.encoding = encoding;
+        let mut w = writer::Writer::new();
+        w.set_align_columns(align);
+        let err = w.write_ply_unchecked(&mut Vec::new(), &ply).unwrap_err();
+        assert!(err.to_string().contains("has 256 entries"), "{}", err);
+        assert!(err.to_string().contains(&format!("Row 1: Property `{}` of element `{}`", key, name)), "{}", err);
+    }
+}
+#[test]
+fn write_consistency_check() {
+    let mut ply = create_list_elements();
+    ply.payload.get_mut("aList").unwrap()[1].insert("x".to_string(), Property::ListUInt(vec![1]));
+    let mut buf = Vec::new();
+    let mut w = writer::Writer::new();
+    assert!(w.write_ply(&mut buf, &mut ply).is_err());
+    assert!(buf.is_empty());
+    w.set_consistency_check(false);
+    assert!(w.write_ply(&mut buf, &mut ply).is_err());
+    assert!(!buf.is_empty());
+}
+#[test]
+fn write_canonical() {
+    let mut ply = create_list_elements();
+    let mut e = ElementDef::new("point".to_string());
+    e.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)));
+    ply.header.elements.add(e);
+    let mut point = DefaultElement::new();
+    point.insert("x".to_string(), Property::Float(0.1));
+    ply.payload.insert("point".to_string(), vec![point]);
+    let mut reordered = ply.clone();
+    let list = reordered.payload.remove("aList").unwrap();
+    reordered.payload.insert("aList".to_string(), list);
+
+    let mut canonical = writer::Writer::new();
+    canonical.set_canonical(true);
+    let mut expected = Vec::new();
+    canonical.write_ply(&mut expected, &mut ply).unwrap();
+    let text = String::from_utf8(expected.clone()).unwrap();
+    assert!(text.ends_with("end_header\n3 -7 17 38\n4 13 -19 8 33\n0.1\n"), "{}", text);
+
+    let mut w = writer::Writer::new();
+    w.set_new_line("\r\n");
+    w.set_align_columns(true);
+    w.set_type_names(writer::TypeNames::Sized);
+    w.set_float_notation(writer::FloatNotation::Scientific);
+    w.set_float_precision(Some(writer::FloatPrecision::Decimals(3)));
+    w.set_canonical(true);
+    let mut buf = Vec::new();
+    w.write_ply(&mut buf, &mut reordered).unwrap();
+    assert_eq!(buf, expected);
+}
+#[test]
+fn write_with_cast() {
+    let mut ply = create_list_elements();
+    let mut point = ElementDef::new("point".to_string());
+    point.properties.add(PropertyDef::new("x".to_string(), PropertyType::Scalar(ScalarType::Float)));
+    point.properties.add(PropertyDef::new("i".to_string(), PropertyType::List(ScalarType::UChar, ScalarType::Short)));
+    ply.header.elements.add(point);
+    let mut row = DefaultElement::new();
+    row.insert("x".to_string(), Property::Double(0.1));
+    row.insert("i".to_string(), Property::ListInt(vec![1, -70000]));
+    ply.payload.insert("point".to_string(), vec![row]);
+    ply.header.encoding = Encoding::BinaryBigEndian;
+
+    let mut w = writer::Writer::new();
+    let err = w.write_ply(&mut Vec::new(), &mut ply).unwrap_err();
+    assert!(err.to_string().contains("isn't consistent"), "{}", err);
+    w.set_cast_policy(Some(OutOfRange::Error));
+    let err = w.write_ply(&mut Vec::new(), &mut ply).unwrap_err();
+    assert!(err.to_string().contains("Property `i` of element `point`: Value -70000 is out of range"), "{}", err);
+    w.set_cast_policy(Some(OutOfRange::Clamp));
+    let mut buf = Vec::new();
+    w.write_ply(&mut buf, &mut ply).unwrap();
+    let written = read_buff(&mut &buf[..]);
+    assert_eq!(written.payload["point"][0]["x"], Property::Float(0.1));
+    assert_eq!(written.payload["point"][0]["i"], Property::ListShort(vec![1, i16::MIN]));
+    assert_eq!(written.payload["aList"], ply.payload["aList"]);
+    w.set_exact_casts(true);
+    assert!(w.write_ply(&mut Vec::new(), &mut ply).is_err());
+}
+#[test]
+fn write_size() {
+    let mut ply = create_list_elements();
+    let w = writer::Writer::new();
+    for encoding in &[Encoding::Ascii, Encoding::BinaryBigEndian] {
+        ply.header.encoding = *encoding;
+        let size = w.header_size(&ply.header).unwrap() + w.payload_size(&ply).unwrap();
+        let mut buf = Vec::new();
+        assert_eq!(w.write_ply(&mut buf, &mut ply).unwrap(), size);
+        assert_eq!(buf.len() as u64, size);
+    }
+}
+#[test]
+fn write_fmt() {
+    let mut ply = create_list_elements();
+    let mut expected = Vec::new();
+    writer::Writer::new().write_ply(&mut expected, &mut ply).unwrap();
+    let mut text = String::new();
+    assert_eq!(writer::Writer::new().write_ply_fmt(&mut text, &mut ply).unwrap(), expected.len() as u64);
+    assert_eq!(text.as_bytes(), &expected[..]);
+    ply.header.encoding = Encoding::BinaryLittleEndian;
+    assert!(writer::Writer::new().write_ply_fmt(&mut String::new(), &mut ply).is_err());
+}
+#[cfg(feature = "derive")]
+#[test]
+fn write_derived_elements() {
+    #[derive(Debug, PartialEq, PlyElement)]
+    struct Vertex {
+        x: f32,
+        y: f32,
+        #[ply(name = "z")]
+        height: f32,
+    }
+    #[derive(Debug, PartialEq, PlyElement)]
+    #[ply(name = "face")]
+    struct Polygon {
+        #[ply(name = "vertex_index")]
+        indices: Vec<i32>,
+    }
+    let mut f = BufReader::new(std::fs::File::open("example_plys/greg_turk_example1_ok_ascii.ply").unwrap());
+    let mut header = parser::Parser::<Vertex>::new().read_header(&mut f).unwrap();
+    let vertices = parser::Parser::<Vertex>::new().read_payload_for_element(&mut f, &header.elements["vertex"], &header).unwrap();
+    let faces = parser::Parser::<Polygon>::new().read_payload_for_element(&mut f, &header.elements["face"], &header).unwrap();
+    assert_eq!(vertices[1], Vertex { x: 0.0, y: 0.0, height: 1.0 });
+    assert_eq!(faces[0], Polygon { indices: vec![0, 1, 2, 3] });
+    let mut vertex = Vertex::element_def();
+    vertex.count = vertices.len();
+    let mut face = Polygon::element_def();
+    face.count = faces.len();
+    assert_eq!(vertex, header.elements["vertex"]);
+    assert_eq!(face, header.elements["face"]);
+
+    header.encoding = Encoding::BinaryBigEndian;
+    let mut buf = Vec::new();
+    writer::Writer::<Vertex>::new().write_header(&mut buf, &header).unwrap();
+    writer::Writer::new().write_payload_of_element(&mut buf, &vertices, &vertex, &header).unwrap();
+    writer::Writer::new().write_payload_of_element(&mut buf, &faces, &face, &header).unwrap();
+    let read = read_buff(&mut &buf[..]);
+    assert_eq!(read.payload["vertex"][1]["z"], Property::Float(1.0));
+    assert_eq!(read.payload["face"][0]["vertex_index"], Property::ListInt(vec![0, 1, 2, 3]));
+}